@@ -52,7 +52,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             matched_count += 1;
             println!("✓ {}", pathname);
             println!("  Type: {:?}", entry.file_type());
-            println!("  Size: {} bytes", entry.size());
+            println!("  Size: {} bytes", entry.size_opt().unwrap_or(0));
 
             if let Some(mtime) = entry.mtime() {
                 println!("  Modified: {:?}", mtime);
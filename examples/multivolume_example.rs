@@ -50,7 +50,7 @@ let mut archive = ReadArchive::open_filenames(&parts)?;
 
 while let Some(entry) = archive.next_entry()? {{
     println!("File: {{}}", entry.pathname().unwrap_or_default());
-    println!("Size: {{}} bytes", entry.size());
+    println!("Size: {{}} bytes", entry.size_opt().unwrap_or(0));
 
     // Read data (libarchive handles volume switching automatically)
     let data = archive.read_data_to_vec()?;
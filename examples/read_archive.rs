@@ -13,7 +13,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     while let Some(entry) = archive.next_entry()? {
         let pathname = entry.pathname().unwrap_or_else(|| "<unknown>".to_string());
-        let size = entry.size();
+        let size = entry.size_opt().unwrap_or(0);
         let file_type = entry.file_type();
 
         println!("{:?} {} ({} bytes)", file_type, pathname, size);
@@ -25,43 +25,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut archive = WriteArchive::new()
         .format(ArchiveFormat::TarPax)
         .compression(CompressionFormat::Gzip)
+        .symlink_mode(SymlinkMode::Physical)
         .open_file(output_archive)?;
 
-    // Create a ReadDisk instance
+    // Walk the directory, copying each entry and its contents into the archive
     let mut disk = ReadDisk::new()?;
     disk.set_symlink_mode(SymlinkMode::Physical)?;
     disk.set_standard_lookup()?;
-
-    // Open the directory
     disk.open(source_dir)?;
 
     let mut file_count = 0;
-
-    // Read entries from disk
+    let mut buf = vec![0u8; 64 * 1024];
     while let Some(entry_mut) = disk.next_entry()? {
         let entry = entry_mut.as_entry();
         let pathname = entry.pathname().unwrap_or_else(|| "<unknown>".to_string());
-        println!("Adding: {}", pathname);
+        println!(
+            "Adding: {} ({} bytes)",
+            pathname,
+            entry.size_opt().unwrap_or(0)
+        );
+        file_count += 1;
 
-        // Write the entry to the archive
         archive.write_header(&entry_mut)?;
-
-        // If it's a regular file, copy the data
-        if entry.file_type() == libarchive2::FileType::RegularFile {
-            // For files with data, we would need to read from disk
-            // ReadDisk doesn't provide read_data - you'd typically use std::fs
-            // This is a simplified example
-            let size = entry.size();
-            if size > 0 {
-                println!(
-                    "  (File has {} bytes - would need to read from filesystem)",
-                    size
-                );
+        loop {
+            let n = disk.read_data(&mut buf)?;
+            if n == 0 {
+                break;
             }
+            archive.write_data(&buf[..n])?;
         }
 
-        file_count += 1;
-
         // Descend into directories
         if disk.can_descend() {
             disk.descend()?;
@@ -42,7 +42,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let pathname = entry.pathname().unwrap_or_else(|| "<unknown>".to_string());
         let file_type = entry.file_type();
-        let size = entry.size();
+        let size = entry.size_opt().unwrap_or(0);
         total_size += size;
 
         let type_str = match file_type {
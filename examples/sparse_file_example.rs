@@ -82,7 +82,7 @@ fn read_sparse_archive() -> Result<(), Box<dyn Error>> {
 
     if let Some(entry) = archive.next_entry()? {
         let pathname = entry.pathname().unwrap_or_default().to_string();
-        let size = entry.size();
+        let size = entry.size_opt().unwrap_or(0);
 
         println!("   Entry: {}", pathname);
         println!("   Size: {} bytes", size);
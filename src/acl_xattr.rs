@@ -94,7 +94,8 @@ pub struct AclEntry {
 }
 
 /// Extended attribute (xattr)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Xattr {
     /// Attribute name
     pub name: String,
@@ -147,49 +148,19 @@ impl<'a> EntryAclExt for Entry<'a> {
     }
 
     fn acl_count(&self) -> usize {
+        // archive_entry_acl_next writes through its out-pointers, so count with
+        // the dedicated function instead of iterating with null pointers
         unsafe {
-            libarchive2_sys::archive_entry_acl_reset(
+            libarchive2_sys::archive_entry_acl_count(
                 self.entry,
                 libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_ACCESS as i32,
-            );
-            let mut count = 0;
-            loop {
-                let ret = libarchive2_sys::archive_entry_acl_next(
-                    self.entry,
-                    libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_ACCESS as i32,
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                );
-                if ret != libarchive2_sys::ARCHIVE_OK as i32 {
-                    break;
-                }
-                count += 1;
-            }
-            count
+            )
+            .max(0) as usize
         }
     }
 
     fn xattr_count(&self) -> usize {
-        unsafe {
-            libarchive2_sys::archive_entry_xattr_reset(self.entry);
-            let mut count = 0;
-            loop {
-                let ret = libarchive2_sys::archive_entry_xattr_next(
-                    self.entry,
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                );
-                if ret != libarchive2_sys::ARCHIVE_OK as i32 {
-                    break;
-                }
-                count += 1;
-            }
-            count
-        }
+        unsafe { libarchive2_sys::archive_entry_xattr_count(self.entry).max(0) as usize }
     }
 
     fn xattrs(&self) -> Vec<Xattr> {
@@ -0,0 +1,282 @@
+//! Appending entries to an existing archive file
+//!
+//! libarchive cannot append to an archive by itself. For an uncompressed tar
+//! the new entries can simply overwrite the end-of-archive marker; anything
+//! else has to be rewritten. [`AppendState`] remembers enough to undo either
+//! kind of append if it does not complete.
+
+use crate::entry::Entry;
+use crate::error::{Error, Result};
+use crate::format::{ArchiveFormat, CompressionFormat};
+use crate::reader::ReadArchive;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// How [`WriteArchive::append_to_file`](crate::WriteArchive::append_to_file)
+/// adds entries to an existing archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendStrategy {
+    /// New entries are written over the end-of-archive marker of the existing
+    /// file, so existing entries are not copied
+    ///
+    /// Used for uncompressed tar archives (and empty files).
+    InPlace,
+    /// The existing entries are copied into a temporary file next to the
+    /// original, the new entries follow, and
+    /// [`finish`](crate::WriteArchive::finish) renames the temporary file over
+    /// the original
+    ///
+    /// Used for compressed and non-tar archives.
+    Rewrite,
+}
+
+impl AppendStrategy {
+    /// Find out which strategy appending to `path` would use
+    ///
+    /// This reads through the whole archive but does not modify it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::AppendStrategy;
+    ///
+    /// if AppendStrategy::for_file("backup.tar.gz")? == AppendStrategy::Rewrite {
+    ///     println!("appending will rewrite the whole archive");
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn for_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Existing::probe(path.as_ref(), None, |_| {})?.strategy)
+    }
+}
+
+/// What appending needs to know about the archive being appended to
+pub(crate) struct Existing {
+    pub(crate) strategy: AppendStrategy,
+    /// Format to continue writing in, or `None` for an empty file
+    pub(crate) format: Option<ArchiveFormat>,
+    pub(crate) compression: CompressionFormat,
+    /// Offset of the end-of-archive marker in the uncompressed data
+    pub(crate) end: u64,
+}
+
+impl Existing {
+    /// Read through the archive at `path`, calling `on_entry` for each entry
+    pub(crate) fn probe(
+        path: &Path,
+        passphrase: Option<&str>,
+        mut on_entry: impl FnMut(&Entry),
+    ) -> Result<Self> {
+        let mut reader = match passphrase {
+            Some(passphrase) => ReadArchive::open_with_passphrase(path, passphrase)?,
+            None => ReadArchive::open(path)?,
+        };
+        while let Some(entry) = reader.next_entry()? {
+            on_entry(&entry);
+        }
+
+        let archive = reader.archive();
+        // SAFETY: archive is valid for the lifetime of reader. At EOF the header
+        // position is where the reader looked for the next header, which is
+        // the start of the end-of-archive marker.
+        let (code, end, filters) = unsafe {
+            let count = libarchive2_sys::archive_filter_count(archive);
+            let filters: Vec<i32> = (0..count)
+                .map(|i| libarchive2_sys::archive_filter_code(archive, i))
+                .filter(|&code| code != libarchive2_sys::ARCHIVE_FILTER_NONE as i32)
+                .collect();
+            (
+                libarchive2_sys::archive_format(archive),
+                libarchive2_sys::archive_read_header_position(archive),
+                filters,
+            )
+        };
+
+        let compression = match filters.as_slice() {
+            [] => CompressionFormat::None,
+            [code] => CompressionFormat::from_code(*code).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "Cannot append to {}: unsupported compression {:?}",
+                    path.display(),
+                    reader.filters()
+                ))
+            })?,
+            _ => {
+                return Err(Error::InvalidArgument(format!(
+                    "Cannot append to {}: it has more than one filter ({:?})",
+                    path.display(),
+                    reader.filters()
+                )));
+            }
+        };
+
+        if code == libarchive2_sys::ARCHIVE_FORMAT_EMPTY as i32 {
+            return Ok(Existing {
+                strategy: AppendStrategy::InPlace,
+                format: None,
+                compression,
+                end: 0,
+            });
+        }
+        let format = ArchiveFormat::from_code(code).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "Cannot append to {}: unsupported format {}",
+                path.display(),
+                reader.format_name().unwrap_or_default()
+            ))
+        })?;
+
+        let in_place = format.is_tar() && compression == CompressionFormat::None;
+        let format = if in_place && format != ArchiveFormat::TarGnu {
+            // The tar reader reports the format of the last header it saw, so a
+            // pax archive can look like ustar; pax extends ustar, so keep
+            // writing pax unless the archive is GNU tar
+            ArchiveFormat::TarPax
+        } else {
+            format
+        };
+        Ok(Existing {
+            strategy: if in_place {
+                AppendStrategy::InPlace
+            } else {
+                AppendStrategy::Rewrite
+            },
+            format: Some(format),
+            compression,
+            end: end.max(0) as u64,
+        })
+    }
+}
+
+/// An append in progress, undone on drop unless committed
+pub(crate) struct AppendState {
+    pub(crate) strategy: AppendStrategy,
+    path: PathBuf,
+    undo: Undo,
+    committed: bool,
+}
+
+enum Undo {
+    /// Put back the bytes from the end-of-archive marker onwards
+    Restore { end: u64, tail: Vec<u8> },
+    /// Delete the temporary file
+    Discard { temp: PathBuf },
+}
+
+impl AppendState {
+    /// Prepare to overwrite the archive at `path` from offset `end`
+    ///
+    /// Returns the file, truncated and positioned at `end`.
+    pub(crate) fn in_place(path: &Path, end: u64) -> Result<(Self, File)> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+        let len = file.metadata()?.len();
+        if end > len {
+            return Err(Error::InvalidArgument(format!(
+                "Cannot append to {}: end of archive ({}) is past the end of the file ({})",
+                path.display(),
+                end,
+                len
+            )));
+        }
+        let mut tail = Vec::with_capacity((len - end) as usize);
+        file.seek(SeekFrom::Start(end))?;
+        file.read_to_end(&mut tail)?;
+
+        let state = AppendState {
+            strategy: AppendStrategy::InPlace,
+            path: path.to_path_buf(),
+            undo: Undo::Restore { end, tail },
+            committed: false,
+        };
+        file.set_len(end)?;
+        file.seek(SeekFrom::Start(end))?;
+        Ok((state, file))
+    }
+
+    /// Prepare to rewrite the archive at `path` into a new temporary file
+    pub(crate) fn rewrite(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("{} is not a file path", path.display()))
+            })?
+            .to_string_lossy();
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        for attempt in 0u32.. {
+            let temp = dir.join(format!(
+                ".{}.append-{}-{}",
+                name,
+                std::process::id(),
+                attempt
+            ));
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temp)
+            {
+                Ok(_) => {
+                    return Ok(AppendState {
+                        strategy: AppendStrategy::Rewrite,
+                        path: path.to_path_buf(),
+                        undo: Undo::Discard { temp },
+                        committed: false,
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Error::Io(e)),
+            }
+        }
+        unreachable!("temporary file names are exhausted")
+    }
+
+    /// The archive being appended to
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file the archive is being written to
+    pub(crate) fn output_path(&self) -> &Path {
+        match &self.undo {
+            Undo::Restore { .. } => &self.path,
+            Undo::Discard { temp } => temp,
+        }
+    }
+
+    /// Make the append permanent once the archive has been closed
+    pub(crate) fn commit(mut self) -> Result<()> {
+        if let Undo::Discard { temp } = &self.undo {
+            std::fs::rename(temp, &self.path)?;
+        }
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for AppendState {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        // Best effort: there is nobody to report a failure to
+        match &self.undo {
+            Undo::Restore { end, tail } => {
+                if let Ok(mut file) = std::fs::OpenOptions::new().write(true).open(&self.path) {
+                    let _ = file
+                        .set_len(*end)
+                        .and_then(|_| file.seek(SeekFrom::Start(*end)))
+                        .and_then(|_| file.write_all(tail));
+                }
+            }
+            Undo::Discard { temp } => {
+                let _ = std::fs::remove_file(temp);
+            }
+        }
+    }
+}
@@ -4,17 +4,31 @@
 //! tracking progress during archive operations.
 
 use std::ffi::c_void;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::os::raw::c_int;
 use std::sync::Mutex;
 
 /// Type for callback cleanup function
 pub(crate) type DropFn = unsafe fn(*mut c_void);
 
+/// Type for the function that takes the last I/O error out of read callback state
+pub(crate) type TakeErrorFn = unsafe fn(*mut c_void) -> Option<std::io::Error>;
+
+/// libarchive seek callback for a reader's state
+pub(crate) type SeekCallbackFn =
+    unsafe extern "C" fn(*mut libarchive2_sys::archive, *mut c_void, i64, c_int) -> i64;
+
+/// libarchive skip callback for a reader's state
+pub(crate) type SkipCallbackFn =
+    unsafe extern "C" fn(*mut libarchive2_sys::archive, *mut c_void, i64) -> i64;
+
 /// Internal state for read callbacks
 struct ReadCallbackState<R: Read> {
     reader: R,
     buffer: Vec<u8>,
+    /// Error returned by the reader, kept so it can be reported instead of
+    /// libarchive's generic read failure
+    error: Option<std::io::Error>,
 }
 
 /// Internal state for write callbacks
@@ -59,21 +73,112 @@ unsafe extern "C" fn read_callback_impl<R: Read>(
             Err(_) => return -1, // Poisoned mutex
         };
 
-        // Get mutable references to both fields
+        // Get mutable references to all fields
         let ReadCallbackState {
             reader,
             buffer: buf,
+            error,
         } = &mut *guard;
 
-        // Read into buffer
-        let result = reader.read(buf);
+        // Read into buffer, retrying reads that were interrupted
+        let result = loop {
+            match reader.read(buf) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
 
         match result {
             Ok(n) => {
                 *buffer = buf.as_ptr() as *const c_void;
                 n as isize
             }
-            Err(_) => -1,
+            Err(e) => {
+                *error = Some(e);
+                -1
+            }
+        }
+    }
+}
+
+/// C callback function for seeking
+///
+/// Translates libarchive's `whence` (`SEEK_SET`, `SEEK_CUR`, `SEEK_END`) to
+/// [`SeekFrom`] and returns the new absolute position.
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to a Mutex<ReadCallbackState<R>> that was created by this module.
+unsafe extern "C" fn seek_callback_impl<R: Read + Seek>(
+    _archive: *mut libarchive2_sys::archive,
+    client_data: *mut c_void,
+    offset: i64,
+    whence: c_int,
+) -> i64 {
+    if client_data.is_null() {
+        return libarchive2_sys::ARCHIVE_FATAL as i64;
+    }
+
+    let pos = match whence {
+        0 => match u64::try_from(offset) {
+            Ok(offset) => SeekFrom::Start(offset),
+            Err(_) => return libarchive2_sys::ARCHIVE_FATAL as i64,
+        },
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return libarchive2_sys::ARCHIVE_FATAL as i64,
+    };
+
+    // SAFETY: client_data is a valid pointer to Mutex<ReadCallbackState<R>>
+    // created by CallbackReader::into_raw_parts. It remains valid until
+    // the drop_fn is called.
+    unsafe {
+        let state = &*(client_data as *mut Mutex<ReadCallbackState<R>>);
+        let mut guard = match state.lock() {
+            Ok(g) => g,
+            Err(_) => return libarchive2_sys::ARCHIVE_FATAL as i64,
+        };
+
+        match guard.reader.seek(pos) {
+            Ok(new_pos) => i64::try_from(new_pos).unwrap_or(libarchive2_sys::ARCHIVE_FATAL as i64),
+            Err(e) => {
+                guard.error = Some(e);
+                libarchive2_sys::ARCHIVE_FATAL as i64
+            }
+        }
+    }
+}
+
+/// C callback function for skipping forward
+///
+/// Skips by seeking relative to the current position and returns the number of
+/// bytes skipped, or 0 to make libarchive fall back to reading.
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to a Mutex<ReadCallbackState<R>> that was created by this module.
+unsafe extern "C" fn skip_callback_impl<R: Read + Seek>(
+    _archive: *mut libarchive2_sys::archive,
+    client_data: *mut c_void,
+    request: i64,
+) -> i64 {
+    if client_data.is_null() || request <= 0 {
+        return 0;
+    }
+
+    // SAFETY: client_data is a valid pointer to Mutex<ReadCallbackState<R>>
+    // created by CallbackReader::into_raw_parts. It remains valid until
+    // the drop_fn is called.
+    unsafe {
+        let state = &*(client_data as *mut Mutex<ReadCallbackState<R>>);
+        let mut guard = match state.lock() {
+            Ok(g) => g,
+            Err(_) => return 0,
+        };
+
+        match guard.reader.seek(SeekFrom::Current(request)) {
+            Ok(_) => request,
+            Err(_) => 0,
         }
     }
 }
@@ -122,6 +227,7 @@ unsafe extern "C" fn close_callback_impl(
 /// Builder for reading archives with custom Read implementations
 pub struct CallbackReader<R: Read> {
     state: Box<Mutex<ReadCallbackState<R>>>,
+    seek_callbacks: Option<(SeekCallbackFn, SkipCallbackFn)>,
 }
 
 impl<R: Read> CallbackReader<R> {
@@ -135,8 +241,59 @@ impl<R: Read> CallbackReader<R> {
             state: Box::new(Mutex::new(ReadCallbackState {
                 reader,
                 buffer: vec![0u8; BUFFER_SIZE],
+                error: None,
             })),
+            seek_callbacks: None,
+        }
+    }
+
+    /// Create a callback reader that also lets libarchive seek and skip
+    ///
+    /// Besides the read callback, this registers seek and skip callbacks, so
+    /// zip and 7z archives are read from their index at the end of the source
+    /// and skipped entry data is seeked over instead of read and discarded.
+    /// [`ReadArchive::open_seekable`](crate::ReadArchive::open_seekable) is a
+    /// shortcut for passing this to
+    /// [`ReadArchive::open_callback`](crate::ReadArchive::open_callback).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{CallbackReader, ReadArchive};
+    /// use std::fs::File;
+    ///
+    /// let callback = CallbackReader::new_seekable(File::open("archive.7z")?);
+    /// let mut archive = ReadArchive::open_callback(callback)?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new_seekable(reader: R) -> Self
+    where
+        R: Seek,
+    {
+        CallbackReader {
+            seek_callbacks: Some((seek_callback_impl::<R>, skip_callback_impl::<R>)),
+            ..Self::new(reader)
+        }
+    }
+
+    /// Get the seek and skip callbacks, if the reader is seekable
+    pub(crate) fn seek_callbacks(&self) -> Option<(SeekCallbackFn, SkipCallbackFn)> {
+        self.seek_callbacks
+    }
+
+    /// Get the function that takes the reader's last I/O error from the raw state
+    /// returned by [`into_raw_parts`](Self::into_raw_parts)
+    pub(crate) fn take_error_fn() -> TakeErrorFn {
+        unsafe fn take_error<R: Read>(ptr: *mut c_void) -> Option<std::io::Error> {
+            // SAFETY: ptr was created by Box::into_raw in into_raw_parts and is
+            // still owned by the archive
+            let state = unsafe { &*(ptr as *mut Mutex<ReadCallbackState<R>>) };
+            state.lock().ok()?.error.take()
         }
+        take_error::<R>
     }
 
     pub(crate) fn into_raw_parts(self) -> (*mut c_void, *const c_void, *const c_void, DropFn) {
@@ -160,6 +317,257 @@ impl<R: Read> CallbackReader<R> {
     }
 }
 
+/// libarchive passphrase callback for a closure's state
+pub(crate) type PassphraseCallbackFn =
+    unsafe extern "C" fn(*mut libarchive2_sys::archive, *mut c_void) -> *const std::os::raw::c_char;
+
+/// Internal state for passphrase callbacks
+struct PassphraseCallbackState<F> {
+    callback: F,
+    /// Last passphrase returned, kept alive until libarchive asks again
+    passphrase: Option<std::ffi::CString>,
+}
+
+/// C callback function asking for the next passphrase to try
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to a PassphraseCallbackState<F> created by
+/// [`passphrase_callback_parts`].
+unsafe extern "C" fn passphrase_callback_impl<F: FnMut() -> Option<String>>(
+    _archive: *mut libarchive2_sys::archive,
+    client_data: *mut c_void,
+) -> *const std::os::raw::c_char {
+    if client_data.is_null() {
+        return std::ptr::null();
+    }
+
+    // SAFETY: client_data was created by passphrase_callback_parts and remains
+    // valid until the drop_fn is called
+    let state = unsafe { &mut *(client_data as *mut PassphraseCallbackState<F>) };
+    // A passphrase with an interior null byte can't be passed to libarchive;
+    // treat it like the callback giving up
+    state.passphrase = (state.callback)().and_then(|p| std::ffi::CString::new(p).ok());
+    state
+        .passphrase
+        .as_ref()
+        .map_or(std::ptr::null(), |p| p.as_ptr())
+}
+
+/// Box a passphrase closure for libarchive
+///
+/// Returns the client data, the C callback and the function that frees the
+/// client data.
+pub(crate) fn passphrase_callback_parts<F>(
+    callback: F,
+) -> (*mut c_void, PassphraseCallbackFn, DropFn)
+where
+    F: FnMut() -> Option<String> + Send + 'static,
+{
+    let ptr = Box::into_raw(Box::new(PassphraseCallbackState {
+        callback,
+        passphrase: None,
+    })) as *mut c_void;
+
+    unsafe fn drop_fn<F>(ptr: *mut c_void) {
+        // SAFETY: ptr was created by Box::into_raw in passphrase_callback_parts
+        unsafe {
+            let _ = Box::from_raw(ptr as *mut PassphraseCallbackState<F>);
+        }
+    }
+
+    (ptr, passphrase_callback_impl::<F>, drop_fn::<F>)
+}
+
+/// Type for C callback reporting an entry excluded by a disk reader's matcher
+pub(crate) type ExcludedCallbackFn = unsafe extern "C" fn(
+    *mut libarchive2_sys::archive,
+    *mut c_void,
+    *mut libarchive2_sys::archive_entry,
+);
+
+/// C callback function passing the excluded entry's pathname to the closure
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to an `F` created by [`excluded_callback_parts`], and entry
+/// must be a valid entry.
+unsafe extern "C" fn excluded_callback_impl<F: FnMut(&str)>(
+    _archive: *mut libarchive2_sys::archive,
+    client_data: *mut c_void,
+    entry: *mut libarchive2_sys::archive_entry,
+) {
+    if client_data.is_null() || entry.is_null() {
+        return;
+    }
+
+    // SAFETY: client_data was created by excluded_callback_parts and remains
+    // valid until the drop_fn is called
+    let callback = unsafe { &mut *(client_data as *mut F) };
+    let entry = crate::entry::Entry {
+        entry,
+        archive: std::ptr::null_mut(),
+        _marker: std::marker::PhantomData,
+    };
+    callback(&entry.pathname().unwrap_or_default());
+}
+
+/// Box an exclusion-reporting closure for libarchive
+///
+/// Returns the client data, the C callback and the function that frees the
+/// client data.
+pub(crate) fn excluded_callback_parts<F>(callback: F) -> (*mut c_void, ExcludedCallbackFn, DropFn)
+where
+    F: FnMut(&str) + Send + 'static,
+{
+    let ptr = Box::into_raw(Box::new(callback)) as *mut c_void;
+
+    unsafe fn drop_fn<F>(ptr: *mut c_void) {
+        // SAFETY: ptr was created by Box::into_raw in excluded_callback_parts
+        unsafe {
+            let _ = Box::from_raw(ptr as *mut F);
+        }
+    }
+
+    (ptr, excluded_callback_impl::<F>, drop_fn::<F>)
+}
+
+/// Type for C callback mapping a uid or gid to a name
+pub(crate) type LookupCallbackFn =
+    unsafe extern "C" fn(*mut c_void, libarchive2_sys::la_int64_t) -> *const std::os::raw::c_char;
+
+/// Type for C callback freeing lookup client data
+pub(crate) type LookupCleanupFn = unsafe extern "C" fn(*mut c_void);
+
+/// Internal state for name lookup callbacks
+struct LookupState<F> {
+    callback: F,
+    /// Last name returned; libarchive copies it before asking again
+    name: Option<std::ffi::CString>,
+}
+
+/// C callback function looking up the name for an id
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to a LookupState<F> created by [`lookup_callback_parts`].
+unsafe extern "C" fn lookup_callback_impl<F: FnMut(i64) -> Option<String>>(
+    client_data: *mut c_void,
+    id: libarchive2_sys::la_int64_t,
+) -> *const std::os::raw::c_char {
+    if client_data.is_null() {
+        return std::ptr::null();
+    }
+
+    // SAFETY: client_data was created by lookup_callback_parts and remains
+    // valid until libarchive calls the cleanup function
+    let state = unsafe { &mut *(client_data as *mut LookupState<F>) };
+    // A name with an interior null byte can't be passed to libarchive; leave
+    // the name unset as if the lookup failed
+    state.name = (state.callback)(id).and_then(|name| std::ffi::CString::new(name).ok());
+    state
+        .name
+        .as_ref()
+        .map_or(std::ptr::null(), |name| name.as_ptr())
+}
+
+/// C callback function freeing a LookupState<F>
+///
+/// # Safety
+/// client_data must have been created by [`lookup_callback_parts`] and not
+/// freed yet.
+unsafe extern "C" fn lookup_cleanup_impl<F>(client_data: *mut c_void) {
+    if !client_data.is_null() {
+        // SAFETY: client_data was created by Box::into_raw in lookup_callback_parts
+        unsafe {
+            let _ = Box::from_raw(client_data as *mut LookupState<F>);
+        }
+    }
+}
+
+/// Box a uid/gid lookup closure for libarchive
+///
+/// Returns the client data, the C callback and the cleanup function libarchive
+/// calls when the lookup is replaced or the archive is freed.
+pub(crate) fn lookup_callback_parts<F>(
+    callback: F,
+) -> (*mut c_void, LookupCallbackFn, LookupCleanupFn)
+where
+    F: FnMut(i64) -> Option<String> + Send + 'static,
+{
+    let ptr = Box::into_raw(Box::new(LookupState {
+        callback,
+        name: None,
+    })) as *mut c_void;
+    (ptr, lookup_callback_impl::<F>, lookup_cleanup_impl::<F>)
+}
+
+/// Type for C callback mapping a user or group name to an id when writing to disk
+pub(crate) type IdLookupCallbackFn = unsafe extern "C" fn(
+    *mut c_void,
+    *const std::os::raw::c_char,
+    libarchive2_sys::la_int64_t,
+) -> libarchive2_sys::la_int64_t;
+
+/// C callback function looking up the id for a name
+///
+/// # Safety
+/// This function is called by libarchive from C. The client_data pointer must be
+/// a valid pointer to an F created by [`id_lookup_callback_parts`], and name
+/// must be null or a valid C string.
+unsafe extern "C" fn id_lookup_callback_impl<F: FnMut(Option<&str>, i64) -> i64>(
+    client_data: *mut c_void,
+    name: *const std::os::raw::c_char,
+    id: libarchive2_sys::la_int64_t,
+) -> libarchive2_sys::la_int64_t {
+    if client_data.is_null() {
+        return id;
+    }
+
+    // SAFETY: client_data was created by id_lookup_callback_parts and remains
+    // valid until libarchive calls the cleanup function
+    let callback = unsafe { &mut *(client_data as *mut F) };
+    let name = if name.is_null() {
+        None
+    } else {
+        // SAFETY: libarchive passes the entry's NUL-terminated name
+        Some(unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy())
+    };
+    callback(name.as_deref(), id)
+}
+
+/// C callback function freeing an id lookup closure
+///
+/// # Safety
+/// client_data must have been created by [`id_lookup_callback_parts`] and not
+/// freed yet.
+unsafe extern "C" fn id_lookup_cleanup_impl<F>(client_data: *mut c_void) {
+    if !client_data.is_null() {
+        // SAFETY: client_data was created by Box::into_raw in id_lookup_callback_parts
+        unsafe {
+            let _ = Box::from_raw(client_data as *mut F);
+        }
+    }
+}
+
+/// Box a user/group name to id lookup closure for libarchive
+///
+/// Returns the client data, the C callback and the cleanup function libarchive
+/// calls when the lookup is replaced or the archive is freed.
+pub(crate) fn id_lookup_callback_parts<F>(
+    callback: F,
+) -> (*mut c_void, IdLookupCallbackFn, LookupCleanupFn)
+where
+    F: FnMut(Option<&str>, i64) -> i64 + Send + 'static,
+{
+    let ptr = Box::into_raw(Box::new(callback)) as *mut c_void;
+    (
+        ptr,
+        id_lookup_callback_impl::<F>,
+        id_lookup_cleanup_impl::<F>,
+    )
+}
+
 /// Builder for writing archives with custom Write implementations
 pub struct CallbackWriter<W: Write> {
     state: Box<Mutex<WriteCallbackState<W>>>,
@@ -221,6 +629,41 @@ impl ProgressTracker {
             .on_progress(self.bytes_processed, self.total_bytes);
     }
 
+    /// Set the absolute position reached
+    ///
+    /// Unlike [`update`](Self::update), which adds to the count, this reports
+    /// a position read from the archive itself, such as
+    /// [`ReadArchive::bytes_read_compressed`](crate::ReadArchive::bytes_read_compressed)
+    /// or [`ReadArchive::header_position`](crate::ReadArchive::header_position).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ProgressCallback, ProgressTracker, ReadArchive};
+    ///
+    /// struct Percent;
+    ///
+    /// impl ProgressCallback for Percent {
+    ///     fn on_progress(&mut self, done: u64, total: u64) {
+    ///         println!("{:.0}%", 100.0 * done as f64 / total as f64);
+    ///     }
+    /// }
+    ///
+    /// let mut tracker = ProgressTracker::new(Percent);
+    /// tracker.set_total(std::fs::metadata("backup.tar.gz")?.len());
+    /// let mut archive = ReadArchive::open("backup.tar.gz")?;
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.skip_data()?;
+    ///     tracker.set_position(archive.bytes_read_compressed());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_position(&mut self, position: u64) {
+        self.bytes_processed = position;
+        self.callback
+            .on_progress(self.bytes_processed, self.total_bytes);
+    }
+
     /// Set total bytes
     pub fn set_total(&mut self, total: u64) {
         self.total_bytes = total;
@@ -0,0 +1,222 @@
+//! Copying every entry of one archive into another
+
+use crate::entry::{Entry, EntryMut};
+use crate::error::{Error, Result};
+use crate::reader::ReadArchive;
+use crate::writer::WriteArchive;
+
+/// Chunk size used to stream entry data from the source to the destination
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type PathMap<'f> = Box<dyn FnMut(&str) -> String + 'f>;
+type EntryFilter<'f> = Box<dyn FnMut(&Entry) -> bool + 'f>;
+
+/// Options for [`convert`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use libarchive2::ConvertOptions;
+///
+/// let options = ConvertOptions::new()
+///     .filter(|entry| !entry.pathname().unwrap_or_default().ends_with(".o"))
+///     .rename(|path| format!("release/{}", path));
+/// ```
+#[derive(Default)]
+pub struct ConvertOptions<'f> {
+    rename: Option<PathMap<'f>>,
+    filter: Option<EntryFilter<'f>>,
+}
+
+impl<'f> ConvertOptions<'f> {
+    /// Create options that copy every entry unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrite the pathname (and hardlink target) of every copied entry
+    pub fn rename<F>(mut self, rename: F) -> Self
+    where
+        F: FnMut(&str) -> String + 'f,
+    {
+        self.rename = Some(Box::new(rename));
+        self
+    }
+
+    /// Copy only the entries for which `filter` returns `true`
+    ///
+    /// The filter sees the entry as read from the source, before
+    /// [`rename`](Self::rename) is applied.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: FnMut(&Entry) -> bool + 'f,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+}
+
+/// Counts collected by [`convert`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConvertStats {
+    /// Entries written to the destination
+    pub entries: u64,
+    /// Entries left out by [`ConvertOptions::filter`]
+    pub skipped: u64,
+    /// Bytes of entry data copied
+    pub bytes: u64,
+}
+
+/// Copy every remaining entry of `src` into `dst`
+///
+/// Each header is copied in full, so the file type, permissions, times,
+/// ownership, symlink and hardlink targets, extended attributes and ACLs are
+/// kept wherever the destination format can store them. Entry data is streamed
+/// in chunks, except for entries whose size the source does not record in the
+/// header (such as those of a streamed ZIP archive), which are read into memory
+/// first so that the destination header can carry the size.
+///
+/// `dst` is not finished, so more entries can be added afterwards. If copying
+/// an entry fails, the error message names its pathname.
+///
+/// # Examples
+///
+/// ```no_run
+/// use libarchive2::{ArchiveFormat, CompressionFormat, ConvertOptions, ReadArchive, WriteArchive};
+///
+/// let mut src = ReadArchive::open("input.zip")?;
+/// let mut dst = WriteArchive::new()
+///     .format(ArchiveFormat::TarPax)
+///     .compression(CompressionFormat::Zstd)
+///     .open_file("output.tar.zst")?;
+/// let stats = libarchive2::convert(&mut src, &mut dst, ConvertOptions::new())?;
+/// dst.finish()?;
+/// println!("{} entries, {} bytes", stats.entries, stats.bytes);
+/// # Ok::<(), libarchive2::Error>(())
+/// ```
+pub fn convert(
+    src: &mut ReadArchive<'_>,
+    dst: &mut WriteArchive<'_>,
+    mut options: ConvertOptions<'_>,
+) -> Result<ConvertStats> {
+    let mut stats = ConvertStats::default();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    while let Some(entry) = src.next_entry()? {
+        if let Some(filter) = options.filter.as_mut()
+            && !filter(&entry)
+        {
+            stats.skipped += 1;
+            continue;
+        }
+        let pathname = entry.pathname().unwrap_or_default();
+
+        // SAFETY: entry.entry is the reader's current header; the clone is
+        // owned by the EntryMut and freed when it is dropped
+        let mut header = unsafe {
+            let cloned = libarchive2_sys::archive_entry_clone(entry.entry);
+            if cloned.is_null() {
+                return Err(Error::NullPointer);
+            }
+            EntryMut {
+                entry: cloned,
+                owned: true,
+            }
+        };
+        // SAFETY: as above
+        let size_known = unsafe { libarchive2_sys::archive_entry_size_is_set(entry.entry) != 0 };
+        let is_file =
+            entry.file_type() == crate::entry::FileType::RegularFile && entry.hardlink().is_none();
+
+        copy_entry(
+            src,
+            dst,
+            &mut header,
+            &mut options,
+            &mut buf,
+            size_known || !is_file,
+        )
+        .map(|bytes| stats.bytes += bytes)
+        .map_err(|e| with_pathname(e, &pathname))?;
+        stats.entries += 1;
+    }
+    Ok(stats)
+}
+
+/// Write `header` to `dst` and copy the current entry's data after it
+fn copy_entry(
+    src: &mut ReadArchive<'_>,
+    dst: &mut WriteArchive<'_>,
+    header: &mut EntryMut,
+    options: &mut ConvertOptions<'_>,
+    buf: &mut [u8],
+    stream: bool,
+) -> Result<u64> {
+    if let Some(rename) = options.rename.as_mut() {
+        // SAFETY: header.entry is owned by header
+        unsafe { rename_entry(header.entry, rename)? };
+    }
+
+    if !stream {
+        let mut data = Vec::new();
+        loop {
+            let n = src.read_data(buf)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+        header.set_size(data.len() as i64);
+        dst.write_header(header)?;
+        dst.write_data_all(&data)?;
+        return Ok(data.len() as u64);
+    }
+
+    dst.write_header(header)?;
+    let mut copied = 0;
+    loop {
+        let n = src.read_data(buf)?;
+        if n == 0 {
+            return Ok(copied);
+        }
+        dst.write_data_all(&buf[..n])?;
+        copied += n as u64;
+    }
+}
+
+/// Apply `rename` to the pathname and hardlink target of `entry`
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+unsafe fn rename_entry(
+    entry: *mut libarchive2_sys::archive_entry,
+    rename: &mut PathMap<'_>,
+) -> Result<()> {
+    unsafe { crate::path::map_entry_paths(entry, |path| Some(rename(path)))? };
+    Ok(())
+}
+
+/// Prefix the message of `error` with the pathname of the entry being copied
+pub(crate) fn with_pathname(error: Error, pathname: &str) -> Error {
+    match error {
+        Error::Archive {
+            status,
+            code,
+            message,
+            errno,
+        } => Error::Archive {
+            status,
+            code,
+            message: format!("{}: {}", pathname, message),
+            errno,
+        },
+        Error::InvalidArgument(message) => {
+            Error::InvalidArgument(format!("{}: {}", pathname, message))
+        }
+        Error::Io(e) => Error::Io(std::io::Error::new(
+            e.kind(),
+            format!("{}: {}", pathname, e),
+        )),
+        other => other,
+    }
+}
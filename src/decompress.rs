@@ -0,0 +1,104 @@
+//! Single-stream decompression built on libarchive's raw format
+//!
+//! libarchive's filters can decode gzip, bzip2, xz, zstd and the other
+//! [`CompressionFormat`](crate::CompressionFormat)s on their own, and its raw
+//! format presents whatever the filters produce as a single pseudo-entry. This
+//! makes libarchive usable as a universal decompressor for files such as
+//! `notes.txt.zst` that are compressed but are not archives.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io::Read;
+//!
+//! let text = libarchive2::decompress_file("notes.txt.xz")?;
+//!
+//! let mut reader = libarchive2::decompress_reader(std::io::stdin())?;
+//! let mut contents = Vec::new();
+//! reader.read_to_end(&mut contents)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::format::{ArchiveFormat, ReadFormat};
+use crate::reader::ReadArchive;
+use std::path::Path;
+
+/// Decompress a single compressed file into memory
+///
+/// The compression is detected from the data, and an uncompressed file is
+/// returned as is. An empty file gives an empty `Vec`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidArgument`] if the file is an archive (such as a
+/// `.tar.gz`) rather than a single stream; use [`ReadArchive`] to read its
+/// entries instead.
+pub fn decompress_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    let mut archive = raw_reader()?.open_file(path)?;
+    if !open_stream(&mut archive)? {
+        return Ok(Vec::new());
+    }
+    archive.read_data_to_vec()
+}
+
+/// Decompress a single compressed stream as it is read
+///
+/// The returned reader pulls decompressed data from `reader` on demand, so
+/// arbitrarily large streams can be processed without holding them in memory.
+/// The first block of input is read immediately to detect the compression.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidArgument`] if the input is an archive rather than
+/// a single stream, as for [`decompress_file`].
+pub fn decompress_reader<R: std::io::Read + Send + 'static>(
+    reader: R,
+) -> Result<impl std::io::Read + Send> {
+    let mut archive = raw_reader()?.open_stream(reader)?;
+    let done = !open_stream(&mut archive)?;
+    Ok(Decompressor { archive, done })
+}
+
+/// Reader returned by [`decompress_reader`]
+struct Decompressor {
+    archive: ReadArchive<'static>,
+    /// Set for empty input, which has no pseudo-entry to read from
+    done: bool,
+}
+
+impl std::io::Read for Decompressor {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        self.archive.read(buf)
+    }
+}
+
+/// A reader that accepts the raw format as a fallback for data no archive
+/// format recognizes
+///
+/// The other formats stay enabled so archives are detected and rejected by
+/// [`open_stream`] instead of being returned as one opaque blob.
+fn raw_reader() -> Result<ReadArchive<'static>> {
+    let mut archive = ReadArchive::new()?;
+    archive.support_filter_all()?;
+    archive.support_format_all()?;
+    archive.support_format(ReadFormat::Format(ArchiveFormat::Raw))?;
+    Ok(archive)
+}
+
+/// Move to the stream's pseudo-entry, returning `false` for empty input
+fn open_stream(archive: &mut ReadArchive<'static>) -> Result<bool> {
+    if archive.next_entry()?.is_none() {
+        return Ok(false);
+    }
+    if archive.format_code() != libarchive2_sys::ARCHIVE_FORMAT_RAW as i32 {
+        return Err(Error::InvalidArgument(format!(
+            "Input is a {} archive, not a single compressed stream; use ReadArchive to read its entries",
+            archive.format_name().unwrap_or_default()
+        )));
+    }
+    Ok(true)
+}
@@ -0,0 +1,169 @@
+//! Cheap identification of archive and compression formats
+//!
+//! [`detect_format`] answers "what is this file?" without reading the archive:
+//! it lets libarchive's bidders look at the start of the data and reads the
+//! first header only, which is enough to name the format, the compression
+//! filters around it and, for formats that record it there, whether entries
+//! are encrypted.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libarchive2::{CompressionFormat, detect_format_file};
+//!
+//! let detected = detect_format_file("download.bin")?;
+//! if detected.format.is_some_and(|format| format.extension() == "tar")
+//!     && detected.filters == [CompressionFormat::Gzip]
+//! {
+//!     println!("a .tar.gz in disguise");
+//! }
+//! # Ok::<(), libarchive2::Error>(())
+//! ```
+
+use crate::error::Result;
+use crate::format::{ArchiveFormat, CompressionFormat};
+use crate::reader::{EncryptionStatus, ReadArchive};
+use std::io::Read;
+use std::path::Path;
+
+/// Most input looked at when detecting a format
+///
+/// Every format's signature and first header fit well within this, even
+/// behind a compression filter.
+const DETECT_PREFIX: usize = 1024 * 1024;
+
+/// Filters [`detect_format`] may register, if libarchive decodes them itself
+const BUILTIN_FILTERS: [CompressionFormat; 9] = [
+    CompressionFormat::Gzip,
+    CompressionFormat::Bzip2,
+    CompressionFormat::Xz,
+    CompressionFormat::Lzma,
+    CompressionFormat::Lzip,
+    CompressionFormat::Zstd,
+    CompressionFormat::Lz4,
+    CompressionFormat::Compress,
+    CompressionFormat::UuEncode,
+];
+
+/// What [`detect_format`] found out about some data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedFormat {
+    /// The archive format, or `None` if no format recognizes the data (such as
+    /// a compressed file that is not an archive, or random bytes)
+    pub format: Option<ArchiveFormat>,
+    /// Filters around the archive, from the one closest to the archive format
+    /// to the one reading the input, e.g. `[Gzip]` for a `.tar.gz`
+    ///
+    /// Uncompressed data gives an empty list. Filters with no
+    /// [`CompressionFormat`], such as the RPM wrapper, are left out.
+    pub filters: Vec<CompressionFormat>,
+    /// Whether the first entry, or the archive as a whole, is encrypted
+    ///
+    /// [`EncryptionStatus::Unknown`] when no format was recognized or the format
+    /// cannot tell from the first header alone.
+    pub encrypted: EncryptionStatus,
+}
+
+/// Identify the archive format and compression of `data`
+///
+/// Only the first megabyte of `data` is looked at, so this is cheap even for
+/// a large archive in memory. Formats that keep their index at the end, such
+/// as 7z, are still recognized from their signature, but their encryption
+/// status may be [`EncryptionStatus::Unknown`]. Unrecognized or corrupt data
+/// is not an error; it gives a [`DetectedFormat`] with no format.
+///
+/// Only filters libarchive decodes in-process are tried, so detection never
+/// starts an external program such as `lrzip` or `lzop`; data compressed with
+/// those is reported as unrecognized.
+///
+/// # Errors
+///
+/// Returns an error only if libarchive cannot allocate a reader.
+///
+/// # Examples
+///
+/// ```no_run
+/// use libarchive2::{CompressionFormat, detect_format};
+///
+/// let data = std::fs::read("archive.tar.xz")?;
+/// let detected = detect_format(&data)?;
+/// assert_eq!(detected.filters, [CompressionFormat::Xz]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn detect_format(data: &[u8]) -> Result<DetectedFormat> {
+    let prefix = &data[..data.len().min(DETECT_PREFIX)];
+    let mut reader = ReadArchive::new()?;
+    // Unlike support_filter_all, never register a filter that runs an external
+    // program: the data may be untrusted
+    for filter in BUILTIN_FILTERS {
+        reader.support_filter_builtin(filter)?;
+    }
+    reader.support_format_all()?;
+    // SAFETY: reader.archive() is valid and not yet opened
+    unsafe {
+        // The RPM wrapper is always decoded in-process
+        libarchive2_sys::archive_read_support_filter_rpm(reader.archive());
+        // Raw accepts whatever no other format does, so compressed data that
+        // is not an archive still opens and its filters can be reported
+        libarchive2_sys::archive_read_support_format_raw(reader.archive());
+    }
+    // Opening fails when a filter rejects its own input, e.g. a truncated
+    // gzip header; that is data nothing recognizes
+    let Ok(mut reader) = reader.open_buffer(prefix) else {
+        return Ok(DetectedFormat {
+            format: None,
+            filters: Vec::new(),
+            encrypted: EncryptionStatus::Unknown,
+        });
+    };
+
+    // A corrupt first header still leaves the format libarchive chose set
+    let first_encrypted = match reader.next_entry() {
+        Ok(Some(entry)) => entry.is_encrypted(),
+        Ok(None) | Err(_) => false,
+    };
+
+    let archive = reader.archive();
+    // SAFETY: archive is valid for the lifetime of reader
+    let (format_code, filter_codes) = unsafe {
+        let count = libarchive2_sys::archive_filter_count(archive);
+        let filters: Vec<i32> = (0..count)
+            .map(|i| libarchive2_sys::archive_filter_code(archive, i))
+            .filter(|&code| code != libarchive2_sys::ARCHIVE_FILTER_NONE as i32)
+            .collect();
+        (libarchive2_sys::archive_format(archive), filters)
+    };
+
+    let format =
+        ArchiveFormat::from_code(format_code).filter(|&format| format != ArchiveFormat::Raw);
+    let encrypted = if format.is_none() {
+        EncryptionStatus::Unknown
+    } else if first_encrypted {
+        EncryptionStatus::Yes
+    } else {
+        reader.has_encrypted_entries()
+    };
+    Ok(DetectedFormat {
+        format,
+        filters: filter_codes
+            .into_iter()
+            .filter_map(CompressionFormat::from_code)
+            .collect(),
+        encrypted,
+    })
+}
+
+/// Identify the archive format and compression of the file at `path`
+///
+/// Reads at most the first megabyte of the file, then works as
+/// [`detect_format`].
+///
+/// # Errors
+///
+/// Returns [`Error::Io`](crate::Error::Io) if the file cannot be read.
+pub fn detect_format_file<P: AsRef<Path>>(path: P) -> Result<DetectedFormat> {
+    let file = std::fs::File::open(path)?;
+    let mut prefix = Vec::new();
+    file.take(DETECT_PREFIX as u64).read_to_end(&mut prefix)?;
+    detect_format(&prefix)
+}
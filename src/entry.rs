@@ -1,5 +1,7 @@
 //! Archive entry types and operations
 
+use crate::acl_xattr::{AclType, EntryAclExt, EntryMutAclExt, Xattr};
+use crate::digest::DigestAlgo;
 use crate::error::{Error, Result};
 use std::ffi::{CStr, CString};
 use std::path::Path;
@@ -12,6 +14,7 @@ static NEXT_INODE: AtomicU64 = AtomicU64::new(1);
 
 /// File type of an archive entry
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FileType {
     /// Regular file
     RegularFile,
@@ -31,8 +34,43 @@ pub enum FileType {
     Unknown,
 }
 
+/// What a symbolic link points to
+///
+/// Windows needs to know this when creating a symlink; Unix ignores it.
+/// libarchive records it when reading from disk on Windows. Of the formats
+/// libarchive writes, only pax preserves it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SymlinkType {
+    /// Not recorded
+    #[default]
+    Undefined,
+    /// The link points to a file
+    File,
+    /// The link points to a directory
+    Directory,
+}
+
+impl SymlinkType {
+    fn from_raw(value: i32) -> Self {
+        match value as u32 {
+            libarchive2_sys::AE_SYMLINK_TYPE_FILE => SymlinkType::File,
+            libarchive2_sys::AE_SYMLINK_TYPE_DIRECTORY => SymlinkType::Directory,
+            _ => SymlinkType::Undefined,
+        }
+    }
+
+    fn to_raw(self) -> i32 {
+        (match self {
+            SymlinkType::Undefined => libarchive2_sys::AE_SYMLINK_TYPE_UNDEFINED,
+            SymlinkType::File => libarchive2_sys::AE_SYMLINK_TYPE_FILE,
+            SymlinkType::Directory => libarchive2_sys::AE_SYMLINK_TYPE_DIRECTORY,
+        }) as i32
+    }
+}
+
 impl FileType {
-    fn from_mode(mode: u32) -> Self {
+    pub(crate) fn from_mode(mode: u32) -> Self {
         const S_IFMT: u32 = 0o170000;
         const S_IFREG: u32 = 0o100000;
         const S_IFDIR: u32 = 0o040000;
@@ -83,6 +121,8 @@ impl FileType {
 /// the mutable borrow of the archive when calling next_entry).
 pub struct Entry<'a> {
     pub(crate) entry: *mut libarchive2_sys::archive_entry,
+    /// Archive the entry was read from (null for entries not produced by a reader)
+    pub(crate) archive: *mut libarchive2_sys::archive,
     pub(crate) _marker: std::marker::PhantomData<&'a ()>,
 }
 
@@ -124,25 +164,60 @@ impl<'a> Entry<'a> {
     }
 
     /// Get the file size in bytes
+    ///
+    /// Returns 0 for entries whose header does not record a size, such as
+    /// directories in some cpio archives; use [`size_opt`](Self::size_opt) to
+    /// tell the two apart.
+    #[deprecated(note = "use `size_opt`, which tells an unset size from a size of 0")]
     pub fn size(&self) -> i64 {
         unsafe { libarchive2_sys::archive_entry_size(self.entry) }
     }
 
+    /// Get the file size in bytes, or `None` if it is not set
+    ///
+    /// Copy the size only when this returns `Some`: formats such as zip and 7z
+    /// write an entry with an explicit size of 0 differently from one whose
+    /// size is unknown.
+    pub fn size_opt(&self) -> Option<i64> {
+        unsafe {
+            if libarchive2_sys::archive_entry_size_is_set(self.entry) != 0 {
+                Some(libarchive2_sys::archive_entry_size(self.entry))
+            } else {
+                None
+            }
+        }
+    }
+
     /// Get the file permissions (mode)
+    ///
+    /// Only the permission bits are returned; see [`mode_raw`](Self::mode_raw)
+    /// for the value including the file type.
     pub fn mode(&self) -> u32 {
         unsafe { libarchive2_sys::archive_entry_perm(self.entry) as u32 }
     }
 
+    /// Get the full mode: file type bits and permission bits together
+    ///
+    /// For a regular file with permissions 0o644 this is 0o100644, where
+    /// [`mode`](Self::mode) returns 0o644. Pass it to
+    /// [`EntryMut::set_mode`] to copy an entry's mode verbatim.
+    pub fn mode_raw(&self) -> u32 {
+        unsafe { libarchive2_sys::archive_entry_mode(self.entry) as u32 }
+    }
+
     /// Get the modification time
+    ///
+    /// Returns `None` if the header does not record one. Times before 1970
+    /// are returned as they are.
     pub fn mtime(&self) -> Option<SystemTime> {
         unsafe {
-            let sec = libarchive2_sys::archive_entry_mtime(self.entry);
-            let nsec = libarchive2_sys::archive_entry_mtime_nsec(self.entry);
-            if sec >= 0 {
-                Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(sec as u64, nsec as u32))
-            } else {
-                None
+            if libarchive2_sys::archive_entry_mtime_is_set(self.entry) == 0 {
+                return None;
             }
+            entry_time(
+                libarchive2_sys::archive_entry_mtime(self.entry) as i64,
+                libarchive2_sys::archive_entry_mtime_nsec(self.entry) as i64,
+            )
         }
     }
 
@@ -240,6 +315,30 @@ impl<'a> Entry<'a> {
         }
     }
 
+    /// Get whether the symlink points to a file or a directory
+    ///
+    /// Returns [`SymlinkType::Undefined`] for entries that are not symlinks
+    /// or whose archive did not record the type.
+    pub fn symlink_type(&self) -> SymlinkType {
+        unsafe { SymlinkType::from_raw(libarchive2_sys::archive_entry_symlink_type(self.entry)) }
+    }
+
+    /// Get the path the entry was read from on disk
+    ///
+    /// [`ReadDisk`](crate::ReadDisk) sets this to the path it opened, which can
+    /// differ from [`pathname`](Self::pathname) when the stored name is
+    /// rewritten. Entries read from an archive have no source path.
+    pub fn sourcepath(&self) -> Option<String> {
+        unsafe {
+            let ptr = libarchive2_sys::archive_entry_sourcepath(self.entry);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        }
+    }
+
     /// Get the hardlink target
     ///
     /// Returns an owned String to ensure safety, as the underlying C string
@@ -264,16 +363,94 @@ impl<'a> Entry<'a> {
         }
     }
 
+    /// Check if the entry is a hard link to an earlier entry
+    ///
+    /// Tar hard links carry no file type of their own ([`file_type`](Self::file_type)
+    /// returns [`FileType::Unknown`]), while cpio repeats the target's type, so
+    /// use this rather than the file type to recognise them.
+    pub fn is_hardlink(&self) -> bool {
+        self.hardlink().is_some_and(|target| !target.is_empty())
+    }
+
+    /// Get the pathname exactly as stored, without any character set conversion
+    ///
+    /// Unlike [`pathname`](Self::pathname), this never mangles names that are not
+    /// valid UTF-8 (e.g. Latin-1 or legacy codepage names).
+    ///
+    /// Names read from an archive are returned as stored. A name that was only
+    /// set as UTF-8, such as with [`EntryMut::set_pathname`], is converted to
+    /// the current locale's character set, or returned as UTF-8 if it cannot
+    /// be represented there. `None` always means the entry has no pathname.
+    /// The same applies to the other `*_bytes` accessors.
+    pub fn pathname_bytes(&self) -> Option<Vec<u8>> {
+        // SAFETY: entry is a valid pointer for the lifetime of self
+        unsafe {
+            raw_bytes(
+                libarchive2_sys::archive_entry_pathname(self.entry),
+                libarchive2_sys::archive_entry_pathname_utf8,
+                self.entry,
+            )
+        }
+    }
+
+    /// Get the user name exactly as stored
+    pub fn uname_bytes(&self) -> Option<Vec<u8>> {
+        // SAFETY: entry is a valid pointer for the lifetime of self
+        unsafe {
+            raw_bytes(
+                libarchive2_sys::archive_entry_uname(self.entry),
+                libarchive2_sys::archive_entry_uname_utf8,
+                self.entry,
+            )
+        }
+    }
+
+    /// Get the group name exactly as stored
+    pub fn gname_bytes(&self) -> Option<Vec<u8>> {
+        // SAFETY: entry is a valid pointer for the lifetime of self
+        unsafe {
+            raw_bytes(
+                libarchive2_sys::archive_entry_gname(self.entry),
+                libarchive2_sys::archive_entry_gname_utf8,
+                self.entry,
+            )
+        }
+    }
+
+    /// Get the symlink target exactly as stored
+    pub fn symlink_bytes(&self) -> Option<Vec<u8>> {
+        // SAFETY: entry is a valid pointer for the lifetime of self
+        unsafe {
+            raw_bytes(
+                libarchive2_sys::archive_entry_symlink(self.entry),
+                libarchive2_sys::archive_entry_symlink_utf8,
+                self.entry,
+            )
+        }
+    }
+
+    /// Get the hardlink target exactly as stored
+    pub fn hardlink_bytes(&self) -> Option<Vec<u8>> {
+        // SAFETY: entry is a valid pointer for the lifetime of self
+        unsafe {
+            raw_bytes(
+                libarchive2_sys::archive_entry_hardlink(self.entry),
+                libarchive2_sys::archive_entry_hardlink_utf8,
+                self.entry,
+            )
+        }
+    }
+
     /// Get the access time
     pub fn atime(&self) -> Option<SystemTime> {
         unsafe {
-            let sec = libarchive2_sys::archive_entry_atime(self.entry);
-            let nsec = libarchive2_sys::archive_entry_atime_nsec(self.entry);
-            if sec >= 0 {
-                Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(sec as u64, nsec as u32))
-            } else {
-                None
+            if libarchive2_sys::archive_entry_atime_is_set(self.entry) == 0 {
+                return None;
             }
+            entry_time(
+                libarchive2_sys::archive_entry_atime(self.entry) as i64,
+                libarchive2_sys::archive_entry_atime_nsec(self.entry) as i64,
+            )
         }
     }
 
@@ -282,26 +459,26 @@ impl<'a> Entry<'a> {
     /// Note: Not all archive formats and filesystems support birth time.
     pub fn birthtime(&self) -> Option<SystemTime> {
         unsafe {
-            let sec = libarchive2_sys::archive_entry_birthtime(self.entry);
-            let nsec = libarchive2_sys::archive_entry_birthtime_nsec(self.entry);
-            if sec >= 0 {
-                Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(sec as u64, nsec as u32))
-            } else {
-                None
+            if libarchive2_sys::archive_entry_birthtime_is_set(self.entry) == 0 {
+                return None;
             }
+            entry_time(
+                libarchive2_sys::archive_entry_birthtime(self.entry) as i64,
+                libarchive2_sys::archive_entry_birthtime_nsec(self.entry) as i64,
+            )
         }
     }
 
     /// Get the status change time
     pub fn ctime(&self) -> Option<SystemTime> {
         unsafe {
-            let sec = libarchive2_sys::archive_entry_ctime(self.entry);
-            let nsec = libarchive2_sys::archive_entry_ctime_nsec(self.entry);
-            if sec >= 0 {
-                Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(sec as u64, nsec as u32))
-            } else {
-                None
+            if libarchive2_sys::archive_entry_ctime_is_set(self.entry) == 0 {
+                return None;
             }
+            entry_time(
+                libarchive2_sys::archive_entry_ctime(self.entry) as i64,
+                libarchive2_sys::archive_entry_ctime_nsec(self.entry) as i64,
+            )
         }
     }
 
@@ -397,6 +574,29 @@ impl<'a> Entry<'a> {
         }
     }
 
+    /// Get a digest recorded in the entry's header
+    ///
+    /// Formats such as mtree store checksums as metadata, and libarchive
+    /// fills them in when reading. This does not hash the entry data; use
+    /// [`ReadArchive::read_data_hash`](crate::ReadArchive::read_data_hash) for that.
+    /// Returns `None` if the archive did not record this digest.
+    pub fn digest(&self, algo: DigestAlgo) -> Option<Vec<u8>> {
+        let (code, len) = algo.code_and_len();
+        unsafe {
+            let ptr = libarchive2_sys::archive_entry_digest(self.entry, code as i32);
+            if ptr.is_null() {
+                return None;
+            }
+            let bytes = std::slice::from_raw_parts(ptr, len);
+            // libarchive zero-fills digests that were never set
+            if bytes.iter().all(|&b| b == 0) {
+                None
+            } else {
+                Some(bytes.to_vec())
+            }
+        }
+    }
+
     /// Check if entry is encrypted
     pub fn is_encrypted(&self) -> bool {
         unsafe { libarchive2_sys::archive_entry_is_encrypted(self.entry) != 0 }
@@ -411,6 +611,371 @@ impl<'a> Entry<'a> {
     pub fn is_metadata_encrypted(&self) -> bool {
         unsafe { libarchive2_sys::archive_entry_is_metadata_encrypted(self.entry) != 0 }
     }
+
+    /// Copy this entry into a writable [`EntryMut`] that does not borrow the archive
+    ///
+    /// Every field libarchive stores is copied, including ones this crate has
+    /// no accessor for, such as device numbers, file flags, ACLs, extended
+    /// attributes and sparse maps. The inode number is kept too, so writers
+    /// that detect hardlinks by inode see the copy as the original. This makes
+    /// it the way to pass entries from one archive, or from
+    /// [`ReadDisk`](crate::ReadDisk), to a writer unchanged or with a few edits.
+    ///
+    /// libarchive converts entry strings between character sets on demand,
+    /// through the archive the entry was read from. The copy's strings are
+    /// converted to every form up front so it can be read and written after
+    /// the source is dropped. Strings later set on the copy through a
+    /// `*_bytes` setter still convert through the source, so set those only
+    /// while it is open.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NullPointer`] if libarchive cannot allocate the copy.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, ReadArchive, WriteArchive};
+    ///
+    /// let mut input = ReadArchive::open("input.tar")?;
+    /// let mut output = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("filtered.tar")?;
+    /// while let Some(entry) = input.next_entry()? {
+    ///     if entry.pathname().is_some_and(|name| name.ends_with(".log")) {
+    ///         continue;
+    ///     }
+    ///     let copy = entry.clone_owned()?;
+    ///     output.write_header(&copy)?;
+    ///     output.write_data_all(&input.read_data_to_vec()?)?;
+    /// }
+    /// output.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn clone_owned(&self) -> Result<EntryMut> {
+        unsafe {
+            let entry = libarchive2_sys::archive_entry_clone(self.entry);
+            if entry.is_null() {
+                return Err(Error::NullPointer);
+            }
+            prepare_strings(entry);
+            Ok(EntryMut { entry, owned: true })
+        }
+    }
+
+    /// Copy all header fields into an [`OwnedEntry`] that does not borrow the archive
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar")?;
+    /// let mut entries = Vec::new();
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     entries.push(entry.to_owned_entry());
+    /// }
+    /// drop(archive);
+    /// println!("{} entries", entries.len());
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn to_owned_entry(&self) -> OwnedEntry {
+        OwnedEntry::from(self)
+    }
+
+    /// Get the name of the compression method the archive used for this entry
+    ///
+    /// This is display-only metadata, reported by the readers that track it per
+    /// entry: LHA (e.g. `"-lh5-"`), CAB (e.g. `"MSZIP"`, `"LZX"`) and ZIP
+    /// (e.g. `"deflation"`). Returns `None` for other formats and for entries that
+    /// were not produced by [`ReadArchive::next_entry`](crate::ReadArchive::next_entry).
+    pub fn compression_method_name(&self) -> Option<String> {
+        // Readers that track a method rewrite the format name for every header,
+        // e.g. "lha -lh5-", "CAB 1.3 (MSZIP)" or "ZIP 2.0 (deflation)"
+        let format_name = self.reader_format_name()?;
+        let method = if let Some(method) = format_name.strip_prefix("lha ") {
+            method
+        } else if format_name.starts_with("CAB ") || format_name.starts_with("ZIP ") {
+            format_name
+                .split_once('(')
+                .and_then(|(_, rest)| rest.strip_suffix(')'))?
+        } else {
+            return None;
+        };
+
+        let method = method.trim();
+        if method.is_empty() {
+            None
+        } else {
+            Some(method.to_string())
+        }
+    }
+
+    /// Get ZIP-specific details for this entry
+    ///
+    /// Returns `None` unless the entry was read from a ZIP archive by
+    /// [`ReadArchive::next_entry`](crate::ReadArchive::next_entry).
+    ///
+    /// libarchive only reports the compression method and the "version needed to
+    /// extract" of each entry. The general purpose flags are not exposed (use
+    /// [`is_encrypted`](Self::is_encrypted) for the encryption bit), and Unix modes
+    /// stored in the external attributes are already applied to
+    /// [`mode`](Self::mode).
+    pub fn zip_info(&self) -> Option<ZipEntryInfo> {
+        // The zip reader reports "ZIP <major>.<minor> (<method name>)"
+        let format_name = self.reader_format_name()?;
+        let rest = format_name.strip_prefix("ZIP ")?;
+        let (version, method_name) = rest.split_once(" (")?;
+        let method_name = method_name.strip_suffix(')')?;
+        let (major, minor) = version.split_once('.')?;
+        let version_needed = major.parse::<u16>().ok()? * 10 + minor.parse::<u16>().ok()?;
+
+        Some(ZipEntryInfo {
+            method: zip_method_code(method_name),
+            method_name: method_name.to_string(),
+            version_needed,
+        })
+    }
+
+    /// Get the format name the reader reported for this entry's header
+    fn reader_format_name(&self) -> Option<String> {
+        if self.archive.is_null() {
+            return None;
+        }
+        unsafe {
+            let ptr = libarchive2_sys::archive_format_name(self.archive);
+            if ptr.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Owned copy of all header fields of an [`Entry`]
+///
+/// Unlike [`Entry`] this does not borrow the archive, so it can be collected,
+/// kept after the reader is dropped or sent to another thread. Use
+/// [`EntryMut::from_owned`] to turn it back into a writable entry.
+///
+/// Also produced by [`ReadArchive::entries`](crate::ReadArchive::entries).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OwnedEntry {
+    /// Pathname exactly as stored (see [`Entry::pathname_bytes`])
+    pub pathname_bytes: Option<Vec<u8>>,
+    /// Pathname as a string (see [`Entry::pathname`])
+    pub pathname: Option<String>,
+    /// File type
+    pub file_type: FileType,
+    /// Size in bytes, or `None` if the header does not record one (see
+    /// [`Entry::size_opt`])
+    pub size: Option<i64>,
+    /// Permission bits, as returned by [`Entry::mode`]
+    pub mode: u32,
+    /// User ID
+    pub uid: Option<u64>,
+    /// Group ID
+    pub gid: Option<u64>,
+    /// User name
+    pub uname: Option<String>,
+    /// Group name
+    pub gname: Option<String>,
+    /// Modification time
+    pub mtime: Option<SystemTime>,
+    /// Access time
+    pub atime: Option<SystemTime>,
+    /// Status change time
+    pub ctime: Option<SystemTime>,
+    /// Creation time
+    pub birthtime: Option<SystemTime>,
+    /// Symlink target
+    pub symlink: Option<String>,
+    /// Hardlink target
+    pub hardlink: Option<String>,
+    /// Extended attributes
+    pub xattrs: Vec<Xattr>,
+    /// Access ACL in libarchive's text form, if the entry has one
+    pub acl_text: Option<String>,
+    /// Whether the entry data is encrypted
+    pub is_data_encrypted: bool,
+    /// Whether the entry metadata is encrypted
+    pub is_metadata_encrypted: bool,
+}
+
+impl OwnedEntry {
+    /// Check if entry is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.is_data_encrypted || self.is_metadata_encrypted
+    }
+}
+
+impl From<&Entry<'_>> for OwnedEntry {
+    fn from(entry: &Entry<'_>) -> Self {
+        OwnedEntry {
+            pathname_bytes: entry.pathname_bytes(),
+            pathname: entry.pathname(),
+            file_type: entry.file_type(),
+            size: entry.size_opt(),
+            mode: entry.mode(),
+            uid: entry.uid(),
+            gid: entry.gid(),
+            uname: entry.uname(),
+            gname: entry.gname(),
+            mtime: entry.mtime(),
+            atime: entry.atime(),
+            ctime: entry.ctime(),
+            birthtime: entry.birthtime(),
+            symlink: entry.symlink(),
+            hardlink: entry.hardlink(),
+            xattrs: entry.xattrs(),
+            // acl_text describes the permission bits when there is no ACL
+            acl_text: if entry.has_acl() {
+                entry.acl_text()
+            } else {
+                None
+            },
+            is_data_encrypted: entry.is_data_encrypted(),
+            is_metadata_encrypted: entry.is_metadata_encrypted(),
+        }
+    }
+}
+
+/// Former name of [`OwnedEntry`], the snapshot yielded by
+/// [`ReadArchive::entries`](crate::ReadArchive::entries)
+#[deprecated(note = "use `OwnedEntry`")]
+pub type EntryMetadata = OwnedEntry;
+
+/// ZIP-specific details of an entry read from a ZIP archive
+///
+/// Obtained from [`Entry::zip_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntryInfo {
+    /// Compression method number from the ZIP header (0 = stored, 8 = deflated, ...)
+    ///
+    /// `None` if libarchive reported a method it has no name for.
+    pub method: Option<u16>,
+    /// libarchive's name for the method (e.g. `"uncompressed"`, `"deflation"`)
+    pub method_name: String,
+    /// Version needed to extract, as major * 10 + minor (e.g. 20 for 2.0)
+    pub version_needed: u16,
+}
+
+impl ZipEntryInfo {
+    /// Check whether the entry data is stored without compression
+    pub fn is_stored(&self) -> bool {
+        self.method == Some(0)
+    }
+}
+
+/// Copy a C string into a byte vector (None for a null pointer)
+///
+/// # Safety
+/// `ptr` must be null or point to a valid NUL-terminated string.
+unsafe fn c_bytes(ptr: *const std::os::raw::c_char) -> Option<Vec<u8>> {
+    if ptr.is_null() {
+        None
+    } else {
+        // SAFETY: Caller guarantees ptr is a valid C string
+        Some(unsafe { CStr::from_ptr(ptr) }.to_bytes().to_vec())
+    }
+}
+
+/// Copy an entry string as stored in the current locale, falling back to its
+/// UTF-8 form
+///
+/// libarchive returns null from the locale accessor both when the string is
+/// unset and when it cannot be converted to the locale's character set; the
+/// UTF-8 accessor tells the two apart.
+///
+/// # Safety
+/// `mbs` must be null or a valid C string, and `entry` a valid entry pointer.
+unsafe fn raw_bytes(
+    mbs: *const std::os::raw::c_char,
+    utf8: unsafe extern "C" fn(*mut libarchive2_sys::archive_entry) -> *const std::os::raw::c_char,
+    entry: *mut libarchive2_sys::archive_entry,
+) -> Option<Vec<u8>> {
+    // SAFETY: Caller guarantees mbs and entry are valid
+    unsafe {
+        if mbs.is_null() {
+            c_bytes(utf8(entry))
+        } else {
+            c_bytes(mbs)
+        }
+    }
+}
+
+/// Convert the entry's strings to every form they may be asked for in
+///
+/// libarchive converts and caches entry strings on first use, looking up the
+/// conversion in the archive the entry was read from. Once every form is
+/// cached, reading or writing the entry no longer touches that archive.
+/// Strings that cannot be converted stay unset in that form.
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+pub(crate) unsafe fn prepare_strings(entry: *mut libarchive2_sys::archive_entry) {
+    // SAFETY: Caller guarantees entry is valid
+    unsafe {
+        libarchive2_sys::archive_entry_pathname(entry);
+        libarchive2_sys::archive_entry_pathname_utf8(entry);
+        libarchive2_sys::archive_entry_pathname_w(entry);
+        libarchive2_sys::archive_entry_hardlink(entry);
+        libarchive2_sys::archive_entry_hardlink_utf8(entry);
+        libarchive2_sys::archive_entry_hardlink_w(entry);
+        libarchive2_sys::archive_entry_symlink(entry);
+        libarchive2_sys::archive_entry_symlink_utf8(entry);
+        libarchive2_sys::archive_entry_symlink_w(entry);
+        libarchive2_sys::archive_entry_uname(entry);
+        libarchive2_sys::archive_entry_uname_utf8(entry);
+        libarchive2_sys::archive_entry_uname_w(entry);
+        libarchive2_sys::archive_entry_gname(entry);
+        libarchive2_sys::archive_entry_gname_utf8(entry);
+        libarchive2_sys::archive_entry_gname_w(entry);
+    }
+}
+
+/// Convert a libarchive timestamp to a `SystemTime`
+///
+/// Times before 1970 are supported; `None` is returned only for a time that
+/// `SystemTime` cannot represent.
+fn entry_time(sec: i64, nsec: i64) -> Option<SystemTime> {
+    let nsec = std::time::Duration::from_nanos(nsec.clamp(0, 999_999_999) as u64);
+    let secs = std::time::Duration::from_secs(sec.unsigned_abs());
+    let whole = if sec >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(secs)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(secs)
+    };
+    whole?.checked_add(nsec)
+}
+
+/// Map libarchive's zip compression method names back to their header values
+fn zip_method_code(name: &str) -> Option<u16> {
+    let code = match name {
+        "uncompressed" => 0,
+        "shrinking" => 1,
+        "reduced-1" => 2,
+        "reduced-2" => 3,
+        "reduced-3" => 4,
+        "reduced-4" => 5,
+        "imploded" => 6,
+        "deflation" => 8,
+        "deflation-64-bit" => 9,
+        "ibm-terse" => 10,
+        "bzip" => 12,
+        "lzma" => 14,
+        "ibm-terse-new" => 18,
+        "ibm-lz777" => 19,
+        "zstd" => 93,
+        "xz" => 95,
+        "jpeg" => 96,
+        "wav-pack" => 97,
+        "ppmd-1" => 98,
+        "aes" => 99,
+        _ => return None,
+    };
+    Some(code)
 }
 
 /// Mutable reference to an archive entry for building/writing
@@ -434,6 +999,73 @@ impl EntryMut {
         }
     }
 
+    /// Reset every field so the entry can be reused for the next header
+    ///
+    /// Like [`new`](Self::new), the cleared entry gets a fresh inode number.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, EntryMut, FileType, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("many.tar")?;
+    /// let mut entry = EntryMut::new();
+    /// for i in 0..1000 {
+    ///     entry.clear();
+    ///     entry.set_pathname(format!("file{i}.txt"))?;
+    ///     entry.set_file_type(FileType::RegularFile);
+    ///     entry.set_size(2);
+    ///     entry.set_perm(0o644)?;
+    ///     archive.write_header(&entry)?;
+    ///     archive.write_data(b"hi")?;
+    /// }
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn clear(&mut self) {
+        unsafe {
+            libarchive2_sys::archive_entry_clear(self.entry);
+            let ino = NEXT_INODE.fetch_add(1, Ordering::Relaxed);
+            libarchive2_sys::archive_entry_set_ino64(self.entry, ino as i64);
+        }
+    }
+
+    /// Make an independent copy of this entry
+    ///
+    /// All fields, including ACLs and extended attributes, are copied except
+    /// the inode number: the copy gets a fresh one so CPIO writers do not
+    /// treat the two entries as hard links.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NullPointer`] if libarchive cannot allocate the copy.
+    pub fn try_clone(&self) -> Result<EntryMut> {
+        unsafe {
+            let entry = libarchive2_sys::archive_entry_clone(self.entry);
+            if entry.is_null() {
+                return Err(Error::NullPointer);
+            }
+            let ino = NEXT_INODE.fetch_add(1, Ordering::Relaxed);
+            libarchive2_sys::archive_entry_set_ino64(entry, ino as i64);
+            Ok(EntryMut { entry, owned: true })
+        }
+    }
+
+    /// Make a writable copy of an entry read from an archive or the disk
+    ///
+    /// Same as [`Entry::clone_owned`]: every field, including the inode
+    /// number, is copied. Use [`try_clone`](Self::try_clone) to copy an
+    /// `EntryMut` with a fresh inode number instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NullPointer`] if libarchive cannot allocate the copy.
+    pub fn from_entry(src: &Entry<'_>) -> Result<EntryMut> {
+        src.clone_owned()
+    }
+
     /// Set the pathname
     pub fn set_pathname<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let path_str = path
@@ -449,6 +1081,20 @@ impl EntryMut {
         Ok(())
     }
 
+    /// Set the pathname from raw bytes, without any character set conversion
+    ///
+    /// Use together with [`Entry::pathname_bytes`] to copy names that are not
+    /// valid UTF-8 from one archive to another unchanged.
+    pub fn set_pathname_bytes(&mut self, path: &[u8]) -> Result<()> {
+        let c_path = CString::new(path)
+            .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+
+        unsafe {
+            libarchive2_sys::archive_entry_set_pathname(self.entry, c_path.as_ptr());
+        }
+        Ok(())
+    }
+
     /// Set the file type
     pub fn set_file_type(&mut self, file_type: FileType) {
         // SAFETY: entry is a valid pointer and file_type.to_mode() returns a valid mode value
@@ -465,6 +1111,16 @@ impl EntryMut {
         }
     }
 
+    /// Clear the file size, so [`Entry::size_opt`] returns `None`
+    ///
+    /// Writers then treat the size as unknown rather than as 0.
+    pub fn unset_size(&mut self) {
+        // SAFETY: entry is a valid pointer
+        unsafe {
+            libarchive2_sys::archive_entry_unset_size(self.entry);
+        }
+    }
+
     /// Set the file permissions
     ///
     /// On platforms where permissions are stored as u16 (macOS, Windows, BSD),
@@ -522,6 +1178,36 @@ impl EntryMut {
         Ok(())
     }
 
+    /// Set the full mode, file type and permission bits at once
+    ///
+    /// This is the counterpart of [`Entry::mode_raw`]; it replaces what
+    /// [`set_file_type`](Self::set_file_type) and [`set_perm`](Self::set_perm)
+    /// set. Like `set_perm`, it returns an error if the value does not fit the
+    /// platform's `mode_t`.
+    pub fn set_mode(&mut self, mode: u32) -> Result<()> {
+        // Same platform split as set_perm: only 64-bit Linux/Android have a
+        // 32-bit mode_t
+        let wide_mode_t = cfg!(all(
+            any(target_os = "linux", target_os = "android"),
+            any(
+                target_arch = "x86_64",
+                target_arch = "aarch64",
+                target_arch = "loongarch64",
+                target_arch = "riscv64"
+            )
+        ));
+        if !wide_mode_t && mode > 0xFFFF {
+            return Err(Error::InvalidArgument(format!(
+                "Mode value 0x{:X} exceeds platform maximum 0xFFFF",
+                mode
+            )));
+        }
+        unsafe {
+            libarchive2_sys::archive_entry_set_mode(self.entry, mode as _);
+        }
+        Ok(())
+    }
+
     /// Set the modification time
     ///
     /// # Platform Notes
@@ -612,6 +1298,16 @@ impl EntryMut {
         Ok(())
     }
 
+    /// Record whether the symlink points to a file or a directory
+    ///
+    /// Set this when writing symlinks that may be extracted on Windows,
+    /// which cannot create a link without knowing its type.
+    pub fn set_symlink_type(&mut self, symlink_type: SymlinkType) {
+        unsafe {
+            libarchive2_sys::archive_entry_set_symlink_type(self.entry, symlink_type.to_raw());
+        }
+    }
+
     /// Set the hardlink target
     pub fn set_hardlink(&mut self, target: &str) -> Result<()> {
         let c_target = CString::new(target).map_err(|_| {
@@ -731,6 +1427,38 @@ impl EntryMut {
         }
     }
 
+    /// Clear the modification time, so [`Entry::mtime`] returns `None`
+    pub fn unset_mtime(&mut self) {
+        // SAFETY: entry is a valid pointer
+        unsafe {
+            libarchive2_sys::archive_entry_unset_mtime(self.entry);
+        }
+    }
+
+    /// Clear the access time, so [`Entry::atime`] returns `None`
+    pub fn unset_atime(&mut self) {
+        // SAFETY: entry is a valid pointer
+        unsafe {
+            libarchive2_sys::archive_entry_unset_atime(self.entry);
+        }
+    }
+
+    /// Clear the status change time, so [`Entry::ctime`] returns `None`
+    pub fn unset_ctime(&mut self) {
+        // SAFETY: entry is a valid pointer
+        unsafe {
+            libarchive2_sys::archive_entry_unset_ctime(self.entry);
+        }
+    }
+
+    /// Clear the creation time, so [`Entry::birthtime`] returns `None`
+    pub fn unset_birthtime(&mut self) {
+        // SAFETY: entry is a valid pointer
+        unsafe {
+            libarchive2_sys::archive_entry_unset_birthtime(self.entry);
+        }
+    }
+
     /// Set the device number
     pub fn set_dev(&mut self, dev: u64) {
         unsafe {
@@ -798,10 +1526,154 @@ impl EntryMut {
         }
     }
 
+    /// Build a writable entry from an [`OwnedEntry`]
+    ///
+    /// The pathname is copied from [`OwnedEntry::pathname_bytes`] when present so
+    /// names that are not valid UTF-8 survive an archive-to-archive copy.
+    pub fn from_owned(owned: &OwnedEntry) -> Result<Self> {
+        let mut entry = EntryMut::new();
+        match (&owned.pathname_bytes, &owned.pathname) {
+            (Some(bytes), _) => entry.set_pathname_bytes(bytes)?,
+            (None, Some(path)) => entry.set_pathname(path)?,
+            (None, None) => {}
+        }
+        entry.set_file_type(owned.file_type);
+        if let Some(size) = owned.size {
+            entry.set_size(size);
+        }
+        entry.set_perm(owned.mode)?;
+        if let Some(uid) = owned.uid {
+            entry.set_uid(uid);
+        }
+        if let Some(gid) = owned.gid {
+            entry.set_gid(gid);
+        }
+        if let Some(uname) = &owned.uname {
+            entry.set_uname(uname)?;
+        }
+        if let Some(gname) = &owned.gname {
+            entry.set_gname(gname)?;
+        }
+        if let Some(time) = owned.mtime {
+            entry.set_mtime(time);
+        }
+        if let Some(time) = owned.atime {
+            entry.set_atime(time);
+        }
+        if let Some(time) = owned.ctime {
+            entry.set_ctime(time);
+        }
+        if let Some(time) = owned.birthtime {
+            entry.set_birthtime(time);
+        }
+        if let Some(target) = &owned.symlink {
+            entry.set_symlink(target)?;
+        }
+        if let Some(target) = &owned.hardlink {
+            entry.set_hardlink(target)?;
+        }
+        // The pax reader reports attributes stored under both the SCHILY and
+        // LIBARCHIVE keywords twice; write each name once
+        for (i, xattr) in owned.xattrs.iter().enumerate() {
+            if owned.xattrs[..i].iter().all(|x| x.name != xattr.name) {
+                entry.add_xattr(&xattr.name, &xattr.value)?;
+            }
+        }
+        if let Some(text) = &owned.acl_text {
+            entry.add_acl_text(text, AclType::Access)?;
+        }
+        unsafe {
+            libarchive2_sys::archive_entry_set_is_data_encrypted(
+                entry.entry,
+                owned.is_data_encrypted as std::os::raw::c_char,
+            );
+            libarchive2_sys::archive_entry_set_is_metadata_encrypted(
+                entry.entry,
+                owned.is_metadata_encrypted as std::os::raw::c_char,
+            );
+        }
+        Ok(entry)
+    }
+
+    /// Get the pathname, as [`Entry::pathname`] does
+    pub fn pathname(&self) -> Option<String> {
+        self.as_entry().pathname()
+    }
+
+    /// Get the raw pathname bytes, as [`Entry::pathname_bytes`] does
+    pub fn pathname_bytes(&self) -> Option<Vec<u8>> {
+        self.as_entry().pathname_bytes()
+    }
+
+    /// Get the file type
+    pub fn file_type(&self) -> FileType {
+        self.as_entry().file_type()
+    }
+
+    /// Get the file size in bytes, or 0 if it is not set
+    #[deprecated(note = "use `size_opt`, which tells an unset size from a size of 0")]
+    pub fn size(&self) -> i64 {
+        #[allow(deprecated)]
+        self.as_entry().size()
+    }
+
+    /// Get the file size, or `None` if it is not set, as [`Entry::size_opt`] does
+    pub fn size_opt(&self) -> Option<i64> {
+        self.as_entry().size_opt()
+    }
+
+    /// Get the permission bits set by [`set_perm`](Self::set_perm)
+    ///
+    /// This is the value [`Entry::mode`] returns.
+    pub fn perm(&self) -> u32 {
+        self.as_entry().mode()
+    }
+
+    /// Get the modification time
+    pub fn mtime(&self) -> Option<SystemTime> {
+        self.as_entry().mtime()
+    }
+
+    /// Get the user ID, as [`Entry::uid`] does
+    pub fn uid(&self) -> Option<u64> {
+        self.as_entry().uid()
+    }
+
+    /// Get the group ID, as [`Entry::gid`] does
+    pub fn gid(&self) -> Option<u64> {
+        self.as_entry().gid()
+    }
+
+    /// Get the path the entry was read from on disk, as [`Entry::sourcepath`] does
+    pub fn sourcepath(&self) -> Option<String> {
+        self.as_entry().sourcepath()
+    }
+
+    /// Get the user name
+    pub fn uname(&self) -> Option<String> {
+        self.as_entry().uname()
+    }
+
+    /// Get the group name
+    pub fn gname(&self) -> Option<String> {
+        self.as_entry().gname()
+    }
+
+    /// Get the symlink target
+    pub fn symlink(&self) -> Option<String> {
+        self.as_entry().symlink()
+    }
+
+    /// Get the hardlink target
+    pub fn hardlink(&self) -> Option<String> {
+        self.as_entry().hardlink()
+    }
+
     /// Get an immutable view of this entry
     pub fn as_entry(&self) -> Entry<'_> {
         Entry {
             entry: self.entry,
+            archive: std::ptr::null_mut(),
             _marker: std::marker::PhantomData,
         }
     }
@@ -6,12 +6,65 @@ use std::fmt;
 /// Result type for libarchive operations
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// libarchive status: the operation succeeded
+pub const ARCHIVE_OK: i32 = libarchive2_sys::ARCHIVE_OK as i32;
+/// libarchive status: the end of the archive was reached
+pub const ARCHIVE_EOF: i32 = libarchive2_sys::ARCHIVE_EOF as i32;
+/// libarchive status: the operation failed but may succeed if tried again
+pub const ARCHIVE_RETRY: i32 = libarchive2_sys::ARCHIVE_RETRY;
+/// libarchive status: the operation succeeded, but with a problem worth reporting
+pub const ARCHIVE_WARN: i32 = libarchive2_sys::ARCHIVE_WARN;
+/// libarchive status: the current operation failed; the archive can still be used
+pub const ARCHIVE_FAILED: i32 = libarchive2_sys::ARCHIVE_FAILED;
+/// libarchive status: the archive can no longer be used
+pub const ARCHIVE_FATAL: i32 = libarchive2_sys::ARCHIVE_FATAL;
+
+/// How serious a libarchive failure is
+///
+/// Derived from the status the failed call returned. Only [`Severity::Fatal`]
+/// leaves the archive unusable; the others affect just the call or entry that
+/// reported them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The call failed but may succeed if repeated ([`ARCHIVE_RETRY`])
+    Retry,
+    /// The call succeeded with a problem worth reporting ([`ARCHIVE_WARN`])
+    Warn,
+    /// The current entry failed; the archive can still be used ([`ARCHIVE_FAILED`])
+    Failed,
+    /// The archive can no longer be used ([`ARCHIVE_FATAL`])
+    Fatal,
+}
+
+impl Severity {
+    /// Get the severity of a negative libarchive status
+    ///
+    /// Returns `None` for [`ARCHIVE_OK`], [`ARCHIVE_EOF`] and other
+    /// non-negative values. Unknown negative values are treated as fatal.
+    pub fn from_status(status: i32) -> Option<Self> {
+        match status {
+            s if s >= 0 => None,
+            ARCHIVE_RETRY => Some(Severity::Retry),
+            ARCHIVE_WARN => Some(Severity::Warn),
+            ARCHIVE_FAILED => Some(Severity::Failed),
+            _ => Some(Severity::Fatal),
+        }
+    }
+}
+
 /// Error type for libarchive operations
 #[derive(Debug)]
 pub enum Error {
     /// Error from libarchive with error message
+    ///
+    /// Match it with `..`: fields may be added. Use [`Error::status`] and
+    /// [`Error::severity`] rather than reading `status` directly.
+    #[non_exhaustive]
     Archive {
-        /// Error code from libarchive
+        /// Status returned by the failed call, one of [`ARCHIVE_RETRY`],
+        /// [`ARCHIVE_WARN`], [`ARCHIVE_FAILED`] or [`ARCHIVE_FATAL`]
+        status: i32,
+        /// Error number from libarchive (`archive_errno`), usually an `errno` value
         code: i32,
         /// Error message from libarchive
         message: String,
@@ -26,11 +79,25 @@ pub enum Error {
     NullPointer,
     /// Invalid argument
     InvalidArgument(String),
+    /// The archive ended before its data was complete
+    ///
+    /// Reported when reading hits the end of the input in the middle of an entry or
+    /// a compressed stream, as with a partially downloaded file. Other damage is
+    /// reported as [`Error::Archive`]. Note that corruption inside a compressed
+    /// stream can make the decompressor stop early, which libarchive reports the
+    /// same way as truncation, and that a tar archive cut exactly between entries
+    /// is indistinguishable from a complete one.
+    Truncated {
+        /// Bytes consumed from the source before the truncation was detected
+        ///
+        /// This is 0 when the truncation is detected while opening the archive.
+        bytes_read: u64,
+    },
 }
 
 impl Error {
-    /// Create an error from a libarchive archive pointer
-    pub(crate) unsafe fn from_archive(archive: *mut libarchive2_sys::archive) -> Self {
+    /// Create an error from a libarchive archive pointer and the status of the failed call
+    pub(crate) unsafe fn from_archive(archive: *mut libarchive2_sys::archive, status: i32) -> Self {
         // SAFETY: Caller must ensure archive is a valid pointer
         unsafe {
             let code = libarchive2_sys::archive_errno(archive);
@@ -45,6 +112,7 @@ impl Error {
             let errno = if code != 0 { Some(code) } else { None };
 
             Error::Archive {
+                status,
                 code,
                 message,
                 errno,
@@ -52,6 +120,50 @@ impl Error {
         }
     }
 
+    /// Get the message libarchive recorded with an `ARCHIVE_WARN` status
+    pub(crate) unsafe fn warning_message(archive: *mut libarchive2_sys::archive) -> String {
+        // SAFETY: Caller must ensure archive is a valid pointer
+        unsafe {
+            let ptr = libarchive2_sys::archive_error_string(archive);
+            if ptr.is_null() {
+                "Unknown warning".to_string()
+            } else {
+                CStr::from_ptr(ptr).to_string_lossy().into_owned()
+            }
+        }
+    }
+
+    /// Create an error for a failed read operation, detecting truncated input
+    pub(crate) unsafe fn from_read_archive(
+        archive: *mut libarchive2_sys::archive,
+        status: i32,
+    ) -> Self {
+        // SAFETY: Caller must ensure archive is a valid pointer
+        let error = unsafe { Self::from_archive(archive, status) };
+        match &error {
+            Error::Archive { message, .. } if is_truncation_message(message) => {
+                Error::Truncated {
+                    // SAFETY: Caller must ensure archive is a valid pointer
+                    bytes_read: unsafe { crate::stats::filter_bytes(archive, -1) },
+                }
+            }
+            _ => error,
+        }
+    }
+
+    /// Check a return code from a read operation, detecting truncated input
+    pub(crate) unsafe fn from_read_return_code(
+        ret: i32,
+        archive: *mut libarchive2_sys::archive,
+    ) -> Result<i32> {
+        if ret < 0 {
+            // SAFETY: Caller must ensure archive is a valid pointer
+            Err(unsafe { Self::from_read_archive(archive, ret) })
+        } else {
+            Ok(ret)
+        }
+    }
+
     /// Check a return code from libarchive and convert to Result
     pub(crate) unsafe fn from_return_code(
         ret: i32,
@@ -59,11 +171,109 @@ impl Error {
     ) -> Result<i32> {
         if ret < 0 {
             // SAFETY: Caller must ensure archive is a valid pointer
-            Err(unsafe { Self::from_archive(archive) })
+            Err(unsafe { Self::from_archive(archive, ret) })
         } else {
             Ok(ret)
         }
     }
+
+    /// Get the libarchive status of the failed call
+    ///
+    /// This is one of [`ARCHIVE_RETRY`], [`ARCHIVE_WARN`], [`ARCHIVE_FAILED`] or
+    /// [`ARCHIVE_FATAL`] for [`Error::Archive`], [`ARCHIVE_FATAL`] for
+    /// [`Error::Truncated`], and `None` for errors that did not come from
+    /// libarchive.
+    pub fn status(&self) -> Option<i32> {
+        match self {
+            Error::Archive { status, .. } => Some(*status),
+            Error::Truncated { .. } => Some(ARCHIVE_FATAL),
+            _ => None,
+        }
+    }
+
+    /// Get the libarchive status code of the failed call
+    ///
+    /// The same as [`status`](Self::status), so it compares against
+    /// [`ARCHIVE_FATAL`] and the other status constants. Use
+    /// [`errno`](Self::errno) for the error number libarchive recorded.
+    pub fn code(&self) -> Option<i32> {
+        self.status()
+    }
+
+    /// Get the error number libarchive recorded (`archive_errno`)
+    ///
+    /// This is usually an `errno` value. Returns `None` when libarchive
+    /// recorded none and for errors that did not come from libarchive.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            Error::Archive { errno, .. } => *errno,
+            _ => None,
+        }
+    }
+
+    /// Get the severity of the failed call
+    ///
+    /// [`Error::Truncated`] is [`Severity::Fatal`]; errors that did not come
+    /// from libarchive have no severity.
+    pub fn severity(&self) -> Option<Severity> {
+        match self {
+            Error::Archive { status, .. } => {
+                Some(Severity::from_status(*status).unwrap_or(Severity::Fatal))
+            }
+            Error::Truncated { .. } => Some(Severity::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Check whether the archive can no longer be used after this error
+    ///
+    /// After a fatal error every further operation on the archive fails, so
+    /// the archive should be dropped. Other errors only fail the call that
+    /// returned them.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Error::NullPointer => true,
+            _ => self.severity() == Some(Severity::Fatal),
+        }
+    }
+
+    /// Check whether this error reports an `ARCHIVE_WARN` status
+    ///
+    /// The high-level APIs record warnings instead of returning them (see
+    /// [`ReadArchive::warnings`](crate::ReadArchive::warnings),
+    /// [`WriteArchive::warnings`](crate::WriteArchive::warnings) and
+    /// [`WriteDisk::warnings`](crate::WriteDisk::warnings)), so this is only
+    /// true for errors built from a raw status.
+    pub fn is_warning(&self) -> bool {
+        self.severity() == Some(Severity::Warn)
+    }
+
+    /// Check whether repeating the call that returned this error may succeed
+    ///
+    /// libarchive returns [`ARCHIVE_RETRY`] for problems it can step past, such
+    /// as a damaged tar header: calling
+    /// [`next_entry`](crate::ReadArchive::next_entry) again resumes at the next
+    /// readable header.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("damaged.tar")?;
+    /// loop {
+    ///     match archive.next_entry() {
+    ///         Ok(Some(entry)) => println!("{}", entry.pathname().unwrap_or_default()),
+    ///         Ok(None) => break,
+    ///         Err(e) if e.is_retryable() => eprintln!("skipping: {}", e),
+    ///         Err(e) => return Err(e),
+    ///     }
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        self.severity() == Some(Severity::Retry)
+    }
 }
 
 impl fmt::Display for Error {
@@ -73,6 +283,7 @@ impl fmt::Display for Error {
                 code,
                 message,
                 errno,
+                ..
             } => {
                 write!(f, "libarchive error (code {}): {}", code, message)?;
                 if let Some(e) = errno {
@@ -84,6 +295,9 @@ impl fmt::Display for Error {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::NullPointer => write!(f, "Null pointer error"),
             Error::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            Error::Truncated { bytes_read } => {
+                write!(f, "Truncated archive (after {} bytes of input)", bytes_read)
+            }
         }
     }
 }
@@ -98,6 +312,16 @@ impl std::error::Error for Error {
     }
 }
 
+/// Check whether a libarchive error message describes input that ended early
+///
+/// Readers and filters report this as e.g. "Truncated tar archive", "truncated
+/// gzip input" or "Truncated input file (needed ...)"; liblzma reports a
+/// truncated xz/lzma stream as "No progress is possible".
+fn is_truncation_message(message: &str) -> bool {
+    message.to_ascii_lowercase().contains("truncated")
+        || message.contains("No progress is possible")
+}
+
 impl From<std::str::Utf8Error> for Error {
     fn from(e: std::str::Utf8Error) -> Self {
         Error::Utf8(e)
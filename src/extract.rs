@@ -2,10 +2,17 @@
 
 use crate::entry::EntryMut;
 use crate::error::{Error, Result};
+use crate::stats::ReadStats;
+use std::ffi::CStr;
+use std::fmt;
 use std::ops::{BitOr, BitOrAssign};
+use std::path::{Path, PathBuf};
 
 /// Flags for controlling extraction behavior
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The values are libarchive's `ARCHIVE_EXTRACT_*` constants. `Debug` prints the
+/// names of the flags that are set, e.g. `ExtractFlags(PERM | TIME)`.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct ExtractFlags(i32);
 
 impl ExtractFlags {
@@ -13,66 +20,149 @@ impl ExtractFlags {
     pub const NONE: ExtractFlags = ExtractFlags(0);
 
     /// Set owner/group on extracted files
-    pub const OWNER: ExtractFlags = ExtractFlags(0x0001);
+    pub const OWNER: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_OWNER as i32);
 
     /// Restore file permissions
-    pub const PERM: ExtractFlags = ExtractFlags(0x0002);
+    pub const PERM: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_PERM as i32);
 
     /// Restore modification time
-    pub const TIME: ExtractFlags = ExtractFlags(0x0004);
+    pub const TIME: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_TIME as i32);
 
     /// Don't overwrite existing files
-    pub const NO_OVERWRITE: ExtractFlags = ExtractFlags(0x0008);
+    pub const NO_OVERWRITE: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_NO_OVERWRITE as i32);
 
     /// Unlink file before creating
-    pub const UNLINK: ExtractFlags = ExtractFlags(0x0010);
+    pub const UNLINK: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_UNLINK as i32);
 
     /// Restore ACLs (Access Control Lists)
-    pub const ACL: ExtractFlags = ExtractFlags(0x0020);
+    pub const ACL: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_ACL as i32);
 
     /// Restore file flags (e.g., immutable, append-only)
-    pub const FFLAGS: ExtractFlags = ExtractFlags(0x0040);
+    pub const FFLAGS: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_FFLAGS as i32);
 
     /// Restore extended attributes
-    pub const XATTR: ExtractFlags = ExtractFlags(0x0080);
+    pub const XATTR: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_XATTR as i32);
 
     /// Guard against symlink attacks
-    pub const SECURE_SYMLINKS: ExtractFlags = ExtractFlags(0x0100);
+    pub const SECURE_SYMLINKS: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_SECURE_SYMLINKS as i32);
 
     /// Reject entries with '..' in path
-    pub const SECURE_NODOTDOT: ExtractFlags = ExtractFlags(0x0200);
+    pub const SECURE_NODOTDOT: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_SECURE_NODOTDOT as i32);
 
     /// Don't create parent directories automatically
-    pub const NO_AUTODIR: ExtractFlags = ExtractFlags(0x0400);
+    pub const NO_AUTODIR: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_NO_AUTODIR as i32);
 
     /// Don't overwrite newer files
-    pub const NO_OVERWRITE_NEWER: ExtractFlags = ExtractFlags(0x0800);
+    pub const NO_OVERWRITE_NEWER: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_NO_OVERWRITE_NEWER as i32);
 
     /// Write sparse files with holes
-    pub const SPARSE: ExtractFlags = ExtractFlags(0x1000);
+    pub const SPARSE: ExtractFlags = ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_SPARSE as i32);
 
     /// Restore Mac OS metadata
-    pub const MAC_METADATA: ExtractFlags = ExtractFlags(0x2000);
+    pub const MAC_METADATA: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_MAC_METADATA as i32);
 
     /// Don't use HFS+ compression
-    pub const NO_HFS_COMPRESSION: ExtractFlags = ExtractFlags(0x4000);
+    pub const NO_HFS_COMPRESSION: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_NO_HFS_COMPRESSION as i32);
 
     /// Force HFS+ compression
-    pub const HFS_COMPRESSION_FORCED: ExtractFlags = ExtractFlags(0x8000);
+    pub const HFS_COMPRESSION_FORCED: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_HFS_COMPRESSION_FORCED as i32);
 
     /// Reject absolute paths
-    pub const SECURE_NOABSOLUTEPATHS: ExtractFlags = ExtractFlags(0x10000);
+    pub const SECURE_NOABSOLUTEPATHS: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_SECURE_NOABSOLUTEPATHS as i32);
 
     /// Clear no-change flags when unlinking
-    pub const CLEAR_NOCHANGE_FFLAGS: ExtractFlags = ExtractFlags(0x20000);
+    pub const CLEAR_NOCHANGE_FFLAGS: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_CLEAR_NOCHANGE_FFLAGS as i32);
 
     /// Use safe writes (rename after extraction)
-    pub const SAFE_WRITES: ExtractFlags = ExtractFlags(0x40000);
+    pub const SAFE_WRITES: ExtractFlags =
+        ExtractFlags(libarchive2_sys::ARCHIVE_EXTRACT_SAFE_WRITES as i32);
+
+    /// Every named flag, in bit order
+    const NAMED: &[(&str, ExtractFlags)] = &[
+        ("OWNER", ExtractFlags::OWNER),
+        ("PERM", ExtractFlags::PERM),
+        ("TIME", ExtractFlags::TIME),
+        ("NO_OVERWRITE", ExtractFlags::NO_OVERWRITE),
+        ("UNLINK", ExtractFlags::UNLINK),
+        ("ACL", ExtractFlags::ACL),
+        ("FFLAGS", ExtractFlags::FFLAGS),
+        ("XATTR", ExtractFlags::XATTR),
+        ("SECURE_SYMLINKS", ExtractFlags::SECURE_SYMLINKS),
+        ("SECURE_NODOTDOT", ExtractFlags::SECURE_NODOTDOT),
+        ("NO_AUTODIR", ExtractFlags::NO_AUTODIR),
+        ("NO_OVERWRITE_NEWER", ExtractFlags::NO_OVERWRITE_NEWER),
+        ("SPARSE", ExtractFlags::SPARSE),
+        ("MAC_METADATA", ExtractFlags::MAC_METADATA),
+        ("NO_HFS_COMPRESSION", ExtractFlags::NO_HFS_COMPRESSION),
+        (
+            "HFS_COMPRESSION_FORCED",
+            ExtractFlags::HFS_COMPRESSION_FORCED,
+        ),
+        (
+            "SECURE_NOABSOLUTEPATHS",
+            ExtractFlags::SECURE_NOABSOLUTEPATHS,
+        ),
+        ("CLEAR_NOCHANGE_FFLAGS", ExtractFlags::CLEAR_NOCHANGE_FFLAGS),
+        ("SAFE_WRITES", ExtractFlags::SAFE_WRITES),
+    ];
 
     /// Get the raw integer value of the flags
     pub fn bits(&self) -> i32 {
         self.0
     }
+
+    /// Check whether all of the flags in `other` are set
+    pub fn contains(&self, other: ExtractFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Clear the given flags (for internal use by sibling modules)
+    pub(crate) fn without(self, other: ExtractFlags) -> Self {
+        ExtractFlags(self.0 & !other.0)
+    }
+}
+
+impl fmt::Debug for ExtractFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = Self::NAMED.iter().map(|(name, flag)| (*name, flag.0));
+        fmt_flags(f, "ExtractFlags", self.0, names)
+    }
+}
+
+/// Format a flag set as `Type(NAME | NAME | 0x...)`, with any unnamed bits in hex
+pub(crate) fn fmt_flags<'n>(
+    f: &mut fmt::Formatter<'_>,
+    type_name: &str,
+    bits: i32,
+    names: impl Iterator<Item = (&'n str, i32)>,
+) -> fmt::Result {
+    write!(f, "{}(", type_name)?;
+    let mut rest = bits;
+    let mut first = true;
+    for (name, flag) in names {
+        if flag != 0 && rest & flag == flag {
+            write!(f, "{}{}", if first { "" } else { " | " }, name)?;
+            rest &= !flag;
+            first = false;
+        }
+    }
+    match (first, rest) {
+        (true, 0) => write!(f, "NONE")?,
+        (_, 0) => {}
+        (true, rest) => write!(f, "{:#x}", rest)?,
+        (false, rest) => write!(f, " | {:#x}", rest)?,
+    }
+    write!(f, ")")
 }
 
 impl BitOr for ExtractFlags {
@@ -89,17 +179,74 @@ impl BitOrAssign for ExtractFlags {
     }
 }
 
+/// Counts collected by [`ReadArchive::extract_to_dir`](crate::ReadArchive::extract_to_dir)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractStats {
+    /// Regular files extracted (hardlinks not included)
+    pub files: u64,
+    /// Directories extracted
+    pub directories: u64,
+    /// Symbolic links extracted
+    pub symlinks: u64,
+    /// Hardlinks extracted
+    pub hardlinks: u64,
+    /// Other entries extracted (devices, FIFOs, sockets)
+    pub other: u64,
+    /// Sum of the sizes of the regular files extracted
+    pub bytes: u64,
+    /// Entries left alone because they already existed and `NO_OVERWRITE` was set
+    pub skipped: u64,
+    /// Warnings libarchive reported while extracting, such as metadata that
+    /// could not be restored (see [`WriteDisk::warnings`]) or headers read with
+    /// a warning (see [`ReadArchive::warnings`](crate::ReadArchive::warnings))
+    pub warnings: u64,
+    /// Read statistics of the archive once every entry had been read, including
+    /// its compressed and uncompressed byte totals
+    pub stats: ReadStats,
+}
+
+impl ExtractStats {
+    /// Total number of entries extracted
+    pub fn entries(&self) -> u64 {
+        self.files + self.directories + self.symlinks + self.hardlinks + self.other
+    }
+}
+
 /// Archive writer for extracting entries to disk
 ///
 /// This provides the `archive_write_disk` API for writing archive entries
 /// directly to the filesystem.
 ///
+/// # Warnings
+///
+/// libarchive reports problems that do not stop an entry from being written,
+/// such as metadata it could not restore, as warnings. They do not fail the call
+/// but are recorded and available from [`warnings`](Self::warnings) and
+/// [`last_warning`](Self::last_warning). Warnings come from:
+///
+/// - [`write_header`](Self::write_header), e.g. when an existing file is in the
+///   way or a hardlink target cannot be resolved
+/// - [`write_data`](Self::write_data) and
+///   [`write_data_block`](Self::write_data_block) when the entry has no file to
+///   write to (a directory, a zero-size file or a link); the data is discarded
+/// - [`finish_entry`](Self::finish_entry) when permissions, ownership, times,
+///   ACLs, extended attributes or file flags requested with
+///   [`set_options`](Self::set_options) could not be restored
+/// - [`ReadArchive::extract_current`](crate::ReadArchive::extract_current) and
+///   [`ReadArchive::copy_data_to_disk`](crate::ReadArchive::copy_data_to_disk),
+///   which go through the calls above
+///
 /// # Thread Safety
 ///
 /// `WriteDisk` is `Send` but not `Sync`. You can transfer ownership between threads,
 /// but cannot share references across threads.
 pub struct WriteDisk {
     archive: *mut libarchive2_sys::archive,
+    strip_components: usize,
+    skipping_entry: bool,
+    warnings: Vec<String>,
+    /// Pathname of the last header written, after stripping
+    current_path: Option<PathBuf>,
 }
 
 // SAFETY: WriteDisk can be sent between threads because the archive pointer
@@ -118,7 +265,13 @@ impl WriteDisk {
             if archive.is_null() {
                 return Err(Error::NullPointer);
             }
-            Ok(WriteDisk { archive })
+            Ok(WriteDisk {
+                archive,
+                strip_components: 0,
+                skipping_entry: false,
+                warnings: Vec::new(),
+                current_path: None,
+            })
         }
     }
 
@@ -133,6 +286,130 @@ impl WriteDisk {
         Ok(())
     }
 
+    /// Remove the first `count` path components from every entry written
+    ///
+    /// Like `tar --strip-components`, this drops the leading directories of each
+    /// entry's pathname (and hardlink target) in
+    /// [`write_header`](Self::write_header) and in
+    /// [`ReadArchive::extract_current`](crate::ReadArchive::extract_current).
+    /// Entries with no components left, such as the top-level directory itself,
+    /// are skipped: their header and data are silently discarded. Empty
+    /// components from leading or doubled slashes are not counted. A count of 0
+    /// turns stripping off.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ReadArchive, WriteDisk};
+    ///
+    /// // project-1.0/src/main.rs is extracted as src/main.rs
+    /// let mut archive = ReadArchive::open("project-1.0.tar.gz")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_strip_components(1);
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.extract_current(&mut disk)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_strip_components(&mut self, count: usize) {
+        self.strip_components = count;
+    }
+
+    /// Strip leading components from an entry's pathname and hardlink target
+    ///
+    /// Returns `Ok(false)` if nothing is left and the entry should be skipped.
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn strip_entry(&self, entry: *mut libarchive2_sys::archive_entry) -> Result<bool> {
+        if self.strip_components == 0 {
+            return Ok(true);
+        }
+        let count = self.strip_components;
+        unsafe { crate::path::map_entry_paths(entry, |path| strip_components(path, count)) }
+    }
+
+    /// Whether the current entry is being skipped by
+    /// [`set_strip_components`](Self::set_strip_components)
+    pub(crate) fn skipping_entry(&self) -> bool {
+        self.skipping_entry
+    }
+
+    /// Get the path of the entry most recently passed to
+    /// [`write_header`](Self::write_header)
+    ///
+    /// This is the pathname libarchive is writing to, after
+    /// [`set_strip_components`](Self::set_strip_components) has been applied. It
+    /// stays set after [`finish_entry`](Self::finish_entry), so it can be used
+    /// to report which file a warning or error belongs to, and is `None` before
+    /// the first header and while an entry is being skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ReadArchive, WriteDisk};
+    ///
+    /// let mut archive = ReadArchive::open("backup.tar")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// while archive.next_entry()?.is_some() {
+    ///     if let Err(e) = archive.extract_current(&mut disk) {
+    ///         eprintln!("{:?}: {}", disk.current_file_path(), e);
+    ///     }
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn current_file_path(&self) -> Option<&Path> {
+        self.current_path.as_deref()
+    }
+
+    /// Get the warnings recorded so far, oldest first
+    ///
+    /// See [Warnings](Self#warnings) for the operations that record them.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get the most recent warning, if any
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive, WriteDisk};
+    ///
+    /// let mut archive = ReadArchive::open("untrusted.tar")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_options(ExtractFlags::PERM | ExtractFlags::XATTR | ExtractFlags::SECURE_NODOTDOT)?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     let name = entry.pathname().unwrap_or_default();
+    ///     let before = disk.warnings().len();
+    ///     archive.extract_current(&mut disk)?;
+    ///     if disk.warnings().len() > before {
+    ///         eprintln!("{}: {}", name, disk.last_warning().unwrap());
+    ///     }
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn last_warning(&self) -> Option<&str> {
+        self.warnings.last().map(String::as_str)
+    }
+
+    /// Check a return code, recording `ARCHIVE_WARN` instead of failing
+    fn check(&mut self, ret: i32) -> Result<()> {
+        if ret == libarchive2_sys::ARCHIVE_WARN {
+            self.record_warning();
+            return Ok(());
+        }
+        // SAFETY: archive is valid for the lifetime of self
+        unsafe { Error::from_return_code(ret, self.archive) }?;
+        Ok(())
+    }
+
+    fn record_warning(&mut self) {
+        // SAFETY: archive is valid for the lifetime of self
+        let message = unsafe { Error::warning_message(self.archive) };
+        self.warnings.push(message);
+    }
+
     /// Use standard lookup functions for user/group names
     ///
     /// This enables looking up uid/gid from uname/gname using system calls
@@ -146,24 +423,176 @@ impl WriteDisk {
         Ok(())
     }
 
+    /// Map user names to uids with a closure instead of the system database
+    ///
+    /// The closure receives each entry's user name (`None` if it has none) and
+    /// the uid stored in the entry, and returns the uid to give the file.
+    /// Returning the stored uid keeps it unchanged. Ownership is only restored
+    /// with [`ExtractFlags::OWNER`], so the closure is not called without it.
+    /// Replaces [`set_standard_lookup`](Self::set_standard_lookup) for users.
+    ///
+    /// Giving files to another user normally needs root; otherwise
+    /// [`finish_entry`](Self::finish_entry) records a warning and the file keeps
+    /// the extracting user as its owner.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, WriteDisk};
+    ///
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_options(ExtractFlags::OWNER)?;
+    /// // Give files owned by "build" on the packaging host to uid 1000 here
+    /// disk.set_user_lookup(|name, uid| if name == Some("build") { 1000 } else { uid })?;
+    /// disk.set_group_lookup(|_, gid| gid)?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_user_lookup<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: FnMut(Option<&str>, i64) -> i64 + Send + 'static,
+    {
+        let (data, func, cleanup) = crate::callbacks::id_lookup_callback_parts(lookup);
+        // libarchive takes ownership of data and calls cleanup when the
+        // lookup is replaced or the archive is freed
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_disk_set_user_lookup(
+                    self.archive,
+                    data,
+                    Some(func),
+                    Some(cleanup),
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Map group names to gids with a closure instead of the system database
+    ///
+    /// Works as [`set_user_lookup`](Self::set_user_lookup) does for users: the
+    /// closure receives the entry's group name and stored gid and returns the
+    /// gid to give the file.
+    pub fn set_group_lookup<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: FnMut(Option<&str>, i64) -> i64 + Send + 'static,
+    {
+        let (data, func, cleanup) = crate::callbacks::id_lookup_callback_parts(lookup);
+        // libarchive takes ownership of data and calls cleanup when the
+        // lookup is replaced or the archive is freed
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_disk_set_group_lookup(
+                    self.archive,
+                    data,
+                    Some(func),
+                    Some(cleanup),
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Refuse to overwrite the file with the given device and inode numbers
+    ///
+    /// Extracting an archive into the directory that holds it could otherwise
+    /// replace the archive while it is being read. A header for this file fails
+    /// in [`write_header`](Self::write_header) instead. On Unix the numbers come
+    /// from `MetadataExt::dev` and `MetadataExt::ino`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(unix)]
+    /// # {
+    /// use libarchive2::WriteDisk;
+    /// use std::os::unix::fs::MetadataExt;
+    ///
+    /// let metadata = std::fs::metadata("backup.tar")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_skip_file(metadata.dev(), metadata.ino())?;
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_skip_file(&mut self, dev: u64, ino: u64) -> Result<()> {
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_disk_set_skip_file(
+                    self.archive,
+                    dev as i64,
+                    ino as i64,
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
     /// Write an entry header to disk
     ///
     /// This creates the file/directory/etc on disk
     pub fn write_header(&mut self, entry: &EntryMut) -> Result<()> {
+        // SAFETY: entry.entry is valid for the lifetime of entry
+        unsafe { self.write_header_raw(entry.entry) }
+    }
+
+    /// Write `entry`, or start skipping it if stripping leaves nothing
+    ///
+    /// `entry` itself is never modified; components are stripped from a copy.
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    pub(crate) unsafe fn write_header_raw(
+        &mut self,
+        entry: *mut libarchive2_sys::archive_entry,
+    ) -> Result<()> {
         // Set locale to UTF-8 on Windows to handle non-ASCII filenames correctly
         let _guard = crate::locale::WindowsUTF8LocaleGuard::new();
+        self.skipping_entry = false;
+        self.current_path = None;
+        if self.strip_components == 0 {
+            // SAFETY: Caller guarantees entry is valid
+            return unsafe { self.write_entry(entry) };
+        }
 
+        // Strip a copy so the caller's entry, which may be a reader's current
+        // header, is left alone
         unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_write_header(self.archive, entry.entry),
-                self.archive,
-            )?;
+            let cloned = libarchive2_sys::archive_entry_clone(entry);
+            if cloned.is_null() {
+                return Err(Error::NullPointer);
+            }
+            let result = match self.strip_entry(cloned) {
+                Ok(true) => self.write_entry(cloned),
+                Ok(false) => {
+                    self.skipping_entry = true;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            };
+            libarchive2_sys::archive_entry_free(cloned);
+            result
         }
-        Ok(())
+    }
+
+    /// Write a header that is ready to go to disk
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn write_entry(&mut self, entry: *mut libarchive2_sys::archive_entry) -> Result<()> {
+        // SAFETY: Caller guarantees entry is valid
+        self.current_path = unsafe { entry_path(entry) };
+        // SAFETY: archive and entry are valid
+        let ret = unsafe { libarchive2_sys::archive_write_header(self.archive, entry) };
+        self.check(ret)
     }
 
     /// Write data for the current entry
     pub fn write_data(&mut self, data: &[u8]) -> Result<usize> {
+        if self.skipping_entry {
+            return Ok(data.len());
+        }
         unsafe {
             let ret = libarchive2_sys::archive_write_data(
                 self.archive,
@@ -171,23 +600,57 @@ impl WriteDisk {
                 data.len(),
             );
 
-            if ret < 0 {
-                Err(Error::from_archive(self.archive))
+            if ret == libarchive2_sys::ARCHIVE_WARN as isize {
+                // The data was discarded, as for a skipped entry
+                self.record_warning();
+                Ok(data.len())
+            } else if ret < 0 {
+                Err(Error::from_archive(self.archive, ret as i32))
             } else {
                 Ok(ret as usize)
             }
         }
     }
 
-    /// Finish writing the current entry
-    pub fn finish_entry(&mut self) -> Result<()> {
+    /// Write a block of data at `offset` in the current entry
+    ///
+    /// Skipping ahead of the data written so far leaves a hole, so writing only
+    /// the non-zero blocks of a sparse entry produces a sparse file (where the
+    /// filesystem supports holes; elsewhere the gap is filled with zeros).
+    /// [`ReadArchive::copy_data_to_disk`](crate::ReadArchive::copy_data_to_disk)
+    /// pairs this with [`read_data_block`](crate::ReadArchive::read_data_block).
+    /// Returns `data.len()` on success.
+    pub fn write_data_block(&mut self, offset: i64, data: &[u8]) -> Result<usize> {
+        if self.skipping_entry {
+            return Ok(data.len());
+        }
         unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_write_finish_entry(self.archive),
+            let ret = libarchive2_sys::archive_write_data_block(
                 self.archive,
-            )?;
+                data.as_ptr() as *const std::os::raw::c_void,
+                data.len(),
+                offset,
+            );
+
+            if ret == libarchive2_sys::ARCHIVE_WARN as isize {
+                self.record_warning();
+                Ok(data.len())
+            } else if ret < 0 {
+                Err(Error::from_archive(self.archive, ret as i32))
+            } else {
+                Ok(data.len())
+            }
         }
-        Ok(())
+    }
+
+    /// Finish writing the current entry
+    pub fn finish_entry(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.skipping_entry) {
+            return Ok(());
+        }
+        // SAFETY: archive is valid for the lifetime of self
+        let ret = unsafe { libarchive2_sys::archive_write_finish_entry(self.archive) };
+        self.check(ret)
     }
 
     /// Close and free the disk writer
@@ -217,5 +680,47 @@ impl Drop for WriteDisk {
     }
 }
 
+/// The pathname of `entry` as a filesystem path
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+unsafe fn entry_path(entry: *mut libarchive2_sys::archive_entry) -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        // SAFETY: Caller guarantees entry is valid
+        let ptr = unsafe { libarchive2_sys::archive_entry_pathname(entry) };
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: ptr is a NUL-terminated string owned by entry
+        let bytes = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        // SAFETY: Caller guarantees entry is valid
+        let ptr = unsafe { libarchive2_sys::archive_entry_pathname_utf8(entry) };
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: ptr is a NUL-terminated string owned by entry
+        let path = unsafe { CStr::from_ptr(ptr) }.to_string_lossy();
+        Some(PathBuf::from(path.into_owned()))
+    }
+}
+
+/// Drop the first `count` components of `path`, or `None` if nothing is left
+fn strip_components(path: &str, count: usize) -> Option<String> {
+    let mut rest = path;
+    for _ in 0..count {
+        rest = rest.trim_start_matches('/');
+        rest = &rest[rest.find('/')? + 1..];
+    }
+    let rest = rest.trim_start_matches('/');
+    (!rest.is_empty()).then(|| rest.to_string())
+}
+
 // Note: Default implementation removed because disk writer creation can fail.
 // Use WriteDisk::new() instead.
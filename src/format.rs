@@ -49,6 +49,18 @@ pub enum ArchiveFormat {
     Cab,
 }
 
+/// Entry metadata beyond pathname, type, size, mode, owner and times that an
+/// archive format may or may not be able to store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MetadataSupport {
+    /// Extended attributes
+    pub(crate) xattrs: bool,
+    /// POSIX.1e and NFSv4 ACLs
+    pub(crate) acls: bool,
+    /// Block and character device nodes, with their device numbers
+    pub(crate) devices: bool,
+}
+
 /// Compression format types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionFormat {
@@ -68,8 +80,15 @@ pub enum CompressionFormat {
     Compress,
     /// UUEncode compression
     UuEncode,
+    /// Base64 encoding (`begin-base64` framing, as written by `uuencode -m`)
+    ///
+    /// libarchive reads it with its uuencode filter, so a read archive reports
+    /// it as [`UuEncode`](Self::UuEncode).
+    B64Encode,
     /// LZIP compression
     Lzip,
+    /// Legacy LZMA ("lzma alone") compression, as written by `lzma`
+    Lzma,
     /// LRZIP compression
     Lrzip,
     /// LZOP compression
@@ -106,6 +125,90 @@ impl ArchiveFormat {
             ArchiveFormat::Cab => "cab",
         }
     }
+
+    /// Map libarchive's `ARCHIVE_FORMAT_*` code to a format
+    pub(crate) fn from_code(code: i32) -> Option<ArchiveFormat> {
+        use libarchive2_sys as sys;
+
+        Some(match code as u32 {
+            sys::ARCHIVE_FORMAT_TAR => ArchiveFormat::Tar,
+            sys::ARCHIVE_FORMAT_TAR_GNUTAR => ArchiveFormat::TarGnu,
+            sys::ARCHIVE_FORMAT_TAR_PAX_INTERCHANGE => ArchiveFormat::TarPax,
+            sys::ARCHIVE_FORMAT_TAR_PAX_RESTRICTED => ArchiveFormat::TarPaxRestricted,
+            sys::ARCHIVE_FORMAT_TAR_USTAR => ArchiveFormat::TarUstar,
+            sys::ARCHIVE_FORMAT_ZIP => ArchiveFormat::Zip,
+            sys::ARCHIVE_FORMAT_7ZIP => ArchiveFormat::SevenZip,
+            sys::ARCHIVE_FORMAT_AR | sys::ARCHIVE_FORMAT_AR_GNU | sys::ARCHIVE_FORMAT_AR_BSD => {
+                ArchiveFormat::Ar
+            }
+            sys::ARCHIVE_FORMAT_CPIO => ArchiveFormat::Cpio,
+            sys::ARCHIVE_FORMAT_CPIO_POSIX => ArchiveFormat::CpioOdc,
+            sys::ARCHIVE_FORMAT_CPIO_SVR4_NOCRC => ArchiveFormat::CpioNewc,
+            sys::ARCHIVE_FORMAT_CPIO_BIN_LE | sys::ARCHIVE_FORMAT_CPIO_BIN_BE => {
+                ArchiveFormat::CpioBin
+            }
+            sys::ARCHIVE_FORMAT_ISO9660 | sys::ARCHIVE_FORMAT_ISO9660_ROCKRIDGE => {
+                ArchiveFormat::Iso9660
+            }
+            sys::ARCHIVE_FORMAT_XAR => ArchiveFormat::Xar,
+            sys::ARCHIVE_FORMAT_MTREE => ArchiveFormat::Mtree,
+            sys::ARCHIVE_FORMAT_RAW => ArchiveFormat::Raw,
+            sys::ARCHIVE_FORMAT_SHAR | sys::ARCHIVE_FORMAT_SHAR_BASE => ArchiveFormat::Shar,
+            sys::ARCHIVE_FORMAT_WARC => ArchiveFormat::Warc,
+            sys::ARCHIVE_FORMAT_RAR => ArchiveFormat::Rar,
+            sys::ARCHIVE_FORMAT_RAR_V5 => ArchiveFormat::Rar5,
+            sys::ARCHIVE_FORMAT_LHA => ArchiveFormat::Lha,
+            sys::ARCHIVE_FORMAT_CAB => ArchiveFormat::Cab,
+            _ => return None,
+        })
+    }
+
+    /// The optional entry metadata this format's writer stores
+    pub(crate) fn metadata_support(&self) -> MetadataSupport {
+        let (xattrs, acls, devices) = match self {
+            // ArchiveFormat::Tar is written as pax
+            ArchiveFormat::Tar | ArchiveFormat::TarPax | ArchiveFormat::TarPaxRestricted => {
+                (true, true, true)
+            }
+            ArchiveFormat::TarGnu | ArchiveFormat::TarUstar => (false, false, true),
+            ArchiveFormat::Cpio
+            | ArchiveFormat::CpioNewc
+            | ArchiveFormat::CpioOdc
+            | ArchiveFormat::CpioBin
+            | ArchiveFormat::Iso9660
+            | ArchiveFormat::Mtree
+            | ArchiveFormat::Shar => (false, false, true),
+            // The xar writer keeps the file type of a device node but not its
+            // device numbers
+            ArchiveFormat::Xar => (true, false, false),
+            ArchiveFormat::Zip
+            | ArchiveFormat::SevenZip
+            | ArchiveFormat::Ar
+            | ArchiveFormat::Raw
+            | ArchiveFormat::Warc
+            | ArchiveFormat::Rar
+            | ArchiveFormat::Rar5
+            | ArchiveFormat::Lha
+            | ArchiveFormat::Cab => (false, false, false),
+        };
+        MetadataSupport {
+            xattrs,
+            acls,
+            devices,
+        }
+    }
+
+    /// Whether this is one of the tar formats
+    pub(crate) fn is_tar(&self) -> bool {
+        matches!(
+            self,
+            ArchiveFormat::Tar
+                | ArchiveFormat::TarGnu
+                | ArchiveFormat::TarPax
+                | ArchiveFormat::TarPaxRestricted
+                | ArchiveFormat::TarUstar
+        )
+    }
 }
 
 impl CompressionFormat {
@@ -120,12 +223,58 @@ impl CompressionFormat {
             CompressionFormat::Lz4 => "lz4",
             CompressionFormat::Compress => "Z",
             CompressionFormat::UuEncode => "uu",
+            CompressionFormat::B64Encode => "b64",
             CompressionFormat::Lzip => "lz",
+            CompressionFormat::Lzma => "lzma",
             CompressionFormat::Lrzip => "lrz",
             CompressionFormat::Lzop => "lzo",
             CompressionFormat::Grzip => "grz",
         }
     }
+
+    /// Map libarchive's `ARCHIVE_FILTER_*` code to a compression format
+    pub(crate) fn from_code(code: i32) -> Option<CompressionFormat> {
+        use libarchive2_sys as sys;
+
+        Some(match code as u32 {
+            sys::ARCHIVE_FILTER_NONE => CompressionFormat::None,
+            sys::ARCHIVE_FILTER_GZIP => CompressionFormat::Gzip,
+            sys::ARCHIVE_FILTER_BZIP2 => CompressionFormat::Bzip2,
+            sys::ARCHIVE_FILTER_XZ => CompressionFormat::Xz,
+            sys::ARCHIVE_FILTER_ZSTD => CompressionFormat::Zstd,
+            sys::ARCHIVE_FILTER_LZ4 => CompressionFormat::Lz4,
+            sys::ARCHIVE_FILTER_COMPRESS => CompressionFormat::Compress,
+            sys::ARCHIVE_FILTER_UU => CompressionFormat::UuEncode,
+            sys::ARCHIVE_FILTER_LZIP => CompressionFormat::Lzip,
+            sys::ARCHIVE_FILTER_LZMA => CompressionFormat::Lzma,
+            sys::ARCHIVE_FILTER_LRZIP => CompressionFormat::Lrzip,
+            sys::ARCHIVE_FILTER_LZOP => CompressionFormat::Lzop,
+            sys::ARCHIVE_FILTER_GRZIP => CompressionFormat::Grzip,
+            _ => return None,
+        })
+    }
+
+    /// Name of the external program libarchive runs for this filter when it
+    /// has no built-in implementation
+    pub(crate) fn external_program(&self) -> Option<&'static str> {
+        match self {
+            CompressionFormat::Lrzip => Some("lrzip"),
+            CompressionFormat::Lzop => Some("lzop"),
+            CompressionFormat::Grzip => Some("grzip"),
+            _ => None,
+        }
+    }
+}
+
+/// Check whether an executable named `program` is on `PATH`
+pub(crate) fn program_in_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
 }
 
 /// Format specifier for reading archives
@@ -146,6 +295,99 @@ pub enum ZipCompressionMethod {
     Deflate,
 }
 
+/// ZIP encryption schemes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipEncryption {
+    /// Traditional PKWARE encryption (ZipCrypto), readable by every zip tool
+    /// but easily broken
+    ZipCrypt,
+    /// WinZip AES with a 128-bit key
+    Aes128,
+    /// WinZip AES with a 256-bit key
+    Aes256,
+}
+
+/// 7z compression methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SevenZipMethod {
+    /// Store data uncompressed
+    Copy,
+    /// Deflate (needs zlib)
+    Deflate,
+    /// Bzip2 (needs libbz2)
+    Bzip2,
+    /// LZMA (needs liblzma)
+    Lzma1,
+    /// LZMA2 (needs liblzma)
+    Lzma2,
+    /// PPMd variant H
+    Ppmd,
+}
+
+/// Keywords the mtree writer can emit for each entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtreeKeyword {
+    /// POSIX `cksum` CRC of the file data
+    Cksum,
+    /// MD5 digest of the file data
+    Md5,
+    /// SHA-1 digest of the file data
+    Sha1,
+    /// SHA-256 digest of the file data
+    Sha256,
+    /// SHA-512 digest of the file data
+    Sha512,
+    /// File size
+    Size,
+    /// Modification time
+    Time,
+    /// Permission bits
+    Mode,
+    /// Numeric owner
+    Uid,
+    /// Numeric group
+    Gid,
+    /// File type
+    Type,
+    /// Symlink target
+    Link,
+}
+
+impl MtreeKeyword {
+    /// The mtree writer option that enables this keyword
+    pub(crate) fn option_name(self) -> &'static str {
+        match self {
+            MtreeKeyword::Cksum => "cksum",
+            MtreeKeyword::Md5 => "md5",
+            MtreeKeyword::Sha1 => "sha1",
+            MtreeKeyword::Sha256 => "sha256",
+            MtreeKeyword::Sha512 => "sha512",
+            MtreeKeyword::Size => "size",
+            MtreeKeyword::Time => "time",
+            MtreeKeyword::Mode => "mode",
+            MtreeKeyword::Uid => "uid",
+            MtreeKeyword::Gid => "gid",
+            MtreeKeyword::Type => "type",
+            MtreeKeyword::Link => "link",
+        }
+    }
+}
+
+/// When the zip writer uses Zip64 extensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zip64Mode {
+    /// Let libarchive decide: Zip64 is used for entries of unknown or large
+    /// size and for archives with more than 65535 entries
+    #[default]
+    Auto,
+    /// Always write Zip64 extra fields and end-of-central-directory records
+    Always,
+    /// Never use Zip64, for consumers that cannot read it
+    ///
+    /// Entries larger than 4 GiB are then rejected by `write_header`.
+    Never,
+}
+
 /// Compression level (0-9)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CompressionLevel(u8);
@@ -187,6 +429,17 @@ pub enum FormatOption {
     /// ZIP: Set compression level (0-9)
     ZipCompressionLevel(CompressionLevel),
 
+    /// ZIP: Choose the encryption scheme used with
+    /// [`WriteArchive::passphrase`](crate::WriteArchive::passphrase)
+    ///
+    /// Without this option a passphrase selects [`ZipEncryption::Aes256`].
+    /// The AES schemes need libarchive to be built with a crypto library;
+    /// otherwise `open_*` fails.
+    ZipEncryption(ZipEncryption),
+
+    /// ZIP: Control the use of Zip64 extensions
+    ZipZip64(Zip64Mode),
+
     /// ISO9660: Set volume ID
     Iso9660VolumeId(String),
 
@@ -196,11 +449,92 @@ pub enum FormatOption {
     /// ISO9660: Allow lowercase filenames
     Iso9660AllowLowercase(bool),
 
+    /// ISO9660: Compress file data with zisofs
+    ///
+    /// Images written this way can be mounted transparently by Linux kernels with
+    /// zisofs support, and libarchive decompresses them when reading. Requires
+    /// libarchive to be built with zlib.
+    ///
+    /// zisofs stores all-zero blocks as holes. `read_data` does not pad holes at
+    /// the end of a file, so use `read_data_block` (or extract to disk) when
+    /// reading back files that may end in zeros.
+    Iso9660Zisofs(bool),
+
+    /// ISO9660: Set the zisofs compression level (0-9)
+    ///
+    /// Only takes effect when [`Iso9660Zisofs`](Self::Iso9660Zisofs) is enabled.
+    Iso9660ZisofsLevel(CompressionLevel),
+
+    /// ISO9660: Write a Joliet directory tree
+    ///
+    /// Joliet stores names as UCS-2, keeping mixed case and names up to 64
+    /// characters, which Windows displays instead of the 8.3 ISO9660 names.
+    /// libarchive enables it by default.
+    Iso9660Joliet(bool),
+
+    /// ISO9660: Write Rock Ridge extensions
+    ///
+    /// Rock Ridge records POSIX names, permissions, owners and links, and is
+    /// preferred over Joliet by readers that support both. libarchive enables
+    /// it by default.
+    Iso9660RockRidge(bool),
+
+    /// ISO9660: Make the image bootable with an El Torito boot image
+    ///
+    /// The value is the pathname of a regular file written to the archive,
+    /// which becomes the boot image. `finish` fails if no such entry was
+    /// written.
+    Iso9660ElToritoBootImage(String),
+
+    /// ISO9660: Set the pathname of the El Torito boot catalog
+    ///
+    /// The catalog is written as a file at this path when a boot image is set
+    /// with [`Iso9660ElToritoBootImage`](Self::Iso9660ElToritoBootImage). The
+    /// default is `boot.catalog`.
+    Iso9660BootCatalog(String),
+
+    /// ISO9660: Set the abstract file identifier (at most 37 characters)
+    Iso9660AbstractFile(String),
+
+    /// ISO9660: Set the application identifier (at most 128 characters)
+    Iso9660ApplicationId(String),
+
+    /// ISO9660: Pad the image with 300 KiB of zeros at the end
+    ///
+    /// The padding works around read-ahead errors at the end of a disc on some
+    /// systems. libarchive enables it by default.
+    Iso9660PadTo64kBlocks(bool),
+
     /// TAR: Use GNU extensions for long pathnames
     TarGnuLongPathnames(bool),
 
     /// 7z: Set compression level (0-9)
+    ///
+    /// For LZMA the level also picks the dictionary size, so lowering it
+    /// reduces the memory needed to write and extract the archive.
     SevenZipCompressionLevel(CompressionLevel),
+
+    /// 7z: Set the compression method
+    ///
+    /// libarchive's default is LZMA1 when liblzma is available. Methods whose
+    /// library was not linked into libarchive make `open_*` fail with the
+    /// error libarchive reports.
+    SevenZipCompressionMethod(SevenZipMethod),
+
+    /// Mtree: Emit `/set` lines for values shared by consecutive entries
+    MtreeUseSet(bool),
+
+    /// Mtree: Indent entries and wrap long lines
+    MtreeIndent(bool),
+
+    /// Mtree: Emit exactly these keywords for each entry
+    ///
+    /// Replaces libarchive's default keyword set (device, flags, gid, gname,
+    /// link, mode, nlink, size, time, type, uid and uname). Digests are
+    /// computed from the data written for each entry. Include
+    /// [`MtreeKeyword::Type`] if libarchive should be able to read the
+    /// manifest back.
+    MtreeKeywords(Vec<MtreeKeyword>),
 }
 
 /// Filter-specific options for compression
@@ -215,9 +549,66 @@ pub enum FilterOption {
     /// XZ: Set compression level (0-9)
     XzCompressionLevel(CompressionLevel),
 
-    /// Zstd: Set compression level (0-22, but typically 0-9)
+    /// XZ: Compress with this many worker threads (0 means one per CPU core)
+    ///
+    /// Multithreaded xz splits the input into blocks that are compressed
+    /// independently, which costs a little compression ratio and more memory.
+    /// Requires liblzma built with multithreading support; otherwise
+    /// libarchive falls back to a single thread.
+    XzThreads(u32),
+
+    /// Zstd: Set compression level (0-22)
+    ///
+    /// Levels above 19 ("ultra" levels) need considerably more memory to
+    /// write and read back. Levels above 22 are rejected by libarchive.
     ZstdCompressionLevel(u8),
 
+    /// Zstd: Compress with this many worker threads (0 means one per CPU core)
+    ///
+    /// Requires libzstd built with multithreading support; otherwise
+    /// libarchive falls back to a single thread.
+    ZstdThreads(u32),
+
+    /// Zstd: Enable long-distance matching with the given window log (10-31)
+    ///
+    /// This is zstd's "long mode" (`zstd --long=<log>`), which finds matches
+    /// far apart in large archives that the default window would miss.
+    /// The window is `1 << log` bytes. Readers need at least that much
+    /// memory, and window logs above 27 must be allowed explicitly by the
+    /// decompressor. Values outside 10-31 are rejected by libarchive.
+    ZstdLongDistanceMatching(u8),
+
     /// LZ4: Set compression level (0-9)
     Lz4CompressionLevel(CompressionLevel),
 }
+
+/// Options for archive reading
+///
+/// Set with [`ReadArchive::set_read_option`](crate::ReadArchive::set_read_option)
+/// before the archive is opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadOption {
+    /// ZIP: Don't fail on CRC-32 mismatches
+    ///
+    /// Useful for salvaging data from damaged archives; corrupted entry data is
+    /// returned as is.
+    ZipIgnoreCrc32(bool),
+
+    /// ZIP: Character set of entry names that aren't flagged as UTF-8 (e.g. `"CP437"`)
+    ZipHeaderCharset(String),
+
+    /// TAR: Character set of entry names in headers (e.g. `"ISO-8859-1"`)
+    ///
+    /// Pax archives declare their own charset; this mostly matters for ustar
+    /// and GNU tar archives written on systems with a non-UTF-8 locale. Names
+    /// are converted to the charset of the process locale, so the locale must
+    /// be able to represent them (e.g. a UTF-8 locale rather than `C`).
+    TarHeaderCharset(String),
+
+    /// TAR: Merge macOS `._` AppleDouble entries into the metadata of the files
+    /// they belong to
+    TarMacExt(bool),
+
+    /// MTREE: Check the files the specification describes against the file system
+    MtreeCheckFs(bool),
+}
@@ -37,36 +37,60 @@
 #![deny(missing_docs)]
 
 mod acl_xattr;
+mod append;
 mod callbacks;
+mod convert;
+mod decompress;
+mod detect;
+mod digest;
 mod entry;
 mod error;
 mod extract;
 mod format;
 mod locale;
 mod match_filter;
+mod parallel;
+mod path;
 /// macOS .pkg Payload (pbzx) decompression and compression
 pub mod pbzx;
 mod pkg;
 mod read_disk;
 mod reader;
+mod stats;
+mod two_pass;
+mod verify;
 mod writer;
 
 pub use acl_xattr::{
     AclEntry, AclPermissions, AclTag, AclType, EntryAclExt, EntryMutAclExt, Xattr,
 };
+pub use append::AppendStrategy;
 pub use callbacks::{CallbackReader, CallbackWriter, ProgressCallback, ProgressTracker};
-pub use entry::{Entry, EntryMut, FileType};
-pub use error::{Error, Result};
-pub use extract::{ExtractFlags, WriteDisk};
+pub use convert::{ConvertOptions, ConvertStats, convert};
+pub use decompress::{decompress_file, decompress_reader};
+pub use detect::{DetectedFormat, detect_format, detect_format_file};
+pub use digest::{DigestAlgo, HashAlgo};
+#[allow(deprecated)]
+pub use entry::EntryMetadata;
+pub use entry::{Entry, EntryMut, FileType, OwnedEntry, SymlinkType, ZipEntryInfo};
+pub use error::{
+    ARCHIVE_EOF, ARCHIVE_FAILED, ARCHIVE_FATAL, ARCHIVE_OK, ARCHIVE_RETRY, ARCHIVE_WARN, Error,
+    Result, Severity,
+};
+pub use extract::{ExtractFlags, ExtractStats, WriteDisk};
 pub use format::{
-    ArchiveFormat, CompressionFormat, CompressionLevel, FilterOption, FormatOption, ReadFormat,
-    ZipCompressionMethod,
+    ArchiveFormat, CompressionFormat, CompressionLevel, FilterOption, FormatOption, MtreeKeyword,
+    ReadFormat, ReadOption, SevenZipMethod, Zip64Mode, ZipCompressionMethod, ZipEncryption,
 };
 pub use match_filter::ArchiveMatch;
+pub use parallel::extract_parallel;
 pub use pkg::{PkgReader, PkgWriter};
 pub use read_disk::{ReadDisk, ReadDiskFlags, SymlinkMode};
-pub use reader::ReadArchive;
-pub use writer::WriteArchive;
+pub use reader::{EncryptionStatus, Entries, ReadArchive};
+pub use stats::{ReadStats, WriteStats};
+pub use two_pass::{EntryData, TwoPass, TwoPassTotals};
+pub use verify::{VerifyFailure, VerifyReport};
+pub use writer::{DuplicatePolicy, EntryWriter, UnsupportedMetadataPolicy, WriteArchive};
 
 /// Returns the version string of the underlying libarchive library
 pub fn version() -> String {
@@ -5,7 +5,9 @@
 
 use crate::entry::Entry;
 use crate::error::{Error, Result};
-use std::ffi::CString;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::io::BufRead;
 
 /// Archive matcher for filtering entries based on patterns
 ///
@@ -15,6 +17,11 @@ use std::ffi::CString;
 /// but cannot share references across threads.
 pub struct ArchiveMatch {
     matcher: *mut libarchive2_sys::archive,
+    // libarchive has no owner exclusion, so these are checked in matches()
+    excluded_uids: HashSet<i64>,
+    excluded_gids: HashSet<i64>,
+    // Escaped patterns registered by include_pathname, mapped back to the pathname
+    included_pathnames: HashMap<String, String>,
 }
 
 // SAFETY: ArchiveMatch can be sent between threads because the matcher pointer
@@ -33,7 +40,12 @@ impl ArchiveMatch {
             if matcher.is_null() {
                 return Err(Error::NullPointer);
             }
-            Ok(ArchiveMatch { matcher })
+            Ok(ArchiveMatch {
+                matcher,
+                excluded_uids: HashSet::new(),
+                excluded_gids: HashSet::new(),
+                included_pathnames: HashMap::new(),
+            })
         }
     }
 
@@ -94,7 +106,10 @@ impl ArchiveMatch {
 
     /// Include entries matching a specific pathname
     ///
-    /// This is for exact pathname matches, not patterns.
+    /// This is for exact pathname matches, not patterns: wildcard characters
+    /// in `pathname` are matched literally, so `"data[1].txt"` only matches
+    /// that name. As with tar, a directory pathname also matches everything
+    /// below it, but `"src"` does not match a sibling such as `"src2"`.
     ///
     /// # Examples
     ///
@@ -107,45 +122,67 @@ impl ArchiveMatch {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn include_pathname(&mut self, pathname: &str) -> Result<()> {
-        let c_pathname = CString::new(pathname)
-            .map_err(|_| Error::InvalidArgument("Pathname contains null byte".to_string()))?;
-
-        unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_match_include_file_time(
-                    self.matcher,
-                    libarchive2_sys::ARCHIVE_MATCH_MTIME as i32,
-                    c_pathname.as_ptr(),
-                ),
-                self.matcher,
-            )?;
-        }
+        let pattern = escape_pathname(pathname)?;
+        self.include_pattern(&pattern)?;
+        self.included_pathnames
+            .insert(pattern, pathname.to_string());
         Ok(())
     }
 
     /// Exclude entries matching a specific pathname
     ///
-    /// **Note**: This method is not yet fully implemented. Use `exclude_pattern()`
-    /// for pattern-based exclusions instead.
+    /// Like [`include_pathname`](Self::include_pathname), `pathname` is
+    /// matched literally, and excluding a directory also excludes its
+    /// contents.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ArchiveMatch;
+    ///
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.exclude_pathname(".DS_Store")?;
+    /// matcher.exclude_pathname("build")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn exclude_pathname(&mut self, pathname: &str) -> Result<()> {
+        self.exclude_pattern(&escape_pathname(pathname)?)
+    }
+
+    /// Include entries matching the patterns read from `reader`, one per line
+    ///
+    /// Blank lines and comments (lines whose first non-blank character is `#`)
+    /// are skipped; every other line is
+    /// passed to [`include_pattern`](Self::include_pattern) as it is, apart
+    /// from its line ending. Returns the number of patterns registered.
+    pub fn include_patterns_from_reader<R: BufRead>(&mut self, reader: R) -> Result<usize> {
+        patterns_from_reader(reader, |pattern| self.include_pattern(pattern))
+    }
+
+    /// Exclude entries matching the patterns read from `reader`, one per line
+    ///
+    /// This reads an exclude file like tar's `--exclude-from`: blank lines and
+    /// `#` comments are skipped, and every other line is passed to
+    /// [`exclude_pattern`](Self::exclude_pattern). Returns the number of
+    /// patterns registered.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use libarchive2::ArchiveMatch;
+    /// use std::fs::File;
+    /// use std::io::BufReader;
     ///
     /// let mut matcher = ArchiveMatch::new()?;
-    /// // Use exclude_pattern instead:
-    /// matcher.exclude_pattern(".DS_Store")?;
-    /// matcher.exclude_pattern("thumbs.db")?;
+    /// let count = matcher.exclude_patterns_from_reader(BufReader::new(File::open(".tarignore")?))?;
+    /// println!("{} exclude patterns", count);
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn exclude_pathname(&mut self, _pathname: &str) -> Result<()> {
-        Err(Error::InvalidArgument(
-            "exclude_pathname is not yet implemented. Use exclude_pattern() instead.".to_string(),
-        ))
+    pub fn exclude_patterns_from_reader<R: BufRead>(&mut self, reader: R) -> Result<usize> {
+        patterns_from_reader(reader, |pattern| self.exclude_pattern(pattern))
     }
 
-    /// Include only entries newer than the specified time (in seconds since epoch)
+    /// Include only entries modified after the specified time (in seconds since epoch)
     ///
     /// # Examples
     ///
@@ -165,7 +202,8 @@ impl ArchiveMatch {
             Error::from_return_code(
                 libarchive2_sys::archive_match_include_time(
                     self.matcher,
-                    libarchive2_sys::ARCHIVE_MATCH_NEWER as i32,
+                    (libarchive2_sys::ARCHIVE_MATCH_MTIME | libarchive2_sys::ARCHIVE_MATCH_NEWER)
+                        as i32,
                     sec,
                     nsec as _,
                 ),
@@ -175,7 +213,7 @@ impl ArchiveMatch {
         Ok(())
     }
 
-    /// Include only entries older than the specified time (in seconds since epoch)
+    /// Include only entries modified before the specified time (in seconds since epoch)
     ///
     /// # Examples
     ///
@@ -195,7 +233,8 @@ impl ArchiveMatch {
             Error::from_return_code(
                 libarchive2_sys::archive_match_include_time(
                     self.matcher,
-                    libarchive2_sys::ARCHIVE_MATCH_OLDER as i32,
+                    (libarchive2_sys::ARCHIVE_MATCH_MTIME | libarchive2_sys::ARCHIVE_MATCH_OLDER)
+                        as i32,
                     sec,
                     nsec as _,
                 ),
@@ -249,27 +288,37 @@ impl ArchiveMatch {
 
     /// Exclude entries with owner matching the specified UID
     ///
-    /// **Note**: This method is not yet implemented as libarchive doesn't provide
-    /// a direct API for excluding UIDs. Use custom filtering in your application code.
-    pub fn exclude_uid(&mut self, _uid: i64) -> Result<()> {
-        Err(Error::InvalidArgument(
-            "exclude_uid is not supported by libarchive. Implement custom filtering.".to_string(),
-        ))
+    /// libarchive has no owner exclusion, so this is checked by
+    /// [`matches`](Self::matches) after libarchive's own filters. Entries
+    /// without a uid are never excluded by it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ArchiveMatch;
+    ///
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.exclude_uid(0)?;  // Skip files owned by root
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn exclude_uid(&mut self, uid: i64) -> Result<()> {
+        self.excluded_uids.insert(uid);
+        Ok(())
     }
 
     /// Exclude entries with group matching the specified GID
     ///
-    /// **Note**: This method is not yet implemented as libarchive doesn't provide
-    /// a direct API for excluding GIDs. Use custom filtering in your application code.
-    pub fn exclude_gid(&mut self, _gid: i64) -> Result<()> {
-        Err(Error::InvalidArgument(
-            "exclude_gid is not supported by libarchive. Implement custom filtering.".to_string(),
-        ))
+    /// Like [`exclude_uid`](Self::exclude_uid), this is checked by
+    /// [`matches`](Self::matches) rather than by libarchive.
+    pub fn exclude_gid(&mut self, gid: i64) -> Result<()> {
+        self.excluded_gids.insert(gid);
+        Ok(())
     }
 
     /// Check if an entry matches the configured filters
     ///
-    /// Returns `true` if the entry should be included based on the configured patterns.
+    /// Returns `true` if the entry should be included based on the configured
+    /// patterns, times and owners, including the uid/gid exclusions.
     ///
     /// # Examples
     ///
@@ -292,12 +341,35 @@ impl ArchiveMatch {
             let ret = libarchive2_sys::archive_match_excluded(self.matcher, entry.entry);
             if ret < 0 {
                 // Error occurred
-                Err(Error::from_archive(self.matcher))
-            } else {
-                // 0 = not excluded (matches), >0 = excluded
-                Ok(ret == 0)
+                return Err(Error::from_archive(self.matcher, ret));
+            }
+            // 0 = not excluded (matches), >0 = excluded
+            if ret != 0 {
+                return Ok(false);
             }
         }
+        Ok(!self.owner_excluded(entry))
+    }
+
+    /// Raw matcher handle, for handing the matcher to a disk reader
+    pub(crate) fn as_ptr(&self) -> *mut libarchive2_sys::archive {
+        self.matcher
+    }
+
+    /// Whether [`exclude_uid`](Self::exclude_uid) or
+    /// [`exclude_gid`](Self::exclude_gid) was used; libarchive can't apply these
+    pub(crate) fn has_owner_exclusions(&self) -> bool {
+        !self.excluded_uids.is_empty() || !self.excluded_gids.is_empty()
+    }
+
+    /// Check if an entry's uid or gid is excluded by
+    /// [`exclude_uid`](Self::exclude_uid) or [`exclude_gid`](Self::exclude_gid)
+    pub fn owner_excluded(&self, entry: &Entry) -> bool {
+        let excluded = |id: Option<u64>, set: &HashSet<i64>| {
+            id.and_then(|id| i64::try_from(id).ok())
+                .is_some_and(|id| set.contains(&id))
+        };
+        excluded(entry.uid(), &self.excluded_uids) || excluded(entry.gid(), &self.excluded_gids)
     }
 
     /// Check if an entry is time-excluded based on the configured time filters
@@ -315,6 +387,64 @@ impl ArchiveMatch {
             Ok(ret != 0)
         }
     }
+
+    /// Get the include patterns that have not matched any entry yet
+    ///
+    /// Call this after checking every entry of the archive with
+    /// [`matches`](Self::matches) (or [`path_excluded`](Self::path_excluded)) to
+    /// find inclusions that matched nothing, such as a misspelled path. Pathnames
+    /// registered with [`include_pathname`](Self::include_pathname) are returned
+    /// as given, not as the escaped pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveMatch, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("backup.tar")?;
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.include_pathname("etc/hosts")?;
+    /// matcher.include_pathname("etc/fstab")?;
+    ///
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     if matcher.matches(&entry)? {
+    ///         println!("{}", entry.pathname().unwrap_or_default());
+    ///     }
+    /// }
+    /// for missing in matcher.unmatched_inclusions()? {
+    ///     eprintln!("{}: not found in archive", missing);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn unmatched_inclusions(&mut self) -> Result<Vec<String>> {
+        let mut unmatched = Vec::new();
+        // SAFETY: matcher is valid for the lifetime of self. The iteration resets
+        // itself once it reports ARCHIVE_EOF, so it always runs to the end.
+        unsafe {
+            let count = libarchive2_sys::archive_match_path_unmatched_inclusions(self.matcher);
+            Error::from_return_code(count, self.matcher)?;
+            loop {
+                let mut ptr: *const std::os::raw::c_char = std::ptr::null();
+                let ret = libarchive2_sys::archive_match_path_unmatched_inclusions_next(
+                    self.matcher,
+                    &mut ptr,
+                );
+                if ret == libarchive2_sys::ARCHIVE_EOF as i32 {
+                    break;
+                }
+                Error::from_return_code(ret, self.matcher)?;
+                if ptr.is_null() {
+                    continue;
+                }
+                let pattern = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+                unmatched.push(match self.included_pathnames.get(&pattern) {
+                    Some(pathname) => pathname.clone(),
+                    None => pattern,
+                });
+            }
+        }
+        Ok(unmatched)
+    }
 }
 
 impl Drop for ArchiveMatch {
@@ -327,5 +457,50 @@ impl Drop for ArchiveMatch {
     }
 }
 
+/// Register each pattern line of `reader`, skipping blank lines and `#` comments
+fn patterns_from_reader<R: BufRead>(
+    reader: R,
+    mut register: impl FnMut(&str) -> Result<()>,
+) -> Result<usize> {
+    let mut count = 0;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let pattern = line.strip_suffix('\r').unwrap_or(&line);
+        let trimmed = pattern.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        register(pattern).map_err(|e| match e {
+            Error::InvalidArgument(message) => {
+                Error::InvalidArgument(format!("line {}: {}", number + 1, message))
+            }
+            other => other,
+        })?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Turn a literal pathname into a pattern that only matches that pathname
+///
+/// libarchive's pattern matcher treats a backslash as an escape, so every
+/// wildcard and anchor character is escaped. A leading `^` anchors the
+/// pattern at the start of the path; without it exclusions also match the
+/// name inside any directory.
+fn escape_pathname(pathname: &str) -> Result<String> {
+    if pathname.is_empty() {
+        return Err(Error::InvalidArgument("Pathname is empty".to_string()));
+    }
+    let mut pattern = String::with_capacity(pathname.len() + 1);
+    pattern.push('^');
+    for c in pathname.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\' | '^' | '$') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    Ok(pattern)
+}
+
 // Note: Default implementation removed because matcher creation can fail.
 // Use ArchiveMatch::new() instead.
@@ -0,0 +1,280 @@
+//! Extraction with several disk writers working concurrently
+
+use crate::entry::{EntryMut, FileType, prepare_strings};
+use crate::error::{Error, Result};
+use crate::extract::{ExtractFlags, ExtractStats, WriteDisk};
+use crate::reader::{ReadArchive, join_contained};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, sync_channel};
+
+/// Entries each worker may have queued before the reader waits for it
+const QUEUE_DEPTH: usize = 16;
+
+/// A header and its data, read on the reader thread and written by a worker
+struct WorkItem {
+    entry: EntryMut,
+    /// Data blocks with their offsets, so sparse files keep their holes
+    blocks: Vec<(i64, Vec<u8>)>,
+}
+
+// SAFETY: The entry is an owned clone that only the thread holding the item
+// touches. Its strings are converted on the reader thread before it is sent
+// (see prepare_strings), so writing it never reaches back into the reader.
+unsafe impl Send for WorkItem {}
+
+/// Extract an archive file into a directory using several threads
+///
+/// The archive is read on the calling thread, which hands regular files and
+/// symlinks to `num_threads` worker threads, each writing through its own
+/// [`WriteDisk`]. Entries with the same pathname always go to the same worker,
+/// so a later copy of a file still replaces an earlier one. Directories are
+/// created on the calling thread before any entry inside them is handed out,
+/// and hardlinks are created once every worker has finished, when their
+/// targets are known to exist. Directory permissions and times are restored
+/// last, as libarchive does for a single writer.
+///
+/// `flags` and the handling of paths are as for
+/// [`ReadArchive::extract_to_dir`], and the returned stats add up the counts
+/// of every thread. A `num_threads` of 0 uses one worker per available CPU.
+///
+/// Each queued file is held in memory with all of its data, so peak memory
+/// grows with the size of the largest files and the number of threads.
+///
+/// # Errors
+///
+/// Returns the first error hit by the reader or any worker. The other threads
+/// stop at their next entry; entries already extracted stay on disk.
+///
+/// # Examples
+///
+/// ```no_run
+/// use libarchive2::{ExtractFlags, extract_parallel};
+///
+/// let stats = extract_parallel("backup.tar.zst", "restore", ExtractFlags::TIME, 4)?;
+/// println!("{} files, {} bytes", stats.files, stats.bytes);
+/// # Ok::<(), libarchive2::Error>(())
+/// ```
+pub fn extract_parallel<P: AsRef<Path>, Q: AsRef<Path>>(
+    archive_path: P,
+    dest: Q,
+    flags: ExtractFlags,
+    num_threads: usize,
+) -> Result<ExtractStats> {
+    let num_threads = match num_threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let dest = dest.as_ref();
+    let mut reader = ReadArchive::open(archive_path)?;
+    std::fs::create_dir_all(dest).map_err(Error::Io)?;
+
+    let flags = (flags | ExtractFlags::SECURE_SYMLINKS | ExtractFlags::SECURE_NODOTDOT)
+        .without(ExtractFlags::SECURE_NOABSOLUTEPATHS);
+    // Directories and hardlinks are written here; closing it last restores
+    // the directories' metadata after everything inside them exists
+    let mut disk = new_disk(flags)?;
+
+    let failure = Mutex::new(None);
+    let mut stats = ExtractStats::default();
+    let mut hardlinks = Vec::new();
+    // Names handed out so far; with NO_OVERWRITE a queued entry may not be on
+    // disk yet when a later entry with the same name is read
+    let mut queued = HashSet::new();
+
+    std::thread::scope(|scope| {
+        let mut senders = Vec::with_capacity(num_threads);
+        let mut workers = Vec::with_capacity(num_threads);
+        for _ in 0..num_threads {
+            let (sender, receiver) = sync_channel(QUEUE_DEPTH);
+            senders.push(sender);
+            let failure = &failure;
+            workers.push(scope.spawn(move || run_worker(receiver, flags, failure)));
+        }
+
+        let result = (|| -> Result<()> {
+            while reader.next_entry()?.is_some() {
+                if failure.lock().unwrap().is_some() {
+                    return Ok(());
+                }
+                let Some(item) = read_item(&mut reader, dest, flags, &mut queued, &mut stats)?
+                else {
+                    continue;
+                };
+                let (is_hardlink, file_type, pathname) = {
+                    let entry = item.entry.as_entry();
+                    (
+                        entry.hardlink().is_some(),
+                        entry.file_type(),
+                        entry.pathname_bytes(),
+                    )
+                };
+                if is_hardlink {
+                    hardlinks.push(item);
+                } else if file_type == FileType::Directory {
+                    write_item(&mut disk, item, &mut stats)?;
+                } else {
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    pathname.hash(&mut hasher);
+                    let worker = (hasher.finish() % num_threads as u64) as usize;
+                    // A closed queue means its worker failed and recorded why
+                    if senders[worker].send(item).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            record_failure(&failure, e);
+        }
+
+        drop(senders);
+        for worker in workers {
+            let worker_stats = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            merge(&mut stats, &worker_stats);
+        }
+    });
+
+    if let Some(e) = failure.into_inner().unwrap() {
+        return Err(e);
+    }
+    for item in hardlinks {
+        write_item(&mut disk, item, &mut stats)?;
+    }
+    stats.warnings += (disk.warnings().len() + reader.warnings().len()) as u64;
+    stats.stats = reader.stats();
+    disk.close()?;
+    Ok(stats)
+}
+
+/// A disk writer set up as [`ReadArchive::extract_to_dir`] sets up its own
+fn new_disk(flags: ExtractFlags) -> Result<WriteDisk> {
+    let mut disk = WriteDisk::new()?;
+    disk.set_options(flags)?;
+    disk.set_standard_lookup()?;
+    Ok(disk)
+}
+
+/// Copy the reader's current entry and its data, with paths joined onto
+/// `dest`, or `None` if `NO_OVERWRITE` leaves it alone because the path exists
+/// or is in `queued`
+fn read_item(
+    reader: &mut ReadArchive<'_>,
+    dest: &Path,
+    flags: ExtractFlags,
+    queued: &mut HashSet<Vec<u8>>,
+    stats: &mut ExtractStats,
+) -> Result<Option<WorkItem>> {
+    let entry_ptr = reader.current_entry_for_extraction()?;
+    // SAFETY: entry_ptr is the reader's current header, valid until next_entry
+    let cloned = unsafe { libarchive2_sys::archive_entry_clone(entry_ptr) };
+    if cloned.is_null() {
+        return Err(Error::NullPointer);
+    }
+    let entry = EntryMut {
+        entry: cloned,
+        owned: true,
+    };
+
+    let view = entry.as_entry();
+    let pathname = view.pathname().unwrap_or_default();
+    let full_path = join_contained(dest, &pathname, &pathname)?;
+    let full_link = match view.hardlink() {
+        Some(target) => Some(join_contained(dest, &target, &pathname)?),
+        None => None,
+    };
+    if flags.contains(ExtractFlags::NO_OVERWRITE) && view.file_type() != FileType::Directory {
+        let name = full_path.as_bytes().to_vec();
+        if queued.contains(&name) || std::fs::symlink_metadata(dest.join(&pathname)).is_ok() {
+            stats.skipped += 1;
+            return Ok(None);
+        }
+        queued.insert(name);
+    }
+
+    // Read to EOF whatever the header says: streamed entries may have no size
+    let mut blocks = Vec::new();
+    while let Some(block) = reader.read_data_block()? {
+        blocks.push(block);
+    }
+
+    // SAFETY: cloned is a valid entry owned by `entry`
+    unsafe {
+        libarchive2_sys::archive_entry_update_pathname_utf8(cloned, full_path.as_ptr());
+        if let Some(link) = &full_link {
+            libarchive2_sys::archive_entry_update_hardlink_utf8(cloned, link.as_ptr());
+        }
+        prepare_strings(cloned);
+    }
+    Ok(Some(WorkItem { entry, blocks }))
+}
+
+/// Write queued entries until the queue closes or another thread fails
+fn run_worker(
+    receiver: Receiver<WorkItem>,
+    flags: ExtractFlags,
+    failure: &Mutex<Option<Error>>,
+) -> ExtractStats {
+    let mut stats = ExtractStats::default();
+    let result = (|| -> Result<()> {
+        let mut disk = new_disk(flags)?;
+        for item in receiver {
+            if failure.lock().unwrap().is_some() {
+                return Ok(());
+            }
+            write_item(&mut disk, item, &mut stats)?;
+        }
+        stats.warnings += disk.warnings().len() as u64;
+        disk.close()
+    })();
+    if let Err(e) = result {
+        record_failure(failure, e);
+    }
+    stats
+}
+
+/// Write one entry and count it
+fn write_item(disk: &mut WriteDisk, item: WorkItem, stats: &mut ExtractStats) -> Result<()> {
+    let read: usize = item.blocks.iter().map(|(_, data)| data.len()).sum();
+    disk.write_header(&item.entry)?;
+    for (offset, data) in &item.blocks {
+        disk.write_data_block(*offset, data)?;
+    }
+    disk.finish_entry()?;
+
+    let entry = item.entry.as_entry();
+    match (entry.file_type(), entry.hardlink().is_some()) {
+        (_, true) => stats.hardlinks += 1,
+        (FileType::RegularFile, false) => {
+            stats.files += 1;
+            stats.bytes += entry
+                .size_opt()
+                .map_or(read as u64, |size| size.max(0) as u64);
+        }
+        (FileType::Directory, false) => stats.directories += 1,
+        (FileType::SymbolicLink, false) => stats.symlinks += 1,
+        _ => stats.other += 1,
+    }
+    Ok(())
+}
+
+/// Keep `error` unless another thread failed first
+fn record_failure(failure: &Mutex<Option<Error>>, error: Error) {
+    failure.lock().unwrap().get_or_insert(error);
+}
+
+fn merge(total: &mut ExtractStats, part: &ExtractStats) {
+    total.files += part.files;
+    total.directories += part.directories;
+    total.symlinks += part.symlinks;
+    total.hardlinks += part.hardlinks;
+    total.other += part.other;
+    total.bytes += part.bytes;
+    total.skipped += part.skipped;
+    total.warnings += part.warnings;
+}
@@ -0,0 +1,95 @@
+//! Conversion of filesystem paths to the C strings libarchive expects, and
+//! rewriting of the paths stored in entries
+//!
+//! On Unix a path is an arbitrary byte string, so it is passed through as-is
+//! rather than requiring UTF-8. On Windows the wide-character (`_w`) entry
+//! points take the path as UTF-16; the narrow ones still need UTF-8.
+
+use crate::error::{Error, Result};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+
+/// Convert a path to a NUL-terminated narrow string
+#[cfg(unix)]
+pub(crate) fn to_cstring(path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))
+}
+
+/// Convert a path to a NUL-terminated narrow string
+#[cfg(not(unix))]
+pub(crate) fn to_cstring(path: &Path) -> Result<CString> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
+    CString::new(path_str)
+        .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))
+}
+
+/// Convert a path to a NUL-terminated UTF-16 string for the `_w` functions
+#[cfg(windows)]
+pub(crate) fn to_wide(path: &Path) -> Result<Vec<u16>> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let mut wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+    if wide.contains(&0) {
+        return Err(Error::InvalidArgument(
+            "Path contains null byte".to_string(),
+        ));
+    }
+    wide.push(0);
+    Ok(wide)
+}
+
+/// Rewrite the pathname and hardlink target of an entry
+///
+/// `map` is called with each of the two names that is set, decoded as UTF-8
+/// (invalid sequences are replaced), and returns the new name. A name is only
+/// updated when `map` changes it. If `map` returns `None` the remaining name is
+/// left alone and `Ok(false)` is returned so the caller can skip the entry.
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+pub(crate) unsafe fn map_entry_paths(
+    entry: *mut libarchive2_sys::archive_entry,
+    mut map: impl FnMut(&str) -> Option<String>,
+) -> Result<bool> {
+    type Get = unsafe extern "C" fn(*mut libarchive2_sys::archive_entry) -> *const c_char;
+    type Update = unsafe extern "C" fn(*mut libarchive2_sys::archive_entry, *const c_char) -> c_int;
+
+    let fields: [(Get, Update); 2] = [
+        (
+            libarchive2_sys::archive_entry_pathname_utf8,
+            libarchive2_sys::archive_entry_update_pathname_utf8,
+        ),
+        (
+            libarchive2_sys::archive_entry_hardlink_utf8,
+            libarchive2_sys::archive_entry_update_hardlink_utf8,
+        ),
+    ];
+    for (get, update) in fields {
+        // SAFETY: Caller guarantees entry is valid
+        let ptr = unsafe { get(entry) };
+        if ptr.is_null() {
+            continue;
+        }
+        // SAFETY: libarchive returns a valid NUL-terminated string
+        let path = unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned();
+        match map(&path) {
+            None => return Ok(false),
+            Some(new_path) if new_path == path => {}
+            Some(new_path) => {
+                let c_path = CString::new(new_path)
+                    .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+                // SAFETY: entry is valid and c_path is a valid C string
+                unsafe { update(entry, c_path.as_ptr()) };
+            }
+        }
+    }
+    Ok(true)
+}
@@ -55,23 +55,16 @@ use std::time::SystemTime;
 /// let mut pkg = PkgReader::open("MyApp.pkg")?;
 /// while let Some(entry) = pkg.next_entry()? {
 ///     let path = entry.pathname().unwrap_or_default();
-///     let size = entry.size();
+///     let size = entry.size_opt().unwrap_or(0);
 ///     println!("{path} ({size} bytes)");
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub struct PkgReader {
-    /// Decompressed CPIO data (owned so the inner ReadArchive can borrow it)
-    _cpio_data: Vec<u8>,
-    /// Inner archive reader over the CPIO data.
-    /// Raw pointer to manage drop order: the reader must be dropped before _cpio_data.
-    inner: *mut ReadArchive<'static>,
+    /// Archive reader over the decompressed CPIO data, which it owns
+    inner: ReadArchive<'static>,
 }
 
-// SAFETY: PkgReader owns both the data and the reader exclusively.
-// Same Send-but-not-Sync contract as ReadArchive.
-unsafe impl Send for PkgReader {}
-
 impl PkgReader {
     /// Open a `.pkg` file and prepare it for reading
     ///
@@ -109,26 +102,24 @@ impl PkgReader {
     ///
     /// Returns `None` when all entries have been read.
     pub fn next_entry(&mut self) -> Result<Option<Entry<'_>>> {
-        // SAFETY: inner is valid and points to a ReadArchive that borrows _cpio_data.
-        // We ensure proper lifetime management through the struct's drop order.
-        unsafe { &mut *self.inner }.next_entry()
+        self.inner.next_entry()
     }
 
     /// Read data from the current entry into the provided buffer
     ///
     /// Returns the number of bytes read (0 means end of entry data).
     pub fn read_data(&mut self, buf: &mut [u8]) -> Result<usize> {
-        unsafe { &mut *self.inner }.read_data(buf)
+        self.inner.read_data(buf)
     }
 
     /// Read all data from the current entry into a vector
     pub fn read_data_to_vec(&mut self) -> Result<Vec<u8>> {
-        unsafe { &mut *self.inner }.read_data_to_vec()
+        self.inner.read_data_to_vec()
     }
 
     /// Skip the data for the current entry
     pub fn skip_data(&mut self) -> Result<()> {
-        unsafe { &mut *self.inner }.skip_data()
+        self.inner.skip_data()
     }
 
     // -- internal helpers --
@@ -138,12 +129,7 @@ impl PkgReader {
         xar.support_filter_all()?;
         xar.support_format(ReadFormat::Format(ArchiveFormat::Xar))?;
 
-        let path_str = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
-        let c_path = std::ffi::CString::new(path_str)
-            .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+        let c_path = crate::path::to_cstring(path.as_ref())?;
 
         unsafe {
             Error::from_return_code(
@@ -165,47 +151,16 @@ impl PkgReader {
     }
 
     fn from_cpio_data(cpio_data: Vec<u8>) -> Result<Self> {
-        let cpio_ptr = cpio_data.as_ptr();
-        let cpio_len = cpio_data.len();
-
         let mut reader = ReadArchive::new()?;
         reader.support_filter_all()?;
         reader.support_format(ReadFormat::Format(ArchiveFormat::Cpio))?;
 
-        unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_open_memory(
-                    reader.archive(),
-                    cpio_ptr as *const std::os::raw::c_void,
-                    cpio_len,
-                ),
-                reader.archive(),
-            )?;
-        }
-
-        // Transmute the lifetime to 'static. This is safe because:
-        // 1. _cpio_data is owned by PkgReader and won't be dropped before inner
-        // 2. We ensure proper drop order in the Drop impl
-        let reader: ReadArchive<'static> = unsafe { std::mem::transmute(reader) };
-        let inner = Box::into_raw(Box::new(reader));
-
         Ok(PkgReader {
-            _cpio_data: cpio_data,
-            inner,
+            inner: reader.open_owned_buffer(cpio_data)?,
         })
     }
 }
 
-impl Drop for PkgReader {
-    fn drop(&mut self) {
-        // Drop the reader first (it borrows _cpio_data), then _cpio_data
-        // is dropped automatically by the compiler.
-        unsafe {
-            drop(Box::from_raw(self.inner));
-        }
-    }
-}
-
 // ---------------------------------------------------------------------------
 // PkgWriter
 // ---------------------------------------------------------------------------
@@ -338,26 +293,19 @@ impl PkgWriter {
 
     fn build_cpio(&self) -> Result<Vec<u8>> {
         // Write CPIO to memory
-        let mut buffer = vec![0u8; 64 * 1024 * 1024]; // 64 MiB max
-        let mut used = 0usize;
-
-        {
-            let mut archive = WriteArchive::new()
-                .format(ArchiveFormat::Cpio)
-                .compression(CompressionFormat::None)
-                .open_memory(&mut buffer, &mut used)?;
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Cpio)
+            .compression(CompressionFormat::None)
+            .open_growable_memory()?;
 
-            for pkg_entry in &self.entries {
-                archive.write_header(&pkg_entry.entry)?;
-                if !pkg_entry.data.is_empty() {
-                    archive.write_data(&pkg_entry.data)?;
-                }
+        for pkg_entry in &self.entries {
+            archive.write_header(&pkg_entry.entry)?;
+            if !pkg_entry.data.is_empty() {
+                archive.write_data_all(&pkg_entry.data)?;
             }
-
-            archive.finish()?;
         }
 
-        Ok(buffer[..used].to_vec())
+        archive.finish_to_vec()
     }
 
     fn build_pbzx_payload(&self) -> Result<Vec<u8>> {
@@ -379,7 +327,7 @@ impl PkgWriter {
         entry.set_mtime(SystemTime::now());
 
         archive.write_header(&entry)?;
-        archive.write_data(payload)?;
+        archive.write_data_all(payload)?;
         archive.finish()?;
         Ok(())
     }
@@ -402,7 +350,7 @@ impl PkgWriter {
             entry.set_mtime(SystemTime::now());
 
             archive.write_header(&entry)?;
-            archive.write_data(payload)?;
+            archive.write_data_all(payload)?;
             archive.finish()?;
         }
 
@@ -1,12 +1,17 @@
 //! Read files from disk into archive entries
 
+use crate::callbacks::{DropFn, ExcludedCallbackFn};
 use crate::entry::EntryMut;
 use crate::error::{Error, Result};
-use std::ffi::CString;
+use crate::match_filter::ArchiveMatch;
+use std::ffi::c_void;
 use std::path::Path;
 
 /// Behavior flags for reading from disk
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The values are libarchive's `ARCHIVE_READDISK_*` constants. `Debug` prints the
+/// names of the flags that are set.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct ReadDiskFlags(i32);
 
 impl ReadDiskFlags {
@@ -14,33 +19,65 @@ impl ReadDiskFlags {
     pub const NONE: ReadDiskFlags = ReadDiskFlags(0);
 
     /// Restore access time after reading
-    pub const RESTORE_ATIME: ReadDiskFlags = ReadDiskFlags(0x0001);
+    pub const RESTORE_ATIME: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_RESTORE_ATIME as i32);
 
     /// Honor nodump flag (skip files marked with nodump)
-    pub const HONOR_NODUMP: ReadDiskFlags = ReadDiskFlags(0x0002);
+    pub const HONOR_NODUMP: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_HONOR_NODUMP as i32);
 
     /// Use Mac copyfile for resource forks
-    pub const MAC_COPYFILE: ReadDiskFlags = ReadDiskFlags(0x0004);
+    pub const MAC_COPYFILE: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_MAC_COPYFILE as i32);
 
     /// Don't traverse mount points
-    pub const NO_TRAVERSE_MOUNTS: ReadDiskFlags = ReadDiskFlags(0x0008);
+    pub const NO_TRAVERSE_MOUNTS: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_NO_TRAVERSE_MOUNTS as i32);
 
     /// Don't read extended attributes
-    pub const NO_XATTR: ReadDiskFlags = ReadDiskFlags(0x0010);
+    pub const NO_XATTR: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_NO_XATTR as i32);
 
     /// Don't read ACLs
-    pub const NO_ACL: ReadDiskFlags = ReadDiskFlags(0x0020);
+    pub const NO_ACL: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_NO_ACL as i32);
 
     /// Don't read file flags
-    pub const NO_FFLAGS: ReadDiskFlags = ReadDiskFlags(0x0040);
+    pub const NO_FFLAGS: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_NO_FFLAGS as i32);
 
     /// Don't read sparse file information
-    pub const NO_SPARSE: ReadDiskFlags = ReadDiskFlags(0x0080);
+    pub const NO_SPARSE: ReadDiskFlags =
+        ReadDiskFlags(libarchive2_sys::ARCHIVE_READDISK_NO_SPARSE as i32);
+
+    /// Every named flag, in bit order
+    const NAMED: &[(&str, ReadDiskFlags)] = &[
+        ("RESTORE_ATIME", ReadDiskFlags::RESTORE_ATIME),
+        ("HONOR_NODUMP", ReadDiskFlags::HONOR_NODUMP),
+        ("MAC_COPYFILE", ReadDiskFlags::MAC_COPYFILE),
+        ("NO_TRAVERSE_MOUNTS", ReadDiskFlags::NO_TRAVERSE_MOUNTS),
+        ("NO_XATTR", ReadDiskFlags::NO_XATTR),
+        ("NO_ACL", ReadDiskFlags::NO_ACL),
+        ("NO_FFLAGS", ReadDiskFlags::NO_FFLAGS),
+        ("NO_SPARSE", ReadDiskFlags::NO_SPARSE),
+    ];
 
     /// Get the raw integer value
     pub fn bits(&self) -> i32 {
         self.0
     }
+
+    /// Check whether all of the flags in `other` are set
+    pub fn contains(&self, other: ReadDiskFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::fmt::Debug for ReadDiskFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = Self::NAMED.iter().map(|(name, flag)| (*name, flag.0));
+        crate::extract::fmt_flags(f, "ReadDiskFlags", self.0, names)
+    }
 }
 
 impl std::ops::BitOr for ReadDiskFlags {
@@ -79,6 +116,11 @@ pub enum SymlinkMode {
 /// but cannot share references across threads.
 pub struct ReadDisk {
     archive: *mut libarchive2_sys::archive,
+    /// Matcher installed by `set_matching`; libarchive borrows it
+    matcher: Option<ArchiveMatch>,
+    /// Closure reporting excluded entries, freed after the archive
+    excluded_callback: Option<(*mut c_void, ExcludedCallbackFn, DropFn)>,
+    warnings: Vec<String>,
 }
 
 // SAFETY: ReadDisk can be sent between threads because the archive pointer
@@ -97,7 +139,12 @@ impl ReadDisk {
             if archive.is_null() {
                 return Err(Error::NullPointer);
             }
-            Ok(ReadDisk { archive })
+            Ok(ReadDisk {
+                archive,
+                matcher: None,
+                excluded_callback: None,
+                warnings: Vec::new(),
+            })
         }
     }
 
@@ -142,24 +189,199 @@ impl ReadDisk {
         Ok(())
     }
 
-    /// Open a path for reading
-    pub fn open<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let path_str = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
-        let c_path = CString::new(path_str)
-            .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+    /// Skip files and directories excluded by `matcher` during traversal
+    ///
+    /// Excluded entries are never returned by [`next_entry`](Self::next_entry),
+    /// so excluded directories are not descended into and their contents are
+    /// never visited. Owner exclusions from
+    /// [`ArchiveMatch::exclude_uid`] and [`ArchiveMatch::exclude_gid`] apply too.
+    /// Replaces any matcher set earlier.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveMatch, ReadDisk};
+    ///
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.exclude_pattern("*/.git")?;
+    /// matcher.exclude_pattern("*/target")?;
+    ///
+    /// let mut disk = ReadDisk::new()?;
+    /// disk.set_matching(matcher)?;
+    /// disk.open("my-project")?;
+    /// while let Some(entry) = disk.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    ///     if disk.can_descend() {
+    ///         disk.descend()?;
+    ///     }
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_matching(&mut self, matcher: ArchiveMatch) -> Result<()> {
+        self.install_matching(matcher, None)
+    }
+
+    /// Like [`set_matching`](Self::set_matching), and call `on_excluded` with the
+    /// pathname of each excluded entry
+    ///
+    /// For an excluded directory the callback fires once, for the directory
+    /// itself.
+    pub fn set_matching_with_callback<F>(
+        &mut self,
+        matcher: ArchiveMatch,
+        on_excluded: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str) + Send + 'static,
+    {
+        let parts = crate::callbacks::excluded_callback_parts(on_excluded);
+        self.install_matching(matcher, Some(parts))
+    }
+
+    fn install_matching(
+        &mut self,
+        matcher: ArchiveMatch,
+        callback: Option<(*mut c_void, ExcludedCallbackFn, DropFn)>,
+    ) -> Result<()> {
+        let (data, func) = match callback {
+            Some((data, func, _)) => (data, Some(func)),
+            None => (std::ptr::null_mut(), None),
+        };
+        let result = unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_read_disk_set_matching(
+                    self.archive,
+                    matcher.as_ptr(),
+                    func,
+                    data,
+                ),
+                self.archive,
+            )
+        };
+        if let Err(e) = result {
+            if let Some((data, _, drop_fn)) = callback {
+                // SAFETY: libarchive rejected the callback, so nothing else holds data
+                unsafe { drop_fn(data) };
+            }
+            return Err(e);
+        }
+
+        // libarchive now refers to the new matcher and callback only, so the
+        // old ones can go
+        self.matcher = Some(matcher);
+        if let Some((old, _, old_drop_fn)) =
+            std::mem::replace(&mut self.excluded_callback, callback)
+        {
+            // SAFETY: old was created by excluded_callback_parts and is no
+            // longer registered with libarchive
+            unsafe { old_drop_fn(old) };
+        }
+        Ok(())
+    }
+
+    /// Whether the installed matcher excludes `entry` by owner
+    ///
+    /// libarchive only applies the path and time rules while traversing.
+    fn owner_excluded(&mut self, entry: *mut libarchive2_sys::archive_entry) -> bool {
+        let Some(matcher) = self.matcher.as_ref() else {
+            return false;
+        };
+        if !matcher.has_owner_exclusions() {
+            return false;
+        }
+        let view = crate::entry::Entry {
+            entry,
+            archive: std::ptr::null_mut(),
+            _marker: std::marker::PhantomData,
+        };
+        if !matcher.owner_excluded(&view) {
+            return false;
+        }
+        if let Some((data, func, _)) = self.excluded_callback {
+            // SAFETY: data is the client data func was registered with
+            unsafe { func(self.archive, data, entry) };
+        }
+        true
+    }
+
+    /// Map uids to user names with a closure instead of the system database
+    ///
+    /// The closure receives each uid and returns the name to record in the
+    /// entry, or `None` to leave the name unset. Replaces
+    /// [`set_standard_lookup`](Self::set_standard_lookup) for user names.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadDisk;
+    ///
+    /// let mut disk = ReadDisk::new()?;
+    /// disk.set_uname_lookup(|uid| (uid == 0).then(|| "root".to_string()))?;
+    /// disk.set_gname_lookup(|_| Some("staff".to_string()))?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_uname_lookup<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: FnMut(i64) -> Option<String> + Send + 'static,
+    {
+        let (data, func, cleanup) = crate::callbacks::lookup_callback_parts(lookup);
+        // libarchive takes ownership of data and calls cleanup when the
+        // lookup is replaced or the archive is freed
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_read_disk_set_uname_lookup(
+                    self.archive,
+                    data,
+                    Some(func),
+                    Some(cleanup),
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
 
+    /// Map gids to group names with a closure instead of the system database
+    ///
+    /// See [`set_uname_lookup`](Self::set_uname_lookup).
+    pub fn set_gname_lookup<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: FnMut(i64) -> Option<String> + Send + 'static,
+    {
+        let (data, func, cleanup) = crate::callbacks::lookup_callback_parts(lookup);
+        // libarchive takes ownership of data and calls cleanup when the
+        // lookup is replaced or the archive is freed
         unsafe {
             Error::from_return_code(
-                libarchive2_sys::archive_read_disk_open(self.archive, c_path.as_ptr()),
+                libarchive2_sys::archive_read_disk_set_gname_lookup(
+                    self.archive,
+                    data,
+                    Some(func),
+                    Some(cleanup),
+                ),
                 self.archive,
             )?;
         }
         Ok(())
     }
 
+    /// Open a path for reading
+    pub fn open<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        #[cfg(windows)]
+        let path = crate::path::to_wide(path.as_ref())?;
+        #[cfg(not(windows))]
+        let path = crate::path::to_cstring(path.as_ref())?;
+
+        unsafe {
+            #[cfg(windows)]
+            let ret = libarchive2_sys::archive_read_disk_open_w(self.archive, path.as_ptr());
+            #[cfg(not(windows))]
+            let ret = libarchive2_sys::archive_read_disk_open(self.archive, path.as_ptr());
+            Error::from_return_code(ret, self.archive)?;
+        }
+        Ok(())
+    }
+
     /// Read the next entry from disk
     ///
     /// Returns `None` when there are no more entries.
@@ -173,6 +395,18 @@ impl ReadDisk {
     /// The entry's lifetime is tied to the ReadDisk instance. Do not use the entry
     /// after calling next_entry() again, as libarchive may reuse or free the memory.
     pub fn next_entry(&mut self) -> Result<Option<EntryMut>> {
+        loop {
+            let entry = self.next_header()?;
+            if let Some(entry) = &entry
+                && self.owner_excluded(entry.entry)
+            {
+                continue;
+            }
+            return Ok(entry);
+        }
+    }
+
+    fn next_header(&mut self) -> Result<Option<EntryMut>> {
         unsafe {
             // Create a new entry that will be populated by libarchive
             let entry_ptr = libarchive2_sys::archive_entry_new();
@@ -188,7 +422,11 @@ impl ReadDisk {
                 return Ok(None);
             }
 
-            if let Err(e) = Error::from_return_code(ret, self.archive) {
+            if ret == libarchive2_sys::ARCHIVE_WARN {
+                // The entry was read, e.g. without ACLs or extended attributes
+                // that could not be read; record the problem
+                self.warnings.push(Error::warning_message(self.archive));
+            } else if let Err(e) = Error::from_return_code(ret, self.archive) {
                 // Clean up the entry on error
                 libarchive2_sys::archive_entry_free(entry_ptr);
                 return Err(e);
@@ -203,6 +441,70 @@ impl ReadDisk {
         }
     }
 
+    /// Get the warnings recorded during traversal, oldest first
+    ///
+    /// [`next_entry`](Self::next_entry) returns entries libarchive read with a
+    /// warning, such as metadata it could not read, and records the warning
+    /// here.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get the most recent warning, if any
+    pub fn last_warning(&self) -> Option<&str> {
+        self.warnings.last().map(String::as_str)
+    }
+
+    /// Read the contents of the file returned by the last
+    /// [`next_entry`](Self::next_entry)
+    ///
+    /// Returns the number of bytes read, 0 at the end of the file. The file is
+    /// read through the handle libarchive opened during traversal, so it is
+    /// the same file the entry describes. Entries without data, such as
+    /// directories and symlinks, read as empty.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, ReadDisk, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("backup.tar")?;
+    /// let mut disk = ReadDisk::new()?;
+    /// disk.open("data")?;
+    /// let mut buf = vec![0u8; 64 * 1024];
+    /// while let Some(entry) = disk.next_entry()? {
+    ///     archive.write_header(&entry)?;
+    ///     loop {
+    ///         let n = disk.read_data(&mut buf)?;
+    ///         if n == 0 {
+    ///             break;
+    ///         }
+    ///         archive.write_data(&buf[..n])?;
+    ///     }
+    ///     if disk.can_descend() {
+    ///         disk.descend()?;
+    ///     }
+    /// }
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn read_data(&mut self, buf: &mut [u8]) -> Result<usize> {
+        unsafe {
+            let ret = libarchive2_sys::archive_read_data(
+                self.archive,
+                buf.as_mut_ptr() as *mut std::os::raw::c_void,
+                buf.len(),
+            );
+            if ret < 0 {
+                Err(Error::from_archive(self.archive, ret as i32))
+            } else {
+                Ok(ret as usize)
+            }
+        }
+    }
+
     /// Request that current directory be descended into
     pub fn descend(&mut self) -> Result<()> {
         unsafe {
@@ -242,6 +544,11 @@ impl Drop for ReadDisk {
                 libarchive2_sys::archive_read_close(self.archive);
                 libarchive2_sys::archive_read_free(self.archive);
             }
+            // The matcher field is dropped after this, once libarchive is done
+            // with it; the callback data must outlive the archive too
+            if let Some((data, _, drop_fn)) = self.excluded_callback.take() {
+                drop_fn(data);
+            }
         }
     }
 }
@@ -1,12 +1,29 @@
 //! Archive reading functionality
 
+use crate::callbacks::{ProgressCallback, ProgressTracker};
 use crate::entry::Entry;
 use crate::error::{Error, Result};
-use crate::format::{CompressionFormat, ReadFormat};
+use crate::format::{CompressionFormat, ReadFormat, ReadOption};
+use crate::stats::ReadStats;
 use std::ffi::CString;
 use std::path::Path;
 use std::ptr;
 
+/// Block size used when opening files and descriptors unless told otherwise
+const DEFAULT_BLOCK_SIZE: usize = 10240;
+
+/// Whether an archive contains encrypted entries, see
+/// [`ReadArchive::has_encrypted_entries`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionStatus {
+    /// At least one entry is encrypted
+    Yes,
+    /// No entry is encrypted, or the format does not support encryption
+    No,
+    /// The reader can't tell yet
+    Unknown,
+}
+
 /// Archive reader with RAII resource management
 ///
 /// The lifetime parameter 'a represents borrowed data (e.g., when reading from memory).
@@ -19,7 +36,24 @@ use std::ptr;
 /// guarantees: archive objects should not be shared between threads, but can be moved.
 pub struct ReadArchive<'a> {
     archive: *mut libarchive2_sys::archive,
+    entries_read: u64,
+    data_bytes_read: u64,
+    entry_bytes_read: u64,
+    current_entry: *mut libarchive2_sys::archive_entry,
+    normalize_backslashes: bool,
+    copy_buffer: Vec<u8>,
     _callback_data: Option<(*mut std::ffi::c_void, crate::callbacks::DropFn)>,
+    take_callback_error: Option<crate::callbacks::TakeErrorFn>,
+    passphrase_callback: Option<(*mut std::ffi::c_void, crate::callbacks::DropFn)>,
+    progress: Option<Box<dyn ProgressCallback + Send>>,
+    tracker: Option<ProgressTracker>,
+    /// Size of the file or buffer being read, 0 if unknown
+    source_size: u64,
+    entry_size: u64,
+    matcher: Option<crate::match_filter::ArchiveMatch>,
+    warnings: Vec<String>,
+    /// Buffer read by [`ReadArchive::open_memory_owned`], freed after the archive
+    _owned_data: Option<Vec<u8>>,
     _phantom: std::marker::PhantomData<&'a [u8]>,
 }
 
@@ -27,7 +61,8 @@ pub struct ReadArchive<'a> {
 // 1. The archive pointer is owned exclusively by this instance
 // 2. libarchive archive objects can be used from different threads (just not concurrently)
 // 3. The callback data is also owned exclusively
-// 4. The phantom data only tracks lifetimes, not actual data
+// 4. The tracker's callback is required to be Send by set_progress
+// 5. The phantom data only tracks lifetimes, not actual data
 unsafe impl<'a> Send for ReadArchive<'a> {}
 
 // Note: ReadArchive is NOT Sync because libarchive archives are not thread-safe
@@ -44,7 +79,22 @@ impl<'a> ReadArchive<'a> {
             }
             Ok(ReadArchive {
                 archive,
+                entries_read: 0,
+                data_bytes_read: 0,
+                entry_bytes_read: 0,
+                current_entry: ptr::null_mut(),
+                normalize_backslashes: true,
+                copy_buffer: Vec::new(),
                 _callback_data: None,
+                take_callback_error: None,
+                passphrase_callback: None,
+                progress: None,
+                tracker: None,
+                source_size: 0,
+                entry_size: 0,
+                matcher: None,
+                warnings: Vec::new(),
+                _owned_data: None,
                 _phantom: std::marker::PhantomData,
             })
         }
@@ -57,25 +107,91 @@ impl<'a> ReadArchive<'a> {
 
     /// Open an archive file for reading
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_block_size(path, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Open an archive file for reading with a specific read block size
+    ///
+    /// [`open`](Self::open) reads the file in 10240-byte blocks. Larger blocks
+    /// (e.g. 1 MiB) mean fewer read calls, which helps on network filesystems
+    /// and with very large archives.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if `block_size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open_with_block_size("/mnt/nfs/backup.tar", 1024 * 1024)?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_with_block_size<P: AsRef<Path>>(path: P, block_size: usize) -> Result<Self> {
+        if block_size == 0 {
+            return Err(Error::InvalidArgument(
+                "Block size must be greater than 0".to_string(),
+            ));
+        }
+
         let mut reader = Self::new()?;
         reader.support_filter_all()?;
         reader.support_format_all()?;
+        reader.open_filename(path, block_size)?;
+
+        Ok(reader)
+    }
+
+    /// Open an archive file with a reader set up by [`new`](Self::new)
+    ///
+    /// Unlike [`open`](Self::open), nothing is enabled automatically: register
+    /// formats and filters (e.g. [`support_format_all`](Self::support_format_all))
+    /// and then set options such as [`set_read_option`](Self::set_read_option)
+    /// or [`set_options`](Self::set_options) before calling this. Options for a
+    /// format are only accepted once that format is registered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ReadArchive, ReadOption};
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// archive.set_read_option(ReadOption::TarHeaderCharset("ISO-8859-1".to_string()))?;
+    /// let mut archive = archive.open_file("legacy.tar")?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        self.open_filename(path, DEFAULT_BLOCK_SIZE)?;
+        Ok(self)
+    }
 
-        let path_str = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
-        let c_path = CString::new(path_str)
-            .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+    /// Open a file on this reader (internal helper)
+    fn open_filename<P: AsRef<Path>>(&mut self, path: P, block_size: usize) -> Result<()> {
+        self.source_size = std::fs::metadata(path.as_ref()).map_or(0, |m| m.len());
+        #[cfg(windows)]
+        let path = crate::path::to_wide(path.as_ref())?;
+        #[cfg(not(windows))]
+        let path = crate::path::to_cstring(path.as_ref())?;
 
         unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_open_filename(reader.archive, c_path.as_ptr(), 10240),
-                reader.archive,
-            )?;
+            #[cfg(windows)]
+            let ret = libarchive2_sys::archive_read_open_filename_w(
+                self.archive,
+                path.as_ptr(),
+                block_size,
+            );
+            #[cfg(not(windows))]
+            let ret = libarchive2_sys::archive_read_open_filename(
+                self.archive,
+                path.as_ptr(),
+                block_size,
+            );
+            Error::from_read_return_code(ret, self.archive)?;
         }
-
-        Ok(reader)
+        Ok(())
     }
 
     /// Open a multi-volume archive from multiple files
@@ -116,18 +232,20 @@ impl<'a> ReadArchive<'a> {
         reader.support_filter_all()?;
         reader.support_format_all()?;
 
+        reader.open_volumes(paths)?;
+        Ok(reader)
+    }
+
+    /// Open the volumes of a multi-volume archive (internal helper)
+    ///
+    /// libarchive has no wide-character variant of this, so on Windows the
+    /// paths must be valid UTF-8.
+    pub(crate) fn open_volumes<P: AsRef<Path>>(&mut self, paths: &[P]) -> Result<()> {
         // Convert paths to C strings and collect them
-        let c_paths: Result<Vec<CString>> = paths
+        let c_paths = paths
             .iter()
-            .map(|p| {
-                let path_str = p.as_ref().to_str().ok_or_else(|| {
-                    Error::InvalidArgument("Path contains invalid UTF-8".to_string())
-                })?;
-                CString::new(path_str)
-                    .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))
-            })
-            .collect();
-        let c_paths = c_paths?;
+            .map(|p| crate::path::to_cstring(p.as_ref()))
+            .collect::<Result<Vec<CString>>>()?;
 
         // Create null-terminated array of pointers
         let mut c_path_ptrs: Vec<*const std::os::raw::c_char> =
@@ -135,17 +253,16 @@ impl<'a> ReadArchive<'a> {
         c_path_ptrs.push(std::ptr::null()); // Null terminator
 
         unsafe {
-            Error::from_return_code(
+            Error::from_read_return_code(
                 libarchive2_sys::archive_read_open_filenames(
-                    reader.archive,
+                    self.archive,
                     c_path_ptrs.as_mut_ptr(),
-                    10240,
+                    DEFAULT_BLOCK_SIZE,
                 ),
-                reader.archive,
+                self.archive,
             )?;
         }
-
-        Ok(reader)
+        Ok(())
     }
 
     /// Open an encrypted multi-volume archive from multiple files with a passphrase
@@ -193,36 +310,44 @@ impl<'a> ReadArchive<'a> {
         // Add passphrase before opening the archive
         reader.add_passphrase(passphrase)?;
 
-        // Convert paths to C strings and collect them
-        let c_paths: Result<Vec<CString>> = paths
-            .iter()
-            .map(|p| {
-                let path_str = p.as_ref().to_str().ok_or_else(|| {
-                    Error::InvalidArgument("Path contains invalid UTF-8".to_string())
-                })?;
-                CString::new(path_str)
-                    .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))
-            })
-            .collect();
-        let c_paths = c_paths?;
-
-        // Create null-terminated array of pointers
-        let mut c_path_ptrs: Vec<*const std::os::raw::c_char> =
-            c_paths.iter().map(|s| s.as_ptr()).collect();
-        c_path_ptrs.push(std::ptr::null()); // Null terminator
+        reader.open_volumes(paths)?;
+        Ok(reader)
+    }
 
-        unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_open_filenames(
-                    reader.archive,
-                    c_path_ptrs.as_mut_ptr(),
-                    10240,
-                ),
-                reader.archive,
-            )?;
-        }
+    /// Prepare to read an archive file in two passes
+    ///
+    /// Returns a [`TwoPass`](crate::TwoPass) handle whose
+    /// [`scan`](crate::TwoPass::scan) reads all headers and whose
+    /// [`process`](crate::TwoPass::process) reopens the file to read the data. The
+    /// file is not opened until the first pass runs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut two_pass = ReadArchive::open_two_pass("archive.tar.zst")?;
+    /// let totals = two_pass.scan(|_entry| {})?;
+    /// println!("{} entries, {} bytes", totals.entries, totals.bytes);
+    ///
+    /// two_pass.process(|entry, data| {
+    ///     let contents = data.read_to_vec()?;
+    ///     println!("{}: {} bytes", entry.pathname().unwrap_or_default(), contents.len());
+    ///     Ok(())
+    /// })?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_two_pass<P: AsRef<Path>>(path: P) -> Result<crate::TwoPass> {
+        crate::TwoPass::new(vec![path.as_ref().to_path_buf()])
+    }
 
-        Ok(reader)
+    /// Prepare to read a multi-volume archive in two passes
+    ///
+    /// The files are read in the order given, as with
+    /// [`open_filenames`](Self::open_filenames). See
+    /// [`open_two_pass`](Self::open_two_pass).
+    pub fn open_two_pass_filenames<P: AsRef<Path>>(paths: &[P]) -> Result<crate::TwoPass> {
+        crate::TwoPass::new(paths.iter().map(|p| p.as_ref().to_path_buf()).collect())
     }
 
     /// Open an archive from memory
@@ -240,26 +365,105 @@ impl<'a> ReadArchive<'a> {
     /// // archive borrows data, so data cannot be dropped until archive is dropped
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
+    ///
+    /// Dropping the data while the archive is still in use does not compile:
+    ///
+    /// ```compile_fail
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let data = std::fs::read("archive.tar.gz").unwrap();
+    /// let mut archive = ReadArchive::open_memory(&data).unwrap();
+    /// drop(data);
+    /// archive.next_entry().unwrap();
+    /// ```
+    ///
+    /// Use [`open_memory_owned`](Self::open_memory_owned) to hand the buffer
+    /// over to the archive instead.
     pub fn open_memory(data: &'a [u8]) -> Result<Self> {
         let mut reader = Self::new()?;
         reader.support_filter_all()?;
         reader.support_format_all()?;
+        reader.open_buffer(data)
+    }
+
+    /// Open an archive from a buffer the archive takes ownership of
+    ///
+    /// Unlike [`open_memory`](Self::open_memory), the returned archive does
+    /// not borrow anything, so it can be returned from functions, stored in
+    /// structs or sent to another thread. The buffer is freed with the archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// fn open_download(url_body: Vec<u8>) -> libarchive2::Result<ReadArchive<'static>> {
+    ///     ReadArchive::open_memory_owned(url_body)
+    /// }
+    /// ```
+    pub fn open_memory_owned(data: Vec<u8>) -> Result<ReadArchive<'static>> {
+        let mut reader = ReadArchive::new()?;
+        reader.support_filter_all()?;
+        reader.support_format_all()?;
+        reader.open_owned_buffer(data)
+    }
+
+    /// Open a buffer this reader takes ownership of (internal helper)
+    pub(crate) fn open_owned_buffer(mut self, data: Vec<u8>) -> Result<Self> {
+        // The Vec's heap allocation does not move with it, and Drop frees the
+        // archive before the buffer
+        let data = self._owned_data.insert(data);
+        self.source_size = data.len() as u64;
+        unsafe {
+            Error::from_read_return_code(
+                libarchive2_sys::archive_read_open_memory(
+                    self.archive,
+                    data.as_ptr() as *const std::os::raw::c_void,
+                    data.len(),
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(self)
+    }
 
+    /// Open an archive in memory on a reader created with [`new`](Self::new)
+    ///
+    /// Like [`open_file`](Self::open_file), this lets the reader be configured
+    /// first, for example with [`set_passphrase`](Self::set_passphrase) to
+    /// read an encrypted archive. Formats and filters must be enabled
+    /// explicitly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let data = std::fs::read("encrypted.zip")?;
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// archive.set_passphrase("secret")?;
+    /// let mut archive = archive.open_buffer(&data)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_buffer(mut self, data: &'a [u8]) -> Result<Self> {
+        self.source_size = data.len() as u64;
         unsafe {
             // SAFETY: The data slice is valid for lifetime 'a, which is tied to
             // the ReadArchive lifetime via the _phantom field. This ensures the
             // data cannot be dropped while libarchive is using it.
-            Error::from_return_code(
+            Error::from_read_return_code(
                 libarchive2_sys::archive_read_open_memory(
-                    reader.archive,
+                    self.archive,
                     data.as_ptr() as *const std::os::raw::c_void,
                     data.len(),
                 ),
-                reader.archive,
+                self.archive,
             )?;
         }
 
-        Ok(reader)
+        Ok(self)
     }
 
     /// Open an archive from a file descriptor
@@ -286,8 +490,8 @@ impl<'a> ReadArchive<'a> {
         reader.support_format_all()?;
 
         unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_open_fd(reader.archive, fd, 10240),
+            Error::from_read_return_code(
+                libarchive2_sys::archive_read_open_fd(reader.archive, fd, DEFAULT_BLOCK_SIZE),
                 reader.archive,
             )?;
         }
@@ -307,11 +511,11 @@ impl<'a> ReadArchive<'a> {
         reader.support_format_all()?;
 
         unsafe {
-            Error::from_return_code(
+            Error::from_read_return_code(
                 libarchive2_sys::archive_read_open_fd(
                     reader.archive,
                     fd as std::os::raw::c_int,
-                    10240,
+                    DEFAULT_BLOCK_SIZE,
                 ),
                 reader.archive,
             )?;
@@ -340,19 +544,7 @@ impl<'a> ReadArchive<'a> {
         reader.support_format_all()?;
         reader.add_passphrase(passphrase)?;
 
-        let path_str = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
-        let c_path = CString::new(path_str)
-            .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
-
-        unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_open_filename(reader.archive, c_path.as_ptr(), 10240),
-                reader.archive,
-            )?;
-        }
+        reader.open_filename(path, DEFAULT_BLOCK_SIZE)?;
 
         Ok(reader)
     }
@@ -379,8 +571,47 @@ impl<'a> ReadArchive<'a> {
         let mut reader = Self::new()?;
         reader.support_filter_all()?;
         reader.support_format_all()?;
+        reader.open_callback_reader(callback)?;
+        Ok(reader)
+    }
+
+    /// Open an archive from a `std::io::Read` source on a reader created with
+    /// [`new`](Self::new)
+    ///
+    /// This is [`open_read`](Self::open_read) for a reader that has been
+    /// configured first, for example with
+    /// [`set_passphrase`](Self::set_passphrase). Formats and filters must be
+    /// enabled explicitly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// archive.set_passphrase("secret")?;
+    /// let mut archive = archive.open_stream(std::io::stdin())?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_stream<R: std::io::Read + 'static>(mut self, reader: R) -> Result<Self> {
+        self.open_callback_reader(crate::callbacks::CallbackReader::new(reader))?;
+        Ok(self)
+    }
 
+    /// Open a callback source on this reader (internal helper)
+    fn open_callback_reader<R: std::io::Read + 'static>(
+        &mut self,
+        callback: crate::callbacks::CallbackReader<R>,
+    ) -> Result<()> {
+        let reader = self;
+        let seek_callbacks = callback.seek_callbacks();
         let (client_data, read_cb, close_cb, drop_fn) = callback.into_raw_parts();
+        // Hand the state to the reader before opening so it is freed exactly once,
+        // by Drop, even if the open fails
+        reader._callback_data = Some((client_data, drop_fn));
+        reader.take_callback_error = Some(crate::callbacks::CallbackReader::<R>::take_error_fn());
 
         unsafe {
             // SAFETY: The function pointers returned from into_raw_parts are guaranteed
@@ -405,19 +636,107 @@ impl<'a> ReadArchive<'a> {
             ));
 
             Error::from_return_code(
-                libarchive2_sys::archive_read_open(
+                libarchive2_sys::archive_read_set_read_callback(reader.archive, read_fn),
+                reader.archive,
+            )?;
+            Error::from_return_code(
+                libarchive2_sys::archive_read_set_close_callback(reader.archive, close_fn),
+                reader.archive,
+            )?;
+            if let Some((seek_fn, skip_fn)) = seek_callbacks {
+                Error::from_return_code(
+                    libarchive2_sys::archive_read_set_seek_callback(reader.archive, Some(seek_fn)),
                     reader.archive,
-                    client_data,
-                    None,
-                    read_fn,
-                    close_fn,
-                ),
+                )?;
+                Error::from_return_code(
+                    libarchive2_sys::archive_read_set_skip_callback(reader.archive, Some(skip_fn)),
+                    reader.archive,
+                )?;
+            }
+            Error::from_return_code(
+                libarchive2_sys::archive_read_set_callback_data(reader.archive, client_data),
                 reader.archive,
             )?;
+
+            let ret = libarchive2_sys::archive_read_open1(reader.archive);
+            if ret < 0 {
+                return Err(reader.read_error(ret));
+            }
         }
 
-        reader._callback_data = Some((client_data, drop_fn));
-        Ok(reader)
+        Ok(())
+    }
+
+    /// Open an archive from any `std::io::Read` source
+    ///
+    /// The archive is read as a stream, so this works with sockets, pipes, stdin
+    /// or a decompressor without buffering the whole archive in memory. The
+    /// reader is owned by the archive and dropped with it.
+    ///
+    /// If the source ends early the read fails with [`Error::Truncated`]. An
+    /// `io::Error` returned by the source is passed through as [`Error::Io`]
+    /// (interrupted reads are retried).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open_read(std::io::stdin())?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_read<R: std::io::Read + 'static>(reader: R) -> Result<Self> {
+        Self::open_callback(crate::callbacks::CallbackReader::new(reader))
+    }
+
+    /// Open an archive from any `std::io::Read` source
+    ///
+    /// Former name of [`open_read`](Self::open_read).
+    #[deprecated(note = "renamed to `open_read`")]
+    pub fn open_reader<R: std::io::Read + 'static>(reader: R) -> Result<Self> {
+        Self::open_read(reader)
+    }
+
+    /// Open an archive from a source that supports `std::io::Seek`
+    ///
+    /// Unlike [`open_read`](Self::open_read), libarchive can seek in the source.
+    /// Formats that keep an index at the end, such as zip's central directory,
+    /// are then read directly instead of by scanning the whole stream, and
+    /// skipped entry data is seeked over rather than read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    /// use std::fs::File;
+    ///
+    /// let mut archive = ReadArchive::open_seekable(File::open("archive.zip")?)?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_seekable<R: std::io::Read + std::io::Seek + 'static>(reader: R) -> Result<Self> {
+        Self::open_callback(crate::callbacks::CallbackReader::new_seekable(reader))
+    }
+
+    /// Build the error for a failed read
+    ///
+    /// An I/O error from a callback reader takes precedence over libarchive's
+    /// own, less specific, error.
+    fn read_error(&self, status: i32) -> Error {
+        if let (Some(take_error), Some((data, _))) = (self.take_callback_error, self._callback_data)
+        {
+            // SAFETY: data is the callback state created for take_error's reader type
+            if let Some(e) = unsafe { take_error(data) } {
+                return Error::Io(e);
+            }
+        }
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe { Error::from_read_archive(self.archive, status) }
     }
 
     /// Enable support for all compression filters
@@ -434,52 +753,37 @@ impl<'a> ReadArchive<'a> {
     /// Enable support for a specific compression filter
     pub fn support_filter(&mut self, filter: CompressionFormat) -> Result<()> {
         unsafe {
-            let ret = match filter {
-                CompressionFormat::None => {
-                    libarchive2_sys::archive_read_support_filter_none(self.archive)
-                }
-                CompressionFormat::Gzip => {
-                    libarchive2_sys::archive_read_support_filter_gzip(self.archive)
-                }
-                CompressionFormat::Bzip2 => {
-                    libarchive2_sys::archive_read_support_filter_bzip2(self.archive)
-                }
-                CompressionFormat::Xz => {
-                    libarchive2_sys::archive_read_support_filter_xz(self.archive)
-                }
-                CompressionFormat::Zstd => {
-                    libarchive2_sys::archive_read_support_filter_zstd(self.archive)
-                }
-                CompressionFormat::Lz4 => {
-                    libarchive2_sys::archive_read_support_filter_lz4(self.archive)
-                }
-                CompressionFormat::Compress => {
-                    libarchive2_sys::archive_read_support_filter_compress(self.archive)
-                }
-                CompressionFormat::UuEncode => {
-                    libarchive2_sys::archive_read_support_filter_uu(self.archive)
-                }
-                CompressionFormat::Lrzip => {
-                    libarchive2_sys::archive_read_support_filter_lrzip(self.archive)
-                }
-                CompressionFormat::Lzop => {
-                    libarchive2_sys::archive_read_support_filter_lzop(self.archive)
-                }
-                CompressionFormat::Grzip => {
-                    libarchive2_sys::archive_read_support_filter_grzip(self.archive)
-                }
-                _ => {
-                    return Err(Error::InvalidArgument(format!(
-                        "Unsupported filter: {:?}",
-                        filter
-                    )));
-                }
-            };
-            Error::from_return_code(ret, self.archive)?;
+            let ret = register_filter(self.archive, filter);
+            // ARCHIVE_WARN means libarchive will run an external program for
+            // this filter, which is still supported
+            if !(ret == libarchive2_sys::ARCHIVE_WARN && filter.external_program().is_some()) {
+                Error::from_return_code(ret, self.archive)?;
+            }
         }
         Ok(())
     }
 
+    /// Enable support for `filter` only if libarchive decodes it in-process
+    ///
+    /// libarchive falls back to running an external program for filters it
+    /// was built without a library for, and always does for lrzip, lzop and
+    /// grzip. The filter is first tried on a scratch reader, so no program is
+    /// ever registered here. Returns whether the filter was enabled.
+    pub(crate) fn support_filter_builtin(&mut self, filter: CompressionFormat) -> Result<bool> {
+        if filter.external_program().is_some() {
+            return Ok(false);
+        }
+        let probe = ReadArchive::new()?;
+        // SAFETY: both archives are valid pointers for the lifetime of their owners
+        unsafe {
+            if register_filter(probe.archive, filter) != libarchive2_sys::ARCHIVE_OK as i32 {
+                return Ok(false);
+            }
+            Error::from_return_code(register_filter(self.archive, filter), self.archive)?;
+        }
+        Ok(true)
+    }
+
     /// Enable support for all archive formats
     pub fn support_format_all(&mut self) -> Result<()> {
         unsafe {
@@ -492,6 +796,27 @@ impl<'a> ReadArchive<'a> {
     }
 
     /// Enable support for a specific archive format
+    ///
+    /// Enabling only the formats an application expects keeps the parsers
+    /// for every other format out of reach of untrusted input. Each
+    /// [`ArchiveFormat`](crate::ArchiveFormat) libarchive can read is accepted,
+    /// including the read-only ones; the tar and cpio variants enable the tar
+    /// and cpio readers, which handle all of them.
+    /// [`ArchiveFormat::Shar`](crate::ArchiveFormat::Shar) is write-only and
+    /// fails with [`Error::InvalidArgument`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, ReadArchive, ReadFormat};
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format(ReadFormat::Format(ArchiveFormat::Rar))?;
+    /// archive.support_format(ReadFormat::Format(ArchiveFormat::Rar5))?;
+    /// let mut archive = archive.open_file("upload.rar")?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
     pub fn support_format(&mut self, format: ReadFormat) -> Result<()> {
         unsafe {
             let ret = match format {
@@ -548,11 +873,11 @@ impl<'a> ReadArchive<'a> {
                         ArchiveFormat::Cab => {
                             libarchive2_sys::archive_read_support_format_cab(self.archive)
                         }
-                        _ => {
-                            return Err(Error::InvalidArgument(format!(
-                                "Unsupported format: {:?}",
-                                fmt
-                            )));
+                        ArchiveFormat::Shar => {
+                            return Err(Error::InvalidArgument(
+                                "Shar archives cannot be read: libarchive only writes them"
+                                    .to_string(),
+                            ));
                         }
                     }
                 }
@@ -566,6 +891,9 @@ impl<'a> ReadArchive<'a> {
     ///
     /// This method can be called multiple times to add multiple passphrases.
     /// libarchive will try each passphrase in the order they were added.
+    /// Passphrases can only be registered before the archive is opened;
+    /// calling this on an opened archive returns an error and leaves the
+    /// archive unusable.
     ///
     /// # Examples
     ///
@@ -588,6 +916,90 @@ impl<'a> ReadArchive<'a> {
         Ok(())
     }
 
+    /// Set the passphrase for reading an encrypted archive
+    ///
+    /// Call this on a reader created with [`new`](Self::new), before opening
+    /// it with [`open_file`](Self::open_file), [`open_buffer`](Self::open_buffer)
+    /// or [`open_stream`](Self::open_stream). This is the same as
+    /// [`add_passphrase`](Self::add_passphrase), except that it fails with
+    /// [`Error::InvalidArgument`] instead of breaking an archive that is
+    /// already open.
+    pub fn set_passphrase(&mut self, passphrase: &str) -> Result<()> {
+        self.check_not_opened("Passphrases")?;
+        self.add_passphrase(passphrase)
+    }
+
+    /// Ask for passphrases only when an encrypted entry needs one
+    ///
+    /// libarchive calls `callback` when it reaches encrypted data and none of
+    /// the passphrases registered with [`add_passphrase`](Self::add_passphrase)
+    /// work. It keeps calling for another passphrase to try until one decrypts
+    /// the data or `callback` returns `None`, after which the read fails.
+    /// Passphrases that worked are remembered for the following entries.
+    ///
+    /// Like [`set_passphrase`](Self::set_passphrase), this must be called
+    /// before the archive is opened. Setting a new callback replaces the old
+    /// one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// let mut attempts = 0;
+    /// archive.set_passphrase_callback(move || {
+    ///     attempts += 1;
+    ///     if attempts > 3 {
+    ///         return None;
+    ///     }
+    ///     eprint!("Password: ");
+    ///     let mut line = String::new();
+    ///     std::io::stdin().read_line(&mut line).ok()?;
+    ///     Some(line.trim_end().to_string())
+    /// })?;
+    /// let mut archive = archive.open_file("encrypted.zip")?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_passphrase_callback<F>(&mut self, callback: F) -> Result<()>
+    where
+        F: FnMut() -> Option<String> + Send + 'static,
+    {
+        self.check_not_opened("The passphrase callback")?;
+        let (data, callback, drop_fn) = crate::callbacks::passphrase_callback_parts(callback);
+        // SAFETY: archive is a valid pointer for the lifetime of self, and data
+        // stays alive until it is replaced or the archive is freed
+        let ret = unsafe {
+            libarchive2_sys::archive_read_set_passphrase_callback(
+                self.archive,
+                data,
+                Some(callback),
+            )
+        };
+        if let Some((old, old_drop_fn)) = self.passphrase_callback.replace((data, drop_fn)) {
+            // SAFETY: libarchive no longer refers to the old state
+            unsafe { old_drop_fn(old) };
+        }
+        // SAFETY: as above
+        unsafe { Error::from_return_code(ret, self.archive) }?;
+        Ok(())
+    }
+
+    /// Fail if the archive has been opened, naming `what` must come first
+    fn check_not_opened(&self, what: &str) -> Result<()> {
+        // SAFETY: archive is a valid pointer for the lifetime of self; the read
+        // pipeline has no filters until the archive is opened
+        if unsafe { libarchive2_sys::archive_filter_count(self.archive) } > 0 {
+            return Err(Error::InvalidArgument(format!(
+                "{} must be set before the archive is opened",
+                what
+            )));
+        }
+        Ok(())
+    }
+
     /// Set a format-specific option
     ///
     /// This allows fine-grained control over format-specific features during reading.
@@ -608,6 +1020,8 @@ impl<'a> ReadArchive<'a> {
             .map_err(|_| Error::InvalidArgument("Option name contains null byte".to_string()))?;
         let c_value = CString::new(value)
             .map_err(|_| Error::InvalidArgument("Option value contains null byte".to_string()))?;
+        // Charset options convert to the locale charset in effect when they are set
+        let _guard = crate::locale::UTF8LocaleGuard::new();
 
         unsafe {
             Error::from_return_code(
@@ -623,28 +1037,367 @@ impl<'a> ReadArchive<'a> {
         Ok(())
     }
 
+    /// Set options from a comma-separated `module:option=value` string
+    ///
+    /// This takes the same syntax as bsdtar's `--options`, e.g.
+    /// `"zip:ignorecrc32,tar:hdrcharset=CP932"`. An option with no module is
+    /// offered to every registered format and filter, and a bare name such as
+    /// `"zip:ignorecrc32"` sets the option to true. Options must be set after the
+    /// formats they apply to are registered and before the archive is opened;
+    /// see [`open_file`](Self::open_file).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// archive.set_options("zip:ignorecrc32,zip:hdrcharset=CP437")?;
+    /// let mut archive = archive.open_file("damaged.zip")?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_options(&mut self, options: &str) -> Result<()> {
+        let c_options = CString::new(options)
+            .map_err(|_| Error::InvalidArgument("Options contain null byte".to_string()))?;
+        // Charset options convert to the locale charset in effect when they are set
+        let _guard = crate::locale::UTF8LocaleGuard::new();
+
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_read_set_options(self.archive, c_options.as_ptr()),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Set the character set used to decode entry names
+    ///
+    /// Applies libarchive's `hdrcharset` option to every registered format that
+    /// supports it (zip, tar, cpio, iso9660, lha, ...). Use it for archives
+    /// written with a legacy code page, e.g. `"CP437"` for old DOS zips or
+    /// `"CP932"` (Shift-JIS) for Japanese ones. Names are converted to the
+    /// process locale's charset, so this needs a locale that can represent
+    /// them, such as a UTF-8 one; under the `C` locale libarchive records a
+    /// [warning](Self::warnings) for each entry whose name it can't convert.
+    ///
+    /// Like [`set_options`](Self::set_options), this must be called after the
+    /// formats are registered and before the archive is opened.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::new()?;
+    /// archive.support_filter_all()?;
+    /// archive.support_format_all()?;
+    /// archive.set_header_charset("CP932")?;
+    /// let mut archive = archive.open_file("japanese.zip")?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_header_charset(&mut self, charset: &str) -> Result<()> {
+        if charset.is_empty() || charset.contains(',') {
+            return Err(Error::InvalidArgument(format!(
+                "Invalid header charset {:?}",
+                charset
+            )));
+        }
+        self.set_options(&format!("hdrcharset={}", charset))
+    }
+
+    /// Set a typed read option
+    ///
+    /// See [`ReadOption`] for the available options and
+    /// [`set_options`](Self::set_options) for when options can be set.
+    pub fn set_read_option(&mut self, option: ReadOption) -> Result<()> {
+        let flag = |enable: bool| if enable { "1" } else { "" };
+        match &option {
+            ReadOption::ZipIgnoreCrc32(enable) => {
+                self.set_option("zip", "ignorecrc32", flag(*enable))
+            }
+            ReadOption::ZipHeaderCharset(charset) => self.set_option("zip", "hdrcharset", charset),
+            ReadOption::TarHeaderCharset(charset) => self.set_option("tar", "hdrcharset", charset),
+            ReadOption::TarMacExt(enable) => self.set_option("tar", "mac-ext", flag(*enable)),
+            ReadOption::MtreeCheckFs(enable) => self.set_option("mtree", "checkfs", flag(*enable)),
+        }
+    }
+
+    /// Control whether extraction treats `\` in entry names as a path separator
+    ///
+    /// Some Windows tools write ZIP entries such as `dir\file.txt`. With this
+    /// enabled (the default), the extraction methods ([`extract_current`](Self::extract_current),
+    /// [`extract_with_flags`](Self::extract_with_flags), ...) convert backslashes in
+    /// pathnames and hardlink targets to `/`, so a real directory hierarchy is
+    /// created instead of a single file with backslashes in its name. Disable it
+    /// to keep names that legitimately contain backslashes on Unix.
+    ///
+    /// This only affects extraction; [`Entry::pathname`] always reports the name
+    /// as stored in the archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.zip")?;
+    /// archive.set_normalize_backslashes(false);
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.extract_current_with_flags(ExtractFlags::TIME)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_normalize_backslashes(&mut self, enable: bool) {
+        self.normalize_backslashes = enable;
+    }
+
+    /// Get the warnings recorded while reading, oldest first
+    ///
+    /// libarchive reports problems that still let it read an entry, such as a
+    /// name it could not convert or an unknown extension header, as warnings.
+    /// [`next_entry`](Self::next_entry) and
+    /// [`read_data_into_fd`](Self::read_data_into_fd) return the entry or data
+    /// as usual and record the warning here.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar")?;
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.skip_data()?;
+    /// }
+    /// for warning in archive.warnings() {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get the most recent warning, if any
+    pub fn last_warning(&self) -> Option<&str> {
+        self.warnings.last().map(String::as_str)
+    }
+
     /// Read the next entry header
     ///
-    /// Returns `None` when there are no more entries
+    /// Returns `None` when there are no more entries. With a matcher set by
+    /// [`set_match`](Self::set_match), entries it excludes are skipped. A
+    /// header libarchive read with a warning is returned, and the warning is
+    /// recorded in [`warnings`](Self::warnings).
     pub fn next_entry(&mut self) -> Result<Option<Entry<'_>>> {
+        self.next_entry_filtered(None)
+    }
+
+    /// Read the next entry header that `matcher` includes
+    ///
+    /// Entries `matcher` excludes are skipped, data and all, so the archive
+    /// stays positioned at the returned entry's data. A matcher set by
+    /// [`set_match`](Self::set_match) applies too, so an entry is returned
+    /// only if both include it. Returns `None` when no matching entries are
+    /// left.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveMatch, ReadArchive};
+    ///
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.include_pattern("*.txt")?;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// while let Some(entry) = archive.next_entry_matching(&mut matcher)? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn next_entry_matching(
+        &mut self,
+        matcher: &mut crate::match_filter::ArchiveMatch,
+    ) -> Result<Option<Entry<'_>>> {
+        self.next_entry_filtered(Some(matcher))
+    }
+
+    /// Read the next header that the matcher set by [`set_match`](Self::set_match)
+    /// and `extra`, if given, both include
+    ///
+    /// Shared by [`next_entry`](Self::next_entry) and
+    /// [`next_entry_matching`](Self::next_entry_matching) so both skip entries
+    /// the same way.
+    fn next_entry_filtered(
+        &mut self,
+        mut extra: Option<&mut crate::match_filter::ArchiveMatch>,
+    ) -> Result<Option<Entry<'_>>> {
+        loop {
+            let Some(entry) = self.next_header()? else {
+                return Ok(None);
+            };
+            let entry = Entry {
+                entry,
+                archive: self.archive,
+                _marker: std::marker::PhantomData,
+            };
+            let included = match self.matcher.as_mut() {
+                Some(matcher) => matcher.matches(&entry)?,
+                None => true,
+            } && match extra.as_deref_mut() {
+                Some(matcher) => matcher.matches(&entry)?,
+                None => true,
+            };
+            if included {
+                return Ok(Some(entry));
+            }
+            // Only the header has been read, so none of this entry's data
+            // can leak into the next one
+            self.skip_data()?;
+        }
+    }
+
+    /// Filter the entries returned by [`next_entry`](Self::next_entry)
+    ///
+    /// Entries `matcher` excludes (by path, time or owner) are skipped after
+    /// their header is read and before any of their data, so every method that
+    /// walks entries with `next_entry`, such as [`entries`](Self::entries),
+    /// [`extract_to_dir`](Self::extract_to_dir) and [`verify`](Self::verify),
+    /// only sees the matching ones. Skipped entries still count in
+    /// [`stats`](Self::stats). Replaces any matcher set before.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveMatch, ExtractFlags, ReadArchive};
+    ///
+    /// let mut matcher = ArchiveMatch::new()?;
+    /// matcher.include_pattern("docs/*")?;
+    /// matcher.exclude_pattern("*.tmp")?;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// archive.set_match(matcher);
+    /// archive.extract_to_dir("out", ExtractFlags::TIME)?;
+    ///
+    /// let mut matcher = archive.take_match().unwrap();
+    /// for missing in matcher.unmatched_inclusions()? {
+    ///     eprintln!("nothing matched {}", missing);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_match(&mut self, matcher: crate::match_filter::ArchiveMatch) {
+        self.matcher = Some(matcher);
+    }
+
+    /// Remove the matcher set by [`set_match`](Self::set_match) and return it
+    pub fn take_match(&mut self) -> Option<crate::match_filter::ArchiveMatch> {
+        self.matcher.take()
+    }
+
+    /// Read the next header, ignoring the matcher
+    fn next_header(&mut self) -> Result<Option<*mut libarchive2_sys::archive_entry>> {
         // Set locale to UTF-8 to handle non-ASCII filenames correctly
         let _guard = crate::locale::UTF8LocaleGuard::new();
 
+        self.current_entry = ptr::null_mut();
+
         unsafe {
             let mut entry: *mut libarchive2_sys::archive_entry = ptr::null_mut();
             let ret = libarchive2_sys::archive_read_next_header(self.archive, &mut entry);
 
             if ret == libarchive2_sys::ARCHIVE_EOF as i32 {
+                self.update_tracker();
                 return Ok(None);
             }
 
-            Error::from_return_code(ret, self.archive)?;
+            if ret == libarchive2_sys::ARCHIVE_WARN {
+                // The header was read; keep the entry and record the problem
+                self.warnings.push(Error::warning_message(self.archive));
+            } else if ret < 0 {
+                return Err(self.read_error(ret));
+            }
 
-            Ok(Some(Entry {
-                entry,
-                _marker: std::marker::PhantomData,
-            }))
+            self.entries_read += 1;
+            self.entry_bytes_read = 0;
+            self.entry_size = if libarchive2_sys::archive_entry_size_is_set(entry) != 0 {
+                libarchive2_sys::archive_entry_size(entry).max(0) as u64
+            } else {
+                0
+            };
+            self.current_entry = entry;
+            Ok(Some(entry))
+        }
+    }
+
+    /// Iterate over the remaining entries as owned metadata snapshots
+    ///
+    /// Each call to `next` advances to the next header, skipping any data of the
+    /// previous entry that hasn't been read. To read an entry's data, call
+    /// [`Entries::archive`] before advancing again; once the iterator moves on,
+    /// the previous entry's data is gone. Iteration stops after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{FileType, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// let files = archive
+    ///     .entries()
+    ///     .filter_map(|entry| match entry {
+    ///         Ok(entry) if entry.file_type == FileType::RegularFile => Some(Ok(entry.pathname)),
+    ///         Ok(_) => None,
+    ///         Err(e) => Some(Err(e)),
+    ///     })
+    ///     .collect::<libarchive2::Result<Vec<_>>>()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn entries(&mut self) -> Entries<'_, 'a> {
+        Entries {
+            archive: self,
+            done: false,
+        }
+    }
+
+    /// Read the remaining entry headers and group hard links by target
+    ///
+    /// Returns each hard link target with the pathnames linking to it, in the
+    /// order they appear in the archive. Entry data is skipped, and the archive
+    /// is at its end afterwards. Entries excluded by a matcher set with
+    /// [`set_match`](Self::set_match) are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("backup.tar")?;
+    /// for (target, links) in archive.hardlink_groups()? {
+    ///     println!("{} is also known as {}", target, links.join(", "));
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn hardlink_groups(&mut self) -> Result<Vec<(String, Vec<String>)>> {
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        let mut index = std::collections::HashMap::new();
+        while let Some(entry) = self.next_entry()? {
+            if !entry.is_hardlink() {
+                continue;
+            }
+            let (Some(target), Some(path)) = (entry.hardlink(), entry.pathname()) else {
+                continue;
+            };
+            let slot = *index.entry(target.clone()).or_insert_with(|| {
+                groups.push((target, Vec::new()));
+                groups.len() - 1
+            });
+            groups[slot].1.push(path);
         }
+        Ok(groups)
     }
 
     /// Read data from the current entry
@@ -657,37 +1410,601 @@ impl<'a> ReadArchive<'a> {
             );
 
             if ret < 0 {
-                Err(Error::from_archive(self.archive))
+                Err(self.read_error(ret as i32))
             } else {
+                self.record_data(ret as u64);
                 Ok(ret as usize)
             }
         }
     }
 
+    fn record_data(&mut self, bytes: u64) {
+        self.data_bytes_read += bytes;
+        self.entry_bytes_read += bytes;
+        if bytes > 0
+            && let Some(progress) = self.progress.as_mut()
+        {
+            progress.on_progress(self.entry_bytes_read, self.entry_size);
+        }
+        if bytes > 0 {
+            self.update_tracker();
+        }
+    }
+
+    /// Report the position in the source to the tracker set by `set_progress`
+    fn update_tracker(&mut self) {
+        if let Some(tracker) = self.tracker.as_mut() {
+            // SAFETY: archive is a valid pointer for the lifetime of self
+            tracker.set_position(unsafe { crate::stats::filter_bytes(self.archive, -1) });
+        }
+    }
+
+    /// Set a callback to report progress through each entry's data
+    ///
+    /// The callback is invoked after every successful read of entry data (by
+    /// [`read_data`](Self::read_data), [`read_data_to_vec`](Self::read_data_to_vec),
+    /// [`read_data_block`](Self::read_data_block) and the other readers) with the
+    /// bytes read so far from the current entry and the entry's size, or 0 if
+    /// the header doesn't record one. [`skip_data`](Self::skip_data) reports the
+    /// entry as complete. The count starts again from 0 on every
+    /// [`next_entry`](Self::next_entry).
+    ///
+    /// The callback runs on the calling thread and no internal lock is held
+    /// while it runs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ProgressCallback, ReadArchive};
+    ///
+    /// struct Print;
+    ///
+    /// impl ProgressCallback for Print {
+    ///     fn on_progress(&mut self, bytes_processed: u64, total_bytes: u64) {
+    ///         println!("{}/{}", bytes_processed, total_bytes);
+    ///     }
+    /// }
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// archive.set_progress_callback(Box::new(Print));
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.read_data_to_vec()?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_progress_callback(&mut self, callback: Box<dyn ProgressCallback + Send>) {
+        self.progress = Some(callback);
+    }
+
+    /// Track progress through the whole archive with a [`ProgressTracker`]
+    ///
+    /// Unlike [`set_progress_callback`](Self::set_progress_callback), which
+    /// follows each entry's data, the callback receives the bytes consumed from
+    /// the source so far (see [`bytes_read_compressed`](Self::bytes_read_compressed))
+    /// and the size of the source: the file size for archives opened from a
+    /// path, the buffer length for in-memory archives, and 0 when it is unknown,
+    /// as for streams. It is invoked whenever entry data is read or skipped and
+    /// once more when [`next_entry`](Self::next_entry) reaches the end of the
+    /// archive, when the position equals the total. Replaces any tracker set
+    /// before.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ProgressCallback, ReadArchive};
+    ///
+    /// struct Percent;
+    ///
+    /// impl ProgressCallback for Percent {
+    ///     fn on_progress(&mut self, bytes_processed: u64, total_bytes: u64) {
+    ///         println!("{:.0}%", 100.0 * bytes_processed as f64 / total_bytes as f64);
+    ///     }
+    /// }
+    ///
+    /// let mut archive = ReadArchive::open("backup.tar.gz")?;
+    /// archive.set_progress(Percent);
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.read_data_to_vec()?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_progress<C: ProgressCallback + Send + 'static>(&mut self, callback: C) {
+        let mut tracker = ProgressTracker::new(callback);
+        tracker.set_total(self.source_size);
+        self.tracker = Some(tracker);
+    }
+
+    /// Get the offset of the current entry's header in the uncompressed stream
+    ///
+    /// This is updated only by [`next_entry`](Self::next_entry): it is where
+    /// the last header read starts (for tar, a multiple of 512), and after the
+    /// last entry it is where the reader looked for another one. Returns 0
+    /// before the first header.
+    pub fn header_position(&self) -> u64 {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe { libarchive2_sys::archive_read_header_position(self.archive) }.max(0) as u64
+    }
+
+    /// Get the number of bytes consumed from the underlying source so far
+    ///
+    /// This is the position in the compressed file, so comparing it with the
+    /// file size gives the overall progress. It advances in blocks as the
+    /// source is read, while data is being read as well as at entry
+    /// boundaries, and may run slightly ahead of the data returned because of
+    /// read-ahead. Same as [`stats().compressed_bytes`](ReadStats::compressed_bytes).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let total = std::fs::metadata("backup.tar.gz")?.len();
+    /// let mut archive = ReadArchive::open("backup.tar.gz")?;
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.skip_data()?;
+    ///     let done = archive.bytes_read_compressed();
+    ///     println!("{:.1}%", 100.0 * done as f64 / total as f64);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn bytes_read_compressed(&self) -> u64 {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe { crate::stats::filter_bytes(self.archive, -1) }
+    }
+
+    /// Get the number of bytes the decompression filters have produced so far
+    ///
+    /// This counts archive headers and padding as well as entry data, and
+    /// advances in blocks like [`bytes_read_compressed`](Self::bytes_read_compressed).
+    /// Same as [`stats().uncompressed_bytes`](ReadStats::uncompressed_bytes).
+    pub fn bytes_read_uncompressed(&self) -> u64 {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe { crate::stats::filter_bytes(self.archive, 0) }
+    }
+
+    /// Get byte and entry statistics for the archive read so far
+    ///
+    /// The compressed and uncompressed counters come from libarchive's filter
+    /// byte counts, so they also account for skipped entries and archive headers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.skip_data()?;
+    /// }
+    /// let stats = archive.stats();
+    /// println!(
+    ///     "{} entries, {} -> {} bytes",
+    ///     stats.entries, stats.compressed_bytes, stats.uncompressed_bytes
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stats(&self) -> ReadStats {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        let (compressed_bytes, uncompressed_bytes) = unsafe {
+            (
+                crate::stats::filter_bytes(self.archive, -1),
+                crate::stats::filter_bytes(self.archive, 0),
+            )
+        };
+        ReadStats {
+            compressed_bytes,
+            uncompressed_bytes,
+            entries: self.entries_read,
+            data_bytes: self.data_bytes_read,
+        }
+    }
+
+    /// Get the name of the detected archive format (e.g. "POSIX pax interchange format")
+    ///
+    /// libarchive only settles on a format while reading the first header, so
+    /// this returns `None` until [`next_entry`](Self::next_entry) has returned an
+    /// entry. Some formats refine the name per entry (e.g. tar variants).
+    pub fn format_name(&self) -> Option<String> {
+        if self.entries_read == 0 {
+            return None;
+        }
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe {
+            let ptr = libarchive2_sys::archive_format_name(self.archive);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    /// Get libarchive's code for the detected archive format (`ARCHIVE_FORMAT_*`)
+    ///
+    /// Returns 0 until [`next_entry`](Self::next_entry) has returned an entry.
+    pub fn format_code(&self) -> i32 {
+        if self.entries_read == 0 {
+            return 0;
+        }
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe { libarchive2_sys::archive_format(self.archive) }
+    }
+
+    /// Get the number of filters in the read pipeline
+    ///
+    /// This counts every filter libarchive set up, including the pass-through
+    /// "none" filter that reads the raw input, so an uncompressed archive has one
+    /// filter. Returns 0 until [`next_entry`](Self::next_entry) has returned an
+    /// entry.
+    pub fn filter_count(&self) -> usize {
+        if self.entries_read == 0 {
+            return 0;
+        }
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        let count = unsafe { libarchive2_sys::archive_filter_count(self.archive) };
+        count.max(0) as usize
+    }
+
+    /// Get the name of the filter at `index` (e.g. "gzip")
+    ///
+    /// Index 0 is the filter closest to the archive format and
+    /// `filter_count() - 1` is the one reading the raw input ("none" unless the
+    /// input is wrapped, e.g. in uuencode). Returns `None` for an out-of-range
+    /// index or before [`next_entry`](Self::next_entry) has returned an entry.
+    pub fn filter_name(&self, index: usize) -> Option<String> {
+        if index >= self.filter_count() {
+            return None;
+        }
+        // SAFETY: archive is valid and index is within the filter count
+        unsafe {
+            let ptr = libarchive2_sys::archive_filter_name(self.archive, index as i32);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    /// Get the names of the detected filters (e.g. `["gzip"]`)
+    ///
+    /// Filters are listed from the one closest to the archive format to the one
+    /// reading the raw input, so a `.tar.gz.uu` file gives `["gzip", "uu"]`. The
+    /// pass-through "none" filter that reads the input itself is left out, so an
+    /// uncompressed archive gives an empty list.
+    pub fn filters(&self) -> Vec<String> {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        unsafe {
+            let count = libarchive2_sys::archive_filter_count(self.archive);
+            (0..count)
+                .filter(|&i| libarchive2_sys::archive_filter_code(self.archive, i) != 0)
+                .filter_map(|i| {
+                    let ptr = libarchive2_sys::archive_filter_name(self.archive, i);
+                    if ptr.is_null() {
+                        None
+                    } else {
+                        Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+                    }
+                })
+                .collect()
+        }
+    }
+
+    /// Get the number of data bytes read so far from the current entry
+    ///
+    /// Reset to 0 by every [`next_entry`](Self::next_entry) call. After reading an
+    /// entry to the end this is the entry's real size, which may differ from the
+    /// size claimed in its header.
+    pub fn entry_bytes_read(&self) -> u64 {
+        self.entry_bytes_read
+    }
+
     /// Read all data from the current entry into a vector
+    ///
+    /// Returns an empty vector for entries without data, such as directories,
+    /// symlinks and tar hard links (whose contents live with the target). cpio
+    /// stores the contents of a hard-linked file with the last link instead,
+    /// so there the final [`Entry::is_hardlink`] entry returns the data.
     pub fn read_data_to_vec(&mut self) -> Result<Vec<u8>> {
         let mut data = Vec::new();
         let mut buf = vec![0u8; 8192];
 
         loop {
-            let n = self.read_data(&mut buf)?;
-            if n == 0 {
-                break;
+            let n = self.read_data(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+
+        Ok(data)
+    }
+
+    /// Stream the rest of the current entry's data into a writer
+    ///
+    /// Data is copied through a buffer owned by the archive and reused across
+    /// calls, so no per-entry allocation is made. Returns the number of bytes
+    /// written. Errors from `w` are returned as [`Error::Io`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    /// use std::fs::File;
+    /// use std::io::BufWriter;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// if archive.next_entry()?.is_some() {
+    ///     let mut out = BufWriter::new(File::create("first-entry.bin")?);
+    ///     let written = archive.read_data_into(&mut out)?;
+    ///     println!("wrote {} bytes", written);
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn read_data_into<W: std::io::Write>(&mut self, w: &mut W) -> Result<u64> {
+        let mut buf = std::mem::take(&mut self.copy_buffer);
+        buf.resize(64 * 1024, 0);
+
+        let result = (|| {
+            let mut total = 0u64;
+            loop {
+                let n = self.read_data(&mut buf)?;
+                if n == 0 {
+                    return Ok(total);
+                }
+                w.write_all(&buf[..n])?;
+                total += n as u64;
+            }
+        })();
+
+        self.copy_buffer = buf;
+        result
+    }
+
+    /// Write the rest of the current entry's data to a file descriptor
+    ///
+    /// The entry's data is consumed completely; call
+    /// [`next_entry`](Self::next_entry) next rather than reading the same
+    /// entry again, which libarchive answers with zero padding. Holes in
+    /// sparse entries are skipped with `lseek` when `fd` is seekable. Returns
+    /// the warning message if libarchive reported a warning, which ends the
+    /// copy as it does in `archive_read_data_into_fd`, and `None` otherwise.
+    ///
+    /// The descriptor is not closed. Errors writing to it are returned as
+    /// fatal [`Error::Archive`] errors.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// if archive.next_entry()?.is_some() {
+    ///     let out = File::create("first-entry.bin")?;
+    ///     if let Some(warning) = archive.read_data_into_fd(out.as_raw_fd())? {
+    ///         eprintln!("warning: {}", warning);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn read_data_into_fd(&mut self, fd: std::os::unix::io::RawFd) -> Result<Option<String>> {
+        self.read_data_into_c_fd(fd)
+    }
+
+    /// Write the rest of the current entry's data to a file handle (Windows)
+    ///
+    /// Data is written through a C runtime descriptor, so a duplicate of
+    /// `handle` is associated with one for the call and closed afterwards.
+    /// `handle` itself is not closed.
+    #[cfg(windows)]
+    pub fn read_data_into_fd(
+        &mut self,
+        handle: std::os::windows::io::RawHandle,
+    ) -> Result<Option<String>> {
+        use std::os::windows::io::{BorrowedHandle, FromRawHandle, IntoRawHandle, OwnedHandle};
+
+        // SAFETY: the caller owns handle and keeps it open for the call
+        let duplicate = unsafe { BorrowedHandle::borrow_raw(handle) }
+            .try_clone_to_owned()
+            .map_err(Error::Io)?
+            .into_raw_handle();
+        // SAFETY: duplicate is a valid handle; on success the descriptor owns it
+        let fd = unsafe { libc::open_osfhandle(duplicate as libc::intptr_t, 0) };
+        if fd < 0 {
+            let error = std::io::Error::last_os_error();
+            // SAFETY: the descriptor was not created, so the duplicate is still ours
+            drop(unsafe { OwnedHandle::from_raw_handle(duplicate) });
+            return Err(Error::Io(error));
+        }
+        let result = self.read_data_into_c_fd(fd);
+        // SAFETY: fd was opened above and is not used again; closing it closes
+        // the duplicate
+        unsafe { libc::close(fd) };
+        result
+    }
+
+    /// Write the rest of the current entry's data to `file`
+    ///
+    /// Like [`read_data_into_fd`](Self::read_data_into_fd), data is written at
+    /// the file's current position.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    /// use std::fs::File;
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// if archive.next_entry()?.is_some() {
+    ///     let mut out = File::create("first-entry.bin")?;
+    ///     archive.read_data_into_file(&mut out)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn read_data_into_file(&mut self, file: &mut std::fs::File) -> Result<Option<String>> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            self.read_data_into_fd(file.as_raw_fd())
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::io::AsRawHandle;
+            self.read_data_into_fd(file.as_raw_handle())
+        }
+    }
+
+    fn read_data_into_c_fd(&mut self, fd: std::os::raw::c_int) -> Result<Option<String>> {
+        // The loop of archive_read_data_into_fd, so that every byte written
+        // is counted
+        let can_seek = fd_is_seekable(fd);
+        let mut written: i64 = 0;
+        loop {
+            let mut buffer: *const std::os::raw::c_void = ptr::null();
+            let mut size: usize = 0;
+            let mut offset: i64 = 0;
+
+            // SAFETY: archive is valid; libarchive fills the out-pointers
+            let ret = unsafe {
+                libarchive2_sys::archive_read_data_block(
+                    self.archive,
+                    &mut buffer,
+                    &mut size,
+                    &mut offset,
+                )
+            };
+            if ret == libarchive2_sys::ARCHIVE_WARN {
+                // SAFETY: as above
+                let message = unsafe { Error::warning_message(self.archive) };
+                self.warnings.push(message.clone());
+                return Ok(Some(message));
+            }
+            if ret != libarchive2_sys::ARCHIVE_OK as i32
+                && ret != libarchive2_sys::ARCHIVE_EOF as i32
+            {
+                return Err(self.read_error(ret));
+            }
+
+            // A hole before this block, or at the end of a sparse entry
+            if offset > written {
+                // Seeking past the end would not extend the file, so the
+                // trailing hole is written out
+                let seek = can_seek && ret != libarchive2_sys::ARCHIVE_EOF as i32;
+                pad_fd(fd, offset - written, seek).map_err(fd_write_error)?;
+                written = offset;
+            }
+            if ret == libarchive2_sys::ARCHIVE_EOF as i32 {
+                return Ok(None);
+            }
+            if size == 0 {
+                continue;
+            }
+
+            // SAFETY: libarchive guarantees buffer holds size bytes until the next call
+            let data = unsafe { std::slice::from_raw_parts(buffer as *const u8, size) };
+            write_all_fd(fd, data).map_err(fd_write_error)?;
+            written += size as i64;
+            self.record_data(size as u64);
+        }
+    }
+
+    /// Hash the rest of the current entry's data
+    ///
+    /// The data is streamed through the digest, so the entry is never held in
+    /// memory. Returns the digest as lowercase hex.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{HashAlgo, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("release.tar.gz")?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     let name = entry.pathname().unwrap_or_default();
+    ///     println!("{}  {}", archive.read_data_hash(HashAlgo::Sha256)?, name);
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn read_data_hash(&mut self, algo: crate::digest::HashAlgo) -> Result<String> {
+        let mut hasher = crate::digest::Hasher::new(algo);
+        self.read_data_into(&mut hasher)?;
+        Ok(hasher.finish_hex())
+    }
+
+    /// Read every remaining entry's data to check that it is intact
+    ///
+    /// Reading the data in full runs the checks the format provides, such as
+    /// the CRC of ZIP entries and the checksums of compressed streams, without
+    /// extracting anything. Entries whose data cannot be read are listed in
+    /// [`VerifyReport::failures`](crate::VerifyReport::failures) and the walk
+    /// carries on with the next entry; after a fatal error, which leaves the
+    /// archive unusable, the walk stops there. Damaged headers the reader can
+    /// step past are listed too.
+    ///
+    /// Returns an error only if an entry header cannot be read at all.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::ReadArchive;
+    ///
+    /// let mut archive = ReadArchive::open("backup.zip")?;
+    /// let report = archive.verify()?;
+    /// for failure in &report.failures {
+    ///     eprintln!("{}: {}", failure.pathname.as_deref().unwrap_or("?"), failure.error);
+    /// }
+    /// println!("{} entries, {} bad", report.entries, report.failures.len());
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn verify(&mut self) -> Result<crate::verify::VerifyReport> {
+        use crate::verify::{VerifyFailure, VerifyReport};
+
+        let mut report = VerifyReport::default();
+        loop {
+            let pathname = match self.next_entry() {
+                Ok(Some(entry)) => entry.pathname(),
+                Ok(None) => break,
+                Err(error) if error.is_retryable() => {
+                    report.failures.push(VerifyFailure {
+                        pathname: None,
+                        error,
+                    });
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            report.entries += 1;
+            match self.read_data_into(&mut std::io::sink()) {
+                Ok(bytes) => report.bytes += bytes,
+                Err(error) => {
+                    let fatal = error.is_fatal();
+                    report.failures.push(VerifyFailure { pathname, error });
+                    if fatal {
+                        break;
+                    }
+                }
             }
-            data.extend_from_slice(&buf[..n]);
         }
-
-        Ok(data)
+        report.stats = self.stats();
+        Ok(report)
     }
 
     /// Skip the data for the current entry
     pub fn skip_data(&mut self) -> Result<()> {
-        unsafe {
-            Error::from_return_code(
-                libarchive2_sys::archive_read_data_skip(self.archive) as i32,
-                self.archive,
-            )?;
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        let ret = unsafe { libarchive2_sys::archive_read_data_skip(self.archive) };
+        if ret < 0 {
+            return Err(self.read_error(ret));
+        }
+        if self.entry_bytes_read < self.entry_size
+            && let Some(progress) = self.progress.as_mut()
+        {
+            progress.on_progress(self.entry_size, self.entry_size);
         }
+        self.update_tracker();
         Ok(())
     }
 
@@ -725,7 +2042,7 @@ impl<'a> ReadArchive<'a> {
         unsafe {
             let pos = libarchive2_sys::archive_seek_data(self.archive, offset, whence);
             if pos < 0 {
-                Err(Error::from_archive(self.archive))
+                Err(Error::from_archive(self.archive, pos as i32))
             } else {
                 Ok(pos)
             }
@@ -734,9 +2051,48 @@ impl<'a> ReadArchive<'a> {
 
     /// Check if the current entry supports data block operations
     ///
-    /// Returns true if you can use read_data_block on this entry.
+    /// Returns true if you can use read_data_block on this entry, i.e. if
+    /// [`next_entry`](Self::next_entry) has returned an entry.
     pub fn has_data_block(&self) -> bool {
-        unsafe { libarchive2_sys::archive_read_has_encrypted_entries(self.archive) > 0 }
+        !self.current_entry.is_null()
+    }
+
+    /// Check whether the archive contains encrypted entries
+    ///
+    /// Formats that support encryption, such as ZIP, 7-Zip and RAR, may not
+    /// know until the first [`next_entry`](Self::next_entry) call, and report
+    /// [`EncryptionStatus::Unknown`] until then. Formats that cannot be
+    /// encrypted report [`EncryptionStatus::No`] once the archive is open.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{EncryptionStatus, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.zip")?;
+    /// archive.next_entry()?;
+    /// if archive.has_encrypted_entries() == EncryptionStatus::Yes {
+    ///     println!("this archive needs a password");
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn has_encrypted_entries(&self) -> EncryptionStatus {
+        // SAFETY: archive is a valid pointer for the lifetime of self
+        let (ret, opened) = unsafe {
+            (
+                libarchive2_sys::archive_read_has_encrypted_entries(self.archive),
+                libarchive2_sys::archive_filter_count(self.archive) > 0,
+            )
+        };
+        match ret {
+            libarchive2_sys::ARCHIVE_READ_FORMAT_ENCRYPTION_DONT_KNOW => EncryptionStatus::Unknown,
+            // Also reported before a format has been chosen
+            libarchive2_sys::ARCHIVE_READ_FORMAT_ENCRYPTION_UNSUPPORTED if !opened => {
+                EncryptionStatus::Unknown
+            }
+            ret if ret > 0 => EncryptionStatus::Yes,
+            _ => EncryptionStatus::No,
+        }
     }
 
     /// Read the next data block from the current entry
@@ -744,6 +2100,12 @@ impl<'a> ReadArchive<'a> {
     /// This is useful for sparse files or when you need fine-grained control over
     /// reading. Returns the offset within the entry and the data block.
     ///
+    /// For well-formed archives the offsets never decrease, and a gap between the
+    /// end of one block and the offset of the next is a hole that reads as zeros.
+    /// A hole at the end of the entry is not reported as a block; the entry's
+    /// [`size`](crate::Entry::size) gives the full length. Writing each block at
+    /// its offset into a file of that length reconstructs the entry.
+    ///
     /// # Returns
     ///
     /// - `Ok(Some((offset, data)))` - Next data block with its offset
@@ -766,35 +2128,471 @@ impl<'a> ReadArchive<'a> {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn read_data_block(&mut self) -> Result<Option<(i64, Vec<u8>)>> {
-        unsafe {
+        loop {
             let mut buffer: *const std::os::raw::c_void = std::ptr::null();
             let mut size: usize = 0;
             let mut offset: i64 = 0;
 
-            let ret = libarchive2_sys::archive_read_data_block(
-                self.archive,
-                &mut buffer,
-                &mut size,
-                &mut offset,
-            );
+            // SAFETY: archive is valid; libarchive fills the out-pointers
+            let ret = unsafe {
+                libarchive2_sys::archive_read_data_block(
+                    self.archive,
+                    &mut buffer,
+                    &mut size,
+                    &mut offset,
+                )
+            };
 
             if ret == libarchive2_sys::ARCHIVE_EOF as i32 {
                 return Ok(None);
             }
+            if ret != libarchive2_sys::ARCHIVE_OK as i32 {
+                return Err(self.read_error(ret));
+            }
+            // Some readers hand out empty blocks before the end of the data
+            if size == 0 {
+                continue;
+            }
 
-            if ret == libarchive2_sys::ARCHIVE_OK as i32 {
-                if size == 0 {
-                    return Ok(None);
-                }
+            // SAFETY: libarchive guarantees buffer is valid and contains 'size' bytes
+            // We copy the data to owned Vec to ensure memory safety
+            let data = unsafe { std::slice::from_raw_parts(buffer as *const u8, size) }.to_vec();
+            self.record_data(size as u64);
+            return Ok(Some((offset, data)));
+        }
+    }
+
+    /// Extract the current entry using an existing disk writer
+    ///
+    /// This writes the header of the entry returned by the last
+    /// [`next_entry`](Self::next_entry) call to `disk`, copies its data
+    /// (preserving holes in sparse files) and restores metadata according to
+    /// the options configured on `disk`, like libarchive's
+    /// `archive_read_extract2`. Problems restoring metadata are recorded as
+    /// [`WriteDisk::warnings`](crate::WriteDisk::warnings) rather than
+    /// returned as errors. Unlike
+    /// [`extract`](Self::extract), it does not take the `Entry` itself, so it can
+    /// be used inside a `next_entry` loop.
+    ///
+    /// The entry is written to its archived pathname, relative to the current
+    /// working directory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive, WriteDisk};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_options(ExtractFlags::TIME | ExtractFlags::PERM)?;
+    /// disk.set_standard_lookup()?;
+    ///
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.extract_current(&mut disk)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn extract_current(&mut self, disk: &mut crate::extract::WriteDisk) -> Result<()> {
+        let entry = self.current_entry_for_extraction()?;
+
+        // Written by hand rather than with archive_read_extract2, which turns
+        // header failures into warnings
+        // SAFETY: entry is the reader's current header, valid until next_entry
+        unsafe { disk.write_header_raw(entry)? };
+        // Entries streamed without a size (e.g. zip data descriptors) still
+        // carry data, so only skip the copy when the size is known to be zero
+        // SAFETY: as above
+        let has_data = unsafe {
+            libarchive2_sys::archive_entry_size_is_set(entry) == 0
+                || libarchive2_sys::archive_entry_size(entry) > 0
+        };
+        if has_data {
+            self.copy_data_to_disk(disk)?;
+        }
+        disk.finish_entry()
+    }
+
+    /// Extract the current entry with the given flags
+    ///
+    /// Like [`extract_current`](Self::extract_current), but uses a disk writer
+    /// owned by the archive (created on first use, with the standard user/group
+    /// lookup) instead of a caller-provided [`WriteDisk`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// let flags = ExtractFlags::TIME | ExtractFlags::PERM | ExtractFlags::SECURE_NODOTDOT;
+    ///
+    /// while archive.next_entry()?.is_some() {
+    ///     archive.extract_current_with_flags(flags)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn extract_current_with_flags(
+        &mut self,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<()> {
+        let entry = self.current_entry_for_extraction()?;
+        let _guard = crate::locale::WindowsUTF8LocaleGuard::new();
+
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_read_extract(self.archive, entry, flags.bits()),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Extract the current entry's data to disk under the given header
+    ///
+    /// This wraps libarchive's `archive_read_extract`: `entry` is written to disk
+    /// with `flags` (using a disk writer owned by the archive, with the standard
+    /// user/group lookup) and filled with the data of the entry last returned
+    /// by [`next_entry`](Self::next_entry). `entry` is copied first, so it is
+    /// never modified.
+    ///
+    /// Because the [`Entry`] returned by `next_entry` borrows the archive, this
+    /// is meant for a header that has been adjusted first, such as an
+    /// [`EntryMut`](crate::EntryMut) copy with a different pathname or
+    /// permissions. To extract the current header unchanged, use
+    /// [`extract_current_with_flags`](Self::extract_current_with_flags).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{EntryMut, ExtractFlags, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     let mut copy = EntryMut::new();
+    ///     copy.set_pathname(format!("renamed/{}", entry.pathname().unwrap_or_default()))?;
+    ///     copy.set_file_type(entry.file_type());
+    ///     if let Some(size) = entry.size_opt() {
+    ///         copy.set_size(size);
+    ///     }
+    ///     copy.set_perm(entry.mode())?;
+    ///     archive.extract_entry(&copy.as_entry(), ExtractFlags::PERM)?;
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn extract_entry(
+        &mut self,
+        entry: &Entry,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<()> {
+        self.current_entry()?;
+        let copy = self.copy_for_extraction(entry)?;
+        self.read_extract(&copy, flags)
+    }
+
+    /// Extract the current entry's data to disk under the given header, inside
+    /// `dest_dir`
+    ///
+    /// Like [`extract_entry`](Self::extract_entry), but the pathname (and any
+    /// hardlink target) of `entry` is placed under `dest_dir` first. Paths that
+    /// are absolute or contain `..` are rejected with
+    /// [`Error::InvalidArgument`] before anything is written, as is a `dest_dir`
+    /// that isn't valid UTF-8.
+    pub fn extract_entry_to<P: AsRef<Path>>(
+        &mut self,
+        entry: &Entry,
+        dest_dir: P,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<()> {
+        self.current_entry()?;
+        let copy = self.copy_for_extraction(entry)?;
+        let dest = dest_dir.as_ref();
+
+        let copied = copy.as_entry();
+        let pathname = copied.pathname().unwrap_or_default();
+        let full_path = join_contained(dest, &pathname, &pathname)?;
+        let full_link = match copied.hardlink() {
+            Some(target) => Some(join_contained(dest, &target, &pathname)?),
+            None => None,
+        };
+        // SAFETY: copy.entry is a valid entry owned by `copy`
+        unsafe {
+            libarchive2_sys::archive_entry_update_pathname_utf8(copy.entry, full_path.as_ptr());
+            if let Some(link) = &full_link {
+                libarchive2_sys::archive_entry_update_hardlink_utf8(copy.entry, link.as_ptr());
+            }
+        }
+
+        self.read_extract(&copy, flags)
+    }
+
+    /// Copy a header for extraction, normalizing backslashes if enabled
+    fn copy_for_extraction(&self, entry: &Entry) -> Result<crate::entry::EntryMut> {
+        // SAFETY: entry.entry is a valid entry for the lifetime of `entry`
+        let cloned = unsafe { libarchive2_sys::archive_entry_clone(entry.entry) };
+        if cloned.is_null() {
+            return Err(Error::NullPointer);
+        }
+        let copy = crate::entry::EntryMut {
+            entry: cloned,
+            owned: true,
+        };
+        if self.normalize_backslashes {
+            // SAFETY: cloned is a valid entry owned by `copy`
+            unsafe { normalize_backslashes(cloned) };
+        }
+        Ok(copy)
+    }
+
+    /// Extract the current entry's data under `entry` with archive_read_extract
+    fn read_extract(
+        &mut self,
+        entry: &crate::entry::EntryMut,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<()> {
+        let _guard = crate::locale::WindowsUTF8LocaleGuard::new();
+
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_read_extract(self.archive, entry.entry, flags.bits()),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Extract every remaining entry into a directory
+    ///
+    /// Each entry's pathname (and hardlink target) is prefixed with `dest` and the
+    /// entry is written through a [`WriteDisk`](crate::WriteDisk) configured with
+    /// `flags` and the standard user/group lookup. Missing parent directories
+    /// are created, and data is copied block by block so sparse files keep their
+    /// holes. `dest` is created if it doesn't exist.
+    ///
+    /// `SECURE_SYMLINKS` and `SECURE_NODOTDOT` are always added to `flags`. Entries
+    /// with absolute paths or `..` components are rejected before anything is
+    /// written for them. With `NO_OVERWRITE`, entries that already exist on disk
+    /// are left alone and counted in [`ExtractStats::skipped`](crate::ExtractStats::skipped).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] naming the entry if its pathname or
+    /// hardlink target would escape `dest`, or if `dest` isn't valid UTF-8.
+    /// Entries extracted before an error stay on disk.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.gz")?;
+    /// let stats = archive.extract_to_dir("out", ExtractFlags::TIME | ExtractFlags::PERM)?;
+    /// println!("{} files, {} bytes", stats.files, stats.bytes);
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn extract_to_dir<P: AsRef<Path>>(
+        &mut self,
+        dest: P,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<crate::extract::ExtractStats> {
+        use crate::entry::FileType;
+        use crate::extract::{ExtractFlags, ExtractStats, WriteDisk};
+
+        let dest = dest.as_ref();
+        std::fs::create_dir_all(dest).map_err(Error::Io)?;
+
+        // Entry paths are checked here and then joined onto `dest`, which may
+        // itself be absolute
+        let flags = (flags | ExtractFlags::SECURE_SYMLINKS | ExtractFlags::SECURE_NODOTDOT)
+            .without(ExtractFlags::SECURE_NOABSOLUTEPATHS);
+        let no_overwrite = flags.contains(ExtractFlags::NO_OVERWRITE);
+        let mut disk = WriteDisk::new()?;
+        disk.set_options(flags)?;
+        disk.set_standard_lookup()?;
+
+        let read_warnings = self.warnings.len();
+        let mut stats = ExtractStats::default();
+        while self.next_entry()?.is_some() {
+            let entry_ptr = self.current_entry_for_extraction()?;
+            let entry = Entry {
+                entry: entry_ptr,
+                archive: self.archive,
+                _marker: std::marker::PhantomData,
+            };
+            let pathname = entry.pathname().unwrap_or_default();
+            let hardlink = entry.hardlink();
+            let file_type = entry.file_type();
+            let size = entry.size_opt();
+
+            let full_path = join_contained(dest, &pathname, &pathname)?;
+            let full_link = match &hardlink {
+                Some(target) => Some(join_contained(dest, target, &pathname)?),
+                None => None,
+            };
+
+            // libarchive silently leaves existing files alone with NO_OVERWRITE;
+            // check up front so they can be counted
+            if no_overwrite
+                && file_type != FileType::Directory
+                && std::fs::symlink_metadata(dest.join(&pathname)).is_ok()
+            {
+                stats.skipped += 1;
+                continue;
+            }
 
-                // SAFETY: libarchive guarantees buffer is valid and contains 'size' bytes
-                // We copy the data to owned Vec to ensure memory safety
-                let data = std::slice::from_raw_parts(buffer as *const u8, size).to_vec();
-                Ok(Some((offset, data)))
+            // SAFETY: entry_ptr is the current header, valid until next_entry
+            unsafe {
+                libarchive2_sys::archive_entry_update_pathname_utf8(entry_ptr, full_path.as_ptr());
+                if let Some(link) = &full_link {
+                    libarchive2_sys::archive_entry_update_hardlink_utf8(entry_ptr, link.as_ptr());
+                }
+            }
+            // Write the entry by hand rather than with archive_read_extract2, which
+            // downgrades header failures (including SECURE_* refusals) to warnings
+            // SAFETY: entry_ptr is valid for this iteration
+            unsafe { disk.write_header_raw(entry_ptr)? };
+            let copied = if size.is_none_or(|size| size > 0) {
+                self.copy_data_to_disk(&mut disk)?
             } else {
-                Err(Error::from_archive(self.archive))
+                0
+            };
+            disk.finish_entry()?;
+
+            match (file_type, hardlink.is_some()) {
+                (_, true) => stats.hardlinks += 1,
+                (FileType::RegularFile, false) => {
+                    stats.files += 1;
+                    stats.bytes += size.map_or(copied, |size| size.max(0) as u64);
+                }
+                (FileType::Directory, false) => stats.directories += 1,
+                (FileType::SymbolicLink, false) => stats.symlinks += 1,
+                _ => stats.other += 1,
+            }
+        }
+
+        stats.warnings = (disk.warnings().len() + self.warnings.len() - read_warnings) as u64;
+        stats.stats = self.stats();
+        disk.close()?;
+        Ok(stats)
+    }
+
+    /// Extract every remaining entry into a directory, like `tar -xf`
+    ///
+    /// Returns the same [`ExtractStats`](crate::ExtractStats) as [`extract_to_dir`](Self::extract_to_dir),
+    /// which this is a shorthand for; see there for how paths, links, sparse
+    /// files and the `SECURE_*` flags are handled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ExtractFlags, ReadArchive};
+    ///
+    /// let mut archive = ReadArchive::open("archive.tar.zst")?;
+    /// let stats = archive.extract_to("out", ExtractFlags::TIME | ExtractFlags::PERM)?;
+    /// println!(
+    ///     "extracted {} entries from {} compressed bytes",
+    ///     stats.entries(),
+    ///     stats.stats.compressed_bytes
+    /// );
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn extract_to<P: AsRef<Path>>(
+        &mut self,
+        dest: P,
+        flags: crate::extract::ExtractFlags,
+    ) -> Result<crate::extract::ExtractStats> {
+        self.extract_to_dir(dest, flags)
+    }
+
+    /// Copy the current entry's data to a disk writer, keeping holes
+    ///
+    /// Each block from [`read_data_block`](Self::read_data_block) is written at
+    /// its offset with [`WriteDisk::write_data_block`](crate::WriteDisk::write_data_block),
+    /// so the gaps in a sparse entry become holes in the file rather than
+    /// written zeros. On filesystems without hole support the gaps read back as
+    /// zeros all the same. Call it after writing the entry's header to `disk`,
+    /// then call [`finish_entry`](crate::WriteDisk::finish_entry).
+    ///
+    /// Returns the number of data bytes copied, not counting holes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{EntryMut, ExtractFlags, FileType, ReadArchive, WriteDisk};
+    ///
+    /// let mut archive = ReadArchive::open("disk-image.tar")?;
+    /// let mut disk = WriteDisk::new()?;
+    /// disk.set_options(ExtractFlags::SPARSE)?;
+    ///
+    /// let entry = archive.next_entry()?.unwrap();
+    /// let mut out = EntryMut::new();
+    /// out.set_pathname("disk.img")?;
+    /// out.set_file_type(FileType::RegularFile);
+    /// if let Some(size) = entry.size_opt() {
+    ///     out.set_size(size);
+    /// }
+    /// out.set_perm(0o644)?;
+    /// disk.write_header(&out)?;
+    /// archive.copy_data_to_disk(&mut disk)?;
+    /// disk.finish_entry()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn copy_data_to_disk(&mut self, disk: &mut crate::extract::WriteDisk) -> Result<u64> {
+        if disk.skipping_entry() {
+            return Ok(0);
+        }
+        let mut copied = 0u64;
+        loop {
+            let mut buffer: *const std::os::raw::c_void = ptr::null();
+            let mut size: usize = 0;
+            let mut offset: i64 = 0;
+
+            // SAFETY: archive is valid; libarchive fills the out-pointers
+            let ret = unsafe {
+                libarchive2_sys::archive_read_data_block(
+                    self.archive,
+                    &mut buffer,
+                    &mut size,
+                    &mut offset,
+                )
+            };
+            if ret == libarchive2_sys::ARCHIVE_EOF as i32 {
+                return Ok(copied);
             }
+            if ret < libarchive2_sys::ARCHIVE_OK as i32 {
+                // SAFETY: archive is valid
+                return Err(self.read_error(ret));
+            }
+            self.record_data(size as u64);
+
+            let data = if size == 0 {
+                &[][..]
+            } else {
+                // SAFETY: buffer holds `size` bytes until the next read call
+                unsafe { std::slice::from_raw_parts(buffer as *const u8, size) }
+            };
+            disk.write_data_block(offset, data)?;
+            copied += size as u64;
+        }
+    }
+
+    fn current_entry(&self) -> Result<*mut libarchive2_sys::archive_entry> {
+        if self.current_entry.is_null() {
+            return Err(Error::InvalidArgument(
+                "No current entry; call next_entry() first".to_string(),
+            ));
+        }
+        Ok(self.current_entry)
+    }
+
+    pub(crate) fn current_entry_for_extraction(
+        &self,
+    ) -> Result<*mut libarchive2_sys::archive_entry> {
+        let entry = self.current_entry()?;
+        if self.normalize_backslashes {
+            // SAFETY: entry is the reader's current header, valid until next_entry
+            unsafe { normalize_backslashes(entry) };
         }
+        Ok(entry)
     }
 
     /// Extract the current entry to disk
@@ -854,7 +2652,10 @@ impl<'a> ReadArchive<'a> {
 
         // Update entry pathname to be relative to destination
         let dest_path = dest.as_ref();
-        let entry_path = entry.pathname().unwrap_or_default();
+        let mut entry_path = entry.pathname().unwrap_or_default();
+        if self.normalize_backslashes {
+            entry_path = entry_path.replace('\\', "/");
+        }
         let full_path = dest_path.join(entry_path);
 
         // Create a new entry with updated path
@@ -863,7 +2664,9 @@ impl<'a> ReadArchive<'a> {
 
         // Copy entry metadata
         new_entry.set_file_type(entry.file_type());
-        new_entry.set_size(entry.size());
+        if let Some(size) = entry.size_opt() {
+            new_entry.set_size(size);
+        }
         new_entry.set_perm(entry.mode())?;
         if let Some(mtime) = entry.mtime() {
             new_entry.set_mtime(mtime);
@@ -901,6 +2704,207 @@ impl<'a> ReadArchive<'a> {
     }
 }
 
+/// Whether holes can be skipped in `fd` with `lseek` rather than written out
+///
+/// Only regular files are seeked, as `archive_read_data_into_fd` does. On
+/// Windows, where the C runtime's `lseek` takes a 32-bit offset, holes are
+/// always written.
+fn fd_is_seekable(fd: std::os::raw::c_int) -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: fstat only writes to st, which is large enough
+        unsafe {
+            let mut st: libc::stat = std::mem::zeroed();
+            libc::fstat(fd, &mut st) == 0 && (st.st_mode & libc::S_IFMT) == libc::S_IFREG
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = fd;
+        false
+    }
+}
+
+/// Write all of `data` to `fd`
+fn write_all_fd(fd: std::os::raw::c_int, mut data: &[u8]) -> std::io::Result<()> {
+    while !data.is_empty() {
+        let chunk = data.len().min(i32::MAX as usize);
+        // SAFETY: data holds at least chunk bytes
+        let n = unsafe { libc::write(fd, data.as_ptr().cast(), chunk as _) };
+        if n < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        data = &data[n as usize..];
+    }
+    Ok(())
+}
+
+/// Move `fd` forward by `gap` bytes, seeking if `seek` is set and writing
+/// zeros otherwise
+fn pad_fd(fd: std::os::raw::c_int, gap: i64, seek: bool) -> std::io::Result<()> {
+    if seek {
+        // SAFETY: lseek has no memory preconditions
+        if unsafe { libc::lseek(fd, gap as _, libc::SEEK_CUR) } >= 0 {
+            return Ok(());
+        }
+    }
+    let zeros = [0u8; 16 * 1024];
+    let mut left = gap as u64;
+    while left > 0 {
+        let n = left.min(zeros.len() as u64) as usize;
+        write_all_fd(fd, &zeros[..n])?;
+        left -= n as u64;
+    }
+    Ok(())
+}
+
+/// Report a failed write to the descriptor given to `read_data_into_fd`
+///
+/// libarchive reports these as fatal, and so does this crate.
+fn fd_write_error(error: std::io::Error) -> Error {
+    let code = error.raw_os_error().unwrap_or(0);
+    Error::Archive {
+        status: crate::error::ARCHIVE_FATAL,
+        code,
+        message: format!("Write error: {}", error),
+        errno: (code != 0).then_some(code),
+    }
+}
+
+/// Register the read filter for `filter` and return libarchive's status
+///
+/// # Safety
+/// `archive` must be a valid read archive that has not been opened.
+unsafe fn register_filter(
+    archive: *mut libarchive2_sys::archive,
+    filter: CompressionFormat,
+) -> i32 {
+    // SAFETY: Caller guarantees archive is valid
+    unsafe {
+        match filter {
+            CompressionFormat::None => libarchive2_sys::archive_read_support_filter_none(archive),
+            CompressionFormat::Gzip => libarchive2_sys::archive_read_support_filter_gzip(archive),
+            CompressionFormat::Bzip2 => libarchive2_sys::archive_read_support_filter_bzip2(archive),
+            CompressionFormat::Xz => libarchive2_sys::archive_read_support_filter_xz(archive),
+            CompressionFormat::Zstd => libarchive2_sys::archive_read_support_filter_zstd(archive),
+            CompressionFormat::Lz4 => libarchive2_sys::archive_read_support_filter_lz4(archive),
+            CompressionFormat::Compress => {
+                libarchive2_sys::archive_read_support_filter_compress(archive)
+            }
+            // libarchive's uudecode filter also decodes base64
+            CompressionFormat::UuEncode | CompressionFormat::B64Encode => {
+                libarchive2_sys::archive_read_support_filter_uu(archive)
+            }
+            CompressionFormat::Lrzip => libarchive2_sys::archive_read_support_filter_lrzip(archive),
+            CompressionFormat::Lzop => libarchive2_sys::archive_read_support_filter_lzop(archive),
+            CompressionFormat::Grzip => libarchive2_sys::archive_read_support_filter_grzip(archive),
+            CompressionFormat::Lzip => libarchive2_sys::archive_read_support_filter_lzip(archive),
+            CompressionFormat::Lzma => libarchive2_sys::archive_read_support_filter_lzma(archive),
+        }
+    }
+}
+
+/// Join an archived path onto `dest`, rejecting paths that would escape it
+///
+/// `entry_name` is the pathname of the entry being extracted, used in the error.
+/// The result is passed to the `_utf8` entry setters, so a `dest` that isn't
+/// valid UTF-8 is rejected with [`Error::InvalidArgument`] as well.
+pub(crate) fn join_contained(dest: &Path, path: &str, entry_name: &str) -> Result<CString> {
+    use std::path::Component;
+
+    let relative = Path::new(path);
+    let escapes = relative.has_root()
+        || relative
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)));
+    if escapes {
+        return Err(Error::InvalidArgument(format!(
+            "Entry {:?} would escape the destination directory (path {:?})",
+            entry_name, path
+        )));
+    }
+
+    let joined = dest.join(relative);
+    let joined = joined.to_str().ok_or_else(|| {
+        Error::InvalidArgument(format!(
+            "Destination path {:?} contains invalid UTF-8",
+            dest
+        ))
+    })?;
+    CString::new(joined).map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))
+}
+
+/// Replace `\` with `/` in an entry's pathname and hardlink target
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+unsafe fn normalize_backslashes(entry: *mut libarchive2_sys::archive_entry) {
+    use std::ffi::CStr;
+
+    unsafe fn replace(current: *const std::os::raw::c_char) -> Option<CString> {
+        if current.is_null() {
+            return None;
+        }
+        // SAFETY: libarchive returns a valid NUL-terminated string
+        let bytes = unsafe { CStr::from_ptr(current) }.to_bytes();
+        if !bytes.contains(&b'\\') {
+            return None;
+        }
+        let replaced: Vec<u8> = bytes
+            .iter()
+            .map(|&b| if b == b'\\' { b'/' } else { b })
+            .collect();
+        CString::new(replaced).ok()
+    }
+
+    unsafe {
+        if let Some(path) = replace(libarchive2_sys::archive_entry_pathname_utf8(entry)) {
+            libarchive2_sys::archive_entry_update_pathname_utf8(entry, path.as_ptr());
+        }
+        if let Some(link) = replace(libarchive2_sys::archive_entry_hardlink_utf8(entry)) {
+            libarchive2_sys::archive_entry_update_hardlink_utf8(entry, link.as_ptr());
+        }
+    }
+}
+
+/// Iterator over archive entries, created by [`ReadArchive::entries`]
+pub struct Entries<'r, 'a> {
+    archive: &'r mut ReadArchive<'a>,
+    done: bool,
+}
+
+impl<'r, 'a> Entries<'r, 'a> {
+    /// Access the archive, e.g. to read the data of the entry last returned
+    pub fn archive(&mut self) -> &mut ReadArchive<'a> {
+        self.archive
+    }
+}
+
+impl<'r, 'a> Iterator for Entries<'r, 'a> {
+    type Item = Result<crate::entry::OwnedEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.archive.next_entry() {
+            Ok(Some(entry)) => Some(Ok(crate::entry::OwnedEntry::from(&entry))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 /// `std::io::Read` implementation for reading data from the current archive entry.
 ///
 /// This allows using `ReadArchive` with anything that accepts a `Read` trait object,
@@ -924,8 +2928,12 @@ impl<'a> ReadArchive<'a> {
 /// ```
 impl<'a> std::io::Read for ReadArchive<'a> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.read_data(buf)
-            .map_err(|e| std::io::Error::other(e.to_string()))
+        self.read_data(buf).map_err(|e| match e {
+            Error::Truncated { .. } => {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e.to_string())
+            }
+            _ => std::io::Error::other(e.to_string()),
+        })
     }
 }
 
@@ -942,6 +2950,9 @@ impl<'a> Drop for ReadArchive<'a> {
             if let Some((data, drop_fn)) = self._callback_data.take() {
                 drop_fn(data);
             }
+            if let Some((data, drop_fn)) = self.passphrase_callback.take() {
+                drop_fn(data);
+            }
         }
     }
 }
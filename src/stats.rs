@@ -0,0 +1,80 @@
+//! Byte and entry statistics for archive reading and writing
+
+/// Statistics collected while reading an archive
+///
+/// Obtained from [`ReadArchive::stats`](crate::ReadArchive::stats). The byte
+/// counters reflect what libarchive has consumed so far, so they are only final
+/// once every entry has been read or skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadStats {
+    /// Bytes consumed from the underlying source (before decompression)
+    pub compressed_bytes: u64,
+    /// Bytes produced by the decompression filters (archive headers included)
+    pub uncompressed_bytes: u64,
+    /// Number of entry headers read
+    pub entries: u64,
+    /// Bytes of entry data actually returned by `read_data`/`read_data_block`
+    ///
+    /// This can differ from the sum of the header sizes, e.g. when entries are
+    /// skipped or when a header claims a size its data does not match.
+    pub data_bytes: u64,
+}
+
+impl ReadStats {
+    /// Ratio of uncompressed to compressed bytes (0.0 if nothing has been read)
+    pub fn compression_ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            0.0
+        } else {
+            self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+        }
+    }
+}
+
+/// Statistics collected while writing an archive
+///
+/// Obtained from [`WriteArchive::stats`](crate::WriteArchive::stats) while
+/// writing. libarchive buffers output internally, so `compressed_bytes` may lag
+/// behind until the archive is finished; the final values are returned by
+/// [`WriteArchive::finish`](crate::WriteArchive::finish).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteStats {
+    /// Bytes emitted by the last filter to the destination
+    pub compressed_bytes: u64,
+    /// Bytes handed to the compression filters by the format writer (headers included)
+    pub uncompressed_bytes: u64,
+    /// Number of entry headers written
+    pub entries: u64,
+    /// Bytes of entry data accepted by `write_data`/`write_data_block`
+    pub data_bytes: u64,
+}
+
+impl WriteStats {
+    /// Ratio of uncompressed to compressed bytes (0.0 if nothing has been written)
+    pub fn compression_ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            0.0
+        } else {
+            self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+        }
+    }
+
+    /// Bytes the format added around the entry data (headers, padding and
+    /// trailers), before compression
+    pub fn format_overhead(&self) -> u64 {
+        self.uncompressed_bytes.saturating_sub(self.data_bytes)
+    }
+}
+
+/// Read the byte counter of a filter, treating errors and missing filters as 0
+///
+/// Index 0 is the filter closest to the format (uncompressed side) and -1 is the
+/// last filter, which talks to the raw source or destination.
+///
+/// # Safety
+/// `archive` must be a valid, non-null archive pointer.
+pub(crate) unsafe fn filter_bytes(archive: *mut libarchive2_sys::archive, index: i32) -> u64 {
+    // SAFETY: Caller guarantees archive is valid
+    let bytes = unsafe { libarchive2_sys::archive_filter_bytes(archive, index) };
+    bytes.max(0) as u64
+}
@@ -0,0 +1,243 @@
+//! Two-pass reading: a header-only scan followed by a data pass
+
+use crate::entry::Entry;
+use crate::error::{Error, Result};
+use crate::reader::ReadArchive;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Entry and byte counts collected by a [`TwoPass`] pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TwoPassTotals {
+    /// Number of entries visited
+    pub entries: u64,
+    /// For [`TwoPass::scan`], the sum of the sizes recorded in the entry headers.
+    /// For [`TwoPass::process`], the number of data bytes read through
+    /// [`EntryData`].
+    pub bytes: u64,
+}
+
+/// Reads an archive file twice: once for metadata, once for data
+///
+/// Progress-accurate extraction needs the total size up front, which means
+/// reading all headers before reading any data. `TwoPass` opens the same
+/// file(s) with the same passphrases and options for both passes, and
+/// [`process`](Self::process) fails if the files or their entries changed after
+/// [`scan`](Self::scan).
+///
+/// Created by [`ReadArchive::open_two_pass`] or
+/// [`ReadArchive::open_two_pass_filenames`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use libarchive2::ReadArchive;
+///
+/// let mut two_pass = ReadArchive::open_two_pass("archive.tar.zst")?;
+/// let totals = two_pass.scan(|_entry| {})?;
+///
+/// let mut done = 0;
+/// two_pass.process(|entry, data| {
+///     let contents = data.read_to_vec()?;
+///     done += contents.len() as u64;
+///     println!("{} ({}/{} bytes)", entry.pathname().unwrap_or_default(), done, totals.bytes);
+///     Ok(())
+/// })?;
+/// # Ok::<(), libarchive2::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TwoPass {
+    paths: Vec<PathBuf>,
+    passphrases: Vec<String>,
+    options: Vec<(String, String, String)>,
+    scanned: Option<ScanRecord>,
+}
+
+#[derive(Debug)]
+struct ScanRecord {
+    files: Vec<(u64, Option<SystemTime>)>,
+    entries: Vec<(Option<String>, Option<i64>)>,
+}
+
+impl TwoPass {
+    pub(crate) fn new(paths: Vec<PathBuf>) -> Result<Self> {
+        if paths.is_empty() {
+            return Err(Error::InvalidArgument(
+                "At least one file path must be provided".to_string(),
+            ));
+        }
+        Ok(TwoPass {
+            paths,
+            passphrases: Vec::new(),
+            options: Vec::new(),
+            scanned: None,
+        })
+    }
+
+    /// Add a passphrase used by both passes
+    pub fn passphrase<S: Into<String>>(mut self, passphrase: S) -> Self {
+        self.passphrases.push(passphrase.into());
+        self
+    }
+
+    /// Set a reader option (see [`ReadArchive::set_option`]) used by both passes
+    pub fn option(mut self, module: &str, option: &str, value: &str) -> Self {
+        self.options
+            .push((module.to_string(), option.to_string(), value.to_string()));
+        self
+    }
+
+    /// Run the metadata pass
+    ///
+    /// Calls `f` for every entry header without reading any data and returns the
+    /// entry count and the sum of the header sizes. Running `scan` again replaces
+    /// the previous results.
+    pub fn scan<F>(&mut self, mut f: F) -> Result<TwoPassTotals>
+    where
+        F: FnMut(&Entry),
+    {
+        let files = self.file_states()?;
+        let mut reader = self.open_reader()?;
+        let mut entries = Vec::new();
+        let mut totals = TwoPassTotals::default();
+
+        while let Some(entry) = reader.next_entry()? {
+            f(&entry);
+            let size = entry.size_opt();
+            entries.push((entry.pathname(), size));
+            totals.entries += 1;
+            totals.bytes += size.unwrap_or(0).max(0) as u64;
+            reader.skip_data()?;
+        }
+
+        self.scanned = Some(ScanRecord { files, entries });
+        Ok(totals)
+    }
+
+    /// Run the data pass
+    ///
+    /// Reopens the archive and calls `f` for every entry with an [`EntryData`]
+    /// handle for reading its contents. Data that `f` leaves unread is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if [`scan`](Self::scan) has not been run,
+    /// if any file's size or modification time changed since the scan, or if the
+    /// entries differ from the ones scanned. Errors returned by `f` stop the pass
+    /// and are passed through.
+    pub fn process<F>(&mut self, mut f: F) -> Result<TwoPassTotals>
+    where
+        F: FnMut(&Entry, &mut EntryData<'_, 'static>) -> Result<()>,
+    {
+        let scanned = self.scanned.as_ref().ok_or_else(|| {
+            Error::InvalidArgument("scan() must be called before process()".to_string())
+        })?;
+        if self.file_states()? != scanned.files {
+            return Err(Error::InvalidArgument(
+                "Archive file changed between scan() and process()".to_string(),
+            ));
+        }
+
+        let mut reader = self.open_reader()?;
+        let mut totals = TwoPassTotals::default();
+        let mut expected = scanned.entries.iter();
+
+        while let Some(entry_ptr) = reader.next_entry()?.map(|entry| entry.entry) {
+            // Build an Entry that doesn't borrow the reader so the callback can
+            // also read data. EntryData cannot advance to the next header, so the
+            // entry stays valid for the duration of the callback.
+            let entry = Entry {
+                entry: entry_ptr,
+                archive: reader.archive(),
+                _marker: std::marker::PhantomData,
+            };
+
+            let matches_scan = expected
+                .next()
+                .is_some_and(|(path, size)| *path == entry.pathname() && *size == entry.size_opt());
+            if !matches_scan {
+                return Err(Error::InvalidArgument(format!(
+                    "Entry {} does not match the scanned entries",
+                    entry.pathname().unwrap_or_default()
+                )));
+            }
+
+            let mut data = EntryData {
+                reader: &mut reader,
+                bytes_read: 0,
+            };
+            f(&entry, &mut data)?;
+            totals.entries += 1;
+            totals.bytes += data.bytes_read;
+        }
+
+        if expected.next().is_some() {
+            return Err(Error::InvalidArgument(
+                "Archive has fewer entries than scanned".to_string(),
+            ));
+        }
+        Ok(totals)
+    }
+
+    fn open_reader(&self) -> Result<ReadArchive<'static>> {
+        let mut reader = ReadArchive::new()?;
+        reader.support_filter_all()?;
+        reader.support_format_all()?;
+        for passphrase in &self.passphrases {
+            reader.add_passphrase(passphrase)?;
+        }
+        // libarchive only accepts read options before the archive is opened
+        for (module, option, value) in &self.options {
+            reader.set_option(module, option, value)?;
+        }
+        reader.open_volumes(&self.paths)?;
+        Ok(reader)
+    }
+
+    fn file_states(&self) -> Result<Vec<(u64, Option<SystemTime>)>> {
+        self.paths
+            .iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(path)?;
+                Ok((metadata.len(), metadata.modified().ok()))
+            })
+            .collect()
+    }
+}
+
+/// Access to the data of the entry being processed by [`TwoPass::process`]
+pub struct EntryData<'r, 'a> {
+    reader: &'r mut ReadArchive<'a>,
+    bytes_read: u64,
+}
+
+impl<'r, 'a> EntryData<'r, 'a> {
+    /// Read data from the entry
+    ///
+    /// Returns 0 at the end of the entry.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.reader.read_data(buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+
+    /// Read the rest of the entry's data into a vector
+    pub fn read_to_vec(&mut self) -> Result<Vec<u8>> {
+        let data = self.reader.read_data_to_vec()?;
+        self.bytes_read += data.len() as u64;
+        Ok(data)
+    }
+
+    /// Get the number of bytes read from this entry so far
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+}
+
+impl<'r, 'a> std::io::Read for EntryData<'r, 'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = std::io::Read::read(self.reader, buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
@@ -0,0 +1,34 @@
+//! Results of checking an archive's entries without extracting them
+
+use crate::error::Error;
+use crate::stats::ReadStats;
+
+/// Outcome of [`ReadArchive::verify`](crate::ReadArchive::verify)
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Entry headers read
+    pub entries: u64,
+    /// Bytes of entry data read successfully
+    pub bytes: u64,
+    /// Entries whose data, or headers that, could not be read
+    pub failures: Vec<VerifyFailure>,
+    /// Read statistics of the archive once the walk ended, including its
+    /// compressed and uncompressed byte totals
+    pub stats: ReadStats,
+}
+
+impl VerifyReport {
+    /// Check whether every entry was read without error
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// An entry that failed verification
+#[derive(Debug)]
+pub struct VerifyFailure {
+    /// Pathname of the entry, or `None` for a damaged header
+    pub pathname: Option<String>,
+    /// The error libarchive reported
+    pub error: Error,
+}
@@ -1,12 +1,53 @@
 //! Archive writing functionality
 
+use crate::append::{AppendState, AppendStrategy, Existing};
+use crate::callbacks::{ProgressCallback, ProgressTracker};
 use crate::entry::{EntryMut, FileType};
 use crate::error::{Error, Result};
-use crate::format::{ArchiveFormat, CompressionFormat, FilterOption, FormatOption};
-use std::ffi::CString;
-use std::path::Path;
+use crate::format::{ArchiveFormat, CompressionFormat, FilterOption, FormatOption, Zip64Mode};
+use crate::read_disk::SymlinkMode;
+use crate::stats::WriteStats;
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::SystemTime;
 
+/// How [`WriteArchive`] handles an entry whose pathname was already written
+///
+/// Extractors disagree on which copy of a duplicated path wins, so archives with
+/// duplicates behave differently depending on the tool used to unpack them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Write every entry, even if its pathname repeats (libarchive's behavior)
+    #[default]
+    Allow,
+    /// Write the entry but record a warning, see [`WriteArchive::warnings`]
+    Warn,
+    /// Fail `write_header` with [`Error::InvalidArgument`] naming the path
+    Error,
+    /// Keep the first entry and silently drop later ones, including their data
+    SkipLater,
+}
+
+/// How [`WriteArchive`] handles entry metadata the archive format cannot store
+///
+/// This covers extended attributes, ACLs and block or character device nodes.
+/// ZIP and 7-Zip, for example, store none of them, and only the pax tar
+/// formats store ACLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnsupportedMetadataPolicy {
+    /// Write the entry and let the format drop what it cannot store
+    /// (libarchive's behavior)
+    #[default]
+    Ignore,
+    /// Write the entry but record a warning, see [`WriteArchive::warnings`]
+    Warn,
+    /// Fail `write_header` with [`Error::InvalidArgument`] naming the entry and
+    /// the metadata that would be lost
+    Error,
+}
+
 /// Archive writer with builder pattern and RAII resource management
 ///
 /// The lifetime parameter 'a represents borrowed data (e.g., when writing to memory).
@@ -24,21 +65,50 @@ pub struct WriteArchive<'a> {
     passphrase: Option<String>,
     format_options: Vec<FormatOption>,
     filter_options: Vec<FilterOption>,
+    raw_options: Vec<(String, String)>,
     default_mtime: Option<SystemTime>,
     default_uid: Option<u64>,
     default_gid: Option<u64>,
     default_uname: Option<String>,
     default_gname: Option<String>,
+    deterministic: bool,
+    /// Timestamp deterministic mode writes, in seconds since the epoch
+    source_date_epoch: Option<i64>,
     strip_directory_trailing_slash: bool,
+    path_prefix: Option<String>,
+    path_transform: Option<PathTransform>,
+    durable: bool,
+    output_path: Option<PathBuf>,
+    #[cfg(unix)]
+    output_fd: Option<std::os::unix::io::RawFd>,
+    entries_written: u64,
+    data_bytes_written: u64,
+    duplicate_policy: DuplicatePolicy,
+    unsupported_metadata: UnsupportedMetadataPolicy,
+    written_paths: HashSet<Vec<u8>>,
+    skipping_entry: bool,
+    warnings: Vec<String>,
+    symlink_mode: SymlinkMode,
+    progress: Option<Box<dyn ProgressCallback + Send>>,
+    tracker: Option<ProgressTracker>,
+    entry_size: u64,
+    entry_bytes_written: u64,
+    append: Option<AppendState>,
+    /// Output of [`open_growable_memory`](Self::open_growable_memory)
+    memory: Option<Arc<Mutex<Vec<u8>>>>,
     _callback_data: Option<(*mut std::ffi::c_void, crate::callbacks::DropFn)>,
     _phantom: std::marker::PhantomData<&'a mut [u8]>,
 }
 
+/// Closure installed by [`WriteArchive::set_path_transform`]
+type PathTransform = Box<dyn FnMut(&str) -> Option<String> + Send>;
+
 // SAFETY: WriteArchive can be sent between threads because:
 // 1. The archive pointer is owned exclusively by this instance
 // 2. libarchive archive objects can be used from different threads (just not concurrently)
 // 3. All other fields (format, compression, passphrase, callback_data) are Send
-// 4. The phantom data only tracks lifetimes, not actual data
+// 4. The tracker's callback is required to be Send by set_progress
+// 5. The phantom data only tracks lifetimes, not actual data
 unsafe impl<'a> Send for WriteArchive<'a> {}
 
 // Note: WriteArchive is NOT Sync because libarchive archives are not thread-safe
@@ -60,12 +130,35 @@ impl<'a> WriteArchive<'a> {
             passphrase: None,
             format_options: Vec::new(),
             filter_options: Vec::new(),
+            raw_options: Vec::new(),
             default_mtime: None,
             default_uid: None,
             default_gid: None,
             default_uname: None,
             default_gname: None,
+            deterministic: false,
+            source_date_epoch: None,
             strip_directory_trailing_slash: false,
+            path_prefix: None,
+            path_transform: None,
+            durable: false,
+            output_path: None,
+            #[cfg(unix)]
+            output_fd: None,
+            entries_written: 0,
+            data_bytes_written: 0,
+            duplicate_policy: DuplicatePolicy::Allow,
+            unsupported_metadata: UnsupportedMetadataPolicy::Ignore,
+            written_paths: HashSet::new(),
+            skipping_entry: false,
+            warnings: Vec::new(),
+            symlink_mode: SymlinkMode::Physical,
+            progress: None,
+            tracker: None,
+            entry_size: 0,
+            entry_bytes_written: 0,
+            append: None,
+            memory: None,
             _callback_data: None,
             _phantom: std::marker::PhantomData,
         }
@@ -83,8 +176,113 @@ impl<'a> WriteArchive<'a> {
         self
     }
 
+    /// Infer the archive format and compression from a file name
+    ///
+    /// This is the inverse of [`ArchiveFormat::extension`] and
+    /// [`CompressionFormat::extension`]: `backup.tar.zst` gives
+    /// `(TarPax, Zstd)` and `site.zip` gives `(Zip, None)`. The single-suffix
+    /// tar forms `.tgz`, `.taz`, `.tbz`, `.tbz2`, `.tb2`, `.txz`, `.tlz`,
+    /// `.tzst` and `.tZ` are recognized too. A compression suffix after a name
+    /// that is not an archive, such as `notes.txt.gz`, gives
+    /// [`ArchiveFormat::Raw`], which stores a single file. Suffixes are
+    /// matched without regard to case, except `.Z` (compress).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if the extension is not one of a
+    /// format libarchive can write.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libarchive2::{ArchiveFormat, CompressionFormat, WriteArchive};
+    ///
+    /// assert_eq!(
+    ///     WriteArchive::guess_from_extension("output.tgz")?,
+    ///     (ArchiveFormat::TarPax, CompressionFormat::Gzip)
+    /// );
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn guess_from_extension<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(ArchiveFormat, CompressionFormat)> {
+        let path = path.as_ref();
+        let unknown = || {
+            Error::InvalidArgument(format!(
+                "Cannot infer an archive format from the extension of {}",
+                path.display()
+            ))
+        };
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(unknown)?;
+        let mut parts = name.rsplit('.');
+        let last = parts
+            .next()
+            .filter(|_| name.contains('.'))
+            .ok_or_else(unknown)?;
+
+        let tar = |compression| Ok((ArchiveFormat::TarPax, compression));
+        match last {
+            "Z" => {}
+            "tZ" => return tar(CompressionFormat::Compress),
+            _ => match last.to_ascii_lowercase().as_str() {
+                "tgz" | "taz" => return tar(CompressionFormat::Gzip),
+                "tbz" | "tbz2" | "tb2" => return tar(CompressionFormat::Bzip2),
+                "txz" => return tar(CompressionFormat::Xz),
+                "tlz" => return tar(CompressionFormat::Lzip),
+                "tzst" => return tar(CompressionFormat::Zstd),
+                _ => {}
+            },
+        }
+
+        let compression = match last {
+            "Z" => Some(CompressionFormat::Compress),
+            _ => match last.to_ascii_lowercase().as_str() {
+                "gz" => Some(CompressionFormat::Gzip),
+                "bz2" => Some(CompressionFormat::Bzip2),
+                "xz" => Some(CompressionFormat::Xz),
+                "zst" => Some(CompressionFormat::Zstd),
+                "lz4" => Some(CompressionFormat::Lz4),
+                "lz" => Some(CompressionFormat::Lzip),
+                "lzma" => Some(CompressionFormat::Lzma),
+                "uu" => Some(CompressionFormat::UuEncode),
+                "b64" => Some(CompressionFormat::B64Encode),
+                "lrz" => Some(CompressionFormat::Lrzip),
+                "lzo" => Some(CompressionFormat::Lzop),
+                "grz" => Some(CompressionFormat::Grzip),
+                _ => None,
+            },
+        };
+        let archive_ext = match compression {
+            Some(_) => parts.next().filter(|_| name.matches('.').count() > 1),
+            None => Some(last),
+        };
+
+        let format = match archive_ext.map(|ext| ext.to_ascii_lowercase()).as_deref() {
+            Some("tar") => ArchiveFormat::TarPax,
+            Some("zip") => ArchiveFormat::Zip,
+            Some("7z") => ArchiveFormat::SevenZip,
+            Some("ar") => ArchiveFormat::Ar,
+            Some("cpio") => ArchiveFormat::Cpio,
+            Some("iso") => ArchiveFormat::Iso9660,
+            Some("xar") => ArchiveFormat::Xar,
+            Some("mtree") => ArchiveFormat::Mtree,
+            Some("shar") => ArchiveFormat::Shar,
+            Some("warc") => ArchiveFormat::Warc,
+            _ if compression.is_some() => ArchiveFormat::Raw,
+            _ => return Err(unknown()),
+        };
+        Ok((format, compression.unwrap_or(CompressionFormat::None)))
+    }
+
     /// Set a passphrase for encryption (ZIP and 7z formats)
     ///
+    /// For ZIP this also turns on encryption, using AES-256 unless
+    /// [`FormatOption::ZipEncryption`] selects another scheme. AES needs
+    /// libarchive to be built with a crypto library; otherwise `open_*` fails.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -145,6 +343,33 @@ impl<'a> WriteArchive<'a> {
         self
     }
 
+    /// Set a libarchive option by name
+    ///
+    /// `key` is either `module:option`, to target one format or filter (for
+    /// example `"zip:experimental"` or `"gzip:compression-level"`), or a bare
+    /// option name, which is offered to every module. This reaches options that
+    /// [`FormatOption`] and [`FilterOption`] don't model. Raw options are applied
+    /// after the typed ones, so they win when both set the same option.
+    ///
+    /// Options are applied when the archive is opened; an option libarchive
+    /// doesn't recognize makes `open_*` fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::Zip)
+    ///     .option("zip:compression", "store")
+    ///     .open_file("output.zip")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn option(mut self, key: &str, value: &str) -> Self {
+        self.raw_options.push((key.to_string(), value.to_string()));
+        self
+    }
+
     /// Set a default modification time for all entries
     ///
     /// When set, every entry written via [`write_header`](Self::write_header),
@@ -220,6 +445,61 @@ impl<'a> WriteArchive<'a> {
         self
     }
 
+    /// Write byte-for-byte reproducible archives
+    ///
+    /// When enabled, every entry written has:
+    ///
+    /// - its mtime, atime and ctime set to the
+    ///   [`source_date_epoch`](Self::source_date_epoch) timestamp, and its
+    ///   birth time removed
+    /// - uid and gid 0 and no user or group name
+    /// - inode, device and link count 0
+    ///
+    /// These take precedence over [`default_mtime`](Self::default_mtime),
+    /// [`default_uid`](Self::default_uid) and the other `default_*` overrides.
+    /// Gzip compression stops recording the time in its header, and the zip
+    /// writer stops writing extended timestamp fields where the linked
+    /// libarchive supports it; otherwise they hold the fixed timestamp.
+    ///
+    /// Entries are written in the order they are added, so callers control
+    /// the ordering: sort paths before adding them if they come from a
+    /// directory listing. Entry contents and other metadata, such as
+    /// permissions, extended attributes and ACLs, are written as given.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, CompressionFormat, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .compression(CompressionFormat::Gzip)
+    ///     .deterministic(true)
+    ///     .open_file("release.tar.gz")?;
+    /// archive.add_file("VERSION", b"1.0.0\n")?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn deterministic(mut self, enable: bool) -> Self {
+        self.deterministic = enable;
+        self
+    }
+
+    /// Set the timestamp written by [`deterministic`](Self::deterministic) mode
+    ///
+    /// Without this, the `SOURCE_DATE_EPOCH` environment variable is used when
+    /// it holds a number of seconds, as the [reproducible builds
+    /// convention](https://reproducible-builds.org/specs/source-date-epoch/)
+    /// specifies, and the Unix epoch otherwise. Times before the epoch are
+    /// written as the epoch. Has no effect unless deterministic mode is on.
+    pub fn source_date_epoch(mut self, time: SystemTime) -> Self {
+        self.source_date_epoch = Some(
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+        );
+        self
+    }
+
     /// Strip trailing slashes from directory entry pathnames
     ///
     /// When enabled, directory entries written via [`write_header`](Self::write_header),
@@ -256,6 +536,191 @@ impl<'a> WriteArchive<'a> {
         self
     }
 
+    /// Prepend a directory prefix to every entry pathname
+    ///
+    /// The prefix is added in [`write_header`](Self::write_header), so it applies
+    /// to every entry whichever method writes it, along with hardlink targets
+    /// (which are archive paths) but not symlink targets. A `/` is appended to
+    /// the prefix if it has none, and leading `/` on entry pathnames are dropped,
+    /// so `"pkg"` and `"pkg/"` both turn `"a.txt"` into `"pkg/a.txt"`. An empty
+    /// prefix disables prefixing.
+    ///
+    /// Prefixing happens before
+    /// [`strip_directory_trailing_slash`](Self::strip_directory_trailing_slash),
+    /// so a directory entry naming the prefix itself still has its trailing
+    /// slash removed.
+    ///
+    /// Names that aren't valid UTF-8 are prefixed as raw bytes. Opening the
+    /// archive fails with [`Error::InvalidArgument`] if the prefix starts with
+    /// `/`, since that would make every member path absolute.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .path_prefix("myapp-1.0/")
+    ///     .open_file("myapp-1.0.tar")?;
+    ///
+    /// archive.add_file("README", b"...")?;
+    /// archive.finish()?;
+    /// // The entry pathname will be "myapp-1.0/README"
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn path_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        let prefix = prefix.into();
+        let trimmed = prefix.trim_end_matches('/');
+        self.path_prefix = if prefix.is_empty() {
+            None
+        } else {
+            Some(format!("{}/", trimmed))
+        };
+        self
+    }
+
+    /// Flush the archive to stable storage when finishing
+    ///
+    /// When enabled, [`finish`](Self::finish) only returns `Ok` once the written
+    /// bytes have been synced to disk: for [`open_file`](Self::open_file) the file
+    /// and its parent directory are fsynced, and for [`open_fd`](Self::open_fd) the
+    /// descriptor is fsynced on Unix. Any I/O error from the sync is returned as
+    /// [`Error::Io`].
+    ///
+    /// # Note
+    ///
+    /// This has no effect on memory ([`open_memory`](Self::open_memory)) or callback
+    /// ([`open_callback`](Self::open_callback)) sinks, since the crate does not own the
+    /// underlying storage. Flush those destinations yourself after `finish()`. The
+    /// same applies to [`open_fd`](Self::open_fd) on Windows; call
+    /// [`File::sync_all`](std::fs::File::sync_all) on the file after `finish()`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .durable(true)
+    ///     .open_file("backup.tar")?;
+    ///
+    /// archive.add_file("file.txt", b"content")?;
+    /// archive.finish()?; // bytes are on stable storage once this returns Ok
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn durable(mut self, durable: bool) -> Self {
+        self.durable = durable;
+        self
+    }
+
+    /// Set how entries with an already-written pathname are handled
+    ///
+    /// Pathnames are compared exactly, ignoring trailing `/`. Any policy other than
+    /// [`DuplicatePolicy::Allow`] keeps a copy of every pathname written until the
+    /// archive is finished, so memory use grows with the number of entries (roughly
+    /// the total length of all pathnames).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat, DuplicatePolicy};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .duplicate_policy(DuplicatePolicy::Error)
+    ///     .open_file("output.tar")?;
+    ///
+    /// archive.add_file("a.txt", b"first")?;
+    /// assert!(archive.add_file("a.txt", b"second").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Set how entries with metadata the format cannot store are handled
+    ///
+    /// The default is [`UnsupportedMetadataPolicy::Ignore`]: the metadata is
+    /// silently dropped. Every entry written is checked, including those added
+    /// by [`add_path`](Self::add_path) and the other `add_*` helpers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, UnsupportedMetadataPolicy, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::Zip)
+    ///     .on_unsupported_metadata(UnsupportedMetadataPolicy::Warn)
+    ///     .open_file("output.zip")?;
+    /// archive.add_path("project", None)?;
+    /// for warning in archive.take_warnings() {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn on_unsupported_metadata(mut self, policy: UnsupportedMetadataPolicy) -> Self {
+        self.unsupported_metadata = policy;
+        self
+    }
+
+    /// Set how [`add_path`](Self::add_path) treats symlinks on disk
+    ///
+    /// The default is [`SymlinkMode::Physical`]: symlinks are archived as links
+    /// rather than followed.
+    pub fn symlink_mode(mut self, mode: SymlinkMode) -> Self {
+        self.symlink_mode = mode;
+        self
+    }
+
+    /// Get the warnings recorded while writing
+    ///
+    /// These are the duplicate pathnames reported under
+    /// [`DuplicatePolicy::Warn`], the metadata dropped under
+    /// [`UnsupportedMetadataPolicy::Warn`] and the warnings libarchive reported
+    /// for headers it still wrote.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get the most recent warning, if any
+    pub fn last_warning(&self) -> Option<&str> {
+        self.warnings.last().map(String::as_str)
+    }
+
+    /// Remove and return the warnings recorded so far
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Open a file for writing, inferring the format and compression from
+    /// its name
+    ///
+    /// See [`guess_from_extension`](Self::guess_from_extension) for the names
+    /// recognized. A [`format`](Self::format) or
+    /// [`compression`](Self::compression) set on the builder takes precedence
+    /// over the inferred one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::WriteArchive;
+    ///
+    /// let mut archive = WriteArchive::new().open_file_auto("output.tar.zst")?;
+    /// archive.add_file("hello.txt", b"Hello, world!")?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_file_auto<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let (format, compression) = Self::guess_from_extension(&path)?;
+        self.format.get_or_insert(format);
+        self.compression.get_or_insert(compression);
+        self.open_file(path)
+    }
+
     /// Open a file for writing
     pub fn open_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
         unsafe {
@@ -268,20 +733,187 @@ impl<'a> WriteArchive<'a> {
             self.configure_format_and_compression()?;
 
             // Open the file
-            let path_str = path
-                .as_ref()
-                .to_str()
-                .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
-            let c_path = CString::new(path_str)
-                .map_err(|_| Error::InvalidArgument("Path contains null byte".to_string()))?;
+            #[cfg(windows)]
+            let ret = {
+                let path = crate::path::to_wide(path.as_ref())?;
+                libarchive2_sys::archive_write_open_filename_w(self.archive, path.as_ptr())
+            };
+            #[cfg(not(windows))]
+            let ret = {
+                let path = crate::path::to_cstring(path.as_ref())?;
+                libarchive2_sys::archive_write_open_filename(self.archive, path.as_ptr())
+            };
+            Error::from_return_code(ret, self.archive)?;
+
+            self.output_path = Some(path.as_ref().to_path_buf());
+            Ok(self)
+        }
+    }
 
-            Error::from_return_code(
-                libarchive2_sys::archive_write_open_filename(self.archive, c_path.as_ptr()),
-                self.archive,
-            )?;
+    /// Open an existing archive file to add entries to it
+    ///
+    /// libarchive cannot append by itself, so one of two strategies is used
+    /// (see [`AppendStrategy::for_file`] to find out which beforehand, or
+    /// [`append_strategy`](Self::append_strategy) afterwards):
+    ///
+    /// - **In place**, for uncompressed tar: new entries overwrite the
+    ///   end-of-archive marker and existing entries are not touched.
+    /// - **Rewrite**, for everything else (including compressed tar): the
+    ///   existing entries are copied into a temporary file in the same
+    ///   directory, new entries are added after them, and [`finish`](Self::finish)
+    ///   renames the temporary file over the original.
+    ///
+    /// The format and compression of the existing archive are kept unless set on
+    /// the builder; setting either to something an in-place append cannot
+    /// produce forces a rewrite. Existing entries are copied as they are, without
+    /// the path prefix, path transform or other entry overrides, and are not
+    /// counted in [`stats`](Self::stats). A passphrase set on the builder is also
+    /// used to read the existing entries.
+    ///
+    /// If the append fails, or the archive is dropped without a successful
+    /// `finish`, the original file is restored: an in-place append puts back the
+    /// bytes it overwrote and a rewrite deletes its temporary file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::WriteArchive;
+    ///
+    /// let mut archive = WriteArchive::new().append_to_file("backup.tar")?;
+    /// archive.add_file("notes.txt", b"added later")?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn append_to_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let track_paths = self.duplicate_policy != DuplicatePolicy::Allow;
+        let mut existing_paths = HashSet::new();
+        let existing = Existing::probe(path, self.passphrase.as_deref(), |entry| {
+            if track_paths {
+                // SAFETY: entry.entry is valid for the duration of the callback
+                existing_paths.insert(unsafe { duplicate_key(entry.entry) });
+            }
+        })?;
 
-            Ok(self)
+        let format = self.format.or(existing.format);
+        let compression = self.compression.unwrap_or(existing.compression);
+        self.format = format;
+        self.compression = Some(compression);
+
+        let in_place = existing.strategy == AppendStrategy::InPlace
+            && compression == CompressionFormat::None
+            && format.is_none_or(|format| format.is_tar());
+        if in_place {
+            let (state, file) = AppendState::in_place(path, existing.end)?;
+            self.append = Some(state);
+            let mut archive = self.open_callback(crate::callbacks::CallbackWriter::new(file))?;
+            archive.output_path = Some(path.to_path_buf());
+            archive.written_paths = existing_paths;
+            Ok(archive)
+        } else {
+            let state = AppendState::rewrite(path)?;
+            let temp = state.output_path().to_path_buf();
+            self.append = Some(state);
+            let mut archive = self.open_file(&temp)?;
+            archive.copy_entries_from(path)?;
+            Ok(archive)
+        }
+    }
+
+    /// Open an archive file to add entries at its end, creating it if missing
+    ///
+    /// Unlike [`append_to_file`](Self::append_to_file), this never rewrites
+    /// the archive: it only supports formats whose new entries can overwrite
+    /// the end-of-archive marker, which are uncompressed tar archives (pax,
+    /// ustar, GNU) and empty files. Compressed archives and other formats,
+    /// including cpio and zip, fail with [`Error::InvalidArgument`] before the
+    /// file is modified, as does a builder format or compression an in-place
+    /// append cannot produce. Use `append_to_file` for those.
+    ///
+    /// This suits logs and backups that grow one batch at a time: each append
+    /// only reads the existing archive to find its end. A missing file is
+    /// created as with [`open_file`](Self::open_file), in the builder's format
+    /// (pax by default).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::WriteArchive;
+    ///
+    /// let mut archive = WriteArchive::new().open_file_append("events.tar")?;
+    /// archive.add_file("events/0001.json", b"{}")?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn open_file_append<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let unsupported = |reason: String| {
+            Err(Error::InvalidArgument(format!(
+                "Cannot append to {} in place: {}; use append_to_file to rewrite it",
+                path.display(),
+                reason
+            )))
+        };
+        if let Some(format) = self.format.filter(|format| !format.is_tar()) {
+            return unsupported(format!("{:?} archives cannot be appended to", format));
+        }
+        if let Some(compression) = self
+            .compression
+            .filter(|&compression| compression != CompressionFormat::None)
+        {
+            return unsupported(format!(
+                "{:?} compression cannot be appended to",
+                compression
+            ));
+        }
+        if !path.exists() {
+            return self.open_file(path);
         }
+        if AppendStrategy::for_file(path)? != AppendStrategy::InPlace {
+            return unsupported("it is compressed or not a tar archive".to_string());
+        }
+        self.append_to_file(path)
+    }
+
+    /// How entries are being appended, for an archive opened with
+    /// [`append_to_file`](Self::append_to_file)
+    pub fn append_strategy(&self) -> Option<AppendStrategy> {
+        self.append.as_ref().map(|append| append.strategy)
+    }
+
+    /// Copy every entry of the archive at `path` unchanged (for rewriting appends)
+    fn copy_entries_from(&mut self, path: &Path) -> Result<()> {
+        let mut reader = match self.passphrase.as_deref() {
+            Some(passphrase) => crate::reader::ReadArchive::open_with_passphrase(path, passphrase)?,
+            None => crate::reader::ReadArchive::open(path)?,
+        };
+        let policy = std::mem::replace(&mut self.duplicate_policy, DuplicatePolicy::Allow);
+        let result = (|| {
+            let mut buf = vec![0u8; 64 * 1024];
+            while let Some(entry) = reader.next_entry()? {
+                let entry = entry.entry;
+                // SAFETY: entry is the reader's current header, valid until the
+                // next call to next_entry
+                unsafe {
+                    if policy != DuplicatePolicy::Allow {
+                        self.written_paths.insert(duplicate_key(entry));
+                    }
+                    self.write_raw_header(entry)?;
+                }
+                loop {
+                    let n = reader.read_data(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    self.write_data_all(&buf[..n])?;
+                }
+            }
+            Ok(())
+        })();
+        self.duplicate_policy = policy;
+        self.entries_written = 0;
+        self.data_bytes_written = 0;
+        result
     }
 
     /// Open an in-memory archive
@@ -334,6 +966,35 @@ impl<'a> WriteArchive<'a> {
         }
     }
 
+    /// Open an in-memory archive that grows as entries are written
+    ///
+    /// Unlike [`open_memory`](Self::open_memory), there is no buffer to size up
+    /// front, so the archive can never run out of room. Call
+    /// [`finish_to_vec`](Self::finish_to_vec) to close the archive and take its
+    /// bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::Zip)
+    ///     .open_growable_memory()?;
+    ///
+    /// archive.add_file("test.txt", b"Hello, world!")?;
+    /// let bytes = archive.finish_to_vec()?;
+    ///
+    /// println!("Archive size: {} bytes", bytes.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_growable_memory(mut self) -> Result<Self> {
+        let memory = Arc::new(Mutex::new(Vec::new()));
+        let callback = crate::callbacks::CallbackWriter::new(VecSink(Arc::clone(&memory)));
+        self.memory = Some(memory);
+        self.open_callback(callback)
+    }
+
     /// Open an archive for writing to a file descriptor
     ///
     /// # Safety
@@ -368,6 +1029,7 @@ impl<'a> WriteArchive<'a> {
                 self.archive,
             )?;
 
+            self.output_fd = Some(fd);
             Ok(self)
         }
     }
@@ -377,6 +1039,8 @@ impl<'a> WriteArchive<'a> {
     /// # Safety
     /// The file descriptor must be valid and remain open for the lifetime of the archive.
     /// The archive will not close the file descriptor when dropped.
+    ///
+    /// [`durable`](Self::durable) does not sync this output.
     #[cfg(windows)]
     pub fn open_fd(mut self, fd: std::os::windows::io::RawHandle) -> Result<Self> {
         unsafe {
@@ -425,6 +1089,14 @@ impl<'a> WriteArchive<'a> {
             }
 
             self.configure_format_and_compression()?;
+            if self.memory.is_some() {
+                // Don't pad the end of the archive to a whole block, as
+                // open_memory and open_file don't
+                Error::from_return_code(
+                    libarchive2_sys::archive_write_set_bytes_in_last_block(self.archive, 1),
+                    self.archive,
+                )?;
+            }
 
             let (client_data, write_cb, close_cb, drop_fn) = callback.into_raw_parts();
 
@@ -468,6 +1140,19 @@ impl<'a> WriteArchive<'a> {
 
     /// Configure format and compression (helper for open methods)
     fn configure_format_and_compression(&mut self) -> Result<()> {
+        if let Some(ref prefix) = self.path_prefix {
+            if prefix.starts_with('/') {
+                return Err(Error::InvalidArgument(format!(
+                    "Path prefix {:?} is absolute; archive paths must be relative",
+                    prefix
+                )));
+            }
+            if prefix.contains('\0') {
+                return Err(Error::InvalidArgument(
+                    "Path prefix contains null byte".to_string(),
+                ));
+            }
+        }
         unsafe {
             // Set format
             match self.format.unwrap_or(ArchiveFormat::TarPax) {
@@ -628,29 +1313,41 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
-                CompressionFormat::Lrzip => {
+                CompressionFormat::B64Encode => {
                     Error::from_return_code(
-                        libarchive2_sys::archive_write_add_filter_lrzip(self.archive),
+                        libarchive2_sys::archive_write_add_filter_b64encode(self.archive),
                         self.archive,
                     )?;
                 }
+                CompressionFormat::Lrzip => {
+                    self.add_program_filter(
+                        CompressionFormat::Lrzip,
+                        libarchive2_sys::archive_write_add_filter_lrzip(self.archive),
+                    )?;
+                }
                 CompressionFormat::Lzop => {
-                    Error::from_return_code(
+                    self.add_program_filter(
+                        CompressionFormat::Lzop,
                         libarchive2_sys::archive_write_add_filter_lzop(self.archive),
-                        self.archive,
                     )?;
                 }
                 CompressionFormat::Grzip => {
-                    Error::from_return_code(
+                    self.add_program_filter(
+                        CompressionFormat::Grzip,
                         libarchive2_sys::archive_write_add_filter_grzip(self.archive),
+                    )?;
+                }
+                CompressionFormat::Lzip => {
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_add_filter_lzip(self.archive),
                         self.archive,
                     )?;
                 }
-                _ => {
-                    return Err(Error::InvalidArgument(format!(
-                        "Unsupported compression: {:?}",
-                        self.compression
-                    )));
+                CompressionFormat::Lzma => {
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_add_filter_lzma(self.archive),
+                        self.archive,
+                    )?;
                 }
             }
 
@@ -666,6 +1363,25 @@ impl<'a> WriteArchive<'a> {
                     ),
                     self.archive,
                 )?;
+
+                // The zip writer ignores the passphrase unless encryption is
+                // requested; default to AES-256 rather than the weak PKWARE
+                // cipher. A ZipEncryption format option, applied below,
+                // overrides this.
+                if self.format == Some(ArchiveFormat::Zip) {
+                    let module = CString::new("zip").unwrap();
+                    let key = CString::new("encryption").unwrap();
+                    let value = CString::new("aes256").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            value.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
             }
 
             // Apply format options
@@ -678,17 +1394,121 @@ impl<'a> WriteArchive<'a> {
                 self.apply_filter_option(option)?;
             }
 
+            if self.deterministic {
+                self.configure_deterministic()?;
+            }
+
+            // Apply raw options
+            for (key, value) in &self.raw_options {
+                self.apply_raw_option(key, value)?;
+            }
+
             Ok(())
         }
     }
 
-    /// Apply a format-specific option (internal helper)
-    fn apply_format_option(&self, option: &FormatOption) -> Result<()> {
-        use crate::format::ZipCompressionMethod;
+    /// Resolve the deterministic timestamp and turn off timestamps that
+    /// libarchive adds by itself
+    fn configure_deterministic(&mut self) -> Result<()> {
+        if self.source_date_epoch.is_none() {
+            self.source_date_epoch = Some(
+                std::env::var("SOURCE_DATE_EPOCH")
+                    .ok()
+                    .and_then(|value| value.trim().parse::<i64>().ok())
+                    .map_or(0, |secs| secs.max(0)),
+            );
+        }
 
-        unsafe {
-            match option {
-                FormatOption::ZipCompressionMethod(method) => {
+        let key = CString::new("timestamp").unwrap();
+        if self.compression == Some(CompressionFormat::Gzip) {
+            let module = CString::new("gzip").unwrap();
+            // SAFETY: archive is valid while configuring
+            unsafe {
+                Error::from_return_code(
+                    libarchive2_sys::archive_write_set_filter_option(
+                        self.archive,
+                        module.as_ptr(),
+                        key.as_ptr(),
+                        std::ptr::null(),
+                    ),
+                    self.archive,
+                )?;
+            }
+        }
+        if self.format == Some(ArchiveFormat::Zip) {
+            // Older zip writers lack the option; with every entry time fixed,
+            // the timestamp fields they write are reproducible anyway
+            let module = CString::new("zip").unwrap();
+            // SAFETY: archive is valid while configuring
+            unsafe {
+                libarchive2_sys::archive_write_set_format_option(
+                    self.archive,
+                    module.as_ptr(),
+                    key.as_ptr(),
+                    std::ptr::null(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the result of adding a filter that may run an external program
+    ///
+    /// libarchive returns `ARCHIVE_WARN` when it will pipe the data through the
+    /// program instead of using a library; that is only an error if the program
+    /// can't be found.
+    fn add_program_filter(&self, filter: CompressionFormat, ret: i32) -> Result<()> {
+        if ret == libarchive2_sys::ARCHIVE_WARN {
+            let program = filter.external_program().unwrap_or_default();
+            if !crate::format::program_in_path(program) {
+                return Err(Error::InvalidArgument(format!(
+                    "{:?} compression needs the external `{}` program, which was not \
+                     found in PATH; reading {:?} archives needs it as well",
+                    filter, program, filter
+                )));
+            }
+            return Ok(());
+        }
+        // SAFETY: archive is valid while configuring
+        unsafe { Error::from_return_code(ret, self.archive) }?;
+        Ok(())
+    }
+
+    /// Apply a `module:option` or bare option (internal helper)
+    fn apply_raw_option(&self, key: &str, value: &str) -> Result<()> {
+        let (module, option) = match key.split_once(':') {
+            Some((module, option)) => (Some(module), option),
+            None => (None, key),
+        };
+        let to_c = |s: &str| {
+            CString::new(s)
+                .map_err(|_| Error::InvalidArgument(format!("Option {} contains null byte", key)))
+        };
+        let module = module.map(to_c).transpose()?;
+        let option = to_c(option)?;
+        let value = to_c(value)?;
+
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_set_option(
+                    self.archive,
+                    module.as_ref().map_or(std::ptr::null(), |m| m.as_ptr()),
+                    option.as_ptr(),
+                    value.as_ptr(),
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Apply a format-specific option (internal helper)
+    fn apply_format_option(&self, option: &FormatOption) -> Result<()> {
+        use crate::format::{SevenZipMethod, ZipCompressionMethod, ZipEncryption};
+
+        unsafe {
+            match option {
+                FormatOption::ZipCompressionMethod(method) => {
                     let method_str = match method {
                         ZipCompressionMethod::Store => CString::new("store").unwrap(),
                         ZipCompressionMethod::Deflate => CString::new("deflate").unwrap(),
@@ -719,6 +1539,27 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FormatOption::ZipZip64(mode) => {
+                    // The zip writer forces Zip64 for any non-empty value and
+                    // avoids it for NULL; Auto leaves the default untouched
+                    let val = CString::new("1").unwrap();
+                    let module = CString::new("zip").unwrap();
+                    let key = CString::new("zip64").unwrap();
+                    let value = match mode {
+                        Zip64Mode::Auto => return Ok(()),
+                        Zip64Mode::Always => val.as_ptr(),
+                        Zip64Mode::Never => std::ptr::null(),
+                    };
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            value,
+                        ),
+                        self.archive,
+                    )?;
+                }
                 FormatOption::Iso9660VolumeId(volume_id) => {
                     let vol_id = CString::new(volume_id.as_str()).map_err(|_| {
                         Error::InvalidArgument("Volume ID contains null byte".to_string())
@@ -765,6 +1606,61 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FormatOption::Iso9660Zisofs(enable) => {
+                    // The iso9660 writer enables zisofs for any non-NULL value,
+                    // so disabling must pass NULL rather than "0"
+                    let val = CString::new("1").unwrap();
+                    let module = CString::new("iso9660").unwrap();
+                    let key = CString::new("zisofs").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            if *enable {
+                                val.as_ptr()
+                            } else {
+                                std::ptr::null()
+                            },
+                        ),
+                        self.archive,
+                    )?;
+                }
+                FormatOption::Iso9660ZisofsLevel(level) => {
+                    let level_str = CString::new(level.value().to_string()).unwrap();
+                    let module = CString::new("iso9660").unwrap();
+                    let key = CString::new("compression-level").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            level_str.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
+                FormatOption::Iso9660Joliet(enable) => {
+                    self.set_iso9660_option("joliet", enable.then_some("1"))?;
+                }
+                FormatOption::Iso9660RockRidge(enable) => {
+                    self.set_iso9660_option("rockridge", enable.then_some("1"))?;
+                }
+                FormatOption::Iso9660ElToritoBootImage(path) => {
+                    self.set_iso9660_option("boot", Some(path.as_str()))?;
+                }
+                FormatOption::Iso9660BootCatalog(path) => {
+                    self.set_iso9660_option("boot-catalog", Some(path.as_str()))?;
+                }
+                FormatOption::Iso9660AbstractFile(name) => {
+                    self.set_iso9660_option("abstract-file", Some(name.as_str()))?;
+                }
+                FormatOption::Iso9660ApplicationId(id) => {
+                    self.set_iso9660_option("application-id", Some(id.as_str()))?;
+                }
+                FormatOption::Iso9660PadTo64kBlocks(enable) => {
+                    self.set_iso9660_option("pad", enable.then_some("1"))?;
+                }
                 FormatOption::TarGnuLongPathnames(enable) => {
                     let val = CString::new(if *enable { "1" } else { "0" }).unwrap();
                     let module = CString::new("gnutar").unwrap();
@@ -779,6 +1675,24 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FormatOption::ZipEncryption(encryption) => {
+                    let value = match encryption {
+                        ZipEncryption::ZipCrypt => CString::new("zipcrypt").unwrap(),
+                        ZipEncryption::Aes128 => CString::new("aes128").unwrap(),
+                        ZipEncryption::Aes256 => CString::new("aes256").unwrap(),
+                    };
+                    let module = CString::new("zip").unwrap();
+                    let key = CString::new("encryption").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            value.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
                 FormatOption::SevenZipCompressionLevel(level) => {
                     let level_str = CString::new(level.value().to_string()).unwrap();
                     let module = CString::new("7zip").unwrap();
@@ -793,11 +1707,108 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FormatOption::SevenZipCompressionMethod(method) => {
+                    let value = match method {
+                        SevenZipMethod::Copy => CString::new("copy").unwrap(),
+                        SevenZipMethod::Deflate => CString::new("deflate").unwrap(),
+                        SevenZipMethod::Bzip2 => CString::new("bzip2").unwrap(),
+                        SevenZipMethod::Lzma1 => CString::new("lzma1").unwrap(),
+                        SevenZipMethod::Lzma2 => CString::new("lzma2").unwrap(),
+                        SevenZipMethod::Ppmd => CString::new("ppmd").unwrap(),
+                    };
+                    let module = CString::new("7zip").unwrap();
+                    let key = CString::new("compression").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_format_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            value.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
+                FormatOption::MtreeUseSet(enable) => {
+                    self.set_mtree_option("use-set", *enable)?;
+                }
+                FormatOption::MtreeIndent(enable) => {
+                    self.set_mtree_option("indent", *enable)?;
+                }
+                FormatOption::MtreeKeywords(keywords) => {
+                    self.set_mtree_option("all", false)?;
+                    for keyword in keywords {
+                        self.set_mtree_option(keyword.option_name(), true)?;
+                    }
+                }
             }
             Ok(())
         }
     }
 
+    /// Enable or disable an mtree writer option
+    ///
+    /// The mtree writer treats any non-NULL value as "on", so disabling must
+    /// pass NULL rather than "0".
+    fn set_mtree_option(&self, key: &str, enable: bool) -> Result<()> {
+        let module = CString::new("mtree").unwrap();
+        let key = CString::new(key).unwrap();
+        let val = CString::new("1").unwrap();
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_set_format_option(
+                    self.archive,
+                    module.as_ptr(),
+                    key.as_ptr(),
+                    if enable {
+                        val.as_ptr()
+                    } else {
+                        std::ptr::null()
+                    },
+                ),
+                self.archive,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Set an iso9660 writer option
+    ///
+    /// Like the mtree writer, the iso9660 writer treats any non-NULL value of
+    /// a boolean option as "on", so `None` disables it. Errors name the option,
+    /// since libarchive's messages for rejected values often don't.
+    fn set_iso9660_option(&self, key: &str, value: Option<&str>) -> Result<()> {
+        let module = CString::new("iso9660").unwrap();
+        let c_key = CString::new(key).unwrap();
+        let value = value
+            .map(|v| {
+                CString::new(v).map_err(|_| {
+                    Error::InvalidArgument(format!("iso9660:{} value contains null byte", key))
+                })
+            })
+            .transpose()?;
+        // SAFETY: archive is valid while configuring
+        unsafe {
+            Error::from_return_code(
+                libarchive2_sys::archive_write_set_format_option(
+                    self.archive,
+                    module.as_ptr(),
+                    c_key.as_ptr(),
+                    value.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
+                ),
+                self.archive,
+            )
+        }
+        .map_err(|mut e| {
+            if let Error::Archive { message, .. } = &mut e
+                && !message.contains(key)
+            {
+                *message = format!("iso9660:{}: {}", key, message);
+            }
+            e
+        })?;
+        Ok(())
+    }
+
     /// Apply a filter-specific option (internal helper)
     fn apply_filter_option(&self, option: &FilterOption) -> Result<()> {
         unsafe {
@@ -844,6 +1855,20 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FilterOption::XzThreads(threads) => {
+                    let threads_str = CString::new(threads.to_string()).unwrap();
+                    let module = CString::new("xz").unwrap();
+                    let key = CString::new("threads").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_filter_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            threads_str.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
                 FilterOption::ZstdCompressionLevel(level) => {
                     let level_str = CString::new(level.to_string()).unwrap();
                     let module = CString::new("zstd").unwrap();
@@ -858,6 +1883,34 @@ impl<'a> WriteArchive<'a> {
                         self.archive,
                     )?;
                 }
+                FilterOption::ZstdThreads(threads) => {
+                    let threads_str = CString::new(threads.to_string()).unwrap();
+                    let module = CString::new("zstd").unwrap();
+                    let key = CString::new("threads").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_filter_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            threads_str.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
+                FilterOption::ZstdLongDistanceMatching(window_log) => {
+                    let log_str = CString::new(window_log.to_string()).unwrap();
+                    let module = CString::new("zstd").unwrap();
+                    let key = CString::new("long").unwrap();
+                    Error::from_return_code(
+                        libarchive2_sys::archive_write_set_filter_option(
+                            self.archive,
+                            module.as_ptr(),
+                            key.as_ptr(),
+                            log_str.as_ptr(),
+                        ),
+                        self.archive,
+                    )?;
+                }
                 FilterOption::Lz4CompressionLevel(level) => {
                     let level_str = CString::new(level.value().to_string()).unwrap();
                     let module = CString::new("lz4").unwrap();
@@ -894,29 +1947,248 @@ impl<'a> WriteArchive<'a> {
                 if cloned.is_null() {
                     return Err(Error::NullPointer);
                 }
+                match self.apply_path_transform(cloned) {
+                    Ok(true) => {}
+                    result => {
+                        libarchive2_sys::archive_entry_free(cloned);
+                        self.skipping_entry = result.is_ok();
+                        return result.map(|_| ());
+                    }
+                }
                 self.apply_overrides(cloned);
-                let ret = libarchive2_sys::archive_write_header(self.archive, cloned);
+                let result = self.write_raw_header(cloned);
                 libarchive2_sys::archive_entry_free(cloned);
-                Error::from_return_code(ret, self.archive)?;
+                result
             }
         } else {
-            unsafe {
-                Error::from_return_code(
-                    libarchive2_sys::archive_write_header(self.archive, entry.entry),
-                    self.archive,
-                )?;
+            unsafe { self.write_raw_header(entry.entry) }
+        }
+    }
+
+    /// Validate and write a header for an entry with overrides already applied
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn write_raw_header(
+        &mut self,
+        entry: *mut libarchive2_sys::archive_entry,
+    ) -> Result<()> {
+        self.skipping_entry = false;
+
+        unsafe {
+            self.check_header_limits(entry)?;
+
+            let path_key = if self.duplicate_policy == DuplicatePolicy::Allow {
+                None
+            } else {
+                let key = duplicate_key(entry);
+                if self.written_paths.contains(&key) {
+                    let path = String::from_utf8_lossy(&key).into_owned();
+                    match self.duplicate_policy {
+                        DuplicatePolicy::Allow => {}
+                        DuplicatePolicy::Warn => {
+                            self.warnings
+                                .push(format!("Duplicate pathname written: {}", path));
+                        }
+                        DuplicatePolicy::Error => {
+                            return Err(Error::InvalidArgument(format!(
+                                "Duplicate pathname: {}",
+                                path
+                            )));
+                        }
+                        DuplicatePolicy::SkipLater => {
+                            self.skipping_entry = true;
+                            return Ok(());
+                        }
+                    }
+                }
+                Some(key)
+            };
+
+            self.check_metadata_support(entry)?;
+
+            let ret = libarchive2_sys::archive_write_header(self.archive, entry);
+            if ret == libarchive2_sys::ARCHIVE_WARN {
+                // The header was written, e.g. with a name that could not be
+                // converted to the header charset; record the problem
+                self.warnings.push(Error::warning_message(self.archive));
+            } else {
+                Error::from_return_code(ret, self.archive)?;
+            }
+
+            if let Some(key) = path_key {
+                self.written_paths.insert(key);
+            }
+            self.entry_size = libarchive2_sys::archive_entry_size(entry).max(0) as u64;
+        }
+        self.entries_written += 1;
+        self.entry_bytes_written = 0;
+        Ok(())
+    }
+
+    /// Reject numeric header fields the selected format cannot represent
+    ///
+    /// libarchive's ustar writer has no extension headers: it only accepts uid/gid
+    /// values up to 262143 (6 octal digits) and sizes below 8 GiB, and otherwise
+    /// fails with a message that doesn't say which value was at fault. Zip without
+    /// Zip64 cannot store sizes of 4 GiB or more.
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn check_header_limits(&self, entry: *mut libarchive2_sys::archive_entry) -> Result<()> {
+        const USTAR_MAX_ID: i64 = 0o777777;
+        const USTAR_MAX_SIZE: i64 = 0o77777777777;
+        const ZIP32_MAX_SIZE: i64 = 0xFFFF_FFFF;
+
+        if self.format == Some(ArchiveFormat::Zip) && self.zip64_mode() == Zip64Mode::Never {
+            // SAFETY: Caller guarantees entry is valid
+            let size = unsafe { libarchive2_sys::archive_entry_size(entry) };
+            if size > ZIP32_MAX_SIZE {
+                return Err(Error::InvalidArgument(format!(
+                    "size {} exceeds the zip limit of {} without Zip64; \
+                     use Zip64Mode::Auto or Zip64Mode::Always instead",
+                    size, ZIP32_MAX_SIZE
+                )));
+            }
+            return Ok(());
+        }
+
+        if self.format != Some(ArchiveFormat::TarUstar) {
+            return Ok(());
+        }
+
+        // SAFETY: Caller guarantees entry is valid
+        let (uid, gid, size) = unsafe {
+            (
+                libarchive2_sys::archive_entry_uid(entry),
+                libarchive2_sys::archive_entry_gid(entry),
+                libarchive2_sys::archive_entry_size(entry),
+            )
+        };
+        for (field, value, max) in [
+            ("uid", uid, USTAR_MAX_ID),
+            ("gid", gid, USTAR_MAX_ID),
+            ("size", size, USTAR_MAX_SIZE),
+        ] {
+            if value > max {
+                return Err(Error::InvalidArgument(format!(
+                    "{} {} exceeds the ustar limit of {}; use ArchiveFormat::TarPax instead",
+                    field, value, max
+                )));
             }
         }
         Ok(())
     }
 
+    /// The Zip64 mode in effect; the last [`FormatOption::ZipZip64`] wins
+    fn zip64_mode(&self) -> Zip64Mode {
+        self.format_options
+            .iter()
+            .rev()
+            .find_map(|option| match option {
+                FormatOption::ZipZip64(mode) => Some(*mode),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Apply the [`UnsupportedMetadataPolicy`] to an entry about to be written
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn check_metadata_support(
+        &mut self,
+        entry: *mut libarchive2_sys::archive_entry,
+    ) -> Result<()> {
+        if self.unsupported_metadata == UnsupportedMetadataPolicy::Ignore {
+            return Ok(());
+        }
+        let format = self.format.unwrap_or(ArchiveFormat::TarPax);
+        let support = format.metadata_support();
+
+        // SAFETY: Caller guarantees entry is valid
+        let (xattrs, acls, file_type) = unsafe {
+            (
+                libarchive2_sys::archive_entry_xattr_count(entry),
+                libarchive2_sys::archive_entry_acl_count(
+                    entry,
+                    (libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_ACCESS
+                        | libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_DEFAULT
+                        | libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_ALLOW
+                        | libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_DENY
+                        | libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_AUDIT
+                        | libarchive2_sys::ARCHIVE_ENTRY_ACL_TYPE_ALARM) as i32,
+                ),
+                FileType::from_mode(libarchive2_sys::archive_entry_filetype(entry) as u32),
+            )
+        };
+        let is_device = matches!(file_type, FileType::BlockDevice | FileType::CharacterDevice);
+
+        let mut dropped = Vec::new();
+        if xattrs > 0 && !support.xattrs {
+            dropped.push("extended attributes");
+        }
+        if acls > 0 && !support.acls {
+            dropped.push("ACLs");
+        }
+        if is_device && !support.devices {
+            dropped.push("device node");
+        }
+        if dropped.is_empty() {
+            return Ok(());
+        }
+
+        // SAFETY: as above
+        let path = unsafe {
+            let ptr = libarchive2_sys::archive_entry_pathname_utf8(entry);
+            if ptr.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(ptr).to_string_lossy().into_owned()
+            }
+        };
+        let message = format!(
+            "{}: {:?} format cannot store {}",
+            path,
+            format,
+            dropped.join(", ")
+        );
+        match self.unsupported_metadata {
+            UnsupportedMetadataPolicy::Ignore => Ok(()),
+            UnsupportedMetadataPolicy::Warn => {
+                self.warnings.push(message);
+                Ok(())
+            }
+            UnsupportedMetadataPolicy::Error => Err(Error::InvalidArgument(message)),
+        }
+    }
+
     fn has_overrides(&self) -> bool {
-        self.default_mtime.is_some()
+        self.deterministic
+            || self.default_mtime.is_some()
             || self.default_uid.is_some()
             || self.default_gid.is_some()
             || self.default_uname.is_some()
             || self.default_gname.is_some()
             || self.strip_directory_trailing_slash
+            || self.path_prefix.is_some()
+            || self.path_transform.is_some()
+    }
+
+    /// Run the path transform on the pathname and hardlink target
+    ///
+    /// Returns `Ok(false)` if the entry should be skipped.
+    ///
+    /// # Safety
+    /// `entry` must be a valid, non-null `archive_entry` pointer.
+    unsafe fn apply_path_transform(
+        &mut self,
+        entry: *mut libarchive2_sys::archive_entry,
+    ) -> Result<bool> {
+        let Some(transform) = self.path_transform.as_mut() else {
+            return Ok(true);
+        };
+        unsafe { crate::path::map_entry_paths(entry, |path| transform(path)) }
     }
 
     /// Apply configured overrides to a raw archive_entry pointer
@@ -973,6 +2245,32 @@ impl<'a> WriteArchive<'a> {
             {
                 libarchive2_sys::archive_entry_set_gname_utf8(entry, c_gname.as_ptr());
             }
+            if let Some(ref prefix) = self.path_prefix {
+                prefix_name(
+                    entry,
+                    prefix,
+                    (
+                        libarchive2_sys::archive_entry_pathname_utf8,
+                        libarchive2_sys::archive_entry_set_pathname_utf8,
+                    ),
+                    (
+                        libarchive2_sys::archive_entry_pathname,
+                        libarchive2_sys::archive_entry_set_pathname,
+                    ),
+                );
+                prefix_name(
+                    entry,
+                    prefix,
+                    (
+                        libarchive2_sys::archive_entry_hardlink_utf8,
+                        libarchive2_sys::archive_entry_set_hardlink_utf8,
+                    ),
+                    (
+                        libarchive2_sys::archive_entry_hardlink,
+                        libarchive2_sys::archive_entry_set_hardlink,
+                    ),
+                );
+            }
             if self.strip_directory_trailing_slash {
                 let filetype = libarchive2_sys::archive_entry_filetype(entry) as u32;
                 const S_IFDIR: u32 = 0o040000;
@@ -993,11 +2291,36 @@ impl<'a> WriteArchive<'a> {
                     }
                 }
             }
+            if self.deterministic {
+                // Applied last so it wins over the default_* overrides
+                let secs = self.source_date_epoch.unwrap_or(0);
+                libarchive2_sys::archive_entry_set_mtime(entry, secs as _, 0);
+                libarchive2_sys::archive_entry_set_atime(entry, secs as _, 0);
+                libarchive2_sys::archive_entry_set_ctime(entry, secs as _, 0);
+                libarchive2_sys::archive_entry_unset_birthtime(entry);
+                libarchive2_sys::archive_entry_set_uid(entry, 0);
+                libarchive2_sys::archive_entry_set_gid(entry, 0);
+                libarchive2_sys::archive_entry_set_uname_utf8(entry, std::ptr::null());
+                libarchive2_sys::archive_entry_set_gname_utf8(entry, std::ptr::null());
+                libarchive2_sys::archive_entry_set_ino64(entry, 0);
+                libarchive2_sys::archive_entry_set_dev(entry, 0);
+                libarchive2_sys::archive_entry_set_nlink(entry, 0);
+            }
         }
     }
 
     /// Write data for the current entry
+    ///
+    /// Like [`std::io::Write::write`], this may consume only part of `data` and
+    /// returns how many bytes were taken; the rest must be passed again. In
+    /// particular libarchive stops accepting data once the entry reaches the
+    /// size in its header. Use [`write_data_all`](Self::write_data_all) to
+    /// write a whole buffer.
     pub fn write_data(&mut self, data: &[u8]) -> Result<usize> {
+        if self.skipping_entry {
+            return Ok(data.len());
+        }
+
         unsafe {
             let ret = libarchive2_sys::archive_write_data(
                 self.archive,
@@ -1006,32 +2329,232 @@ impl<'a> WriteArchive<'a> {
             );
 
             if ret < 0 {
-                Err(Error::from_archive(self.archive))
+                Err(Error::from_archive(self.archive, ret as i32))
             } else {
+                self.record_data(ret as u64);
                 Ok(ret as usize)
             }
         }
     }
 
-    /// Write a data block at a specific offset
-    ///
-    /// This is useful for sparse files where you want to write data at specific
-    /// offsets, leaving holes (zeros) in between. This method provides fine-grained
-    /// control over data placement within an entry.
-    ///
-    /// # Arguments
-    ///
-    /// * `offset` - Byte offset within the entry where data should be written
-    /// * `data` - Data to write at the specified offset
+    /// Write all of `data` to the current entry
+    ///
+    /// Calls [`write_data`](Self::write_data) until every byte has been
+    /// consumed. A write that makes no progress fails with
+    /// [`Error::InvalidArgument`], which happens when `data` runs past the
+    /// size in the entry's header.
+    pub fn write_data_all(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let n = self.write_data(data)?;
+            if n == 0 {
+                return Err(Error::InvalidArgument(
+                    "Entry data is larger than the size in its header".to_string(),
+                ));
+            }
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    fn record_data(&mut self, bytes: u64) {
+        self.data_bytes_written += bytes;
+        self.entry_bytes_written += bytes;
+        if bytes > 0
+            && let Some(progress) = self.progress.as_mut()
+        {
+            progress.on_progress(self.entry_bytes_written, self.entry_size);
+        }
+        if bytes > 0
+            && let Some(tracker) = self.tracker.as_mut()
+        {
+            tracker.update(bytes);
+        }
+    }
+
+    /// Set a callback to report progress through each entry's data
     ///
-    /// # Returns
+    /// The callback is invoked after every successful
+    /// [`write_data`](Self::write_data) or
+    /// [`write_data_block`](Self::write_data_block) call, including those made by
+    /// helpers such as [`add_file`](Self::add_file), with the bytes written so
+    /// far for the current entry and the size from its header. The count starts
+    /// again from 0 on every [`write_header`](Self::write_header).
     ///
-    /// Returns the number of bytes written on success.
+    /// The callback runs on the calling thread and no internal lock is held
+    /// while it runs.
     ///
     /// # Examples
     ///
     /// ```no_run
-    /// use libarchive2::{WriteArchive, ArchiveFormat, EntryMut, FileType};
+    /// use libarchive2::{ArchiveFormat, ProgressCallback, WriteArchive};
+    ///
+    /// struct Print;
+    ///
+    /// impl ProgressCallback for Print {
+    ///     fn on_progress(&mut self, bytes_processed: u64, total_bytes: u64) {
+    ///         println!("{}/{}", bytes_processed, total_bytes);
+    ///     }
+    /// }
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    /// archive.set_progress_callback(Box::new(Print));
+    /// archive.add_file("file.txt", b"Hello, world!")?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_progress_callback(&mut self, callback: Box<dyn ProgressCallback + Send>) {
+        self.progress = Some(callback);
+    }
+
+    /// Track progress through the whole archive with a [`ProgressTracker`]
+    ///
+    /// Unlike [`set_progress_callback`](Self::set_progress_callback), the count
+    /// does not restart for each entry: the callback receives the entry data
+    /// written so far across all entries, the same count as
+    /// [`stats().data_bytes`](WriteStats::data_bytes), after every write that
+    /// adds data. The total is reported as 0 since the size of the archive is
+    /// not known until it is finished. Replaces any tracker set before.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, ProgressCallback, WriteArchive};
+    ///
+    /// struct Print;
+    ///
+    /// impl ProgressCallback for Print {
+    ///     fn on_progress(&mut self, bytes_processed: u64, _total_bytes: u64) {
+    ///         println!("{} bytes archived", bytes_processed);
+    ///     }
+    /// }
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    /// archive.set_progress(Print);
+    /// archive.add_path("data", None)?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_progress<C: ProgressCallback + Send + 'static>(&mut self, callback: C) {
+        self.tracker = Some(ProgressTracker::new(callback));
+    }
+
+    /// Pass every entry pathname through `transform` before it is written
+    ///
+    /// `transform` runs in [`write_header`](Self::write_header), so it sees the
+    /// entries from every write method. Returning `Some` renames the entry;
+    /// returning `None` skips it, and any data written for it is discarded as
+    /// with [`DuplicatePolicy::SkipLater`]. Hardlink targets are archive paths
+    /// and go through `transform` too; an entry whose target is dropped is
+    /// skipped. Symlink targets are left alone.
+    ///
+    /// The transform runs before [`path_prefix`](Self::path_prefix) and the
+    /// other entry overrides are applied.
+    ///
+    /// # Examples
+    ///
+    /// Drop the first path component, like `tar --strip-components=1`:
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    /// archive.set_path_transform(|path| {
+    ///     path.trim_start_matches('/')
+    ///         .split_once('/')
+    ///         .map(|(_, rest)| rest.to_string())
+    ///         .filter(|rest| !rest.is_empty())
+    /// });
+    /// archive.add_file("project/src/main.rs", b"fn main() {}")?; // "src/main.rs"
+    /// archive.add_file("README", b"skipped")?; // nothing left to keep
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn set_path_transform<F>(&mut self, transform: F)
+    where
+        F: FnMut(&str) -> Option<String> + Send + 'static,
+    {
+        self.path_transform = Some(Box::new(transform));
+    }
+
+    /// Get the number of bytes written to the destination so far
+    ///
+    /// libarchive writes whole blocks, and compressors hold data back until
+    /// they have enough to emit, so this advances in steps and is only final
+    /// after [`finish`](Self::finish). Returns 0 if the archive has not been
+    /// opened yet. Same as [`stats().compressed_bytes`](WriteStats::compressed_bytes).
+    pub fn bytes_written(&self) -> u64 {
+        if self.archive.is_null() {
+            return 0;
+        }
+        // SAFETY: archive is a valid, non-null pointer
+        unsafe { crate::stats::filter_bytes(self.archive, -1) }
+    }
+
+    /// Get byte and entry statistics for the archive written so far
+    ///
+    /// libarchive buffers output (and compressors buffer further), so
+    /// `compressed_bytes` is only exact once all data has been flushed. Returns
+    /// zeroed byte counters if the archive has not been opened yet.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat, CompressionFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .compression(CompressionFormat::Gzip)
+    ///     .open_file("output.tar.gz")?;
+    /// archive.add_file("file.txt", b"Hello, world!")?;
+    /// let stats = archive.stats();
+    /// println!("{} entries, {} data bytes", stats.entries, stats.data_bytes);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stats(&self) -> WriteStats {
+        let (compressed_bytes, uncompressed_bytes) = if self.archive.is_null() {
+            (0, 0)
+        } else {
+            // SAFETY: archive is a valid, non-null pointer
+            unsafe {
+                (
+                    crate::stats::filter_bytes(self.archive, -1),
+                    crate::stats::filter_bytes(self.archive, 0),
+                )
+            }
+        };
+        WriteStats {
+            compressed_bytes,
+            uncompressed_bytes,
+            entries: self.entries_written,
+            data_bytes: self.data_bytes_written,
+        }
+    }
+
+    /// Write a data block at a specific offset
+    ///
+    /// This is useful for sparse files where you want to write data at specific
+    /// offsets, leaving holes (zeros) in between. This method provides fine-grained
+    /// control over data placement within an entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset within the entry where data should be written
+    /// * `data` - Data to write at the specified offset
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of bytes written on success.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat, EntryMut, FileType};
     ///
     /// let mut archive = WriteArchive::new()
     ///     .format(ArchiveFormat::TarPax)
@@ -1066,6 +2589,10 @@ impl<'a> WriteArchive<'a> {
     /// - Not all archive formats support sparse files (e.g., TAR formats do, but ZIP does not)
     /// - The entry's size must be set appropriately before writing blocks
     pub fn write_data_block(&mut self, offset: i64, data: &[u8]) -> Result<usize> {
+        if self.skipping_entry {
+            return Ok(data.len());
+        }
+
         unsafe {
             let ret = libarchive2_sys::archive_write_data_block(
                 self.archive,
@@ -1075,10 +2602,11 @@ impl<'a> WriteArchive<'a> {
             );
 
             if ret < 0 {
-                Err(Error::from_archive(self.archive))
+                Err(Error::from_archive(self.archive, ret as i32))
             } else {
                 // archive_write_data_block returns ARCHIVE_OK (0) on success
                 // We return the number of bytes written (data.len())
+                self.record_data(data.len() as u64);
                 Ok(data.len())
             }
         }
@@ -1094,7 +2622,306 @@ impl<'a> WriteArchive<'a> {
         entry.set_mtime(SystemTime::now());
 
         self.write_header(&entry)?;
-        self.write_data(data)?;
+        self.write_data_all(data)?;
+
+        Ok(())
+    }
+
+    /// Add several in-memory files, each as with [`add_file`](Self::add_file)
+    ///
+    /// Stops at the first file that fails and returns its error with the
+    /// file's path prepended to the message. The files before it stay in the
+    /// archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::Zip)
+    ///     .open_file("bundle.zip")?;
+    /// archive.add_files([
+    ///     ("index.html", b"<h1>Hello</h1>".to_vec()),
+    ///     ("style.css", b"h1 { color: red }".to_vec()),
+    /// ])?;
+    /// archive.finish()?;
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn add_files<I, P>(&mut self, items: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (P, Vec<u8>)>,
+        P: AsRef<Path>,
+    {
+        for (path, data) in items {
+            let path = path.as_ref();
+            self.add_file(path, &data)
+                .map_err(|e| crate::convert::with_pathname(e, &path.display().to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Add a file whose contents are streamed from a reader
+    ///
+    /// The header is written with the declared `size`, then the data is copied
+    /// from `reader` in fixed-size chunks, so the contents never need to fit in
+    /// memory. Returns the number of bytes written.
+    ///
+    /// The reader must yield exactly `size` bytes. If it ends early, or has more
+    /// data than declared, an [`Error::Io`] naming the path and the byte counts
+    /// is returned; in the latter case only `size` bytes are written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    ///
+    /// let file = std::fs::File::open("large.bin")?;
+    /// let size = file.metadata()?.len() as i64;
+    /// archive.add_file_from_reader("large.bin", size, file)?;
+    /// archive.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn add_file_from_reader<P: AsRef<Path>, R: std::io::Read>(
+        &mut self,
+        path: P,
+        size: i64,
+        reader: R,
+    ) -> Result<u64> {
+        let mut entry = EntryMut::new();
+        entry.set_pathname(&path)?;
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o644)?;
+        entry.set_mtime(SystemTime::now());
+
+        self.write_entry_from_reader(entry, size, reader)
+    }
+
+    /// Add a file from disk, streaming its contents
+    ///
+    /// The file's size, modification time and permissions (plus the owning
+    /// uid/gid on Unix) are taken from `disk_path` (following symlinks), and
+    /// the contents are copied in chunks as for
+    /// [`add_file_from_reader`](Self::add_file_from_reader). Only regular files
+    /// are accepted; use [`add_path`](Self::add_path) for directory trees.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    ///
+    /// archive.add_file_from_path("docs/readme.txt", "/tmp/readme.txt")?;
+    /// archive.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn add_file_from_path<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        archive_path: P,
+        disk_path: Q,
+    ) -> Result<()> {
+        let disk_path = disk_path.as_ref();
+        let file = std::fs::File::open(disk_path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(Error::InvalidArgument(format!(
+                "{} is not a regular file",
+                disk_path.display()
+            )));
+        }
+
+        let mut entry = EntryMut::new();
+        entry.set_pathname(&archive_path)?;
+        entry.set_file_type(FileType::RegularFile);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            use std::os::unix::fs::PermissionsExt;
+            entry.set_perm(metadata.permissions().mode() & 0o7777)?;
+            entry.set_uid(u64::from(metadata.uid()));
+            entry.set_gid(u64::from(metadata.gid()));
+        }
+        #[cfg(not(unix))]
+        entry.set_perm(if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        })?;
+        entry.set_mtime(metadata.modified()?);
+
+        self.write_entry_from_reader(entry, metadata.len() as i64, file)?;
+        Ok(())
+    }
+
+    /// Write `entry` with `size` bytes of data copied from `reader`
+    fn write_entry_from_reader<R: std::io::Read>(
+        &mut self,
+        mut entry: EntryMut,
+        size: i64,
+        mut reader: R,
+    ) -> Result<u64> {
+        use std::io::{ErrorKind, Read};
+
+        let declared = u64::try_from(size)
+            .map_err(|_| Error::InvalidArgument(format!("Invalid file size {}", size)))?;
+        let name = entry.as_entry().pathname().unwrap_or_default();
+        entry.set_size(size);
+        self.write_header(&entry)?;
+
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut written = 0u64;
+        let mut limited = reader.by_ref().take(declared);
+        loop {
+            let n = match limited.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::Io(e)),
+            };
+            self.write_data_all(&buf[..n])?;
+            written += n as u64;
+        }
+
+        if written < declared {
+            return Err(Error::Io(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{}: reader ended after {} of {} declared bytes",
+                    name, written, declared
+                ),
+            )));
+        }
+        let extra = loop {
+            match reader.read(&mut buf[..1]) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        if extra > 0 {
+            return Err(Error::Io(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{}: reader has more than the {} declared bytes; wrote {}",
+                    name, declared, written
+                ),
+            )));
+        }
+
+        Ok(written)
+    }
+
+    /// Start a regular file entry whose size is not known yet
+    ///
+    /// Archive formats record an entry's size in its header, ahead of the data,
+    /// so [`write_header`](Self::write_header) needs it up front. The returned
+    /// [`EntryWriter`] implements `std::io::Write` and spools everything written
+    /// to it to a temporary file; [`EntryWriter::finish`] then writes the header
+    /// with the final size, followed by the data. This suits data generated on
+    /// the fly, such as a log compressed while it is being produced. Any size
+    /// already set on `entry` is replaced.
+    ///
+    /// Since the header is only written once the data is complete, this works
+    /// with every output format.
+    ///
+    /// Nothing reaches the archive until `finish`, and the writer borrows the
+    /// archive so no other entry can be written in the meantime. Dropping the
+    /// writer without finishing it discards the entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] for entries that are not regular
+    /// files, and [`Error::Io`] if the temporary file cannot be created.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, CompressionFormat, EntryMut, FileType, WriteArchive};
+    /// use std::io::Write;
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .compression(CompressionFormat::Zstd)
+    ///     .open_file("logs.tar.zst")?;
+    ///
+    /// let mut entry = EntryMut::new();
+    /// entry.set_pathname("service.log")?;
+    /// entry.set_file_type(FileType::RegularFile);
+    /// entry.set_perm(0o644)?;
+    ///
+    /// let mut writer = archive.start_entry_streaming(&entry)?;
+    /// for line in ["starting", "ready", "stopping"] {
+    ///     writeln!(writer, "{line}")?;
+    /// }
+    /// writer.finish()?;
+    /// archive.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn start_entry_streaming(&mut self, entry: &EntryMut) -> Result<EntryWriter<'_, 'a>> {
+        if entry.as_entry().file_type() != FileType::RegularFile {
+            return Err(Error::InvalidArgument(
+                "Only regular file entries can be streamed".to_string(),
+            ));
+        }
+
+        Ok(EntryWriter {
+            archive: self,
+            entry: entry.as_entry().clone_owned()?,
+            spool: std::io::BufWriter::new(spool_file()?),
+            len: 0,
+        })
+    }
+
+    /// Add a file with extended attributes to the archive
+    ///
+    /// Each `(name, value)` pair is attached to the entry as an xattr before the
+    /// header is written, and default overrides are applied as for
+    /// [`add_file`](Self::add_file). Whether the xattrs are stored depends on the
+    /// format: pax tar keeps them, formats without xattr support drop them.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .open_file("output.tar")?;
+    ///
+    /// archive.add_file_with_xattrs(
+    ///     "bin/tool",
+    ///     b"binary contents",
+    ///     &[("user.checksum", b"abc123"), ("user.origin", b"build-42")],
+    /// )?;
+    /// archive.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn add_file_with_xattrs<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        data: &[u8],
+        xattrs: &[(&str, &[u8])],
+    ) -> Result<()> {
+        use crate::acl_xattr::EntryMutAclExt;
+
+        let mut entry = EntryMut::new();
+        entry.set_pathname(path)?;
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_size(data.len() as i64);
+        entry.set_perm(0o644)?;
+        entry.set_mtime(SystemTime::now());
+        for (name, value) in xattrs {
+            entry.add_xattr(name, value)?;
+        }
+
+        self.write_header(&entry)?;
+        self.write_data_all(data)?;
 
         Ok(())
     }
@@ -1113,14 +2940,215 @@ impl<'a> WriteArchive<'a> {
         Ok(())
     }
 
+    /// Add a symbolic link to the archive
+    ///
+    /// `target` is stored as given; it is not checked or resolved.
+    pub fn add_symlink<P: AsRef<Path>>(&mut self, path: P, target: &str) -> Result<()> {
+        let mut entry = EntryMut::new();
+        entry.set_pathname(path)?;
+        entry.set_file_type(FileType::SymbolicLink);
+        entry.set_symlink(target)?;
+        entry.set_size(0);
+        entry.set_perm(0o777)?;
+        entry.set_mtime(SystemTime::now());
+
+        self.write_header(&entry)?;
+
+        Ok(())
+    }
+
+    /// Add a hard link to an entry already written to the archive
+    ///
+    /// `target` is the archive path of the earlier entry. The link itself
+    /// carries no data; readers share the target's contents.
+    pub fn add_hardlink<P: AsRef<Path>>(&mut self, path: P, target: &str) -> Result<()> {
+        let mut entry = EntryMut::new();
+        entry.set_pathname(path)?;
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_hardlink(target)?;
+        entry.set_size(0);
+        entry.set_perm(0o644)?;
+        entry.set_mtime(SystemTime::now());
+
+        self.write_header(&entry)?;
+
+        Ok(())
+    }
+
+    /// Add a file or directory tree from disk
+    ///
+    /// Metadata (permissions, times, ownership, xattrs, ...) is captured with
+    /// [`ReadDisk`](crate::ReadDisk), directories are added recursively, and file
+    /// contents are copied in chunks rather than read into memory. Symlinks are
+    /// handled according to [`symlink_mode`](Self::symlink_mode).
+    ///
+    /// Entries are stored under `archive_path` in place of `disk_path`, e.g.
+    /// adding `/home/user/project` as `Some("project")` stores
+    /// `/home/user/project/src/main.rs` as `project/src/main.rs`. With `None`
+    /// the disk path is used as given, without a leading `/`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{WriteArchive, ArchiveFormat, CompressionFormat};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .compression(CompressionFormat::Gzip)
+    ///     .open_file("project.tar.gz")?;
+    ///
+    /// archive.add_path("/home/user/project", Some("project"))?;
+    /// archive.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn add_path<P: AsRef<Path>>(
+        &mut self,
+        disk_path: P,
+        archive_path: Option<&str>,
+    ) -> Result<()> {
+        use crate::read_disk::ReadDisk;
+        use std::io::Read;
+
+        let disk_root = disk_path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| Error::InvalidArgument("Path contains invalid UTF-8".to_string()))?;
+        // libarchive appends child names to the path exactly as it was opened
+        let disk_root = match disk_root.trim_end_matches('/') {
+            "" => "/",
+            trimmed => trimmed,
+        };
+        let archive_root = archive_path
+            .unwrap_or(disk_root)
+            .trim_start_matches('/')
+            .trim_end_matches('/');
+
+        let mut disk = ReadDisk::new()?;
+        disk.set_symlink_mode(self.symlink_mode)?;
+        disk.set_standard_lookup()?;
+        disk.open(disk_root)?;
+
+        let mut buf = vec![0u8; 64 * 1024];
+        while let Some(mut entry) = disk.next_entry()? {
+            let source = entry
+                .as_entry()
+                .pathname()
+                .ok_or_else(|| Error::InvalidArgument("Disk entry has no pathname".to_string()))?;
+            let relative = source
+                .strip_prefix(disk_root)
+                .unwrap_or(&source)
+                .trim_start_matches('/');
+            let name = match (archive_root, relative) {
+                (root, "") => root.to_string(),
+                ("", rel) => rel.to_string(),
+                (root, rel) => format!("{}/{}", root, rel),
+            };
+            entry.set_pathname(&name)?;
+
+            let has_data = entry.as_entry().file_type() == FileType::RegularFile
+                && entry.as_entry().size_opt().is_some_and(|size| size > 0);
+            self.write_header(&entry)?;
+            if has_data {
+                let mut file = std::fs::File::open(&source)?;
+                loop {
+                    let n = file.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    self.write_data_all(&buf[..n])?;
+                }
+            }
+
+            if disk.can_descend() {
+                disk.descend()?;
+            }
+        }
+
+        disk.close()
+    }
+
     /// Finish writing and close the archive
-    pub fn finish(mut self) -> Result<()> {
+    ///
+    /// If [`durable`](Self::durable) is enabled, the output is synced to stable
+    /// storage before this returns.
+    ///
+    /// Returns the final [`stats`](Self::stats), taken once libarchive has
+    /// flushed its buffers, so `compressed_bytes` is the size of the archive
+    /// as written to its destination. Callers that don't need them can ignore
+    /// the value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, CompressionFormat, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::TarPax)
+    ///     .compression(CompressionFormat::Gzip)
+    ///     .open_file("output.tar.gz")?;
+    /// archive.add_file("file.txt", b"Hello, world!")?;
+    /// let stats = archive.finish()?;
+    /// println!(
+    ///     "{} entries, {} bytes, ratio {:.1}",
+    ///     stats.entries,
+    ///     stats.compressed_bytes,
+    ///     stats.compression_ratio()
+    /// );
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn finish(mut self) -> Result<WriteStats> {
+        self.close()
+    }
+
+    /// Finish writing and return the bytes of an archive opened with
+    /// [`open_growable_memory`](Self::open_growable_memory)
+    ///
+    /// Returns [`Error::InvalidArgument`] for archives opened any other way.
+    /// The archive is finalized exactly as by [`finish`](Self::finish), so the
+    /// bytes are a complete archive, including trailers such as the zip
+    /// central directory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libarchive2::{ArchiveFormat, ReadArchive, WriteArchive};
+    ///
+    /// let mut archive = WriteArchive::new()
+    ///     .format(ArchiveFormat::Zip)
+    ///     .open_growable_memory()?;
+    /// archive.add_file("hello.txt", b"Hello, world!")?;
+    /// let bytes = archive.finish_to_vec()?;
+    ///
+    /// let mut archive = ReadArchive::open_memory(&bytes)?;
+    /// while let Some(entry) = archive.next_entry()? {
+    ///     println!("{}", entry.pathname().unwrap_or_default());
+    /// }
+    /// # Ok::<(), libarchive2::Error>(())
+    /// ```
+    pub fn finish_to_vec(mut self) -> Result<Vec<u8>> {
+        let Some(memory) = self.memory.take() else {
+            return Err(Error::InvalidArgument(
+                "finish_to_vec requires an archive opened with open_growable_memory".to_string(),
+            ));
+        };
+        self.close()?;
+        let mut bytes = memory.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(std::mem::take(&mut *bytes))
+    }
+
+    /// Close the archive, shared by [`finish`](Self::finish) and
+    /// [`finish_to_vec`](Self::finish_to_vec)
+    fn close(&mut self) -> Result<WriteStats> {
+        let mut stats = self.stats();
         unsafe {
             if !self.archive.is_null() {
                 Error::from_return_code(
                     libarchive2_sys::archive_write_close(self.archive),
                     self.archive,
                 )?;
+                // The filter counters are only final once close has flushed
+                // them, and are gone once the archive is freed
+                stats = self.stats();
                 libarchive2_sys::archive_write_free(self.archive);
                 self.archive = std::ptr::null_mut();
             }
@@ -1130,10 +3158,173 @@ impl<'a> WriteArchive<'a> {
                 drop_fn(data);
             }
         }
+
+        if self.durable {
+            if let Some(ref path) = self.output_path {
+                sync_file_and_parent(path)?;
+            }
+            #[cfg(unix)]
+            if let Some(fd) = self.output_fd {
+                sync_fd(fd)?;
+            }
+        }
+
+        if let Some(append) = self.append.take() {
+            let path = append.path().to_path_buf();
+            let renamed = append.strategy == AppendStrategy::Rewrite;
+            append.commit()?;
+            if self.durable && renamed {
+                sync_file_and_parent(&path)?;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// Output of [`WriteArchive::open_growable_memory`], shared with the archive
+/// so the bytes can be taken after the callback data is freed
+struct VecSink(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for VecSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut bytes = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
         Ok(())
     }
 }
 
+/// Entry being written with [`WriteArchive::start_entry_streaming`]
+///
+/// Data written through `std::io::Write` is held in a temporary file until
+/// [`finish`](Self::finish) adds the entry to the archive. The temporary file
+/// is removed when the writer is finished or dropped.
+pub struct EntryWriter<'w, 'a> {
+    archive: &'w mut WriteArchive<'a>,
+    entry: EntryMut,
+    spool: std::io::BufWriter<std::fs::File>,
+    len: u64,
+}
+
+impl<'w, 'a> EntryWriter<'w, 'a> {
+    /// Get the number of bytes written so far
+    pub fn bytes_written(&self) -> u64 {
+        self.len
+    }
+
+    /// Write the entry to the archive with its final size
+    ///
+    /// Returns the size of the entry's data.
+    pub fn finish(mut self) -> Result<u64> {
+        use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+        self.spool.flush()?;
+        let file = self.spool.get_mut();
+        file.seek(SeekFrom::Start(0))?;
+
+        self.entry.set_size(self.len as i64);
+        self.archive.write_header(&self.entry)?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = match file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::Io(e)),
+            };
+            self.archive.write_data_all(&buf[..n])?;
+        }
+        Ok(self.len)
+    }
+}
+
+impl<'w, 'a> std::io::Write for EntryWriter<'w, 'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = std::io::Write::write(&mut self.spool, buf)?;
+        self.len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::Write::flush(&mut self.spool)
+    }
+}
+
+/// Create an anonymous temporary file for [`EntryWriter`]
+///
+/// The file is unlinked as soon as it is open on Unix, and deleted when its
+/// handle is closed on Windows, so nothing is left behind even if the process
+/// dies before the entry is finished.
+fn spool_file() -> Result<std::fs::File> {
+    let dir = std::env::temp_dir();
+    for attempt in 0u32.. {
+        let path = dir.join(format!(
+            "libarchive2-entry-{}-{}",
+            std::process::id(),
+            attempt
+        ));
+        let mut options = std::fs::OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        // The name is predictable; keep other users out until it is unlinked
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            // FILE_FLAG_DELETE_ON_CLOSE
+            options.custom_flags(0x0400_0000);
+        }
+        match options.open(&path) {
+            Ok(file) => {
+                #[cfg(unix)]
+                std::fs::remove_file(&path)?;
+                return Ok(file);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
+    unreachable!("temporary file names are exhausted")
+}
+
+/// Flush a finished archive file and its parent directory to stable storage
+fn sync_file_and_parent(path: &Path) -> std::io::Result<()> {
+    // FlushFileBuffers on Windows requires write access, so open for writing
+    // (without truncating) on every platform.
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .sync_all()?;
+
+    // Directory entries are only made durable by syncing the directory itself.
+    // Windows cannot open directories this way, and NTFS journals metadata anyway.
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Flush a caller-provided file descriptor to stable storage
+#[cfg(unix)]
+fn sync_fd(fd: std::os::unix::io::RawFd) -> std::io::Result<()> {
+    // SAFETY: The caller of open_fd guarantees fd stays open for the archive's lifetime
+    if unsafe { libc::fsync(fd) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 /// `std::io::Write` implementation for writing data to the current archive entry.
 ///
 /// This allows using `WriteArchive` with anything that accepts a `Write` trait object,
@@ -1195,6 +3386,77 @@ impl<'a> std::io::Write for WriteArchive<'a> {
     }
 }
 
+/// Key used to detect duplicate pathnames: the raw pathname without trailing `/`
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+unsafe fn duplicate_key(entry: *mut libarchive2_sys::archive_entry) -> Vec<u8> {
+    // SAFETY: Caller guarantees entry is valid
+    let ptr = unsafe {
+        let utf8 = libarchive2_sys::archive_entry_pathname_utf8(entry);
+        if utf8.is_null() {
+            libarchive2_sys::archive_entry_pathname(entry)
+        } else {
+            utf8
+        }
+    };
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    // SAFETY: libarchive returns a valid NUL-terminated string
+    let bytes = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+    let trimmed = match bytes.iter().rposition(|&b| b != b'/') {
+        Some(end) => &bytes[..=end],
+        None => bytes,
+    };
+    trimmed.to_vec()
+}
+
+/// Getter and setter for one form of an entry name
+type NameAccessors = (
+    unsafe extern "C" fn(*mut libarchive2_sys::archive_entry) -> *const std::os::raw::c_char,
+    unsafe extern "C" fn(*mut libarchive2_sys::archive_entry, *const std::os::raw::c_char),
+);
+
+/// Prepend `prefix` (ending in `/`) to one of the entry's names
+///
+/// The name is joined as bytes in its UTF-8 form, or in the locale form when
+/// it has no UTF-8 form (a name set from raw bytes that aren't valid in the
+/// current locale's charset), so such names are prefixed too rather than
+/// skipped. Leading `/` are dropped from the name.
+///
+/// # Safety
+/// `entry` must be a valid, non-null `archive_entry` pointer.
+unsafe fn prefix_name(
+    entry: *mut libarchive2_sys::archive_entry,
+    prefix: &str,
+    utf8: NameAccessors,
+    mbs: NameAccessors,
+) {
+    for (get, set) in [utf8, mbs] {
+        // SAFETY: Caller guarantees entry is valid
+        let ptr = unsafe { get(entry) };
+        if ptr.is_null() {
+            continue;
+        }
+        // SAFETY: libarchive returns a valid NUL-terminated string
+        let path = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+        if let Some(c_path) = prefixed_path(prefix, path) {
+            // SAFETY: entry is valid and c_path is a valid C string
+            unsafe { set(entry, c_path.as_ptr()) };
+        }
+        return;
+    }
+}
+
+/// Join `prefix` (ending in `/`) and the archive path `path`
+fn prefixed_path(prefix: &str, path: &[u8]) -> Option<CString> {
+    let start = path.iter().position(|&b| b != b'/').unwrap_or(path.len());
+    let mut joined = prefix.as_bytes().to_vec();
+    joined.extend_from_slice(&path[start..]);
+    CString::new(joined).ok()
+}
+
 impl<'a> Drop for WriteArchive<'a> {
     fn drop(&mut self) {
         unsafe {
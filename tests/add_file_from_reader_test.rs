@@ -0,0 +1,224 @@
+use libarchive2::{ArchiveFormat, Error, ReadArchive, WriteArchive};
+use std::io::Read;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+/// Reader that generates `remaining` deterministic bytes without buffering them
+struct Generated {
+    state: u32,
+    remaining: u64,
+}
+
+impl Generated {
+    fn new(len: u64) -> Self {
+        Generated {
+            state: 0x2545_f491,
+            remaining: len,
+        }
+    }
+}
+
+impl Read for Generated {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.remaining as usize);
+        for byte in &mut buf[..n] {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 17;
+            self.state ^= self.state << 5;
+            *byte = self.state as u8;
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_add_file_from_reader_large() {
+    const SIZE: u64 = 100 * 1024 * 1024;
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("large.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    let written = archive
+        .add_file_from_reader("large.bin", SIZE as i64, Generated::new(SIZE))
+        .unwrap();
+    assert_eq!(written, SIZE);
+    archive.finish().unwrap();
+
+    // Compare chunk by chunk so neither side holds the whole file
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "large.bin");
+    assert_eq!(entry.size_opt(), Some(SIZE as i64));
+
+    let mut expected = Generated::new(SIZE);
+    let mut actual = vec![0u8; 64 * 1024];
+    let mut wanted = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        let n = archive.read_data(&mut actual).unwrap();
+        if n == 0 {
+            break;
+        }
+        expected.read_exact(&mut wanted[..n]).unwrap();
+        assert!(actual[..n] == wanted[..n], "mismatch at offset {total}");
+        total += n as u64;
+    }
+    assert_eq!(total, SIZE);
+}
+
+#[test]
+fn test_add_file_from_reader_short() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+
+    match archive.add_file_from_reader("short.bin", 100, Generated::new(60)) {
+        Err(Error::Io(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+            let message = e.to_string();
+            assert!(message.contains("short.bin"), "{message}");
+            assert!(
+                message.contains("60") && message.contains("100"),
+                "{message}"
+            );
+        }
+        other => panic!("expected a short read error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_add_file_from_reader_long() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+
+        match archive.add_file_from_reader("long.bin", 100, Generated::new(150)) {
+            Err(Error::Io(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+                assert!(e.to_string().contains("long.bin"), "{e}");
+            }
+            other => panic!("expected an oversized reader error, got {other:?}"),
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    // Only the declared size was written
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    let mut expected = vec![0u8; 100];
+    Generated::new(100).read_exact(&mut expected).unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), expected);
+}
+
+#[test]
+fn test_add_file_from_reader_negative_size() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    assert!(matches!(
+        archive.add_file_from_reader("bad.bin", -1, std::io::empty()),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_add_file_from_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("source.txt");
+    std::fs::write(&source, b"contents from disk").unwrap();
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(&source)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&source, std::fs::Permissions::from_mode(0o600)).unwrap();
+    }
+
+    let path = temp_dir.path().join("out.tar");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive
+        .add_file_from_path("docs/source.txt", &source)
+        .unwrap();
+    assert!(matches!(
+        archive.add_file_from_path("dir", temp_dir.path()),
+        Err(Error::InvalidArgument(_))
+    ));
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "docs/source.txt");
+    assert_eq!(entry.size_opt(), Some(18));
+    assert_eq!(entry.mtime(), Some(mtime));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(&source).unwrap();
+        assert_eq!(entry.mode() & 0o7777, 0o600);
+        assert_eq!(entry.uid(), Some(u64::from(metadata.uid())));
+        assert_eq!(entry.gid(), Some(u64::from(metadata.gid())));
+    }
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"contents from disk");
+    assert!(archive.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_add_file_from_path_large() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("large.bin");
+    let len = 48 * 1024 * 1024 + 7;
+    std::io::copy(
+        &mut Generated::new(len),
+        &mut std::fs::File::create(&source).unwrap(),
+    )
+    .unwrap();
+
+    let path = temp_dir.path().join("large.tar");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file_from_path("large.bin", &source).unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.size_opt(), Some(len as i64));
+    let mut expected = Generated::new(len);
+    let mut expected_chunk = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        let n = archive.read_data(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        expected.read_exact(&mut expected_chunk[..n]).unwrap();
+        assert_eq!(chunk[..n], expected_chunk[..n]);
+        total += n as u64;
+    }
+    assert_eq!(total, len);
+}
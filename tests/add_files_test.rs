@@ -0,0 +1,58 @@
+use libarchive2::{ArchiveFormat, Error, ReadArchive, WriteArchive};
+
+#[test]
+fn test_add_files() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive
+            .add_files(vec![
+                ("a.txt", b"first".to_vec()),
+                ("dir/b.txt", b"second".to_vec()),
+                ("empty", Vec::new()),
+            ])
+            .unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buf[..used]).unwrap();
+    let mut files = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        assert_eq!(entry.mode(), 0o644);
+        files.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    assert_eq!(
+        files,
+        vec![
+            ("a.txt".to_string(), b"first".to_vec()),
+            ("dir/b.txt".to_string(), b"second".to_vec()),
+            ("empty".to_string(), Vec::new()),
+        ]
+    );
+}
+
+#[test]
+fn test_add_files_names_failing_item() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarUstar)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    // ustar can't store a name component this long
+    let long_name = format!("{}.txt", "x".repeat(300));
+    let err = archive
+        .add_files([
+            ("ok.txt".to_string(), b"ok".to_vec()),
+            (long_name.clone(), b"too long".to_vec()),
+            ("never.txt".to_string(), b"never".to_vec()),
+        ])
+        .unwrap_err();
+    assert!(matches!(err, Error::Archive { .. }), "{err:?}");
+    assert!(err.to_string().contains(&long_name), "{err}");
+}
@@ -0,0 +1,146 @@
+use libarchive2::{ArchiveFormat, FileType, ReadArchive, SymlinkMode, WriteArchive};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const MTIME: u64 = 1_600_000_000;
+
+#[derive(Debug)]
+struct Item {
+    file_type: FileType,
+    perm: u32,
+    mtime: Option<SystemTime>,
+    symlink: Option<String>,
+    data: Vec<u8>,
+}
+
+fn build_tree(root: &Path) -> Vec<u8> {
+    let big: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::create_dir_all(root.join("sub/deeper")).unwrap();
+    std::fs::write(root.join("a.txt"), b"alpha").unwrap();
+    std::fs::write(root.join("sub/big.bin"), &big).unwrap();
+    std::fs::write(root.join("sub/deeper/empty"), b"").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::os::unix::fs::symlink("../a.txt", root.join("sub/link")).unwrap();
+        std::fs::set_permissions(root.join("a.txt"), std::fs::Permissions::from_mode(0o640))
+            .unwrap();
+    }
+    let file = std::fs::File::options()
+        .write(true)
+        .open(root.join("a.txt"))
+        .unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME))
+        .unwrap();
+    big
+}
+
+fn write_and_read(
+    disk_path: &Path,
+    archive_path: Option<&str>,
+    mode: SymlinkMode,
+) -> BTreeMap<String, Item> {
+    let mut buf = vec![0u8; 4 * 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .symlink_mode(mode)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_path(disk_path, archive_path).unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buf[..used]).unwrap();
+    let mut items = BTreeMap::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        let mut item = Item {
+            file_type: entry.file_type(),
+            perm: entry.mode() & 0o7777,
+            mtime: entry.mtime(),
+            symlink: entry.symlink(),
+            data: Vec::new(),
+        };
+        item.data = archive.read_data_to_vec().unwrap();
+        items.insert(name, item);
+    }
+    items
+}
+
+#[test]
+fn test_add_path_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    let big = build_tree(&root);
+
+    let items = write_and_read(&root, Some("proj"), SymlinkMode::Physical);
+    let mut names: Vec<_> = items.keys().map(String::as_str).collect();
+    names.sort();
+    let mut expected = vec![
+        "proj/",
+        "proj/a.txt",
+        "proj/sub/",
+        "proj/sub/big.bin",
+        "proj/sub/deeper/",
+        "proj/sub/deeper/empty",
+    ];
+    if cfg!(unix) {
+        expected.push("proj/sub/link");
+    }
+    expected.sort();
+    assert_eq!(names, expected);
+
+    assert_eq!(items["proj/"].file_type, FileType::Directory);
+    assert_eq!(items["proj/a.txt"].data, b"alpha");
+    assert_eq!(
+        items["proj/a.txt"].mtime,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME))
+    );
+    assert_eq!(items["proj/sub/big.bin"].data, big);
+    assert!(items["proj/sub/deeper/empty"].data.is_empty());
+    #[cfg(unix)]
+    {
+        assert_eq!(items["proj/a.txt"].perm, 0o640);
+        let link = &items["proj/sub/link"];
+        assert_eq!(link.file_type, FileType::SymbolicLink);
+        assert_eq!(link.symlink.as_deref(), Some("../a.txt"));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_add_path_follows_symlinks_in_logical_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    build_tree(&root);
+
+    let items = write_and_read(&root, Some("proj"), SymlinkMode::Logical);
+    let link = &items["proj/sub/link"];
+    assert_eq!(link.file_type, FileType::RegularFile);
+    assert_eq!(link.data, b"alpha");
+}
+
+#[test]
+fn test_add_path_default_name_and_single_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    build_tree(&root);
+
+    // Without a rename the disk path is kept, minus the leading '/'
+    let items = write_and_read(&root, None, SymlinkMode::Physical);
+    let expected = root.join("a.txt");
+    let expected = expected.to_str().unwrap().trim_start_matches('/');
+    assert_eq!(items[expected].data, b"alpha");
+
+    // A single file is stored under the given name
+    let items = write_and_read(
+        &root.join("a.txt"),
+        Some("renamed.txt"),
+        SymlinkMode::Physical,
+    );
+    assert_eq!(items.len(), 1);
+    assert_eq!(items["renamed.txt"].data, b"alpha");
+}
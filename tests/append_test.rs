@@ -0,0 +1,306 @@
+use libarchive2::{
+    AppendStrategy, ArchiveFormat, CompressionFormat, DuplicatePolicy, Error, ReadArchive,
+    WriteArchive,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create(path: &Path, format: ArchiveFormat, compression: CompressionFormat) {
+    let mut archive = WriteArchive::new()
+        .format(format)
+        .compression(compression)
+        .open_file(path)
+        .unwrap();
+    archive.add_directory("docs").unwrap();
+    archive.add_file("docs/a.txt", b"first").unwrap();
+    archive.finish().unwrap();
+}
+
+fn read_all(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        entries.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    entries
+}
+
+fn names(path: &Path) -> Vec<String> {
+    read_all(path).into_iter().map(|(name, _)| name).collect()
+}
+
+/// Files left in `dir` besides the archive itself
+fn leftovers(dir: &Path, archive: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p != archive)
+        .map(|p| p.display().to_string())
+        .collect()
+}
+
+#[test]
+fn test_append_to_tar_in_place() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar");
+    create(&path, ArchiveFormat::TarPax, CompressionFormat::None);
+    assert_eq!(
+        AppendStrategy::for_file(&path).unwrap(),
+        AppendStrategy::InPlace
+    );
+
+    let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::InPlace));
+    archive.add_file("b.txt", b"second").unwrap();
+    archive.add_file("docs/c.txt", b"third").unwrap();
+    assert_eq!(archive.stats().entries, 2);
+    archive.finish().unwrap();
+
+    assert_eq!(
+        read_all(&path),
+        vec![
+            ("docs/".to_string(), vec![]),
+            ("docs/a.txt".to_string(), b"first".to_vec()),
+            ("b.txt".to_string(), b"second".to_vec()),
+            ("docs/c.txt".to_string(), b"third".to_vec()),
+        ]
+    );
+
+    // Appending again keeps extending the same archive
+    let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+    archive.add_file("d.txt", b"fourth").unwrap();
+    archive.finish().unwrap();
+    assert_eq!(
+        names(&path),
+        vec!["docs/", "docs/a.txt", "b.txt", "docs/c.txt", "d.txt"]
+    );
+    assert!(leftovers(temp_dir.path(), &path).is_empty());
+}
+
+#[test]
+fn test_append_to_gnu_and_ustar_tar() {
+    let temp_dir = TempDir::new().unwrap();
+    for format in [ArchiveFormat::TarGnu, ArchiveFormat::TarUstar] {
+        let path = temp_dir.path().join("test.tar");
+        create(&path, format, CompressionFormat::None);
+
+        let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+        assert_eq!(archive.append_strategy(), Some(AppendStrategy::InPlace));
+        archive
+            .add_file(format!("{}/{}", "long".repeat(40), "b.txt"), b"second")
+            .unwrap();
+        archive.finish().unwrap();
+
+        let entries = read_all(&path);
+        assert_eq!(entries.len(), 3, "{:?}", format);
+        assert_eq!(entries[2].1, b"second", "{:?}", format);
+    }
+}
+
+#[test]
+fn test_append_to_compressed_tar_rewrites() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.gz");
+    create(&path, ArchiveFormat::TarPax, CompressionFormat::Gzip);
+    assert_eq!(
+        AppendStrategy::for_file(&path).unwrap(),
+        AppendStrategy::Rewrite
+    );
+
+    let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::Rewrite));
+    archive.add_file("b.txt", b"second").unwrap();
+    archive.add_file("c.txt", b"third").unwrap();
+    assert_eq!(archive.stats().entries, 2);
+    archive.finish().unwrap();
+
+    assert_eq!(
+        read_all(&path),
+        vec![
+            ("docs/".to_string(), vec![]),
+            ("docs/a.txt".to_string(), b"first".to_vec()),
+            ("b.txt".to_string(), b"second".to_vec()),
+            ("c.txt".to_string(), b"third".to_vec()),
+        ]
+    );
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap();
+    assert_eq!(archive.filters(), vec!["gzip".to_string()]);
+    assert!(leftovers(temp_dir.path(), &path).is_empty());
+}
+
+#[test]
+fn test_append_to_zip_rewrites() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.zip");
+    create(&path, ArchiveFormat::Zip, CompressionFormat::None);
+
+    let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::Rewrite));
+    archive.add_file("b.txt", b"second").unwrap();
+    archive.finish().unwrap();
+
+    assert_eq!(names(&path), vec!["docs/", "docs/a.txt", "b.txt"]);
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap();
+    assert!(archive.format_name().unwrap().starts_with("ZIP"));
+}
+
+#[test]
+fn test_append_builder_options_apply_to_new_entries_only() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar");
+    create(&path, ArchiveFormat::TarPax, CompressionFormat::None);
+
+    let mut archive = WriteArchive::new()
+        .path_prefix("extra")
+        .compression(CompressionFormat::Zstd)
+        .append_to_file(&path)
+        .unwrap();
+    // Changing the compression can only be done by rewriting
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::Rewrite));
+    archive.add_file("b.txt", b"second").unwrap();
+    archive.finish().unwrap();
+
+    assert_eq!(names(&path), vec!["docs/", "docs/a.txt", "extra/b.txt"]);
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap();
+    assert_eq!(archive.filters(), vec!["zstd".to_string()]);
+}
+
+#[test]
+fn test_append_duplicate_policy_sees_existing_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    for compression in [CompressionFormat::None, CompressionFormat::Gzip] {
+        let path = temp_dir.path().join("test.tar");
+        create(&path, ArchiveFormat::TarPax, compression);
+
+        let mut archive = WriteArchive::new()
+            .duplicate_policy(DuplicatePolicy::Error)
+            .append_to_file(&path)
+            .unwrap();
+        assert!(matches!(
+            archive.add_file("docs/a.txt", b"again"),
+            Err(Error::InvalidArgument(_))
+        ));
+        archive.add_file("b.txt", b"second").unwrap();
+        archive.finish().unwrap();
+        assert_eq!(names(&path), vec!["docs/", "docs/a.txt", "b.txt"]);
+    }
+}
+
+#[test]
+fn test_failed_append_leaves_original_untouched() {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, compression) in [
+        ("test.tar", CompressionFormat::None),
+        ("test.tar.gz", CompressionFormat::Gzip),
+    ] {
+        let path = temp_dir.path().join(name);
+        create(&path, ArchiveFormat::TarPax, compression);
+        let original = std::fs::read(&path).unwrap();
+
+        // Dropped without finish
+        let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+        archive.add_file("b.txt", b"second").unwrap();
+        drop(archive);
+        assert_eq!(std::fs::read(&path).unwrap(), original, "{}", name);
+
+        // Failing part-way through an entry
+        let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+        archive
+            .add_file_from_reader("c.txt", 100, &b"too short"[..])
+            .unwrap_err();
+        drop(archive);
+        assert_eq!(std::fs::read(&path).unwrap(), original, "{}", name);
+
+        assert!(leftovers(temp_dir.path(), &path).is_empty(), "{}", name);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_append_to_empty_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("empty.tar");
+    std::fs::write(&path, b"").unwrap();
+
+    let mut archive = WriteArchive::new().append_to_file(&path).unwrap();
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::InPlace));
+    archive.add_file("a.txt", b"first").unwrap();
+    archive.finish().unwrap();
+    assert_eq!(names(&path), vec!["a.txt"]);
+}
+
+#[test]
+fn test_append_to_missing_file_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("missing.tar");
+    assert!(WriteArchive::new().append_to_file(&path).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_open_file_append_tar_repeatedly() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("log.tar");
+    create(&path, ArchiveFormat::TarUstar, CompressionFormat::None);
+
+    for name in ["one.txt", "two.txt"] {
+        let mut archive = WriteArchive::new().open_file_append(&path).unwrap();
+        archive.add_file(name, name.as_bytes()).unwrap();
+        archive.finish().unwrap();
+    }
+
+    assert_eq!(
+        read_all(&path),
+        vec![
+            ("docs/".to_string(), vec![]),
+            ("docs/a.txt".to_string(), b"first".to_vec()),
+            ("one.txt".to_string(), b"one.txt".to_vec()),
+            ("two.txt".to_string(), b"two.txt".to_vec()),
+        ]
+    );
+    assert!(leftovers(temp_dir.path(), &path).is_empty());
+}
+
+#[test]
+fn test_open_file_append_creates_missing_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("new.tar");
+
+    let mut archive = WriteArchive::new().open_file_append(&path).unwrap();
+    archive.add_file("a.txt", b"a").unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = WriteArchive::new().open_file_append(&path).unwrap();
+    archive.add_file("b.txt", b"b").unwrap();
+    archive.finish().unwrap();
+
+    assert_eq!(names(&path), vec!["a.txt", "b.txt"]);
+}
+
+#[test]
+fn test_open_file_append_rejects_compressed_tar() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.gz");
+    create(&path, ArchiveFormat::TarPax, CompressionFormat::Gzip);
+    let before = std::fs::read(&path).unwrap();
+
+    let result = WriteArchive::new().open_file_append(&path);
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+}
+
+#[test]
+fn test_open_file_append_rejects_non_tar_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.zip");
+
+    let result = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_file_append(&path);
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    assert!(!path.exists());
+}
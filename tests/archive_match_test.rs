@@ -0,0 +1,207 @@
+use libarchive2::{ArchiveMatch, EntryMut, Error, FileType};
+
+fn entry(pathname: &str) -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname(pathname).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry
+}
+
+fn owned_entry(pathname: &str, uid: u64, gid: u64) -> EntryMut {
+    let mut entry = entry(pathname);
+    entry.set_uid(uid);
+    entry.set_gid(gid);
+    entry
+}
+
+fn matches(matcher: &mut ArchiveMatch, pathname: &str) -> bool {
+    matcher.matches(&entry(pathname).as_entry()).unwrap()
+}
+
+#[test]
+fn test_include_pathname_exact() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pathname("docs/readme.txt").unwrap();
+    matcher.include_pathname("src").unwrap();
+
+    assert!(matches(&mut matcher, "docs/readme.txt"));
+    assert!(!matches(&mut matcher, "docs/readme.txt.bak"));
+    assert!(!matches(&mut matcher, "docs/readme.tx"));
+    assert!(!matches(&mut matcher, "docs/other.txt"));
+
+    // A directory includes its contents, but not siblings sharing a prefix
+    assert!(matches(&mut matcher, "src"));
+    assert!(matches(&mut matcher, "src/main.rs"));
+    assert!(!matches(&mut matcher, "src2/main.rs"));
+    assert!(!matches(&mut matcher, "srcfile"));
+}
+
+#[test]
+fn test_include_pathname_is_literal() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pathname("data[1].txt").unwrap();
+    matcher.include_pathname("what?").unwrap();
+    matcher.include_pathname("*.log").unwrap();
+
+    assert!(matches(&mut matcher, "data[1].txt"));
+    assert!(!matches(&mut matcher, "data1.txt"));
+    assert!(matches(&mut matcher, "what?"));
+    assert!(!matches(&mut matcher, "whats"));
+    assert!(matches(&mut matcher, "*.log"));
+    assert!(!matches(&mut matcher, "server.log"));
+    assert!(!matches(&mut matcher, "logs/*.log"));
+}
+
+#[test]
+fn test_exclude_pathname() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_pathname("build").unwrap();
+    matcher.exclude_pathname(".DS_Store").unwrap();
+
+    assert!(!matches(&mut matcher, "build"));
+    assert!(!matches(&mut matcher, "build/out.o"));
+    assert!(!matches(&mut matcher, ".DS_Store"));
+    assert!(matches(&mut matcher, "builder.rs"));
+    assert!(matches(&mut matcher, "src/main.rs"));
+    assert!(matches(&mut matcher, "src/build"));
+    assert!(matches(&mut matcher, "assets/.DS_Store"));
+}
+
+#[test]
+fn test_exclude_pathname_path_excluded() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_pathname("secret.key").unwrap();
+    matcher.exclude_pathname("keys/*.pem").unwrap();
+
+    let mut excluded = |pathname: &str| matcher.path_excluded(&entry(pathname).as_entry()).unwrap();
+    assert!(excluded("secret.key"));
+    assert!(!excluded("secret.key.bak"));
+    assert!(!excluded("secret_key"));
+    assert!(!excluded("old-secret.key"));
+    // Only the exact path, not a file of the same name in a directory
+    assert!(!excluded("backup/secret.key"));
+
+    // Glob characters in the pathname are taken literally
+    assert!(excluded("keys/*.pem"));
+    assert!(!excluded("keys/server.pem"));
+}
+
+#[test]
+fn test_pathname_errors() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    assert!(matches!(
+        matcher.include_pathname(""),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        matcher.exclude_pathname("a\0b"),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_exclude_uid_gid() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_uid(0).unwrap();
+    matcher.exclude_gid(50).unwrap();
+
+    let root = owned_entry("a", 0, 100);
+    let staff = owned_entry("b", 1000, 50);
+    let user = owned_entry("c", 1000, 100);
+    assert!(!matcher.matches(&root.as_entry()).unwrap());
+    assert!(matcher.owner_excluded(&root.as_entry()));
+    assert!(!matcher.matches(&staff.as_entry()).unwrap());
+    assert!(matcher.matches(&user.as_entry()).unwrap());
+    assert!(!matcher.owner_excluded(&user.as_entry()));
+}
+
+#[test]
+fn test_owner_exclusion_combines_with_patterns() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pattern("*.txt").unwrap();
+    matcher.exclude_uid(0).unwrap();
+
+    assert!(
+        matcher
+            .matches(&owned_entry("a.txt", 1000, 1000).as_entry())
+            .unwrap()
+    );
+    assert!(
+        !matcher
+            .matches(&owned_entry("a.txt", 0, 1000).as_entry())
+            .unwrap()
+    );
+    assert!(
+        !matcher
+            .matches(&owned_entry("a.rs", 1000, 1000).as_entry())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_exclude_patterns_from_reader() {
+    let ignore = "# build output\n*.o\n\n   \ntarget\r\n  # indented comment\n*.tmp\n";
+    let mut matcher = ArchiveMatch::new().unwrap();
+    let count = matcher
+        .exclude_patterns_from_reader(ignore.as_bytes())
+        .unwrap();
+    assert_eq!(count, 3);
+
+    assert!(!matches(&mut matcher, "main.o"));
+    assert!(!matches(&mut matcher, "target/debug/app"));
+    assert!(!matches(&mut matcher, "scratch.tmp"));
+    assert!(matches(&mut matcher, "main.c"));
+    assert!(matches(&mut matcher, "# build output"));
+}
+
+#[test]
+fn test_include_patterns_from_reader() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    let count = matcher
+        .include_patterns_from_reader(std::io::Cursor::new("src/*.rs\n\n# docs\nREADME.md"))
+        .unwrap();
+    assert_eq!(count, 2);
+
+    assert!(matches(&mut matcher, "src/lib.rs"));
+    assert!(matches(&mut matcher, "README.md"));
+    assert!(!matches(&mut matcher, "Cargo.toml"));
+}
+
+#[test]
+fn test_patterns_from_reader_reports_line() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    let err = matcher
+        .exclude_patterns_from_reader("ok\n# comment\nbad\0pattern\n".as_bytes())
+        .unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{err}");
+}
+
+#[test]
+fn test_unmatched_inclusions() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pattern("*.txt").unwrap();
+    matcher.include_pattern("*.md").unwrap();
+    matcher.include_pathname("data[1].csv").unwrap();
+    matcher.include_pathname("reprot.pdf").unwrap();
+
+    for name in ["notes.txt", "data[1].csv", "report.pdf"] {
+        matches(&mut matcher, name);
+    }
+
+    let mut unmatched = matcher.unmatched_inclusions().unwrap();
+    unmatched.sort();
+    assert_eq!(unmatched, vec!["*.md", "reprot.pdf"]);
+    // Asking again gives the same answer
+    assert_eq!(matcher.unmatched_inclusions().unwrap().len(), 2);
+
+    matches(&mut matcher, "README.md");
+    assert_eq!(matcher.unmatched_inclusions().unwrap(), vec!["reprot.pdf"]);
+}
+
+#[test]
+fn test_unmatched_inclusions_without_includes() {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_pattern("*.o").unwrap();
+    matches(&mut matcher, "main.c");
+    assert!(matcher.unmatched_inclusions().unwrap().is_empty());
+}
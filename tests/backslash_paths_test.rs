@@ -0,0 +1,65 @@
+use libarchive2::{ArchiveFormat, ExtractFlags, ReadArchive, WriteArchive};
+use std::path::Path;
+
+/// Build a zip whose entry names use Windows separators below `root`
+fn build_windows_style_zip(root: &Path) -> Vec<u8> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        let name = format!("{}/dir\\sub\\file.txt", root.display());
+        archive.add_file(name, b"from windows").unwrap();
+        archive.finish().unwrap();
+    }
+    buffer.truncate(used);
+    buffer
+}
+
+#[test]
+fn test_listing_keeps_raw_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_windows_style_zip(dir.path());
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(entry.pathname().unwrap().ends_with("dir\\sub\\file.txt"));
+}
+
+#[test]
+fn test_extract_normalizes_backslashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_windows_style_zip(dir.path());
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    while archive.next_entry().unwrap().is_some() {
+        archive
+            .extract_current_with_flags(ExtractFlags::TIME)
+            .unwrap();
+    }
+
+    let extracted = dir.path().join("dir").join("sub").join("file.txt");
+    assert!(dir.path().join("dir").is_dir());
+    assert_eq!(std::fs::read(extracted).unwrap(), b"from windows");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_opt_out_preserves_backslashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_windows_style_zip(dir.path());
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_normalize_backslashes(false);
+    while archive.next_entry().unwrap().is_some() {
+        archive
+            .extract_current_with_flags(ExtractFlags::TIME)
+            .unwrap();
+    }
+
+    let extracted = dir.path().join("dir\\sub\\file.txt");
+    assert!(!dir.path().join("dir").exists());
+    assert_eq!(std::fs::read(extracted).unwrap(), b"from windows");
+}
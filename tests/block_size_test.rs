@@ -0,0 +1,52 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+
+fn write_archive(path: &std::path::Path) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(path)
+        .unwrap();
+    archive.add_file("a.txt", b"alpha").unwrap();
+    archive
+        .add_file(
+            "big.bin",
+            &(0..200_000u32).map(|i| i as u8).collect::<Vec<_>>(),
+        )
+        .unwrap();
+    archive.finish().unwrap();
+}
+
+fn read_all(mut archive: ReadArchive) -> Vec<(String, Vec<u8>)> {
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        entries.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    entries
+}
+
+#[test]
+fn test_open_with_block_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar.gz");
+    write_archive(&path);
+
+    let expected = read_all(ReadArchive::open(&path).unwrap());
+    assert_eq!(expected.len(), 2);
+    for block_size in [1, 512, 1024 * 1024] {
+        let archive = ReadArchive::open_with_block_size(&path, block_size).unwrap();
+        assert_eq!(read_all(archive), expected, "block size {block_size}");
+    }
+}
+
+#[test]
+fn test_open_with_zero_block_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar.gz");
+    write_archive(&path);
+
+    assert!(matches!(
+        ReadArchive::open_with_block_size(&path, 0),
+        Err(Error::InvalidArgument(_))
+    ));
+}
@@ -0,0 +1,95 @@
+use libarchive2::{
+    AclType, ArchiveFormat, EntryAclExt, EntryMut, EntryMutAclExt, FileType, ReadArchive,
+    WriteArchive, Xattr,
+};
+use std::time::{Duration, SystemTime};
+
+const ACL: &str = "user::rw-,user:1234:r--,group::r--,mask::r--,other::---";
+
+fn original() -> Vec<u8> {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_growable_memory()
+        .unwrap();
+    let mut entry = EntryMut::new();
+    entry.set_pathname("file").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o640).unwrap();
+    entry.set_size(4);
+    entry.set_mtime(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    entry.set_uid(1234);
+    entry.set_uname("alice").unwrap();
+    entry.add_xattr("user.origin", b"test").unwrap();
+    entry.add_xattr("user.binary", &[0, 1, 2, 255]).unwrap();
+    entry.add_acl_text(ACL, AclType::Access).unwrap();
+    archive.write_header(&entry).unwrap();
+    archive.write_data_all(b"data").unwrap();
+    archive.finish_to_vec().unwrap()
+}
+
+fn unique(xattrs: Vec<Xattr>) -> Vec<(String, Vec<u8>)> {
+    let mut pairs: Vec<_> = xattrs.into_iter().map(|x| (x.name, x.value)).collect();
+    pairs.sort();
+    pairs.dedup();
+    pairs
+}
+
+#[test]
+fn test_clone_owned_preserves_xattrs_and_acl() {
+    let source = original();
+
+    let mut input = ReadArchive::open_memory(&source).unwrap();
+    let entry = input.next_entry().unwrap().unwrap();
+    let expected_acl = entry.acl_text().unwrap();
+    let expected_xattrs = entry.xattrs();
+    let copy = entry.clone_owned().unwrap();
+    let data = input.read_data_to_vec().unwrap();
+    // The copy owns its fields and outlives the reader
+    drop(input);
+    assert_eq!(copy.pathname().as_deref(), Some("file"));
+    assert_eq!(copy.as_entry().uname().as_deref(), Some("alice"));
+
+    let mut output = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_growable_memory()
+        .unwrap();
+    output.write_header(&copy).unwrap();
+    output.write_data_all(&data).unwrap();
+    let copied = output.finish_to_vec().unwrap();
+
+    let mut archive = ReadArchive::open_memory(&copied).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "file");
+    assert_eq!(entry.mode() & 0o777, 0o640);
+    assert_eq!(entry.uid(), Some(1234));
+    assert_eq!(entry.uname().as_deref(), Some("alice"));
+    assert_eq!(
+        entry.mtime(),
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+    assert_eq!(entry.acl_text().unwrap(), expected_acl);
+    // The pax writer records each xattr under both the LIBARCHIVE and SCHILY
+    // keywords, and the reader returns both copies
+    assert_eq!(unique(entry.xattrs()), unique(expected_xattrs));
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"data");
+}
+
+#[test]
+fn test_from_entry_is_independent() {
+    let source = original();
+    let mut input = ReadArchive::open_memory(&source).unwrap();
+    let entry = input.next_entry().unwrap().unwrap();
+    let ino = entry.ino();
+    let xattrs = entry.xattrs();
+    assert!(!xattrs.is_empty());
+
+    let mut copy = EntryMut::from_entry(&entry).unwrap();
+    assert_eq!(copy.as_entry().ino(), ino);
+    copy.set_pathname("renamed").unwrap();
+    copy.clear_xattrs();
+
+    assert_eq!(entry.pathname().unwrap(), "file");
+    assert_eq!(entry.xattrs(), xattrs);
+    assert_eq!(copy.pathname().as_deref(), Some("renamed"));
+    assert!(copy.as_entry().xattrs().is_empty());
+}
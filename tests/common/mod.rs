@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+/// Whether the environment locale can represent converted names
+///
+/// libarchive converts names to the locale's charset, so in the C locale a
+/// non-ASCII name can't be converted at all.
+pub fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .is_some_and(|v| {
+            let v = v.to_lowercase();
+            v.contains("utf-8") || v.contains("utf8")
+        })
+}
+
+/// A uid that `chown` is certain to refuse for this process
+///
+/// Without root that is any other user. Root can only be refused an id its
+/// user namespace doesn't map, so this is `None` when every id is mapped.
+#[cfg(unix)]
+pub fn unassignable_uid() -> Option<u32> {
+    // SAFETY: geteuid has no preconditions
+    if unsafe { libc::geteuid() } != 0 {
+        return Some(0);
+    }
+    let map = std::fs::read_to_string("/proc/self/uid_map").ok()?;
+    let ranges: Vec<(u64, u64)> = map
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<u64> = line
+                .split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            (fields.len() == 3).then(|| (fields[0], fields[0] + fields[2]))
+        })
+        .collect();
+    // The first id past a mapped range is the only place a gap can start;
+    // u32::MAX is chown's "leave unchanged" and doesn't count
+    std::iter::once(0)
+        .chain(ranges.iter().map(|&(_, end)| end))
+        .filter(|&id| id < u32::MAX as u64)
+        .find(|id| !ranges.iter().any(|(start, end)| (start..end).contains(&id)))
+        .map(|id| id as u32)
+}
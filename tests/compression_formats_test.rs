@@ -0,0 +1,192 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Every variant, plus the external program it depends on (if any)
+///
+/// The exhaustive match makes this fail to compile when a variant is added
+/// without being covered here.
+fn external_program(compression: CompressionFormat) -> Option<&'static str> {
+    match compression {
+        CompressionFormat::None
+        | CompressionFormat::Gzip
+        | CompressionFormat::Bzip2
+        | CompressionFormat::Xz
+        | CompressionFormat::Zstd
+        | CompressionFormat::Lz4
+        | CompressionFormat::Compress
+        | CompressionFormat::UuEncode
+        | CompressionFormat::B64Encode
+        | CompressionFormat::Lzip
+        | CompressionFormat::Lzma => None,
+        CompressionFormat::Lrzip => Some("lrzip"),
+        CompressionFormat::Lzop => Some("lzop"),
+        CompressionFormat::Grzip => Some("grzip"),
+    }
+}
+
+const ALL: [CompressionFormat; 14] = [
+    CompressionFormat::None,
+    CompressionFormat::Gzip,
+    CompressionFormat::Bzip2,
+    CompressionFormat::Xz,
+    CompressionFormat::Zstd,
+    CompressionFormat::Lz4,
+    CompressionFormat::Compress,
+    CompressionFormat::UuEncode,
+    CompressionFormat::B64Encode,
+    CompressionFormat::Lzip,
+    CompressionFormat::Lzma,
+    CompressionFormat::Lrzip,
+    CompressionFormat::Lzop,
+    CompressionFormat::Grzip,
+];
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+fn write_tar(path: &Path, compression: CompressionFormat) -> libarchive2::Result<()> {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(compression)
+        .open_file(path)?;
+    archive.add_file("hello.txt", b"Hello, filters!")?;
+    archive.finish()?;
+    Ok(())
+}
+
+#[test]
+fn test_every_compression_format_roundtrips() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for compression in ALL {
+        let path = temp_dir
+            .path()
+            .join(format!("test.tar.{}", compression.extension()));
+        let result = write_tar(&path, compression);
+
+        if let Some(program) = external_program(compression)
+            && !in_path(program)
+        {
+            match result {
+                Err(Error::InvalidArgument(msg)) => {
+                    assert!(msg.contains(&format!("{:?}", compression)), "{msg}");
+                    assert!(msg.contains(program), "{msg}");
+                }
+                other => panic!(
+                    "{:?}: expected InvalidArgument, got {:?}",
+                    compression, other
+                ),
+            }
+            continue;
+        }
+        result.unwrap_or_else(|e| panic!("{:?}: {}", compression, e));
+
+        let mut archive = ReadArchive::open(&path).unwrap();
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "hello.txt", "{:?}", compression);
+        assert_eq!(
+            archive.read_data_to_vec().unwrap(),
+            b"Hello, filters!",
+            "{:?}",
+            compression
+        );
+        assert_eq!(
+            archive.filters().is_empty(),
+            compression == CompressionFormat::None,
+            "{:?}: {:?}",
+            compression,
+            archive.filters()
+        );
+    }
+}
+
+#[test]
+fn test_support_filter_reads_each_format() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for compression in ALL {
+        if external_program(compression).is_some_and(|program| !in_path(program)) {
+            continue;
+        }
+        let path = temp_dir
+            .path()
+            .join(format!("explicit.tar.{}", compression.extension()));
+        write_tar(&path, compression).unwrap();
+
+        let mut archive = ReadArchive::new().unwrap();
+        archive
+            .support_filter(compression)
+            .unwrap_or_else(|e| panic!("{:?}: {}", compression, e));
+        archive.support_format_all().unwrap();
+        let mut archive = archive.open_file(&path).unwrap();
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "hello.txt", "{:?}", compression);
+        assert_eq!(archive.read_data_to_vec().unwrap(), b"Hello, filters!");
+    }
+}
+
+#[test]
+fn test_b64encode_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.b64");
+    write_tar(&path, CompressionFormat::B64Encode).unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    assert!(data.starts_with(b"begin-base64 "));
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.filters(), vec!["uu".to_string()]);
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"Hello, filters!");
+}
+
+#[test]
+fn test_lzip_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.lz");
+    write_tar(&path, CompressionFormat::Lzip).unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(&data[..4], b"LZIP");
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.filters(), vec!["lzip".to_string()]);
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"Hello, filters!");
+}
+
+#[test]
+fn test_lzma_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.lzma");
+    write_tar(&path, CompressionFormat::Lzma).unwrap();
+
+    // lzma-alone streams start with the properties byte of the default
+    // lc=3, lp=0, pb=2 settings
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data[0], 0x5d);
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.filters(), vec!["lzma".to_string()]);
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"Hello, filters!");
+
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_filter(CompressionFormat::Lzma).unwrap();
+}
+
+#[test]
+fn test_support_external_filters() {
+    let mut archive = ReadArchive::new().unwrap();
+    for compression in [
+        CompressionFormat::Lrzip,
+        CompressionFormat::Lzop,
+        CompressionFormat::Grzip,
+    ] {
+        archive.support_filter(compression).unwrap();
+    }
+}
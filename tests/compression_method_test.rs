@@ -0,0 +1,47 @@
+use libarchive2::{ArchiveFormat, ReadArchive, WriteArchive};
+
+const FIXTURE_DATA: &[u8] = b"Hello from a legacy archive!\n";
+
+fn method_names(path: &str) -> Vec<Option<String>> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut methods = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        methods.push(entry.compression_method_name());
+        let data = archive.read_data_to_vec().unwrap();
+        assert_eq!(data, FIXTURE_DATA.repeat(4));
+    }
+    methods
+}
+
+#[test]
+fn test_cab_compression_method() {
+    let methods = method_names("tests/fixtures/mszip.cab");
+    assert_eq!(methods, vec![Some("MSZIP".to_string())]);
+}
+
+#[test]
+fn test_lha_compression_method() {
+    let methods = method_names("tests/fixtures/stored.lzh");
+    assert_eq!(methods, vec![Some("-lh0-".to_string())]);
+}
+
+#[test]
+fn test_compression_method_not_reported() {
+    let mut buffer = vec![0u8; 16 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        archive.add_file("file.txt", b"data").unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buffer[..used]).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.compression_method_name(), None);
+
+    let entry = libarchive2::EntryMut::new();
+    assert_eq!(entry.as_entry().compression_method_name(), None);
+}
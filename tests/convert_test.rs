@@ -0,0 +1,208 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, ConvertOptions, ConvertStats, EntryAclExt, EntryMut,
+    EntryMutAclExt, FileType, ReadArchive, WriteArchive,
+};
+
+fn write_archive(
+    format: ArchiveFormat,
+    compression: CompressionFormat,
+    build: impl FnOnce(&mut WriteArchive),
+) -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .compression(compression)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        build(&mut archive);
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn convert_to(
+    data: &[u8],
+    format: ArchiveFormat,
+    compression: CompressionFormat,
+    options: ConvertOptions,
+) -> (Vec<u8>, ConvertStats) {
+    let mut src = ReadArchive::open_memory(data).unwrap();
+    let mut stats = None;
+    let out = write_archive(format, compression, |dst| {
+        stats = Some(libarchive2::convert(&mut src, dst, options).unwrap());
+    });
+    (out, stats.unwrap())
+}
+
+/// (pathname, file type, permissions, symlink target, data) of an entry
+type EntrySummary = (String, FileType, u32, Option<String>, Vec<u8>);
+
+fn read_back(data: &[u8]) -> Vec<EntrySummary> {
+    let mut archive = ReadArchive::open_memory(data).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let info = (
+            entry.pathname().unwrap(),
+            entry.file_type(),
+            entry.mode(),
+            entry.symlink(),
+        );
+        let contents = archive.read_data_to_vec().unwrap();
+        entries.push((info.0, info.1, info.2, info.3, contents));
+    }
+    entries
+}
+
+#[test]
+fn test_zip_to_tar_keeps_symlink_and_permissions() {
+    let zip = write_archive(ArchiveFormat::Zip, CompressionFormat::None, |archive| {
+        let mut dir = EntryMut::new();
+        dir.set_pathname("bin/").unwrap();
+        dir.set_file_type(FileType::Directory);
+        dir.set_perm(0o750).unwrap();
+        archive.write_header(&dir).unwrap();
+
+        let mut tool = EntryMut::new();
+        tool.set_pathname("bin/tool").unwrap();
+        tool.set_file_type(FileType::RegularFile);
+        tool.set_perm(0o755).unwrap();
+        tool.set_size(7);
+        archive.write_header(&tool).unwrap();
+        archive.write_data(b"#!/bin/").unwrap();
+
+        archive.add_symlink("tool-link", "bin/tool").unwrap();
+    });
+
+    let (tar, stats) = convert_to(
+        &zip,
+        ArchiveFormat::TarPax,
+        CompressionFormat::Zstd,
+        ConvertOptions::new(),
+    );
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.skipped, 0);
+    assert_eq!(stats.bytes, 7);
+
+    let entries = read_back(&tar);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].0.trim_end_matches('/'), "bin");
+    assert_eq!(entries[0].1, FileType::Directory);
+    assert_eq!(entries[0].2, 0o750);
+    assert_eq!(entries[1].0, "bin/tool");
+    assert_eq!(entries[1].2, 0o755);
+    assert_eq!(entries[1].4, b"#!/bin/");
+    assert_eq!(entries[2].0, "tool-link");
+    assert_eq!(entries[2].1, FileType::SymbolicLink);
+    assert_eq!(entries[2].3.as_deref(), Some("bin/tool"));
+}
+
+#[test]
+fn test_tar_conversion_keeps_xattrs_and_acls() {
+    let tar = write_archive(ArchiveFormat::TarPax, CompressionFormat::None, |archive| {
+        let mut entry = EntryMut::new();
+        entry.set_pathname("file").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o640).unwrap();
+        entry.set_size(4);
+        entry.add_xattr("user.origin", b"test").unwrap();
+        entry
+            .add_acl_text(
+                "user::rw-,user:1234:r--,group::r--,mask::r--,other::---",
+                libarchive2::AclType::Access,
+            )
+            .unwrap();
+        archive.write_header(&entry).unwrap();
+        archive.write_data(b"data").unwrap();
+    });
+
+    let (converted, _) = convert_to(
+        &tar,
+        ArchiveFormat::TarPax,
+        CompressionFormat::Gzip,
+        ConvertOptions::new(),
+    );
+
+    let mut archive = ReadArchive::open_memory(&converted).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(
+        entry
+            .xattrs()
+            .iter()
+            .any(|x| x.name == "user.origin" && x.value == b"test")
+    );
+    assert!(entry.has_acl());
+    assert!(entry.acl_text().unwrap().contains("user:1234:r--"));
+}
+
+#[test]
+fn test_filter_and_rename() {
+    let tar = write_archive(ArchiveFormat::TarPax, CompressionFormat::None, |archive| {
+        archive.add_file("src/main.c", b"int main;").unwrap();
+        archive.add_file("src/main.o", b"\x7fELF").unwrap();
+        archive.add_file("src/util.o", b"\x7fELF").unwrap();
+        archive.add_hardlink("src/alias.c", "src/main.c").unwrap();
+    });
+
+    let mut seen = Vec::new();
+    let options = ConvertOptions::new()
+        .filter(|entry| {
+            let path = entry.pathname().unwrap_or_default();
+            seen.push(path.clone());
+            !path.ends_with(".o")
+        })
+        .rename(|path| format!("release/{}", path));
+    let (out, stats) = convert_to(&tar, ArchiveFormat::TarPax, CompressionFormat::Xz, options);
+
+    assert_eq!(seen.len(), 4);
+    assert_eq!(
+        stats,
+        ConvertStats {
+            entries: 2,
+            skipped: 2,
+            bytes: 9,
+        }
+    );
+
+    let mut archive = ReadArchive::open_memory(&out).unwrap();
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        names.push((entry.pathname().unwrap(), entry.hardlink()));
+    }
+    assert_eq!(
+        names,
+        vec![
+            ("release/src/main.c".to_string(), None),
+            (
+                "release/src/alias.c".to_string(),
+                Some("release/src/main.c".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_error_names_entry() {
+    let tar = write_archive(ArchiveFormat::TarPax, CompressionFormat::None, |archive| {
+        archive.add_file("ok.txt", b"fine").unwrap();
+        let mut entry = EntryMut::new();
+        entry.set_pathname("big-uid.txt").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o644).unwrap();
+        entry.set_uid(10_000_000);
+        entry.set_size(0);
+        archive.write_header(&entry).unwrap();
+    });
+
+    let mut src = ReadArchive::open_memory(&tar).unwrap();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut dst = WriteArchive::new()
+        .format(ArchiveFormat::TarUstar)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    let err = libarchive2::convert(&mut src, &mut dst, ConvertOptions::new()).unwrap_err();
+    assert!(err.to_string().contains("big-uid.txt"), "{err}");
+}
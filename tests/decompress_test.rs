@@ -0,0 +1,87 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, Error, WriteArchive, decompress_file, decompress_reader,
+};
+use std::io::Read;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn sample() -> Vec<u8> {
+    (0..200_000u32)
+        .flat_map(|i| format!("line {}\n", i).into_bytes())
+        .collect()
+}
+
+/// Compress `data` as a single raw stream
+fn compress(path: &Path, compression: CompressionFormat, data: &[u8]) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Raw)
+        .compression(compression)
+        .open_file(path)
+        .unwrap();
+    archive.add_file("data", data).unwrap();
+    archive.finish().unwrap();
+}
+
+#[test]
+fn test_decompress_zstd_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("data.zst");
+    let data = sample();
+    compress(&path, CompressionFormat::Zstd, &data);
+    assert!(std::fs::metadata(&path).unwrap().len() < data.len() as u64);
+
+    assert!(decompress_file(&path).unwrap() == data);
+}
+
+#[test]
+fn test_decompress_xz_reader() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("data.xz");
+    let data = sample();
+    compress(&path, CompressionFormat::Xz, &data);
+
+    let mut reader = decompress_reader(std::fs::File::open(&path).unwrap()).unwrap();
+    let mut first = [0u8; 7];
+    reader.read_exact(&mut first).unwrap();
+    assert_eq!(&first, b"line 0\n");
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest == data[first.len()..]);
+}
+
+#[test]
+fn test_decompress_empty_input() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("empty");
+    std::fs::write(&path, b"").unwrap();
+    assert!(decompress_file(&path).unwrap().is_empty());
+
+    let mut reader = decompress_reader(std::io::empty()).unwrap();
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents).unwrap();
+    assert!(contents.is_empty());
+}
+
+#[test]
+fn test_decompress_rejects_archive() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.tar.gz");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("a.txt", b"alpha").unwrap();
+    archive.add_file("b.txt", b"bravo").unwrap();
+    archive.finish().unwrap();
+
+    match decompress_file(&path) {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains("ReadArchive"), "{}", msg),
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+    let file = std::fs::File::open(&path).unwrap();
+    assert!(matches!(
+        decompress_reader(file),
+        Err(Error::InvalidArgument(_))
+    ));
+}
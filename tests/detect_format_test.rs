@@ -0,0 +1,144 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, EncryptionStatus, WriteArchive, detect_format,
+    detect_format_file,
+};
+
+fn build(
+    format: ArchiveFormat,
+    compression: CompressionFormat,
+    passphrase: Option<&str>,
+) -> Vec<u8> {
+    let mut builder = WriteArchive::new().format(format).compression(compression);
+    if let Some(passphrase) = passphrase {
+        builder = builder.passphrase(passphrase);
+    }
+    let mut archive = builder.open_growable_memory().unwrap();
+    archive.add_file("hello.txt", b"Hello, world!").unwrap();
+    archive.finish_to_vec().unwrap()
+}
+
+fn is_tar(format: Option<ArchiveFormat>) -> bool {
+    format.is_some_and(|format| format.extension() == "tar")
+}
+
+#[test]
+fn test_detect_compressed_tar() {
+    for compression in [
+        CompressionFormat::None,
+        CompressionFormat::Gzip,
+        CompressionFormat::Bzip2,
+        CompressionFormat::Xz,
+        CompressionFormat::Zstd,
+    ] {
+        let detected = detect_format(&build(ArchiveFormat::TarPax, compression, None)).unwrap();
+        assert!(is_tar(detected.format), "{compression:?}: {detected:?}");
+        let expected: &[CompressionFormat] = match compression {
+            CompressionFormat::None => &[],
+            _ => std::slice::from_ref(&compression),
+        };
+        assert_eq!(detected.filters, expected);
+        assert_eq!(detected.encrypted, EncryptionStatus::No);
+    }
+}
+
+#[test]
+fn test_detect_zip_and_7z() {
+    let detected =
+        detect_format(&build(ArchiveFormat::Zip, CompressionFormat::None, None)).unwrap();
+    assert_eq!(detected.format, Some(ArchiveFormat::Zip));
+    assert!(detected.filters.is_empty());
+    assert_eq!(detected.encrypted, EncryptionStatus::No);
+
+    let detected = detect_format(&build(
+        ArchiveFormat::SevenZip,
+        CompressionFormat::None,
+        None,
+    ))
+    .unwrap();
+    assert_eq!(detected.format, Some(ArchiveFormat::SevenZip));
+}
+
+#[test]
+fn test_detect_encrypted_zip() {
+    let data = build(ArchiveFormat::Zip, CompressionFormat::None, Some("secret"));
+    let detected = detect_format(&data).unwrap();
+    assert_eq!(detected.format, Some(ArchiveFormat::Zip));
+    assert_eq!(detected.encrypted, EncryptionStatus::Yes);
+}
+
+#[test]
+fn test_detect_compressed_non_archive() {
+    let mut writer = WriteArchive::new()
+        .format(ArchiveFormat::Raw)
+        .compression(CompressionFormat::Xz)
+        .open_growable_memory()
+        .unwrap();
+    writer.add_file("notes.txt", b"just some text").unwrap();
+    let data = writer.finish_to_vec().unwrap();
+
+    let detected = detect_format(&data).unwrap();
+    assert_eq!(detected.format, None);
+    assert_eq!(detected.filters, [CompressionFormat::Xz]);
+    assert_eq!(detected.encrypted, EncryptionStatus::Unknown);
+
+    let detected = detect_format(b"").unwrap();
+    assert_eq!(detected.format, None);
+    assert!(detected.filters.is_empty());
+}
+
+#[test]
+fn test_detect_garbage_never_panics() {
+    let mut state = 0x9e37_79b9_u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    let signatures: [&[u8]; 6] = [
+        b"",
+        b"\x1f\x8b",
+        b"PK\x03\x04",
+        b"7z\xbc\xaf\x27\x1c",
+        b"\xfd7zXZ\x00",
+        b"070701",
+    ];
+    for round in 0..300 {
+        // Some inputs start with a real signature so the bidders get further
+        let mut data = signatures[round % signatures.len()].to_vec();
+        let len = next() as usize % 4096;
+        data.extend((0..len).map(|_| next() as u8));
+        let _ = detect_format(&data);
+    }
+}
+
+#[test]
+fn test_detect_format_file_reads_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.tar.gz");
+
+    // Incompressible data so the file is well past the prefix that is read
+    let mut state = 0x2545_f491_u32;
+    let data: Vec<u8> = (0..3 * 1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("random.bin", &data).unwrap();
+    archive.finish().unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > 2 * 1024 * 1024);
+
+    let detected = detect_format_file(&path).unwrap();
+    assert!(is_tar(detected.format), "{detected:?}");
+    assert_eq!(detected.filters, [CompressionFormat::Gzip]);
+
+    assert!(detect_format_file(dir.path().join("missing")).is_err());
+}
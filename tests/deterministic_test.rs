@@ -0,0 +1,114 @@
+use std::time::{Duration, SystemTime};
+
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, EntryMut, FileType, ReadArchive, WriteArchive,
+};
+
+fn epoch_plus(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Build a small archive whose entries carry metadata that differs per `seed`
+fn build(
+    format: ArchiveFormat,
+    compression: CompressionFormat,
+    seed: u64,
+    configure: impl FnOnce(WriteArchive) -> WriteArchive,
+) -> Vec<u8> {
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = configure(
+            WriteArchive::new()
+                .format(format)
+                .compression(compression)
+                .deterministic(true),
+        )
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+
+        let mut entry = EntryMut::new();
+        entry.set_pathname("data.txt").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o644).unwrap();
+        entry.set_size(5);
+        entry.set_mtime(epoch_plus(1_600_000_000 + seed));
+        entry.set_atime(epoch_plus(1_700_000_000 + seed));
+        entry.set_ctime(epoch_plus(1_650_000_000 + seed));
+        entry.set_uid(1000 + seed);
+        entry.set_gid(100 + seed);
+        entry.set_uname(&format!("user{seed}")).unwrap();
+        entry.set_gname(&format!("group{seed}")).unwrap();
+        entry.set_ino(4242 + seed);
+        entry.set_dev(7 + seed);
+        entry.set_nlink(1 + seed as u32);
+        archive.write_header(&entry).unwrap();
+        archive.write_data(b"hello").unwrap();
+
+        // add_file stamps the current time
+        archive.add_file("now.txt", b"now").unwrap();
+        archive.add_directory("dir").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_tar_is_reproducible() {
+    // Pin the timestamp so a SOURCE_DATE_EPOCH in the environment can't
+    // change it
+    fn pin(archive: WriteArchive<'_>) -> WriteArchive<'_> {
+        archive.source_date_epoch(SystemTime::UNIX_EPOCH)
+    }
+    let first = build(ArchiveFormat::TarPax, CompressionFormat::None, 1, pin);
+    let second = build(ArchiveFormat::TarPax, CompressionFormat::None, 2, pin);
+    assert_eq!(first, second);
+
+    let mut archive = ReadArchive::open_memory(&first).unwrap();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        assert_eq!(entry.mtime(), Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(entry.uid(), Some(0));
+        assert_eq!(entry.gid(), Some(0));
+        assert!(entry.uname().unwrap_or_default().is_empty());
+        assert!(entry.gname().unwrap_or_default().is_empty());
+    }
+}
+
+#[test]
+fn test_gzip_tar_is_reproducible() {
+    let first = build(ArchiveFormat::TarPax, CompressionFormat::Gzip, 1, |a| a);
+    std::thread::sleep(Duration::from_millis(1100));
+    let second = build(ArchiveFormat::TarPax, CompressionFormat::Gzip, 2, |a| a);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_zip_is_reproducible() {
+    let first = build(ArchiveFormat::Zip, CompressionFormat::None, 1, |a| a);
+    let second = build(ArchiveFormat::Zip, CompressionFormat::None, 2, |a| a);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_deterministic_overrides_defaults() {
+    let fixed = epoch_plus(1_234_567_890);
+    let data = build(ArchiveFormat::TarPax, CompressionFormat::None, 1, |a| {
+        a.default_mtime(epoch_plus(42))
+            .default_uid(1000)
+            .default_gid(1000)
+            .default_uname("builder")
+            .default_gname("builders")
+            .source_date_epoch(fixed)
+    });
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut entries = 0;
+    while let Some(entry) = archive.next_entry().unwrap() {
+        assert_eq!(entry.mtime(), Some(fixed));
+        assert_eq!(entry.uid(), Some(0));
+        assert!(entry.uname().unwrap_or_default().is_empty());
+        entries += 1;
+    }
+    assert_eq!(entries, 3);
+}
@@ -0,0 +1,105 @@
+use libarchive2::{ArchiveFormat, DuplicatePolicy, Error, ReadArchive, WriteArchive};
+
+struct Outcome {
+    results: Vec<libarchive2::Result<()>>,
+    warnings: Vec<String>,
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+/// Add "a.txt" twice (plus a directory given with and without a trailing slash)
+/// and return the write results, warnings and archive contents
+fn write_duplicates(policy: DuplicatePolicy) -> Outcome {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let (results, warnings) = {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .duplicate_policy(policy)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        let results = vec![
+            archive.add_file("a.txt", b"first"),
+            archive.add_file("a.txt", b"second"),
+            archive.add_directory("dir"),
+            archive.add_directory("dir/"),
+        ];
+        let warnings = archive.warnings().to_vec();
+        archive.finish().unwrap();
+        (results, warnings)
+    };
+
+    let mut archive = ReadArchive::open_memory(&buffer[..used]).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        entries.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    Outcome {
+        results,
+        warnings,
+        entries,
+    }
+}
+
+fn names(entries: &[(String, Vec<u8>)]) -> Vec<&str> {
+    entries.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+#[test]
+fn test_duplicate_policy_allow() {
+    let Outcome {
+        results,
+        warnings,
+        entries,
+    } = write_duplicates(DuplicatePolicy::Allow);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert!(warnings.is_empty());
+    assert_eq!(names(&entries), ["a.txt", "a.txt", "dir/", "dir/"]);
+    assert_eq!(entries[1].1, b"second");
+}
+
+#[test]
+fn test_duplicate_policy_warn() {
+    let Outcome {
+        results,
+        warnings,
+        entries,
+    } = write_duplicates(DuplicatePolicy::Warn);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("a.txt"));
+    assert!(warnings[1].contains("dir"));
+    assert_eq!(entries.len(), 4);
+}
+
+#[test]
+fn test_duplicate_policy_error() {
+    let Outcome {
+        results,
+        warnings,
+        entries,
+    } = write_duplicates(DuplicatePolicy::Error);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains("a.txt")),
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+    assert!(results[2].is_ok());
+    assert!(results[3].is_err());
+    assert!(warnings.is_empty());
+    assert_eq!(names(&entries), ["a.txt", "dir/"]);
+    assert_eq!(entries[0].1, b"first");
+}
+
+#[test]
+fn test_duplicate_policy_skip_later() {
+    let Outcome {
+        results,
+        warnings,
+        entries,
+    } = write_duplicates(DuplicatePolicy::SkipLater);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert!(warnings.is_empty());
+    assert_eq!(names(&entries), ["a.txt", "dir/"]);
+    assert_eq!(entries[0].1, b"first");
+}
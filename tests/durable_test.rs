@@ -0,0 +1,142 @@
+use libarchive2::{
+    AppendStrategy, ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive,
+};
+use std::path::Path;
+
+fn read_all(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        entries.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    entries
+}
+
+#[test]
+fn test_durable_file_is_complete_after_finish() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("durable.tar.gz");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .durable(true)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("a.txt", b"first").unwrap();
+    archive.add_file("b.txt", &vec![7u8; 100_000]).unwrap();
+    let stats = archive.finish().unwrap();
+
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().len(),
+        stats.compressed_bytes
+    );
+    assert_eq!(
+        read_all(&path),
+        vec![
+            ("a.txt".to_string(), b"first".to_vec()),
+            ("b.txt".to_string(), vec![7u8; 100_000]),
+        ]
+    );
+}
+
+#[test]
+fn test_durable_rewrite_append_is_complete_after_finish() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("append.tar.gz");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("a.txt", b"first").unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = WriteArchive::new()
+        .durable(true)
+        .append_to_file(&path)
+        .unwrap();
+    assert_eq!(archive.append_strategy(), Some(AppendStrategy::Rewrite));
+    archive.add_file("b.txt", b"second").unwrap();
+    archive.finish().unwrap();
+
+    assert_eq!(
+        read_all(&path),
+        vec![
+            ("a.txt".to_string(), b"first".to_vec()),
+            ("b.txt".to_string(), b"second".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_durable_memory_sink_finishes() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0usize;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .durable(true)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        archive.add_file("a.txt", b"first").unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buffer[..used]).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().as_deref(), Some("a.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_durable_fd_is_complete_after_finish() {
+    use std::os::unix::io::AsRawFd;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fd.tar");
+    let file = std::fs::File::create(&path).unwrap();
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .durable(true)
+        .open_fd(file.as_raw_fd())
+        .unwrap();
+    archive.add_file("a.txt", b"first").unwrap();
+    archive.finish().unwrap();
+    drop(file);
+
+    assert_eq!(
+        read_all(&path),
+        vec![("a.txt".to_string(), b"first".to_vec())]
+    );
+}
+
+/// Pipes cannot be synced, so a durable finish must report the failed fsync
+#[cfg(unix)]
+#[test]
+fn test_durable_fd_reports_sync_errors() {
+    fn finish_into_pipe(durable: bool) -> libarchive2::Result<()> {
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for both ends of the pipe
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let result = (|| {
+            let mut archive = WriteArchive::new()
+                .format(ArchiveFormat::TarPax)
+                .durable(durable)
+                .open_fd(fds[1])?;
+            archive.add_file("a.txt", b"first")?;
+            archive.finish().map(|_| ())
+        })();
+        // SAFETY: both ends were opened above and are no longer used
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        result
+    }
+
+    finish_into_pipe(false).unwrap();
+    let err = finish_into_pipe(true).unwrap_err();
+    assert!(matches!(err, Error::Io(_)), "{err:?}");
+}
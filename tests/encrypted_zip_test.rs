@@ -0,0 +1,163 @@
+use libarchive2::{
+    ArchiveFormat, EncryptionStatus, Error, FormatOption, ReadArchive, WriteArchive, ZipEncryption,
+};
+use std::path::Path;
+
+const SECRET: &[u8] = b"This is secret information!";
+
+fn write_zip(path: &Path, passphrase: Option<&str>) {
+    let mut builder = WriteArchive::new().format(ArchiveFormat::Zip);
+    if let Some(passphrase) = passphrase {
+        builder = builder.passphrase(passphrase);
+    }
+    let mut archive = builder.open_file(path).unwrap();
+    archive.add_file("secret.txt", SECRET).unwrap();
+    archive.add_file("other.txt", b"more data").unwrap();
+    archive.finish().unwrap();
+}
+
+#[test]
+fn test_read_encrypted_zip_with_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.zip");
+    write_zip(&path, Some("correct horse"));
+
+    let mut archive = ReadArchive::open_with_passphrase(&path, "correct horse").unwrap();
+    assert!(!archive.has_data_block());
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(entry.is_encrypted());
+    // A passphrase alone selects WinZip AES, stored under method 99
+    assert_eq!(entry.zip_info().unwrap().method, Some(99));
+    assert_eq!(archive.has_encrypted_entries(), EncryptionStatus::Yes);
+    assert!(archive.has_data_block());
+    assert_eq!(archive.read_data_to_vec().unwrap(), SECRET);
+
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"more data");
+}
+
+#[test]
+fn test_read_encrypted_zip_wrong_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.zip");
+    write_zip(&path, Some("correct horse"));
+
+    let mut archive = ReadArchive::open_with_passphrase(&path, "battery staple").unwrap();
+    archive.next_entry().unwrap().unwrap();
+    match archive.read_data_to_vec() {
+        Err(Error::Archive { message, .. }) => {
+            assert!(message.to_lowercase().contains("passphrase"), "{message}")
+        }
+        other => panic!("expected a passphrase error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_add_passphrase_after_open_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.zip");
+    write_zip(&path, Some("correct horse"));
+
+    let mut archive = ReadArchive::open_with_passphrase(&path, "correct horse").unwrap();
+    assert!(archive.add_passphrase("too late").is_err());
+}
+
+#[test]
+fn test_has_encrypted_entries_unencrypted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("plain.zip");
+    write_zip(&path, None);
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(!entry.is_encrypted());
+    assert_eq!(archive.has_encrypted_entries(), EncryptionStatus::No);
+
+    // Formats without encryption support can't hold encrypted entries
+    let tar_path = dir.path().join("plain.tar");
+    let mut tar = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&tar_path)
+        .unwrap();
+    tar.add_file("a.txt", b"a").unwrap();
+    tar.finish().unwrap();
+    let mut archive = ReadArchive::open(&tar_path).unwrap();
+    archive.next_entry().unwrap();
+    assert_eq!(archive.has_encrypted_entries(), EncryptionStatus::No);
+}
+
+fn write_zip_with(path: &Path, encryption: ZipEncryption) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .passphrase("correct horse")
+        .format_option(FormatOption::ZipEncryption(encryption))
+        .open_file(path)
+        .unwrap();
+    archive.add_file("secret.txt", SECRET).unwrap();
+    archive.add_file("other.txt", b"more data").unwrap();
+    archive.finish().unwrap();
+}
+
+#[test]
+fn test_zip_encryption_round_trip() {
+    for encryption in [
+        ZipEncryption::ZipCrypt,
+        ZipEncryption::Aes128,
+        ZipEncryption::Aes256,
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("encrypted.zip");
+        write_zip_with(&path, encryption);
+
+        // The secret must not be stored in the clear
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!raw.windows(SECRET.len()).any(|w| w == SECRET));
+
+        let mut archive = ReadArchive::open_with_passphrase(&path, "correct horse").unwrap();
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert!(entry.is_encrypted(), "{:?}", encryption);
+        // WinZip AES entries are stored under method 99, with the real method
+        // in their AES extra field
+        assert_eq!(
+            entry.zip_info().unwrap().method == Some(99),
+            encryption != ZipEncryption::ZipCrypt,
+            "{:?}",
+            encryption
+        );
+        assert_eq!(
+            archive.read_data_to_vec().unwrap(),
+            SECRET,
+            "{:?}",
+            encryption
+        );
+        archive.next_entry().unwrap().unwrap();
+        assert_eq!(archive.read_data_to_vec().unwrap(), b"more data");
+    }
+}
+
+#[test]
+fn test_zip_aes256_wrong_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.zip");
+    write_zip_with(&path, ZipEncryption::Aes256);
+
+    let mut archive = ReadArchive::open_with_passphrase(&path, "battery staple").unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert!(archive.read_data_to_vec().is_err());
+}
+
+#[test]
+fn test_has_encrypted_entries_before_first_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.zip");
+    write_zip(&path, Some("correct horse"));
+
+    let archive = ReadArchive::new().unwrap();
+    assert_eq!(archive.has_encrypted_entries(), EncryptionStatus::Unknown);
+
+    let mut archive = ReadArchive::open_with_passphrase(&path, "correct horse").unwrap();
+    assert_ne!(archive.has_encrypted_entries(), EncryptionStatus::No);
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.has_encrypted_entries(), EncryptionStatus::Yes);
+}
@@ -0,0 +1,81 @@
+use libarchive2::{ArchiveFormat, FileType, OwnedEntry, ReadArchive, Result, WriteArchive};
+
+fn build_archive() -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_directory("docs").unwrap();
+        archive.add_file("docs/a.txt", b"alpha").unwrap();
+        archive.add_file("docs/b.md", b"bravo").unwrap();
+        archive.add_file("c.txt", b"charlie").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_entries_filter_map_collect() {
+    let data = build_archive();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let txt_files = archive
+        .entries()
+        .filter_map(|entry| match entry {
+            Ok(entry) if entry.file_type == FileType::RegularFile => {
+                entry.pathname.filter(|name| name.ends_with(".txt")).map(Ok)
+            }
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(txt_files, vec!["docs/a.txt", "c.txt"]);
+}
+
+#[test]
+fn test_entries_metadata() {
+    let data = build_archive();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let entries: Vec<OwnedEntry> = archive.entries().collect::<Result<_>>().unwrap();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[0].file_type, FileType::Directory);
+    assert_eq!(entries[3].pathname.as_deref(), Some("c.txt"));
+    assert_eq!(entries[3].size, Some(7));
+}
+
+#[test]
+fn test_entries_read_data_before_advancing() {
+    let data = build_archive();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let mut entries = archive.entries();
+    let mut contents = Vec::new();
+    while let Some(entry) = entries.next() {
+        let entry = entry.unwrap();
+        if entry.file_type == FileType::RegularFile {
+            let data = entries.archive().read_data_to_vec().unwrap();
+            contents.push((entry.pathname.unwrap(), data));
+        }
+    }
+    assert_eq!(contents.len(), 3);
+    assert_eq!(contents[1], ("docs/b.md".to_string(), b"bravo".to_vec()));
+}
+
+#[test]
+fn test_entries_stops_after_error() {
+    let mut data = build_archive();
+    // Corrupt the checksum of a header after the first entry
+    data[512 * 3 + 148] ^= 0x55;
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let results: Vec<_> = archive.entries().collect();
+    assert!(results.len() > 1);
+    assert!(results.last().unwrap().is_err());
+    assert!(results[..results.len() - 1].iter().all(|r| r.is_ok()));
+}
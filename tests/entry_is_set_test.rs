@@ -0,0 +1,93 @@
+use libarchive2::{ArchiveFormat, CallbackReader, EntryMut, FileType, ReadArchive, WriteArchive};
+use std::io::Cursor;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_unset_fields_are_none() {
+    let mut entry = EntryMut::new();
+    assert_eq!(entry.size_opt(), None);
+    #[allow(deprecated)]
+    let size = entry.size();
+    assert_eq!(size, 0);
+    assert_eq!(entry.mtime(), None);
+    assert_eq!(entry.as_entry().atime(), None);
+    assert_eq!(entry.as_entry().ctime(), None);
+    assert_eq!(entry.as_entry().birthtime(), None);
+
+    entry.set_size(0);
+    assert_eq!(entry.size_opt(), Some(0));
+    entry.unset_size();
+    assert_eq!(entry.size_opt(), None);
+
+    // The epoch itself is a real time, not a missing one
+    entry.set_mtime(SystemTime::UNIX_EPOCH);
+    assert_eq!(entry.mtime(), Some(SystemTime::UNIX_EPOCH));
+    entry.unset_mtime();
+    assert_eq!(entry.mtime(), None);
+
+    let when = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 500);
+    entry.set_atime(when);
+    entry.set_ctime(when);
+    entry.set_birthtime(when);
+    assert_eq!(entry.as_entry().atime(), Some(when));
+    entry.unset_atime();
+    entry.unset_ctime();
+    entry.unset_birthtime();
+    assert_eq!(entry.as_entry().atime(), None);
+    assert_eq!(entry.as_entry().ctime(), None);
+    assert_eq!(entry.as_entry().birthtime(), None);
+}
+
+#[test]
+fn test_clone_keeps_size_unset() {
+    let mut entry = EntryMut::new();
+    entry.set_pathname("file").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    assert_eq!(entry.as_entry().clone_owned().unwrap().size_opt(), None);
+
+    entry.set_size(0);
+    assert_eq!(entry.as_entry().clone_owned().unwrap().size_opt(), Some(0));
+}
+
+#[test]
+fn test_unknown_size_survives_zip_round_trip() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_growable_memory()
+        .unwrap();
+    for (name, known) in [("known.txt", true), ("unknown.txt", false)] {
+        let mut entry = EntryMut::new();
+        entry.set_pathname(name).unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o644).unwrap();
+        if known {
+            entry.set_size(5);
+        }
+        archive.write_header(&entry).unwrap();
+        archive.write_data_all(b"hello").unwrap();
+    }
+    let bytes = archive.finish_to_vec().unwrap();
+
+    // Without seeking the reader only sees the local headers, and libarchive's
+    // writer defers every size to the data descriptor, so none are known
+    let mut reader =
+        ReadArchive::open_callback(CallbackReader::new(Cursor::new(bytes.clone()))).unwrap();
+    let mut sizes = Vec::new();
+    while let Some(entry) = reader.next_entry().unwrap() {
+        sizes.push((entry.pathname().unwrap(), entry.size_opt()));
+        assert_eq!(reader.read_data_to_vec().unwrap(), b"hello");
+    }
+    assert_eq!(
+        sizes,
+        [
+            ("known.txt".to_string(), None),
+            ("unknown.txt".to_string(), None),
+        ]
+    );
+
+    // The central directory read from memory has every size
+    let mut reader = ReadArchive::open_memory(&bytes).unwrap();
+    while let Some(entry) = reader.next_entry().unwrap() {
+        assert_eq!(entry.size_opt(), Some(5));
+    }
+}
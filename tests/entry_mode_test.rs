@@ -0,0 +1,54 @@
+use libarchive2::{ArchiveFormat, EntryMut, FileType, ReadArchive, WriteArchive};
+
+#[test]
+fn test_mode_raw_includes_file_type() {
+    let mut entry = EntryMut::new();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o644).unwrap();
+
+    let view = entry.as_entry();
+    assert_eq!(view.mode(), 0o644);
+    assert_eq!(view.mode_raw(), 0o100644);
+}
+
+#[test]
+fn test_set_mode_sets_type_and_perm() {
+    let mut entry = EntryMut::new();
+    entry.set_mode(0o040755).unwrap();
+
+    assert_eq!(entry.file_type(), FileType::Directory);
+    assert_eq!(entry.perm(), 0o755);
+    assert_eq!(entry.as_entry().mode_raw(), 0o040755);
+
+    // Special bits survive as well
+    entry.set_mode(0o104755).unwrap();
+    assert_eq!(entry.file_type(), FileType::RegularFile);
+    assert_eq!(entry.perm(), 0o4755);
+}
+
+#[test]
+fn test_mode_raw_copy_verbatim() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut entry = EntryMut::new();
+        entry.set_pathname("bin/tool").unwrap();
+        entry.set_mode(0o102750).unwrap();
+        archive.write_header(&entry).unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.mode_raw(), 0o102750);
+    assert_eq!(entry.mode(), 0o2750);
+
+    let mut copy = EntryMut::new();
+    copy.set_mode(entry.mode_raw()).unwrap();
+    assert_eq!(copy.as_entry().mode_raw(), entry.mode_raw());
+}
@@ -0,0 +1,60 @@
+use libarchive2::{EntryMut, FileType};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_getters_reflect_setters() {
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut entry = EntryMut::new();
+    entry.set_pathname("dir/file.txt").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(42);
+    entry.set_perm(0o640).unwrap();
+    entry.set_uid(1000);
+    entry.set_gid(100);
+    entry.set_uname("alice").unwrap();
+    entry.set_gname("staff").unwrap();
+    entry.set_mtime(mtime);
+
+    assert_eq!(entry.pathname().as_deref(), Some("dir/file.txt"));
+    assert_eq!(
+        entry.pathname_bytes().as_deref(),
+        Some(&b"dir/file.txt"[..])
+    );
+    assert_eq!(entry.file_type(), FileType::RegularFile);
+    assert_eq!(entry.size_opt(), Some(42));
+    assert_eq!(entry.perm(), 0o640);
+    assert_eq!(entry.uid(), Some(1000));
+    assert_eq!(entry.gid(), Some(100));
+    assert_eq!(entry.uname().as_deref(), Some("alice"));
+    assert_eq!(entry.gname().as_deref(), Some("staff"));
+    assert_eq!(entry.mtime(), Some(mtime));
+    assert_eq!(entry.symlink(), None);
+    assert_eq!(entry.hardlink(), None);
+}
+
+#[test]
+fn test_getters_agree_with_as_entry() {
+    let mut entry = EntryMut::new();
+    entry.set_pathname("link").unwrap();
+    entry.set_file_type(FileType::SymbolicLink);
+    entry.set_symlink("target").unwrap();
+    entry.set_perm(0o777).unwrap();
+
+    let view = entry.as_entry();
+    assert_eq!(entry.pathname(), view.pathname());
+    assert_eq!(entry.file_type(), view.file_type());
+    assert_eq!(entry.size_opt(), view.size_opt());
+    assert_eq!(entry.perm(), view.mode());
+    assert_eq!(entry.uid(), view.uid());
+    assert_eq!(entry.mtime(), view.mtime());
+    assert_eq!(entry.symlink().as_deref(), Some("target"));
+    assert_eq!(entry.symlink(), view.symlink());
+}
+
+#[test]
+fn test_getters_on_fresh_entry() {
+    let entry = EntryMut::new();
+    assert_eq!(entry.pathname(), None);
+    assert_eq!(entry.size_opt(), None);
+    assert_eq!(entry.uid(), None);
+}
@@ -0,0 +1,84 @@
+use libarchive2::{ArchiveFormat, EntryMut, EntryMutAclExt, FileType, ReadArchive, WriteArchive};
+
+#[test]
+fn test_clear_resets_fields() {
+    let mut entry = EntryMut::new();
+    entry.set_pathname("old.txt").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(1234);
+    entry.set_uid(42);
+    entry.set_symlink("target").unwrap();
+
+    entry.clear();
+
+    assert_eq!(entry.pathname(), None);
+    assert_eq!(entry.size_opt(), None);
+    assert_eq!(entry.uid(), None);
+    assert_eq!(entry.symlink(), None);
+    assert_ne!(entry.as_entry().ino(), 0);
+}
+
+#[test]
+fn test_clear_reuse_across_headers() {
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut entry = EntryMut::new();
+
+        entry.set_pathname("big.bin").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_size(10);
+        entry.set_perm(0o600).unwrap();
+        entry.set_uname("alice").unwrap();
+        archive.write_header(&entry).unwrap();
+        archive.write_data(&[7u8; 10]).unwrap();
+
+        entry.clear();
+        entry.set_pathname("dir").unwrap();
+        entry.set_file_type(FileType::Directory);
+        entry.set_perm(0o755).unwrap();
+        archive.write_header(&entry).unwrap();
+
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let first = archive.next_entry().unwrap().unwrap();
+    assert_eq!(first.pathname().as_deref(), Some("big.bin"));
+    assert_eq!(first.size_opt(), Some(10));
+
+    let second = archive.next_entry().unwrap().unwrap();
+    assert_eq!(second.pathname().as_deref(), Some("dir/"));
+    assert_eq!(second.file_type(), FileType::Directory);
+    assert_eq!(second.size_opt(), Some(0));
+    assert_ne!(second.uname().as_deref(), Some("alice"));
+}
+
+#[test]
+fn test_try_clone_is_independent() {
+    let mut original = EntryMut::new();
+    original.set_pathname("a.txt").unwrap();
+    original.set_file_type(FileType::RegularFile);
+    original.set_size(5);
+    original.add_xattr("user.tag", b"v1").unwrap();
+
+    let mut copy = original.try_clone().unwrap();
+    assert_eq!(copy.pathname().as_deref(), Some("a.txt"));
+    assert_eq!(copy.size_opt(), Some(5));
+    assert_ne!(copy.as_entry().ino(), original.as_entry().ino());
+
+    copy.set_pathname("b.txt").unwrap();
+    copy.set_size(9);
+    assert_eq!(original.pathname().as_deref(), Some("a.txt"));
+    assert_eq!(original.size_opt(), Some(5));
+
+    // Each entry frees its own copy
+    drop(original);
+    assert_eq!(copy.pathname().as_deref(), Some("b.txt"));
+    assert_eq!(copy.size_opt(), Some(9));
+}
@@ -0,0 +1,127 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, EntryMut, Error, FileType, ReadArchive, WriteArchive,
+};
+use std::io::Write;
+
+fn regular(path: &str) -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname(path).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o644).unwrap();
+    entry
+}
+
+fn read_all(bytes: &[u8]) -> Vec<(String, i64, Vec<u8>)> {
+    let mut archive = ReadArchive::open_memory(bytes).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        let size = entry.size_opt().unwrap();
+        entries.push((name, size, archive.read_data_to_vec().unwrap()));
+    }
+    entries
+}
+
+#[test]
+fn test_streamed_entry_gets_final_size() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_growable_memory()
+        .unwrap();
+
+    let mut expected = Vec::new();
+    let mut writer = archive.start_entry_streaming(&regular("live.log")).unwrap();
+    for i in 0..10_000 {
+        let line = format!("line {i}\n");
+        writer.write_all(line.as_bytes()).unwrap();
+        expected.extend_from_slice(line.as_bytes());
+    }
+    assert_eq!(writer.bytes_written(), expected.len() as u64);
+    assert_eq!(writer.finish().unwrap(), expected.len() as u64);
+    archive.add_file("after.txt", b"after").unwrap();
+
+    let entries = read_all(&archive.finish_to_vec().unwrap());
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].0, "live.log");
+    assert_eq!(entries[0].1, expected.len() as i64);
+    assert!(entries[0].2 == expected);
+    assert_eq!(entries[1].2, b"after");
+}
+
+#[test]
+fn test_streamed_empty_entry_and_cpio() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::CpioNewc)
+        .open_growable_memory()
+        .unwrap();
+
+    // A size set beforehand is replaced
+    let mut entry = regular("empty");
+    entry.set_size(100);
+    archive
+        .start_entry_streaming(&entry)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut writer = archive.start_entry_streaming(&regular("data")).unwrap();
+    writer.write_all(b"streamed").unwrap();
+    writer.finish().unwrap();
+
+    let entries = read_all(&archive.finish_to_vec().unwrap());
+    assert_eq!(entries[0], ("empty".to_string(), 0, Vec::new()));
+    assert_eq!(entries[1], ("data".to_string(), 8, b"streamed".to_vec()));
+}
+
+#[test]
+fn test_dropped_writer_discards_entry() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_growable_memory()
+        .unwrap();
+
+    let mut writer = archive
+        .start_entry_streaming(&regular("abandoned"))
+        .unwrap();
+    writer.write_all(b"never written").unwrap();
+    drop(writer);
+    archive.add_file("kept.txt", b"kept").unwrap();
+
+    let entries = read_all(&archive.finish_to_vec().unwrap());
+    assert_eq!(entries, [("kept.txt".to_string(), 4, b"kept".to_vec())]);
+}
+
+#[test]
+fn test_zip_and_7z_stream() {
+    for format in [ArchiveFormat::Zip, ArchiveFormat::SevenZip] {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .open_growable_memory()
+            .unwrap();
+        let mut writer = archive.start_entry_streaming(&regular("file")).unwrap();
+        writer.write_all(b"spooled").unwrap();
+        writer.finish().unwrap();
+
+        let entries = read_all(&archive.finish_to_vec().unwrap());
+        assert_eq!(
+            entries,
+            [("file".to_string(), 7, b"spooled".to_vec())],
+            "{format:?}"
+        );
+    }
+}
+
+#[test]
+fn test_only_regular_files_stream() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_growable_memory()
+        .unwrap();
+
+    let mut entry = regular("dir");
+    entry.set_file_type(FileType::Directory);
+    assert!(matches!(
+        archive.start_entry_streaming(&entry),
+        Err(Error::InvalidArgument(_))
+    ));
+}
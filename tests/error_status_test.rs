@@ -0,0 +1,111 @@
+use libarchive2::{
+    ARCHIVE_EOF, ARCHIVE_FAILED, ARCHIVE_FATAL, ARCHIVE_OK, ARCHIVE_RETRY, ARCHIVE_WARN,
+    ArchiveFormat, Error, ReadArchive, Severity, WriteArchive,
+};
+
+fn tar_with_damaged_second_header() -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarUstar)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("one.txt", b"one").unwrap();
+        archive.add_file("two.txt", b"two").unwrap();
+        archive.add_file("three.txt", b"three").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    // Each file is a header block and one data block; break the second
+    // header's checksum
+    buf[1024 + 148] ^= 0x01;
+    buf
+}
+
+#[test]
+fn test_damaged_header_is_retryable() {
+    let data = tar_with_damaged_second_header();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut names = Vec::new();
+    let mut retries = 0;
+    loop {
+        match archive.next_entry() {
+            Ok(Some(entry)) => names.push(entry.pathname().unwrap()),
+            Ok(None) => break,
+            Err(e) => {
+                assert!(e.is_retryable(), "{e:?}");
+                assert!(!e.is_fatal());
+                assert_eq!(e.status(), Some(ARCHIVE_RETRY));
+                assert_eq!(e.severity(), Some(Severity::Retry));
+                retries += 1;
+                assert!(retries < 10);
+            }
+        }
+    }
+    // Both the damaged header and the orphaned data block after it are skipped
+    assert!(retries >= 1);
+    assert_eq!(names, vec!["one.txt", "three.txt"]);
+}
+
+#[test]
+fn test_garbage_is_fatal() {
+    let data = vec![0x55u8; 4096];
+    let err = match ReadArchive::open_memory(&data) {
+        Ok(mut archive) => archive.next_entry().map(|_| ()).unwrap_err(),
+        Err(e) => e,
+    };
+    assert!(err.is_fatal(), "{err:?}");
+    assert!(!err.is_retryable());
+    assert_eq!(err.status(), Some(ARCHIVE_FATAL));
+    assert_eq!(err.severity(), Some(Severity::Fatal));
+    assert!(!err.is_warning());
+    assert_eq!(err.code(), Some(ARCHIVE_FATAL));
+    if let Error::Archive { code, .. } = err {
+        assert_eq!(err.errno(), (code != 0).then_some(code));
+    }
+    assert!(matches!(
+        err,
+        Error::Archive {
+            status: ARCHIVE_FATAL,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_non_archive_errors_have_no_code() {
+    let err = Error::InvalidArgument("bad".to_string());
+    assert_eq!(err.code(), None);
+    assert_eq!(err.errno(), None);
+    assert_eq!(err.status(), None);
+    assert!(!err.is_fatal());
+    assert!(!err.is_retryable());
+    assert!(!err.is_warning());
+    assert_eq!(err.severity(), None);
+}
+
+#[test]
+fn test_severity_from_status() {
+    assert_eq!(Severity::from_status(ARCHIVE_OK), None);
+    assert_eq!(Severity::from_status(ARCHIVE_EOF), None);
+    assert_eq!(Severity::from_status(ARCHIVE_RETRY), Some(Severity::Retry));
+    assert_eq!(Severity::from_status(ARCHIVE_WARN), Some(Severity::Warn));
+    assert_eq!(
+        Severity::from_status(ARCHIVE_FAILED),
+        Some(Severity::Failed)
+    );
+    assert_eq!(Severity::from_status(ARCHIVE_FATAL), Some(Severity::Fatal));
+    assert_eq!(Severity::from_status(-1), Some(Severity::Fatal));
+}
+
+#[test]
+fn test_missing_file_reports_errno() {
+    let dir = tempfile::tempdir().unwrap();
+    let Err(err) = ReadArchive::open(dir.path().join("missing.tar")) else {
+        panic!("opened a missing file");
+    };
+    assert_eq!(err.code(), Some(ARCHIVE_FATAL));
+    assert_eq!(err.code(), err.status());
+    assert_eq!(err.errno(), Some(libc::ENOENT), "{err:?}");
+}
@@ -0,0 +1,230 @@
+use libarchive2::{
+    ArchiveFormat, CallbackReader, EntryMut, ExtractFlags, FileType, ReadArchive, WriteArchive,
+    WriteDisk,
+};
+use std::io::Cursor;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const MTIME: u64 = 1_600_000_000;
+
+/// Build a tar archive whose entries live under `root` (absolute paths), so
+/// extraction doesn't depend on the current working directory.
+fn build_archive(root: &Path) -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME);
+    let files: [(&str, FileType, u32, &[u8]); 4] = [
+        ("dir", FileType::Directory, 0o755, b""),
+        (
+            "dir/hello.txt",
+            FileType::RegularFile,
+            0o640,
+            b"Hello, extract!",
+        ),
+        (
+            "dir/script.sh",
+            FileType::RegularFile,
+            0o755,
+            b"#!/bin/sh\necho hi\n",
+        ),
+        ("big.bin", FileType::RegularFile, 0o600, &[7u8; 100_000]),
+    ];
+
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for (name, file_type, perm, data) in files {
+            let mut entry = EntryMut::new();
+            entry.set_pathname(root.join(name)).unwrap();
+            entry.set_file_type(file_type);
+            entry.set_perm(perm).unwrap();
+            entry.set_size(data.len() as i64);
+            entry.set_mtime(mtime);
+            archive.write_header(&entry).unwrap();
+            if !data.is_empty() {
+                archive.write_data(data).unwrap();
+            }
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn extract_manually(data: &[u8]) {
+    let mut archive = ReadArchive::open_memory(data).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::TIME | ExtractFlags::PERM)
+        .unwrap();
+
+    loop {
+        let mut new_entry = EntryMut::new();
+        match archive.next_entry().unwrap() {
+            Some(entry) => {
+                new_entry.set_pathname(entry.pathname().unwrap()).unwrap();
+                new_entry.set_file_type(entry.file_type());
+                if let Some(size) = entry.size_opt() {
+                    new_entry.set_size(size);
+                }
+                new_entry.set_perm(entry.mode()).unwrap();
+                new_entry.set_mtime(entry.mtime().unwrap());
+            }
+            None => break,
+        }
+        disk.write_header(&new_entry).unwrap();
+        let contents = archive.read_data_to_vec().unwrap();
+        if !contents.is_empty() {
+            disk.write_data(&contents).unwrap();
+        }
+        disk.finish_entry().unwrap();
+    }
+    disk.close().unwrap();
+}
+
+fn snapshot(root: &Path) -> Vec<(String, u32, SystemTime, Vec<u8>)> {
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut result = Vec::new();
+    for name in ["dir", "dir/hello.txt", "dir/script.sh", "big.bin"] {
+        let path = root.join(name);
+        let meta = std::fs::metadata(&path).unwrap();
+        #[cfg(unix)]
+        let mode = meta.permissions().mode() & 0o7777;
+        #[cfg(not(unix))]
+        let mode = 0;
+        let contents = if meta.is_file() {
+            std::fs::read(&path).unwrap()
+        } else {
+            Vec::new()
+        };
+        result.push((name.to_string(), mode, meta.modified().unwrap(), contents));
+    }
+    result
+}
+
+#[test]
+fn test_extract_current_matches_manual_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let fast_root = dir.path().join("fast");
+    let manual_root = dir.path().join("manual");
+
+    let data = build_archive(&fast_root);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::TIME | ExtractFlags::PERM)
+        .unwrap();
+    while archive.next_entry().unwrap().is_some() {
+        archive.extract_current(&mut disk).unwrap();
+    }
+    disk.close().unwrap();
+
+    extract_manually(&build_archive(&manual_root));
+
+    let fast = snapshot(&fast_root);
+    assert_eq!(fast, snapshot(&manual_root));
+    assert_eq!(fast[1].3, b"Hello, extract!");
+    assert_eq!(
+        fast[1].2,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME)
+    );
+    #[cfg(unix)]
+    {
+        assert_eq!(fast[1].1, 0o640);
+        assert_eq!(fast[2].1, 0o755);
+    }
+}
+
+#[test]
+fn test_extract_current_with_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("out");
+
+    let data = build_archive(&root);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    while archive.next_entry().unwrap().is_some() {
+        archive
+            .extract_current_with_flags(ExtractFlags::TIME | ExtractFlags::PERM)
+            .unwrap();
+    }
+
+    let files = snapshot(&root);
+    assert_eq!(files[3].3, vec![7u8; 100_000]);
+    assert_eq!(
+        files[3].2,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME)
+    );
+}
+
+#[test]
+fn test_extract_current_without_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_archive(&dir.path().join("out"));
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+
+    assert!(archive.extract_current(&mut disk).is_err());
+
+    while archive.next_entry().unwrap().is_some() {}
+    assert!(
+        archive
+            .extract_current_with_flags(ExtractFlags::TIME)
+            .is_err()
+    );
+}
+
+/// A zip whose entries were written without a size, so a streaming reader
+/// only learns it from the data descriptor after the data
+fn build_unsized_zip(root: &Path) -> Vec<u8> {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_growable_memory()
+        .unwrap();
+    let mut entry = EntryMut::new();
+    entry.set_pathname(root.join("streamed.txt")).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o644).unwrap();
+    archive.write_header(&entry).unwrap();
+    archive.write_data_all(b"streamed contents").unwrap();
+    archive.finish_to_vec().unwrap()
+}
+
+#[test]
+fn test_extract_current_streamed_zip_without_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_unsized_zip(dir.path());
+
+    let mut archive = ReadArchive::open_callback(CallbackReader::new(Cursor::new(data))).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        assert_eq!(entry.size_opt(), None);
+        archive.extract_current(&mut disk).unwrap();
+    }
+    disk.close().unwrap();
+
+    assert_eq!(
+        std::fs::read(dir.path().join("streamed.txt")).unwrap(),
+        b"streamed contents"
+    );
+}
+
+#[test]
+fn test_extract_to_dir_streamed_zip_without_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_unsized_zip(Path::new("out"));
+
+    let mut archive = ReadArchive::open_callback(CallbackReader::new(Cursor::new(data))).unwrap();
+    let stats = archive
+        .extract_to_dir(dir.path(), ExtractFlags::NONE)
+        .unwrap();
+
+    assert_eq!(stats.files, 1);
+    assert_eq!(stats.bytes, 17);
+    assert_eq!(
+        std::fs::read(dir.path().join("out/streamed.txt")).unwrap(),
+        b"streamed contents"
+    );
+}
@@ -0,0 +1,130 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, Error, ExtractFlags, FileType, ReadArchive, WriteArchive,
+};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const CONTENTS: &[u8] = b"#!/bin/sh\necho tool\n";
+
+fn mtime() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_500_000_000)
+}
+
+fn build_archive(name: &str) -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut entry = EntryMut::new();
+        entry.set_pathname(name).unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_size(CONTENTS.len() as i64);
+        entry.set_perm(0o750).unwrap();
+        entry.set_mtime(mtime());
+        archive.write_header(&entry).unwrap();
+        archive.write_data(CONTENTS).unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+/// Copy the fields the tests care about, renaming the entry
+fn copy_header(archive: &mut ReadArchive, pathname: &std::path::Path) -> EntryMut {
+    let entry = archive.next_entry().unwrap().unwrap();
+    let mut copy = EntryMut::new();
+    copy.set_pathname(pathname).unwrap();
+    copy.set_file_type(entry.file_type());
+    if let Some(size) = entry.size_opt() {
+        copy.set_size(size);
+    }
+    copy.set_perm(entry.mode()).unwrap();
+    copy.set_mtime(entry.mtime().unwrap());
+    copy
+}
+
+fn assert_extracted(path: &std::path::Path) {
+    assert_eq!(std::fs::read(path).unwrap(), CONTENTS);
+    let metadata = std::fs::metadata(path).unwrap();
+    assert_eq!(metadata.modified().unwrap(), mtime());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o750);
+    }
+}
+
+#[test]
+fn test_extract_entry_perm_time() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("renamed-tool");
+    let data = build_archive("bin/tool");
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let copy = copy_header(&mut archive, &target);
+    archive
+        .extract_entry(&copy.as_entry(), ExtractFlags::PERM | ExtractFlags::TIME)
+        .unwrap();
+
+    assert_extracted(&target);
+    // The caller's header is left as it was
+    assert_eq!(
+        copy.as_entry().pathname().unwrap(),
+        target.to_str().unwrap()
+    );
+}
+
+#[test]
+fn test_extract_entry_to() {
+    let dir = TempDir::new().unwrap();
+    let data = build_archive("bin/tool");
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let copy = copy_header(&mut archive, std::path::Path::new("bin/tool"));
+    archive
+        .extract_entry_to(
+            &copy.as_entry(),
+            dir.path(),
+            ExtractFlags::PERM | ExtractFlags::TIME,
+        )
+        .unwrap();
+
+    assert_extracted(&dir.path().join("bin/tool"));
+}
+
+#[test]
+fn test_extract_entry_to_rejects_escape() {
+    let dir = TempDir::new().unwrap();
+    let dest = dir.path().join("dest");
+    let data = build_archive("bin/tool");
+
+    for name in ["../escaped", "/tmp/absolute-escape"] {
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        let copy = copy_header(&mut archive, std::path::Path::new(name));
+        assert!(matches!(
+            archive.extract_entry_to(&copy.as_entry(), &dest, ExtractFlags::NONE),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+    assert!(!dir.path().join("escaped").exists());
+    assert!(!dest.exists());
+}
+
+#[test]
+fn test_extract_entry_without_current_entry() {
+    let dir = TempDir::new().unwrap();
+    let data = build_archive("bin/tool");
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname(dir.path().join("never")).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    assert!(matches!(
+        archive.extract_entry(&entry.as_entry(), ExtractFlags::NONE),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(!dir.path().join("never").exists());
+}
@@ -0,0 +1,166 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, Error, ExtractFlags, ReadArchive, WriteArchive,
+    extract_parallel,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn build(path: &Path) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(path)
+        .unwrap();
+    for d in 0..5 {
+        archive.add_directory(format!("tree/{}", d)).unwrap();
+        archive.add_directory(format!("tree/{}/nested", d)).unwrap();
+        for f in 0..40 {
+            let data = format!("{}/{}\n", d, f).repeat(f * 50 + 1);
+            archive
+                .add_file(format!("tree/{}/nested/{}.txt", d, f), data.as_bytes())
+                .unwrap();
+        }
+        archive
+            .add_symlink(format!("tree/{}/latest", d), "nested/39.txt")
+            .unwrap();
+        archive
+            .add_hardlink(
+                format!("tree/{}/first.txt", d),
+                &format!("tree/{}/nested/0.txt", d),
+            )
+            .unwrap();
+    }
+    // Replaced by a later entry with the same name
+    archive
+        .add_file("tree/0/nested/1.txt", b"second copy")
+        .unwrap();
+    archive.finish().unwrap();
+}
+
+/// Everything under `root` by relative path: file contents, symlink targets
+/// and directories
+fn snapshot(root: &Path) -> BTreeMap<String, String> {
+    let mut found = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.strip_prefix(root).unwrap().display().to_string();
+            let meta = std::fs::symlink_metadata(&path).unwrap();
+            let value = if meta.file_type().is_symlink() {
+                format!("-> {}", std::fs::read_link(&path).unwrap().display())
+            } else if meta.is_dir() {
+                pending.push(path);
+                "dir".to_string()
+            } else {
+                String::from_utf8(std::fs::read(&path).unwrap()).unwrap()
+            };
+            found.insert(name, value);
+        }
+    }
+    found
+}
+
+#[test]
+fn test_extract_parallel_matches_serial() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("tree.tar.gz");
+    build(&archive_path);
+
+    let serial = temp_dir.path().join("serial");
+    let serial_stats = ReadArchive::open(&archive_path)
+        .unwrap()
+        .extract_to_dir(&serial, ExtractFlags::TIME | ExtractFlags::PERM)
+        .unwrap();
+
+    let parallel = temp_dir.path().join("parallel");
+    let parallel_stats = extract_parallel(
+        &archive_path,
+        &parallel,
+        ExtractFlags::TIME | ExtractFlags::PERM,
+        4,
+    )
+    .unwrap();
+
+    assert_eq!(parallel_stats, serial_stats);
+    assert_eq!(parallel_stats.files, 201);
+    assert_eq!(parallel_stats.directories, 10);
+    assert_eq!(parallel_stats.symlinks, 5);
+    assert_eq!(parallel_stats.hardlinks, 5);
+    assert!(parallel_stats.stats.compressed_bytes > 0);
+    assert!(parallel_stats.stats.uncompressed_bytes > parallel_stats.stats.compressed_bytes);
+
+    let expected = snapshot(&serial);
+    assert_eq!(snapshot(&parallel), expected);
+    assert_eq!(expected["tree/0/nested/1.txt"], "second copy");
+    assert_eq!(expected["tree/3/latest"], "-> nested/39.txt");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let original = std::fs::metadata(parallel.join("tree/2/nested/0.txt")).unwrap();
+        let link = std::fs::metadata(parallel.join("tree/2/first.txt")).unwrap();
+        assert_eq!(original.ino(), link.ino());
+    }
+}
+
+#[test]
+fn test_extract_parallel_reports_first_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("escape.tar");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&archive_path)
+        .unwrap();
+    archive.add_file("ok.txt", b"fine").unwrap();
+    archive.add_file("../escape.txt", b"nope").unwrap();
+    archive.add_file("later.txt", b"never").unwrap();
+    archive.finish().unwrap();
+
+    let dest = temp_dir.path().join("out");
+    let result = extract_parallel(&archive_path, &dest, ExtractFlags::NONE, 2);
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    assert!(!temp_dir.path().join("escape.txt").exists());
+    assert!(!dest.join("later.txt").exists());
+}
+
+#[test]
+fn test_extract_parallel_no_overwrite_keeps_first_duplicate() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("dups.tar");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&archive_path)
+        .unwrap();
+    for i in 0..20 {
+        archive
+            .add_file(format!("file{}.txt", i), format!("first {}", i).as_bytes())
+            .unwrap();
+    }
+    for i in 0..20 {
+        archive
+            .add_file(format!("file{}.txt", i), format!("second {}", i).as_bytes())
+            .unwrap();
+    }
+    archive.finish().unwrap();
+
+    let serial = temp_dir.path().join("serial");
+    let serial_stats = ReadArchive::open(&archive_path)
+        .unwrap()
+        .extract_to_dir(&serial, ExtractFlags::NO_OVERWRITE)
+        .unwrap();
+
+    let parallel = temp_dir.path().join("parallel");
+    let stats = extract_parallel(&archive_path, &parallel, ExtractFlags::NO_OVERWRITE, 4).unwrap();
+
+    assert_eq!(stats, serial_stats);
+    assert_eq!(stats.files, 20);
+    assert_eq!(stats.skipped, 20);
+    assert_eq!(stats.warnings, 0);
+    assert_eq!(snapshot(&parallel), snapshot(&serial));
+    assert_eq!(
+        std::fs::read_to_string(parallel.join("file7.txt")).unwrap(),
+        "first 7"
+    );
+}
@@ -0,0 +1,215 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, Error, ExtractFlags, ExtractStats, FileType, ReadArchive, WriteArchive,
+};
+use std::path::Path;
+
+enum Item<'a> {
+    File(&'a str, &'a [u8]),
+    Dir(&'a str),
+    Symlink(&'a str, &'a str),
+    Hardlink(&'a str, &'a str),
+}
+
+fn build_archive(items: &[Item]) -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for item in items {
+            let mut entry = EntryMut::new();
+            let mut data: &[u8] = b"";
+            match *item {
+                Item::File(name, contents) => {
+                    entry.set_pathname(name).unwrap();
+                    entry.set_file_type(FileType::RegularFile);
+                    entry.set_size(contents.len() as i64);
+                    data = contents;
+                }
+                Item::Dir(name) => {
+                    entry.set_pathname(name).unwrap();
+                    entry.set_file_type(FileType::Directory);
+                }
+                Item::Symlink(name, target) => {
+                    entry.set_pathname(name).unwrap();
+                    entry.set_file_type(FileType::SymbolicLink);
+                    entry.set_symlink(target).unwrap();
+                }
+                Item::Hardlink(name, target) => {
+                    entry.set_pathname(name).unwrap();
+                    entry.set_file_type(FileType::RegularFile);
+                    entry.set_hardlink(target).unwrap();
+                }
+            }
+            entry.set_perm(0o755).unwrap();
+            archive.write_header(&entry).unwrap();
+            if !data.is_empty() {
+                archive.write_data(data).unwrap();
+            }
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn extract(data: &[u8], dest: &Path, flags: ExtractFlags) -> libarchive2::Result<ExtractStats> {
+    ReadArchive::open_memory(data)
+        .unwrap()
+        .extract_to_dir(dest, flags)
+}
+
+#[test]
+fn test_extract_to_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("new/out");
+    let data = build_archive(&[
+        Item::Dir("top"),
+        Item::File("top/a.txt", b"alpha"),
+        // Parent directories that have no entry of their own
+        Item::File("deep/nested/b.txt", b"bravo!"),
+        Item::Symlink("top/link", "a.txt"),
+        Item::Hardlink("top/hard.txt", "top/a.txt"),
+    ]);
+
+    let stats = extract(&data, &dest, ExtractFlags::PERM).unwrap();
+    assert_eq!(
+        stats,
+        ExtractStats {
+            files: 2,
+            directories: 1,
+            symlinks: 1,
+            hardlinks: 1,
+            other: 0,
+            bytes: 11,
+            skipped: 0,
+            warnings: 0,
+            stats: stats.stats,
+        }
+    );
+    assert_eq!(stats.entries(), 5);
+
+    assert_eq!(std::fs::read(dest.join("top/a.txt")).unwrap(), b"alpha");
+    assert_eq!(
+        std::fs::read(dest.join("deep/nested/b.txt")).unwrap(),
+        b"bravo!"
+    );
+    assert_eq!(std::fs::read(dest.join("top/hard.txt")).unwrap(), b"alpha");
+    #[cfg(unix)]
+    assert_eq!(
+        std::fs::read_link(dest.join("top/link")).unwrap(),
+        Path::new("a.txt")
+    );
+}
+
+#[test]
+fn test_extract_to_dir_rejects_escaping_paths() {
+    for name in ["../evil.txt", "ok/../../evil.txt", "/tmp/evil.txt"] {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        let data = build_archive(&[Item::File("fine.txt", b"ok"), Item::File(name, b"evil")]);
+
+        match extract(&data, &dest, ExtractFlags::NONE) {
+            Err(Error::InvalidArgument(message)) => assert!(message.contains(name), "{message}"),
+            other => panic!("expected an error for {name}, got {other:?}"),
+        }
+        assert!(dest.join("fine.txt").exists());
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_to_dir_rejects_non_utf8_dest() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join(std::ffi::OsStr::from_bytes(b"out-\xff"));
+    let data = build_archive(&[Item::File("a.txt", b"hello")]);
+
+    match extract(&data, &dest, ExtractFlags::NONE) {
+        Err(Error::InvalidArgument(message)) => assert!(message.contains("UTF-8"), "{message}"),
+        other => panic!("expected an error, got {other:?}"),
+    }
+    assert!(!dest.join("a.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_to_dir_refuses_symlinked_parent() {
+    let dir = tempfile::tempdir().unwrap();
+    let outside = dir.path().join("outside");
+    std::fs::create_dir(&outside).unwrap();
+    let data = build_archive(&[
+        Item::Symlink("escape", outside.to_str().unwrap()),
+        Item::File("escape/evil.txt", b"evil"),
+    ]);
+
+    assert!(extract(&data, &dir.path().join("out"), ExtractFlags::NONE).is_err());
+    assert!(!outside.join("evil.txt").exists());
+}
+
+#[test]
+fn test_extract_to_dir_rejects_escaping_hardlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build_archive(&[Item::Hardlink("link", "../../etc/passwd")]);
+
+    match extract(&data, &dir.path().join("out"), ExtractFlags::NONE) {
+        Err(Error::InvalidArgument(message)) => assert!(message.contains("link"), "{message}"),
+        other => panic!("expected an error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_extract_to_dir_no_overwrite() {
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("out");
+    let data = build_archive(&[Item::File("a.txt", b"new contents")]);
+
+    std::fs::create_dir_all(&dest).unwrap();
+    std::fs::write(dest.join("a.txt"), b"old").unwrap();
+
+    let stats = extract(&data, &dest, ExtractFlags::NO_OVERWRITE).unwrap();
+    assert_eq!((stats.files, stats.skipped), (0, 1));
+    assert_eq!(std::fs::read(dest.join("a.txt")).unwrap(), b"old");
+
+    let stats = extract(&data, &dest, ExtractFlags::NONE).unwrap();
+    assert_eq!((stats.files, stats.skipped), (1, 0));
+    assert_eq!(std::fs::read(dest.join("a.txt")).unwrap(), b"new contents");
+}
+
+#[test]
+fn test_extract_to_with_sparse_file() {
+    // GNU tar --sparse --format=pax archive holding data/ and a 1 MiB file with
+    // "START" at 0, "MIDDLE" at 512 KiB and "END" at the very end
+    let dir = tempfile::tempdir().unwrap();
+    let size = 1024 * 1024;
+
+    let dest = dir.path().join("out");
+    let stats = ReadArchive::open("tests/fixtures/sparse.tar.gz")
+        .unwrap()
+        .extract_to(&dest, ExtractFlags::PERM | ExtractFlags::SPARSE)
+        .unwrap();
+    assert_eq!(stats.entries(), 2);
+    // A gzip'd tar: the read totals cover the compressed input and the tar
+    // stream inside it
+    assert!(stats.stats.compressed_bytes > 0);
+    assert!(stats.stats.uncompressed_bytes > stats.stats.compressed_bytes);
+
+    let contents = std::fs::read(dest.join("data/sparse.bin")).unwrap();
+    assert_eq!(contents.len(), size);
+    assert_eq!(&contents[..5], b"START");
+    assert_eq!(&contents[512 * 1024..512 * 1024 + 6], b"MIDDLE");
+    assert_eq!(&contents[size - 3..], b"END");
+    assert!(contents[5..512 * 1024].iter().all(|&b| b == 0));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Holes are preserved rather than written out as zeros
+        let meta = std::fs::metadata(dest.join("data/sparse.bin")).unwrap();
+        assert!(meta.blocks() * 512 < size as u64);
+    }
+}
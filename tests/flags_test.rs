@@ -0,0 +1,122 @@
+use libarchive2::{ExtractFlags, ReadDiskFlags};
+
+#[test]
+fn test_extract_flags_match_libarchive() {
+    use libarchive2_sys as sys;
+
+    let flags = [
+        (ExtractFlags::OWNER, sys::ARCHIVE_EXTRACT_OWNER),
+        (ExtractFlags::PERM, sys::ARCHIVE_EXTRACT_PERM),
+        (ExtractFlags::TIME, sys::ARCHIVE_EXTRACT_TIME),
+        (
+            ExtractFlags::NO_OVERWRITE,
+            sys::ARCHIVE_EXTRACT_NO_OVERWRITE,
+        ),
+        (ExtractFlags::UNLINK, sys::ARCHIVE_EXTRACT_UNLINK),
+        (ExtractFlags::ACL, sys::ARCHIVE_EXTRACT_ACL),
+        (ExtractFlags::FFLAGS, sys::ARCHIVE_EXTRACT_FFLAGS),
+        (ExtractFlags::XATTR, sys::ARCHIVE_EXTRACT_XATTR),
+        (
+            ExtractFlags::SECURE_SYMLINKS,
+            sys::ARCHIVE_EXTRACT_SECURE_SYMLINKS,
+        ),
+        (
+            ExtractFlags::SECURE_NODOTDOT,
+            sys::ARCHIVE_EXTRACT_SECURE_NODOTDOT,
+        ),
+        (ExtractFlags::NO_AUTODIR, sys::ARCHIVE_EXTRACT_NO_AUTODIR),
+        (
+            ExtractFlags::NO_OVERWRITE_NEWER,
+            sys::ARCHIVE_EXTRACT_NO_OVERWRITE_NEWER,
+        ),
+        (ExtractFlags::SPARSE, sys::ARCHIVE_EXTRACT_SPARSE),
+        (
+            ExtractFlags::MAC_METADATA,
+            sys::ARCHIVE_EXTRACT_MAC_METADATA,
+        ),
+        (
+            ExtractFlags::NO_HFS_COMPRESSION,
+            sys::ARCHIVE_EXTRACT_NO_HFS_COMPRESSION,
+        ),
+        (
+            ExtractFlags::HFS_COMPRESSION_FORCED,
+            sys::ARCHIVE_EXTRACT_HFS_COMPRESSION_FORCED,
+        ),
+        (
+            ExtractFlags::SECURE_NOABSOLUTEPATHS,
+            sys::ARCHIVE_EXTRACT_SECURE_NOABSOLUTEPATHS,
+        ),
+        (
+            ExtractFlags::CLEAR_NOCHANGE_FFLAGS,
+            sys::ARCHIVE_EXTRACT_CLEAR_NOCHANGE_FFLAGS,
+        ),
+        (ExtractFlags::SAFE_WRITES, sys::ARCHIVE_EXTRACT_SAFE_WRITES),
+    ];
+    for (flag, value) in flags {
+        assert_eq!(flag.bits(), value as i32, "{flag:?}");
+    }
+    assert_eq!(ExtractFlags::NONE.bits(), 0);
+}
+
+#[test]
+fn test_read_disk_flags_match_libarchive() {
+    use libarchive2_sys as sys;
+
+    let flags = [
+        (
+            ReadDiskFlags::RESTORE_ATIME,
+            sys::ARCHIVE_READDISK_RESTORE_ATIME,
+        ),
+        (
+            ReadDiskFlags::HONOR_NODUMP,
+            sys::ARCHIVE_READDISK_HONOR_NODUMP,
+        ),
+        (
+            ReadDiskFlags::MAC_COPYFILE,
+            sys::ARCHIVE_READDISK_MAC_COPYFILE,
+        ),
+        (
+            ReadDiskFlags::NO_TRAVERSE_MOUNTS,
+            sys::ARCHIVE_READDISK_NO_TRAVERSE_MOUNTS,
+        ),
+        (ReadDiskFlags::NO_XATTR, sys::ARCHIVE_READDISK_NO_XATTR),
+        (ReadDiskFlags::NO_ACL, sys::ARCHIVE_READDISK_NO_ACL),
+        (ReadDiskFlags::NO_FFLAGS, sys::ARCHIVE_READDISK_NO_FFLAGS),
+        (ReadDiskFlags::NO_SPARSE, sys::ARCHIVE_READDISK_NO_SPARSE),
+    ];
+    for (flag, value) in flags {
+        assert_eq!(flag.bits(), value as i32, "{flag:?}");
+    }
+    assert_eq!(ReadDiskFlags::NONE.bits(), 0);
+}
+
+#[test]
+fn test_flags_debug() {
+    assert_eq!(format!("{:?}", ExtractFlags::NONE), "ExtractFlags(NONE)");
+    assert_eq!(
+        format!("{:?}", ExtractFlags::TIME | ExtractFlags::PERM),
+        "ExtractFlags(PERM | TIME)"
+    );
+    assert_eq!(
+        format!("{:?}", ExtractFlags::SPARSE | ExtractFlags::MAC_METADATA),
+        "ExtractFlags(SPARSE | MAC_METADATA)"
+    );
+    assert_eq!(
+        format!("{:?}", ReadDiskFlags::NO_XATTR | ReadDiskFlags::NO_ACL),
+        "ReadDiskFlags(NO_XATTR | NO_ACL)"
+    );
+    assert_eq!(format!("{:?}", ReadDiskFlags::NONE), "ReadDiskFlags(NONE)");
+}
+
+#[test]
+fn test_flags_contains() {
+    let flags = ExtractFlags::PERM | ExtractFlags::TIME;
+    assert!(flags.contains(ExtractFlags::PERM));
+    assert!(flags.contains(ExtractFlags::PERM | ExtractFlags::TIME));
+    assert!(!flags.contains(ExtractFlags::OWNER));
+    assert!(flags.contains(ExtractFlags::NONE));
+
+    let flags = ReadDiskFlags::NO_SPARSE | ReadDiskFlags::HONOR_NODUMP;
+    assert!(flags.contains(ReadDiskFlags::NO_SPARSE));
+    assert!(!flags.contains(ReadDiskFlags::NO_ACL));
+}
@@ -0,0 +1,105 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, ReadArchive, WriteArchive};
+
+fn write_archive(format: ArchiveFormat, compression: CompressionFormat) -> Vec<u8> {
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .compression(compression)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("hello.txt", b"Hello, detection!").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_format_and_filters_detected() {
+    let cases = [
+        (
+            ArchiveFormat::TarPax,
+            CompressionFormat::Gzip,
+            "pax",
+            &["gzip"][..],
+        ),
+        (
+            ArchiveFormat::TarUstar,
+            CompressionFormat::Zstd,
+            "ustar",
+            &["zstd"][..],
+        ),
+        (
+            ArchiveFormat::TarGnu,
+            CompressionFormat::None,
+            "tar",
+            &[][..],
+        ),
+        (ArchiveFormat::Zip, CompressionFormat::None, "ZIP", &[][..]),
+        (
+            ArchiveFormat::CpioNewc,
+            CompressionFormat::Xz,
+            "SVR4",
+            &["xz"][..],
+        ),
+    ];
+
+    for (format, compression, name_part, filters) in cases {
+        let data = write_archive(format, compression);
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        assert!(archive.next_entry().unwrap().is_some());
+
+        let name = archive.format_name().unwrap();
+        assert!(name.contains(name_part), "{format:?}: {name}");
+        assert_ne!(archive.format_code(), 0);
+        assert_eq!(archive.filters(), filters, "{format:?}/{compression:?}");
+    }
+}
+
+#[test]
+fn test_format_codes_match_libarchive() {
+    let data = write_archive(ArchiveFormat::TarPax, CompressionFormat::None);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.next_entry().unwrap();
+    assert_eq!(
+        archive.format_code(),
+        libarchive2_sys::ARCHIVE_FORMAT_TAR_PAX_INTERCHANGE as i32
+    );
+}
+
+#[test]
+fn test_format_unknown_before_first_entry() {
+    let data = write_archive(ArchiveFormat::TarPax, CompressionFormat::Gzip);
+    let archive = ReadArchive::open_memory(&data).unwrap();
+    assert_eq!(archive.format_name(), None);
+    assert_eq!(archive.format_code(), 0);
+    // Filters are detected while opening
+    assert_eq!(archive.filters(), vec!["gzip"]);
+}
+
+#[test]
+fn test_filter_count_and_names() {
+    let cases = [
+        (CompressionFormat::Gzip, vec!["gzip", "none"]),
+        (CompressionFormat::Zstd, vec!["zstd", "none"]),
+        (CompressionFormat::Bzip2, vec!["bzip2", "none"]),
+        (CompressionFormat::None, vec!["none"]),
+    ];
+
+    for (compression, expected) in cases {
+        let data = write_archive(ArchiveFormat::TarPax, compression);
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        assert_eq!(archive.filter_count(), 0);
+        assert_eq!(archive.filter_name(0), None);
+
+        archive.next_entry().unwrap();
+        assert_eq!(archive.filter_count(), expected.len(), "{compression:?}");
+        let names: Vec<_> = (0..archive.filter_count())
+            .map(|i| archive.filter_name(i).unwrap())
+            .collect();
+        assert_eq!(names, expected);
+        assert_eq!(archive.filter_name(expected.len()), None);
+    }
+}
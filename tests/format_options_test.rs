@@ -1,8 +1,8 @@
 //! Integration tests for runtime format and filter options
 
 use libarchive2::{
-    ArchiveFormat, CompressionFormat, CompressionLevel, FilterOption, FormatOption, WriteArchive,
-    ZipCompressionMethod,
+    ArchiveFormat, CompressionFormat, CompressionLevel, FilterOption, FormatOption, ReadArchive,
+    ReadFormat, SevenZipMethod, WriteArchive, ZipCompressionMethod,
 };
 use std::fs;
 use tempfile::TempDir;
@@ -194,6 +194,30 @@ fn test_xz_compression_level() {
     assert_eq!(data, test_data);
 }
 
+#[test]
+fn test_xz_threads() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_data = b"XZ multithreaded test data. ".repeat(100_000);
+
+    // 0 lets liblzma pick one thread per core
+    for threads in [0, 4] {
+        let archive_path = temp_dir.path().join(format!("threads-{}.tar.xz", threads));
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Xz)
+            .filter_option(FilterOption::XzThreads(threads))
+            .open_file(&archive_path)
+            .unwrap();
+        archive.add_file("test.txt", &test_data).unwrap();
+        archive.finish().unwrap();
+
+        let mut read_archive = ReadArchive::open(&archive_path).unwrap();
+        let entry = read_archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "test.txt");
+        assert_eq!(read_archive.read_data_to_vec().unwrap(), test_data);
+    }
+}
+
 #[test]
 fn test_zstd_compression_level() {
     let temp_dir = TempDir::new().unwrap();
@@ -368,6 +392,140 @@ fn test_iso9660_lowercase_option() {
     }
 }
 
+// zisofs stores all-zero blocks as holes, which read_data() doesn't pad at the
+// end of a file, so use compressible data that isn't all zeros
+fn pattern_data() -> Vec<u8> {
+    (0..256 * 1024).map(|i| (i % 7) as u8 + 1).collect()
+}
+
+fn write_iso(path: &std::path::Path, zisofs: bool) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Iso9660)
+        .format_option(FormatOption::Iso9660Zisofs(zisofs))
+        .format_option(FormatOption::Iso9660ZisofsLevel(CompressionLevel::BEST))
+        .open_file(path)
+        .unwrap();
+
+    archive.add_file("pattern.bin", &pattern_data()).unwrap();
+    archive
+        .add_file("text.txt", "zisofs ".repeat(10_000).as_bytes())
+        .unwrap();
+    archive.finish().unwrap();
+}
+
+fn read_iso_files(path: &std::path::Path) -> Vec<(String, Vec<u8>)> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut files = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        if entry.file_type() == libarchive2::FileType::RegularFile {
+            let name = entry.pathname().unwrap();
+            files.push((name, archive.read_data_to_vec().unwrap()));
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn test_iso9660_zisofs() {
+    let temp_dir = TempDir::new().unwrap();
+    let plain_path = temp_dir.path().join("plain.iso");
+    let zisofs_path = temp_dir.path().join("zisofs.iso");
+
+    write_iso(&plain_path, false);
+    write_iso(&zisofs_path, true);
+
+    let plain_size = fs::metadata(&plain_path).unwrap().len();
+    let zisofs_size = fs::metadata(&zisofs_path).unwrap().len();
+    assert!(
+        zisofs_size < plain_size,
+        "zisofs image ({zisofs_size}) should be smaller than plain image ({plain_size})"
+    );
+
+    // libarchive decompresses zisofs data transparently on read
+    let plain_files = read_iso_files(&plain_path);
+    assert_eq!(plain_files.len(), 2);
+    assert_eq!(plain_files[0], ("pattern.bin".to_string(), pattern_data()));
+    assert_eq!(plain_files, read_iso_files(&zisofs_path));
+}
+
+#[test]
+fn test_iso9660_joliet_names() {
+    let temp_dir = TempDir::new().unwrap();
+    let name = "MixedCase_Long_FileName.Text";
+
+    let write = |path: &std::path::Path, joliet: bool| {
+        // Without Rock Ridge, readers fall back to the Joliet names
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Iso9660)
+            .format_option(FormatOption::Iso9660RockRidge(false))
+            .format_option(FormatOption::Iso9660Joliet(joliet))
+            .format_option(FormatOption::Iso9660ApplicationId(
+                "libarchive2 tests".to_string(),
+            ))
+            .open_file(path)
+            .unwrap();
+        archive.add_file(name, b"joliet").unwrap();
+        archive.finish().unwrap();
+    };
+
+    let joliet_path = temp_dir.path().join("joliet.iso");
+    write(&joliet_path, true);
+    assert_eq!(
+        read_iso_files(&joliet_path),
+        vec![(name.to_string(), b"joliet".to_vec())]
+    );
+
+    let plain_path = temp_dir.path().join("plain.iso");
+    write(&plain_path, false);
+    let plain_files = read_iso_files(&plain_path);
+    assert_eq!(plain_files.len(), 1);
+    assert_ne!(plain_files[0].0, name);
+    assert_eq!(plain_files[0].1, b"joliet");
+}
+
+#[test]
+fn test_iso9660_boot_image() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("boot.iso");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Iso9660)
+        .format_option(FormatOption::Iso9660ElToritoBootImage(
+            "boot.img".to_string(),
+        ))
+        .format_option(FormatOption::Iso9660BootCatalog("BOOT.CAT".to_string()))
+        .format_option(FormatOption::Iso9660PadTo64kBlocks(false))
+        .open_file(&archive_path)
+        .unwrap();
+    archive.add_file("boot.img", &[0xAA; 10 * 1024]).unwrap();
+    archive.add_file("readme.txt", b"bootable").unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&archive_path).unwrap();
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        names.push(entry.pathname().unwrap());
+    }
+    assert!(
+        names.iter().any(|n| n.eq_ignore_ascii_case("boot.cat")),
+        "{names:?}"
+    );
+    assert!(names.iter().any(|n| n == "boot.img"), "{names:?}");
+}
+
+#[test]
+fn test_iso9660_invalid_option_names_option() {
+    let temp_dir = TempDir::new().unwrap();
+    let err = WriteArchive::new()
+        .format(ArchiveFormat::Iso9660)
+        .format_option(FormatOption::Iso9660AbstractFile("x".repeat(64)))
+        .open_file(temp_dir.path().join("bad.iso"))
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.to_string().contains("abstract-file"), "{err}");
+}
+
 #[test]
 fn test_compression_no_data() {
     let temp_dir = TempDir::new().unwrap();
@@ -390,3 +548,111 @@ fn test_compression_no_data() {
     // Should have no entries
     assert!(read_archive.next_entry().unwrap().is_none());
 }
+
+fn write_7z(method: SevenZipMethod, payload: &[u8]) -> libarchive2::Result<Vec<u8>> {
+    let mut buffer = vec![0u8; payload.len() + 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::SevenZip)
+            .format_option(FormatOption::SevenZipCompressionMethod(method))
+            .open_memory(&mut buffer, &mut used)?;
+        archive.add_file("payload.txt", payload)?;
+        archive.finish()?;
+    }
+    buffer.truncate(used);
+    Ok(buffer)
+}
+
+fn read_7z(data: &[u8]) -> Vec<u8> {
+    let mut archive = ReadArchive::new().unwrap();
+    archive
+        .support_format(ReadFormat::Format(ArchiveFormat::SevenZip))
+        .unwrap();
+    let mut archive = archive.open_buffer(data).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().as_deref(), Some("payload.txt"));
+    archive.read_data_to_vec().unwrap()
+}
+
+fn write_7z_at_level(level: CompressionLevel, entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::SevenZip)
+            .format_option(FormatOption::SevenZipCompressionLevel(level))
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        for (name, data) in entries {
+            archive.add_file(name, data).unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buffer.truncate(used);
+    buffer
+}
+
+#[test]
+fn test_7z_compression_level_round_trip() {
+    let entries = vec![
+        ("a.txt", "first entry ".repeat(10_000).into_bytes()),
+        ("dir/b.txt", "second entry ".repeat(5_000).into_bytes()),
+        ("c.bin", (0..50_000u32).map(|i| (i % 251) as u8).collect()),
+    ];
+
+    let expected: Vec<_> = entries
+        .iter()
+        .map(|(name, data)| (name.to_string(), data.clone()))
+        .collect();
+    let total: usize = entries.iter().map(|(_, data)| data.len()).sum();
+
+    for level in [CompressionLevel::NONE, CompressionLevel::FASTEST] {
+        let data = write_7z_at_level(level, &entries);
+        assert!(data.len() < total / 10, "{} >= {}", data.len(), total);
+
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        let mut read = Vec::new();
+        while let Some(entry) = archive.next_entry().unwrap() {
+            let name = entry.pathname().unwrap();
+            read.push((name, archive.read_data_to_vec().unwrap()));
+        }
+        assert_eq!(read, expected);
+    }
+}
+
+#[test]
+fn test_7z_compression_method() {
+    let payload = "compression method ".repeat(20_000).into_bytes();
+
+    let copy = write_7z(SevenZipMethod::Copy, &payload).unwrap();
+    let lzma2 = write_7z(SevenZipMethod::Lzma2, &payload).unwrap();
+
+    assert!(copy.len() > payload.len());
+    assert!(copy.len() > lzma2.len() * 10);
+    assert_eq!(read_7z(&copy), payload);
+    assert_eq!(read_7z(&lzma2), payload);
+}
+
+#[test]
+fn test_7z_compression_method_all_variants() {
+    let payload = "every method ".repeat(1_000).into_bytes();
+    for method in [
+        SevenZipMethod::Copy,
+        SevenZipMethod::Deflate,
+        SevenZipMethod::Bzip2,
+        SevenZipMethod::Lzma1,
+        SevenZipMethod::Lzma2,
+        SevenZipMethod::Ppmd,
+    ] {
+        // A method libarchive was built without must fail when opening,
+        // not when the archive is finished
+        match write_7z(method, &payload) {
+            Ok(data) => assert_eq!(read_7z(&data), payload, "{method:?}"),
+            Err(e) => assert!(
+                e.to_string().contains("not supported"),
+                "{method:?}: unexpected error {e}"
+            ),
+        }
+    }
+}
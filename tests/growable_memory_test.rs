@@ -0,0 +1,130 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+
+#[test]
+fn test_growable_memory_outgrows_any_fixed_buffer() {
+    // Incompressible data well past the 64 KiB buffers used elsewhere
+    let mut state = 0x2545_f491_u32;
+    let data: Vec<u8> = (0..4 * 1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_growable_memory()
+        .unwrap();
+    for i in 0..4 {
+        archive.add_file(format!("{}.bin", i), &data).unwrap();
+    }
+    let bytes = archive.finish_to_vec().unwrap();
+    assert!(bytes.len() > 16 * 1024 * 1024);
+
+    let mut archive = ReadArchive::open_memory(&bytes).unwrap();
+    let mut count = 0;
+    while let Some(entry) = archive.next_entry().unwrap() {
+        assert_eq!(entry.pathname().unwrap(), format!("{}.bin", count));
+        assert!(archive.read_data_to_vec().unwrap() == data);
+        count += 1;
+    }
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn test_growable_memory_zip() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_growable_memory()
+        .unwrap();
+    archive.add_file("hello.txt", b"Hello, world!").unwrap();
+    let bytes = archive.finish_to_vec().unwrap();
+    assert!(bytes.starts_with(b"PK\x03\x04"));
+
+    let mut archive = ReadArchive::open_memory(&bytes).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "hello.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"Hello, world!");
+}
+
+#[test]
+fn test_finish_to_vec_requires_growable_memory() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buffer, &mut used)
+        .unwrap();
+    assert!(matches!(
+        archive.finish_to_vec(),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_finish_to_vec_zip_round_trip() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_growable_memory()
+        .unwrap();
+    archive.add_directory("docs").unwrap();
+    archive.add_file("docs/a.txt", b"alpha").unwrap();
+    archive.add_file("docs/b.txt", b"").unwrap();
+    let bytes = archive.finish_to_vec().unwrap();
+
+    // The central directory is only written when the archive is closed
+    assert!(bytes.windows(4).any(|w| w == b"PK\x05\x06"));
+
+    let mut archive = ReadArchive::open_memory(&bytes).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        entries.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    assert_eq!(
+        entries,
+        vec![
+            ("docs/".to_string(), vec![]),
+            ("docs/a.txt".to_string(), b"alpha".to_vec()),
+            ("docs/b.txt".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_growable_memory_matches_open_memory() {
+    fn build(archive: &mut WriteArchive) {
+        archive.add_file("a.txt", b"alpha").unwrap();
+        archive.add_file("b.txt", b"bravo").unwrap();
+    }
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .deterministic(true)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        build(&mut archive);
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .deterministic(true)
+        .open_growable_memory()
+        .unwrap();
+    build(&mut archive);
+    let bytes = archive.finish_to_vec().unwrap();
+
+    // Neither pads the compressed output to a whole 10 KiB block
+    assert!(bytes.len() < 10240, "{}", bytes.len());
+    assert!(bytes == buf);
+}
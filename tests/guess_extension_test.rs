@@ -0,0 +1,94 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+use tempfile::TempDir;
+
+#[test]
+fn test_guess_from_extension() {
+    use ArchiveFormat as A;
+    use CompressionFormat as C;
+
+    let cases = [
+        ("backup.tar", A::TarPax, C::None),
+        ("backup.tar.gz", A::TarPax, C::Gzip),
+        ("backup.tgz", A::TarPax, C::Gzip),
+        ("backup.taz", A::TarPax, C::Gzip),
+        ("backup.tar.bz2", A::TarPax, C::Bzip2),
+        ("backup.tbz", A::TarPax, C::Bzip2),
+        ("backup.tbz2", A::TarPax, C::Bzip2),
+        ("backup.tb2", A::TarPax, C::Bzip2),
+        ("backup.tar.xz", A::TarPax, C::Xz),
+        ("backup.txz", A::TarPax, C::Xz),
+        ("backup.tar.zst", A::TarPax, C::Zstd),
+        ("backup.tzst", A::TarPax, C::Zstd),
+        ("backup.tar.lz", A::TarPax, C::Lzip),
+        ("backup.tlz", A::TarPax, C::Lzip),
+        ("backup.tar.lzma", A::TarPax, C::Lzma),
+        ("backup.tar.lz4", A::TarPax, C::Lz4),
+        ("backup.tar.Z", A::TarPax, C::Compress),
+        ("backup.tZ", A::TarPax, C::Compress),
+        ("backup.tar.uu", A::TarPax, C::UuEncode),
+        ("BACKUP.TAR.GZ", A::TarPax, C::Gzip),
+        ("dir/release-1.0.tgz", A::TarPax, C::Gzip),
+        ("site.zip", A::Zip, C::None),
+        ("site.7z", A::SevenZip, C::None),
+        ("lib.ar", A::Ar, C::None),
+        ("initrd.cpio.gz", A::Cpio, C::Gzip),
+        ("disk.iso", A::Iso9660, C::None),
+        ("pkg.xar", A::Xar, C::None),
+        ("spec.mtree", A::Mtree, C::None),
+        ("install.shar", A::Shar, C::None),
+        ("crawl.warc.gz", A::Warc, C::Gzip),
+        ("notes.txt.gz", A::Raw, C::Gzip),
+        ("notes.xz", A::Raw, C::Xz),
+    ];
+    for (name, format, compression) in cases {
+        assert_eq!(
+            WriteArchive::guess_from_extension(name).unwrap(),
+            (format, compression),
+            "{name}"
+        );
+    }
+
+    for name in ["notes.txt", "archive", "movie.rar", "old.z", "tar.", ""] {
+        assert!(
+            matches!(
+                WriteArchive::guess_from_extension(name),
+                Err(Error::InvalidArgument(_))
+            ),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_open_file_auto() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("output.tar.zst");
+
+    let mut archive = WriteArchive::new().open_file_auto(&path).unwrap();
+    archive.add_file("hello.txt", b"Hello, world!").unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "hello.txt");
+    assert_eq!(archive.filters(), vec!["zstd".to_string()]);
+    assert_eq!(archive.format_code() & 0xff0000, 0x30000);
+}
+
+#[test]
+fn test_open_file_auto_builder_takes_precedence() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("output.tgz");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarUstar)
+        .open_file_auto(&path)
+        .unwrap();
+    archive.add_file("hello.txt", b"Hello, world!").unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.filters(), vec!["gzip".to_string()]);
+    assert_eq!(archive.format_name().as_deref(), Some("POSIX ustar format"));
+}
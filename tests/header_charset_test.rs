@@ -0,0 +1,120 @@
+mod common;
+
+use libarchive2::{Error, ReadArchive};
+
+/// "café.txt" in code page 437
+const CP437_NAME: &[u8] = b"caf\x82.txt";
+const CONTENTS: &[u8] = b"legacy contents";
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Build a stored zip by hand, as a DOS-era tool would: the name is written in
+/// the local code page and the UTF-8 flag (bit 11) is not set
+fn legacy_zip(name: &[u8], data: &[u8]) -> Vec<u8> {
+    let crc = crc32(data);
+    let mut common = Vec::new();
+    common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+    common.extend_from_slice(&0u16.to_le_bytes()); // flags
+    common.extend_from_slice(&0u16.to_le_bytes()); // stored
+    common.extend_from_slice(&0u16.to_le_bytes()); // time
+    common.extend_from_slice(&0x21u16.to_le_bytes()); // date: 1980-01-01
+    common.extend_from_slice(&crc.to_le_bytes());
+    common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    common.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+    let mut zip = Vec::new();
+    zip.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+    zip.extend_from_slice(&common);
+    zip.extend_from_slice(name);
+    zip.extend_from_slice(data);
+
+    let central_offset = zip.len() as u32;
+    zip.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+    zip.extend_from_slice(&20u16.to_le_bytes()); // version made by: MS-DOS
+    zip.extend_from_slice(&common);
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    zip.extend_from_slice(&0u16.to_le_bytes()); // disk number
+    zip.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+    zip.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+    zip.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+    zip.extend_from_slice(name);
+    let central_size = zip.len() as u32 - central_offset;
+
+    zip.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&central_size.to_le_bytes());
+    zip.extend_from_slice(&central_offset.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip
+}
+
+fn write_fixture(dir: &std::path::Path) -> std::path::PathBuf {
+    let path = dir.join("legacy.zip");
+    std::fs::write(&path, legacy_zip(CP437_NAME, CONTENTS)).unwrap();
+    path
+}
+
+#[test]
+fn test_cp437_zip_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(dir.path());
+
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_filter_all().unwrap();
+    archive.support_format_all().unwrap();
+    archive.set_header_charset("CP437").unwrap();
+    let mut archive = archive.open_file(&path).unwrap();
+
+    if !common::utf8_locale() {
+        // The decoded name can't be represented, which the zip reader reports
+        // as a warning
+        assert!(archive.next_entry().unwrap().is_some());
+        assert!(archive.last_warning().is_some());
+        return;
+    }
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "caf\u{e9}.txt");
+    assert_eq!(entry.pathname_bytes().unwrap(), "caf\u{e9}.txt".as_bytes());
+    assert_eq!(archive.read_data_to_vec().unwrap(), CONTENTS);
+}
+
+#[test]
+fn test_without_header_charset_keeps_raw_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(dir.path());
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname_bytes().unwrap(), CP437_NAME);
+}
+
+#[test]
+fn test_invalid_header_charset() {
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_format_all().unwrap();
+    for charset in ["", "CP437,zip:ignorecrc32"] {
+        assert!(matches!(
+            archive.set_header_charset(charset),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+    assert!(archive.set_header_charset("NO-SUCH-CHARSET").is_err());
+}
@@ -89,7 +89,7 @@ fn test_io_copy_between_archives() {
     {
         let mut src = ReadArchive::open(&src_path).unwrap();
         let src_entry = src.next_entry().unwrap().unwrap();
-        let size = src_entry.size();
+        let size = src_entry.size_opt().unwrap();
         let name = src_entry.pathname().unwrap();
 
         let mut dst = WriteArchive::new()
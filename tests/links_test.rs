@@ -0,0 +1,165 @@
+use libarchive2::{ArchiveFormat, Error, ExtractFlags, FileType, ReadArchive, WriteArchive};
+
+fn build(format: ArchiveFormat) -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive
+            .add_file("data/original.txt", b"shared contents")
+            .unwrap();
+        archive
+            .add_symlink("data/link.txt", "original.txt")
+            .unwrap();
+        archive
+            .add_hardlink("data/hard.txt", "data/original.txt")
+            .unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_links_round_trip() {
+    for format in [
+        ArchiveFormat::TarPax,
+        ArchiveFormat::TarGnu,
+        ArchiveFormat::TarUstar,
+    ] {
+        let data = build(format);
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "data/original.txt");
+
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "data/link.txt");
+        assert_eq!(entry.file_type(), FileType::SymbolicLink);
+        assert_eq!(entry.symlink().unwrap(), "original.txt");
+        assert_eq!(entry.hardlink(), None);
+        assert_eq!(entry.size_opt().unwrap_or(0), 0);
+        assert_eq!(entry.mode() & 0o777, 0o777);
+        assert!(entry.mtime().is_some());
+
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.pathname().unwrap(), "data/hard.txt");
+        assert_eq!(entry.hardlink().unwrap(), "data/original.txt");
+        assert_eq!(entry.symlink(), None);
+        assert_eq!(entry.size_opt().unwrap_or(0), 0);
+
+        assert!(archive.next_entry().unwrap().is_none(), "{format:?}");
+    }
+}
+
+#[test]
+fn test_links_extract() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = build(ArchiveFormat::TarPax);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let stats = archive
+        .extract_to_dir(dir.path(), ExtractFlags::NONE)
+        .unwrap();
+    assert_eq!((stats.files, stats.symlinks, stats.hardlinks), (1, 1, 1));
+
+    let link = dir.path().join("data/link.txt");
+    assert!(
+        std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(std::fs::read(&link).unwrap(), b"shared contents");
+    assert_eq!(
+        std::fs::read(dir.path().join("data/hard.txt")).unwrap(),
+        b"shared contents"
+    );
+}
+
+#[test]
+fn test_links_reject_nul() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    assert!(matches!(
+        archive.add_symlink("link", "bad\0target"),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        archive.add_hardlink("hard", "bad\0target"),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_is_hardlink() {
+    let data = build(ArchiveFormat::TarPax);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let mut flags = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let is_hardlink = entry.is_hardlink();
+        flags.push((entry.pathname().unwrap(), is_hardlink));
+        if is_hardlink {
+            // The contents stay with the target
+            assert!(archive.read_data_to_vec().unwrap().is_empty());
+        }
+    }
+    assert_eq!(
+        flags,
+        vec![
+            ("data/original.txt".to_string(), false),
+            ("data/link.txt".to_string(), false),
+            ("data/hard.txt".to_string(), true),
+        ]
+    );
+}
+
+#[test]
+fn test_hardlink_groups() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("a.txt", b"first").unwrap();
+        archive.add_file("b.txt", b"second").unwrap();
+        archive.add_hardlink("a2.txt", "a.txt").unwrap();
+        archive.add_hardlink("b2.txt", "b.txt").unwrap();
+        archive.add_hardlink("a3.txt", "a.txt").unwrap();
+        archive.add_file("c.txt", b"third").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let groups = archive.hardlink_groups().unwrap();
+    assert_eq!(
+        groups,
+        vec![
+            (
+                "a.txt".to_string(),
+                vec!["a2.txt".to_string(), "a3.txt".to_string()]
+            ),
+            ("b.txt".to_string(), vec!["b2.txt".to_string()]),
+        ]
+    );
+
+    let data = build(ArchiveFormat::TarGnu);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    assert_eq!(
+        archive.hardlink_groups().unwrap(),
+        vec![(
+            "data/original.txt".to_string(),
+            vec!["data/hard.txt".to_string()]
+        )]
+    );
+}
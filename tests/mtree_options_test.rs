@@ -0,0 +1,99 @@
+use libarchive2::{
+    ArchiveFormat, DigestAlgo, EntryMut, FileType, FormatOption, MtreeKeyword, ReadArchive,
+    WriteArchive,
+};
+
+const DATA: &[u8] = b"hello\n";
+const DATA_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+fn write_mtree(options: Vec<FormatOption>) -> String {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut writer = WriteArchive::new().format(ArchiveFormat::Mtree);
+        for option in options {
+            writer = writer.format_option(option);
+        }
+        let mut archive = writer.open_memory(&mut buf, &mut used).unwrap();
+
+        for name in ["a.txt", "b.txt"] {
+            let mut entry = EntryMut::new();
+            entry.set_pathname(name).unwrap();
+            entry.set_file_type(FileType::RegularFile);
+            entry.set_size(DATA.len() as i64);
+            entry.set_perm(0o644).unwrap();
+            entry.set_uid(1000);
+            archive.write_header(&entry).unwrap();
+            archive.write_data(DATA).unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    String::from_utf8(buf).unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_mtree_keywords_limit_output() {
+    let text = write_mtree(vec![FormatOption::MtreeKeywords(vec![
+        MtreeKeyword::Sha256,
+        MtreeKeyword::Size,
+    ])]);
+
+    assert!(text.contains(&format!("sha256digest={}", DATA_SHA256)));
+    assert!(text.contains("size=6"));
+    for absent in ["uid=", "mode=", "time=", "flags=", "gname=", "nlink="] {
+        assert!(!text.contains(absent), "{absent} in output:\n{text}");
+    }
+}
+
+#[test]
+fn test_mtree_digest_read_back() {
+    let text = write_mtree(vec![FormatOption::MtreeKeywords(vec![
+        MtreeKeyword::Sha256,
+        MtreeKeyword::Size,
+        // libarchive refuses to read mtree entries without a type
+        MtreeKeyword::Type,
+    ])]);
+
+    let mut archive = ReadArchive::open_memory(text.as_bytes()).unwrap();
+    let mut seen = 0;
+    while let Some(entry) = archive.next_entry().unwrap() {
+        if entry.file_type() != FileType::RegularFile {
+            continue;
+        }
+        seen += 1;
+        let digest = entry.digest(DigestAlgo::Sha256).unwrap();
+        assert_eq!(hex(&digest), DATA_SHA256);
+        assert_eq!(entry.digest(DigestAlgo::Md5), None);
+        assert_eq!(entry.size_opt(), Some(DATA.len() as i64));
+    }
+    assert_eq!(seen, 2);
+}
+
+#[test]
+fn test_mtree_default_keywords_have_no_digest() {
+    let text = write_mtree(Vec::new());
+    assert!(text.contains("uid=1000"));
+    assert!(!text.contains("sha256digest="));
+
+    let mut archive = ReadArchive::open_memory(text.as_bytes()).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.digest(DigestAlgo::Sha256), None);
+}
+
+#[test]
+fn test_mtree_use_set_and_indent() {
+    let plain = write_mtree(vec![FormatOption::MtreeUseSet(false)]);
+    assert!(!plain.contains("/set"));
+
+    let with_set = write_mtree(vec![FormatOption::MtreeUseSet(true)]);
+    assert!(with_set.contains("/set"));
+
+    let indented = write_mtree(vec![FormatOption::MtreeIndent(true)]);
+    assert!(indented.contains("./a.txt  "));
+    assert!(!plain.contains("./a.txt  "));
+}
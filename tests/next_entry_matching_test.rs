@@ -0,0 +1,75 @@
+use libarchive2::{ArchiveFormat, ArchiveMatch, CompressionFormat, ReadArchive, WriteArchive};
+
+fn build_mixed_archive() -> Vec<u8> {
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_directory("docs").unwrap();
+        archive.add_file("docs/readme.txt", b"read me").unwrap();
+        archive.add_file("bin/tool", &vec![0xAB; 100_000]).unwrap();
+        archive.add_symlink("docs/link.txt", "readme.txt").unwrap();
+        archive.add_file("notes.txt", b"notes").unwrap();
+        archive.add_file("image.png", b"\x89PNG").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_next_entry_matching_returns_only_matches() {
+    let data = build_mixed_archive();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pattern("*.txt").unwrap();
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut seen = Vec::new();
+    while let Some(entry) = archive.next_entry_matching(&mut matcher).unwrap() {
+        let name = entry.pathname().unwrap();
+        seen.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    assert_eq!(
+        seen,
+        vec![
+            ("docs/readme.txt".to_string(), b"read me".to_vec()),
+            ("docs/link.txt".to_string(), Vec::new()),
+            ("notes.txt".to_string(), b"notes".to_vec()),
+        ]
+    );
+    assert!(matcher.unmatched_inclusions().unwrap().is_empty());
+}
+
+#[test]
+fn test_next_entry_matching_skips_unread_data() {
+    let data = build_mixed_archive();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pattern("*.png").unwrap();
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let entry = archive.next_entry_matching(&mut matcher).unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "image.png");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"\x89PNG");
+    assert!(archive.next_entry_matching(&mut matcher).unwrap().is_none());
+}
+
+#[test]
+fn test_next_entry_matching_combines_with_set_match() {
+    let data = build_mixed_archive();
+    let mut docs = ArchiveMatch::new().unwrap();
+    docs.include_pattern("docs/*").unwrap();
+    let mut txt = ArchiveMatch::new().unwrap();
+    txt.include_pattern("*.txt").unwrap();
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_match(docs);
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry_matching(&mut txt).unwrap() {
+        names.push(entry.pathname().unwrap());
+    }
+    assert_eq!(names, vec!["docs/readme.txt", "docs/link.txt"]);
+}
@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+use libarchive2::{ArchiveFormat, Error, ReadArchive, ReadDisk, WriteArchive};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// A directory whose name is not valid UTF-8
+fn non_utf8_dir(temp_dir: &TempDir) -> PathBuf {
+    let dir = temp_dir.path().join(OsStr::from_bytes(b"caf\xe9-\xff"));
+    std::fs::create_dir(&dir).unwrap();
+    assert!(dir.to_str().is_none());
+    dir
+}
+
+#[test]
+fn test_write_and_read_under_non_utf8_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = non_utf8_dir(&temp_dir).join("test.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("hello.txt", b"hello").unwrap();
+    archive.finish().unwrap();
+    assert!(path.is_file());
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "hello.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"hello");
+
+    let mut archive = ReadArchive::open_filenames(&[&path]).unwrap();
+    assert!(archive.next_entry().unwrap().is_some());
+}
+
+#[test]
+fn test_read_disk_non_utf8_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = non_utf8_dir(&temp_dir);
+    std::fs::write(dir.join("a.txt"), b"a").unwrap();
+
+    let mut disk = ReadDisk::new().unwrap();
+    disk.open(&dir).unwrap();
+    let mut count = 0;
+    while disk.next_entry().unwrap().is_some() {
+        disk.descend().unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn test_nul_in_path_is_invalid_argument() {
+    let path = OsStr::from_bytes(b"bad\0name.tar");
+    assert!(matches!(
+        ReadArchive::open(path),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_file(path),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        ReadDisk::new().unwrap().open(path),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        ReadArchive::open_filenames(&[path]),
+        Err(Error::InvalidArgument(_))
+    ));
+}
@@ -0,0 +1,62 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, ReadArchive, WriteArchive};
+
+fn build_archive() -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("a.txt", b"alpha").unwrap();
+        archive.add_file("b.txt", b"bravo").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+/// The returned archive outlives every local, including the buffer's owner
+fn open_owned() -> ReadArchive<'static> {
+    let data = build_archive();
+    ReadArchive::open_memory_owned(data).unwrap()
+}
+
+#[test]
+fn test_open_memory_owned() {
+    let mut archive = open_owned();
+    let mut seen = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        seen.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    assert_eq!(
+        seen,
+        vec![
+            ("a.txt".to_string(), b"alpha".to_vec()),
+            ("b.txt".to_string(), b"bravo".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_open_memory_owned_moves_to_thread() {
+    let mut archive = open_owned();
+    archive.next_entry().unwrap().unwrap();
+    let data = std::thread::spawn(move || {
+        let first = archive.read_data_to_vec().unwrap();
+        archive.next_entry().unwrap().unwrap();
+        (first, archive.read_data_to_vec().unwrap())
+    })
+    .join()
+    .unwrap();
+    assert_eq!(data, (b"alpha".to_vec(), b"bravo".to_vec()));
+}
+
+#[test]
+fn test_open_memory_owned_invalid_data() {
+    let result = ReadArchive::open_memory_owned(b"not an archive at all".to_vec())
+        .and_then(|mut archive| archive.next_entry().map(|_| ()));
+    assert!(result.is_err());
+}
@@ -0,0 +1,131 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+use std::error::Error as _;
+use std::io::{Cursor, Read};
+
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn build_tar_gz() -> Vec<u8> {
+    let mut buf = vec![0u8; 2 * 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("hello.txt", b"Hello, reader!").unwrap();
+        archive.add_file("noise.bin", &noise(500_000)).unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+/// Read every entry to the end, returning the names or the first error
+fn read_all(mut archive: ReadArchive) -> libarchive2::Result<Vec<String>> {
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry()? {
+        names.push(entry.pathname().unwrap());
+        archive.read_data_to_vec()?;
+    }
+    Ok(names)
+}
+
+/// Reader that serves `limit` bytes and then either ends or fails
+struct CutOff {
+    inner: Cursor<Vec<u8>>,
+    limit: u64,
+    fail: bool,
+}
+
+impl Read for CutOff {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.inner.position() >= self.limit {
+            return if self.fail {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionReset,
+                    "connection reset by peer",
+                ))
+            } else {
+                Ok(0)
+            };
+        }
+        let remaining = (self.limit - self.inner.position()) as usize;
+        let len = buf.len().min(remaining).min(4096);
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn test_open_read_cursor() {
+    let data = build_tar_gz();
+    let archive = ReadArchive::open_read(Cursor::new(data)).unwrap();
+    assert_eq!(read_all(archive).unwrap(), vec!["hello.txt", "noise.bin"]);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_open_reader_alias() {
+    let data = build_tar_gz();
+    let archive = ReadArchive::open_reader(Cursor::new(data)).unwrap();
+    assert_eq!(read_all(archive).unwrap(), vec!["hello.txt", "noise.bin"]);
+}
+
+#[test]
+fn test_open_read_error_halfway() {
+    let data = build_tar_gz();
+    let limit = data.len() as u64 / 2;
+    let reader = CutOff {
+        inner: Cursor::new(data),
+        limit,
+        fail: true,
+    };
+
+    let archive = ReadArchive::open_read(reader).unwrap();
+    match read_all(archive) {
+        Err(err @ Error::Io(_)) => {
+            let source = err.source().unwrap();
+            let io = source.downcast_ref::<std::io::Error>().unwrap();
+            assert_eq!(io.kind(), std::io::ErrorKind::ConnectionReset);
+            assert_eq!(io.to_string(), "connection reset by peer");
+        }
+        other => panic!("expected an I/O error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_open_read_early_eof_is_truncation() {
+    let data = build_tar_gz();
+    let limit = data.len() as u64 / 2;
+    let reader = CutOff {
+        inner: Cursor::new(data),
+        limit,
+        fail: false,
+    };
+
+    let archive = ReadArchive::open_read(reader).unwrap();
+    match read_all(archive) {
+        Err(Error::Truncated { bytes_read }) => assert!(bytes_read <= limit),
+        other => panic!("expected a truncation error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_open_read_error_at_open() {
+    let reader = CutOff {
+        inner: Cursor::new(Vec::new()),
+        limit: 0,
+        fail: true,
+    };
+    assert!(matches!(ReadArchive::open_read(reader), Err(Error::Io(_))));
+}
@@ -0,0 +1,162 @@
+use libarchive2::{ArchiveFormat, CallbackReader, CompressionFormat, ReadArchive, WriteArchive};
+use std::cell::Cell;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::rc::Rc;
+
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn build_archive(format: ArchiveFormat, compression: CompressionFormat) -> Vec<u8> {
+    let mut buf = vec![0u8; 16 * 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .compression(compression)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive
+            .add_file("first.bin", &noise(4 * 1024 * 1024))
+            .unwrap();
+        archive.add_file("second.txt", b"second entry").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+/// Cursor that counts the bytes read from it and the seeks made on it
+struct Counting {
+    inner: Cursor<Vec<u8>>,
+    bytes_read: Rc<Cell<u64>>,
+    seeks: Rc<Cell<u32>>,
+}
+
+impl Counting {
+    fn new(data: Vec<u8>) -> (Self, Rc<Cell<u64>>, Rc<Cell<u32>>) {
+        let bytes_read = Rc::new(Cell::new(0));
+        let seeks = Rc::new(Cell::new(0));
+        let reader = Counting {
+            inner: Cursor::new(data),
+            bytes_read: bytes_read.clone(),
+            seeks: seeks.clone(),
+        };
+        (reader, bytes_read, seeks)
+    }
+}
+
+impl Read for Counting {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl Seek for Counting {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.seeks.set(self.seeks.get() + 1);
+        self.inner.seek(pos)
+    }
+}
+
+fn names(mut archive: ReadArchive) -> Vec<String> {
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        names.push(entry.pathname().unwrap());
+    }
+    names
+}
+
+#[test]
+fn test_open_seekable_zip() {
+    let data = build_archive(ArchiveFormat::Zip, CompressionFormat::None);
+    let mut archive = ReadArchive::open_seekable(Cursor::new(data)).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "first.bin");
+    assert!(archive.read_data_to_vec().unwrap() == noise(4 * 1024 * 1024));
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "second.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"second entry");
+
+    assert!(archive.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_open_seekable_avoids_reading_skipped_data() {
+    let data = build_archive(ArchiveFormat::Zip, CompressionFormat::None);
+    let size = data.len() as u64;
+
+    let (reader, streamed, _) = Counting::new(data.clone());
+    let streaming = ReadArchive::open_read(reader).unwrap();
+    assert_eq!(names(streaming), ["first.bin", "second.txt"]);
+
+    let (reader, seeked, seeks) = Counting::new(data);
+    let seekable = ReadArchive::open_seekable(reader).unwrap();
+    assert_eq!(names(seekable), ["first.bin", "second.txt"]);
+
+    // Streaming has to read through the first entry's data; seeking jumps over it
+    assert!(streamed.get() >= size / 2);
+    assert!(seeked.get() < size / 4, "read {} of {size}", seeked.get());
+    assert!(seeks.get() > 0);
+}
+
+#[test]
+fn test_open_seekable_compressed_tar() {
+    let data = build_archive(ArchiveFormat::TarPax, CompressionFormat::Gzip);
+    let mut archive = ReadArchive::open_seekable(Cursor::new(data)).unwrap();
+
+    archive.next_entry().unwrap().unwrap();
+    archive.skip_data().unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "second.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"second entry");
+}
+
+#[test]
+fn test_open_seekable_uncompressed_tar_skips() {
+    let data = build_archive(ArchiveFormat::TarPax, CompressionFormat::None);
+    let size = data.len() as u64;
+
+    let (reader, bytes_read, _) = Counting::new(data);
+    let mut archive = ReadArchive::open_seekable(reader).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "second.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"second entry");
+
+    assert!(
+        bytes_read.get() < size / 4,
+        "read {} of {size}",
+        bytes_read.get()
+    );
+}
+
+#[test]
+fn test_seekable_callback_reader_zip() {
+    let data = build_archive(ArchiveFormat::Zip, CompressionFormat::None);
+    let (reader, _, seeks) = Counting::new(data);
+    let mut archive = ReadArchive::open_callback(CallbackReader::new_seekable(reader)).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "first.bin");
+    assert!(archive.read_data_to_vec().unwrap() == noise(4 * 1024 * 1024));
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "second.txt");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"second entry");
+
+    assert!(archive.next_entry().unwrap().is_none());
+    assert!(seeks.get() > 0);
+}
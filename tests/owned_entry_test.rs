@@ -0,0 +1,138 @@
+use libarchive2::{
+    ArchiveFormat, EntryAclExt, EntryMut, EntryMutAclExt, FileType, OwnedEntry, ReadArchive,
+    WriteArchive,
+};
+use std::time::{Duration, SystemTime};
+
+fn build_archive() -> Vec<u8> {
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+
+        let mut entry = EntryMut::new();
+        entry.set_pathname("dir/file.txt").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_size(5);
+        entry.set_perm(0o640).unwrap();
+        entry.set_uid(1234);
+        entry.set_gid(5678);
+        entry.set_uname("alice").unwrap();
+        entry.set_gname("staff").unwrap();
+        entry.set_mtime(mtime);
+        entry.add_xattr("user.comment", b"hello").unwrap();
+        archive.write_header(&entry).unwrap();
+        archive.write_data(b"hello").unwrap();
+
+        let mut link = EntryMut::new();
+        link.set_pathname("dir/link").unwrap();
+        link.set_file_type(FileType::SymbolicLink);
+        link.set_symlink("file.txt").unwrap();
+        link.set_perm(0o777).unwrap();
+        archive.write_header(&link).unwrap();
+
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn snapshot(data: &[u8]) -> Vec<OwnedEntry> {
+    let mut archive = ReadArchive::open_memory(data).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        entries.push(entry.to_owned_entry());
+    }
+    entries
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_owned_entries_outlive_archive() {
+    assert_send_sync::<OwnedEntry>();
+    let data = build_archive();
+    let entries = snapshot(&data);
+    drop(data);
+
+    assert_eq!(entries.len(), 2);
+    let file = &entries[0];
+    assert_eq!(file.pathname.as_deref(), Some("dir/file.txt"));
+    assert_eq!(file.pathname_bytes.as_deref(), Some(&b"dir/file.txt"[..]));
+    assert_eq!(file.file_type, FileType::RegularFile);
+    assert_eq!(file.size, Some(5));
+    assert_eq!(file.mode, 0o640);
+    assert_eq!(file.uid, Some(1234));
+    assert_eq!(file.gid, Some(5678));
+    assert_eq!(file.uname.as_deref(), Some("alice"));
+    assert_eq!(file.gname.as_deref(), Some("staff"));
+    assert_eq!(
+        file.mtime,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+    assert!(!file.xattrs.is_empty());
+    assert!(
+        file.xattrs
+            .iter()
+            .all(|x| x.name == "user.comment" && x.value == b"hello")
+    );
+    assert_eq!(file.acl_text, None);
+    assert!(!file.is_encrypted());
+
+    let link = &entries[1];
+    assert_eq!(link.file_type, FileType::SymbolicLink);
+    assert_eq!(link.symlink.as_deref(), Some("file.txt"));
+    assert_eq!(link.hardlink, None);
+}
+
+#[test]
+fn test_owned_entries_sent_to_thread() {
+    let entries = snapshot(&build_archive());
+    let names = std::thread::spawn(move || {
+        entries
+            .into_iter()
+            .filter_map(|e| e.pathname)
+            .collect::<Vec<_>>()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(names, vec!["dir/file.txt", "dir/link"]);
+}
+
+#[test]
+fn test_from_owned_round_trip() {
+    let entries = snapshot(&build_archive());
+
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for owned in &entries {
+            let entry = EntryMut::from_owned(owned).unwrap();
+            assert!(!entry.as_entry().has_acl());
+            archive.write_header(&entry).unwrap();
+            if owned.size.is_some_and(|size| size > 0) {
+                archive.write_data(b"hello").unwrap();
+            }
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    assert_eq!(snapshot(&buf), entries);
+}
+
+#[test]
+fn test_from_entry_matches_to_owned_entry() {
+    let data = build_archive();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(OwnedEntry::from(&entry), entry.to_owned_entry());
+}
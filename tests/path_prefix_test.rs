@@ -0,0 +1,125 @@
+use libarchive2::{ArchiveFormat, EntryMut, Error, FileType, ReadArchive, WriteArchive};
+
+fn names(path: &std::path::Path) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        names.push((entry.pathname().unwrap(), entry.hardlink(), entry.symlink()));
+    }
+    names
+}
+
+#[test]
+fn test_path_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .path_prefix("pkg/")
+        .open_file(&path)
+        .unwrap();
+    archive.add_file("a.txt", b"hello").unwrap();
+    archive.add_file("/abs/b.txt", b"world").unwrap();
+    archive.add_symlink("link", "a.txt").unwrap();
+    archive.add_hardlink("hard", "a.txt").unwrap();
+    archive.finish().unwrap();
+
+    assert_eq!(
+        names(&path),
+        vec![
+            ("pkg/a.txt".to_string(), None, None),
+            ("pkg/abs/b.txt".to_string(), None, None),
+            ("pkg/link".to_string(), None, Some("a.txt".to_string())),
+            ("pkg/hard".to_string(), Some("pkg/a.txt".to_string()), None),
+        ]
+    );
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"hello");
+}
+
+#[test]
+fn test_path_prefix_normalizes_trailing_slash() {
+    let dir = tempfile::tempdir().unwrap();
+    for (prefix, expected) in [
+        ("pkg", "pkg/a.txt"),
+        ("pkg//", "pkg/a.txt"),
+        ("./", "./a.txt"),
+        ("", "a.txt"),
+    ] {
+        let path = dir.path().join("out.tar");
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .path_prefix(prefix)
+            .open_file(&path)
+            .unwrap();
+        archive.add_file("a.txt", b"x").unwrap();
+        archive.finish().unwrap();
+        assert_eq!(names(&path)[0].0, expected, "prefix {:?}", prefix);
+    }
+}
+
+#[test]
+fn test_path_prefix_with_strip_directory_trailing_slash() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.cpio");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Cpio)
+        .path_prefix("pkg")
+        .strip_directory_trailing_slash(true)
+        .open_file(&path)
+        .unwrap();
+    let mut root = EntryMut::new();
+    root.set_pathname("/").unwrap();
+    root.set_file_type(FileType::Directory);
+    root.set_perm(0o755).unwrap();
+    root.set_size(0);
+    archive.write_header(&root).unwrap();
+    archive.add_directory("share/").unwrap();
+    archive.add_file("share/a.txt", b"x").unwrap();
+    archive.finish().unwrap();
+
+    let names: Vec<String> = names(&path).into_iter().map(|(name, _, _)| name).collect();
+    assert_eq!(names, vec!["pkg", "pkg/share", "pkg/share/a.txt"]);
+}
+
+#[test]
+fn test_path_prefix_non_utf8_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarGnu)
+        .path_prefix("pkg")
+        .open_file(&path)
+        .unwrap();
+    let mut entry = EntryMut::new();
+    entry.set_pathname_bytes(b"caf\xe9.txt").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o644).unwrap();
+    entry.set_size(0);
+    archive.write_header(&entry).unwrap();
+    archive.finish().unwrap();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(
+        entry.pathname_bytes().as_deref(),
+        Some(&b"pkg/caf\xe9.txt"[..])
+    );
+}
+
+#[test]
+fn test_path_prefix_rejects_absolute_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.tar");
+
+    let result = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .path_prefix("/pkg")
+        .open_file(&path);
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+}
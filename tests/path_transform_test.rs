@@ -0,0 +1,110 @@
+use libarchive2::{ArchiveFormat, Error, ReadArchive, WriteArchive};
+use std::path::Path;
+
+fn read_all(path: &Path) -> Vec<(String, Option<String>, Vec<u8>)> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        let hardlink = entry.hardlink();
+        entries.push((name, hardlink, archive.read_data_to_vec().unwrap()));
+    }
+    entries
+}
+
+fn strip_components(path: &str, count: usize) -> Option<String> {
+    let rest: Vec<&str> = path
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .skip(count)
+        .collect();
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest.join("/"))
+    }
+}
+
+#[test]
+fn test_path_transform_rename() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("renamed.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.set_path_transform(|path| Some(path.replace(".txt", ".md")));
+    archive.add_file("docs/a.txt", b"a").unwrap();
+    archive.add_hardlink("docs/b.txt", "docs/a.txt").unwrap();
+    archive.finish().unwrap();
+
+    let entries = read_all(&path);
+    assert_eq!(entries[0].0, "docs/a.md");
+    assert_eq!(entries[0].2, b"a");
+    assert_eq!(entries[1].0, "docs/b.md");
+    assert_eq!(entries[1].1.as_deref(), Some("docs/a.md"));
+}
+
+#[test]
+fn test_path_transform_skip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("filtered.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.set_path_transform(|path| (!path.ends_with(".o")).then(|| path.to_string()));
+    archive.add_file("main.c", b"int main;").unwrap();
+    archive.add_file("main.o", b"\x7fELF").unwrap();
+    archive.add_file("util.c", b"int util;").unwrap();
+    archive.finish().unwrap();
+
+    let entries = read_all(&path);
+    let names: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["main.c", "util.c"]);
+    assert_eq!(entries[1].2, b"int util;");
+}
+
+#[test]
+fn test_path_transform_strip_components() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stripped.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .path_prefix("pkg")
+        .open_file(&path)
+        .unwrap();
+    archive.set_path_transform(|path| strip_components(path, 1));
+    archive.add_directory("project").unwrap();
+    archive
+        .add_file("project/src/main.rs", b"fn main() {}")
+        .unwrap();
+    archive.add_file("/project/README", b"readme").unwrap();
+    archive.add_file("top-level", b"dropped").unwrap();
+    archive.finish().unwrap();
+
+    let entries = read_all(&path);
+    let names: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
+    // The prefix is applied after the transform
+    assert_eq!(names, vec!["pkg/src/main.rs", "pkg/README"]);
+    assert_eq!(entries[0].2, b"fn main() {}");
+}
+
+#[test]
+fn test_path_transform_nul_is_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nul.tar");
+
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.set_path_transform(|path| Some(format!("{}\0", path)));
+    assert!(matches!(
+        archive.add_file("a.txt", b"a"),
+        Err(Error::InvalidArgument(_))
+    ));
+}
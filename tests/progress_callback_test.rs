@@ -0,0 +1,143 @@
+use libarchive2::{ArchiveFormat, ProgressCallback, ReadArchive, WriteArchive};
+use std::sync::{Arc, Mutex};
+
+/// Records every (bytes_processed, total_bytes) pair it is given
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(u64, u64)>>>);
+
+impl ProgressCallback for Recorder {
+    fn on_progress(&mut self, bytes_processed: u64, total_bytes: u64) {
+        self.0.lock().unwrap().push((bytes_processed, total_bytes));
+    }
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<(u64, u64)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Check the calls for one entry count up to `size`
+fn assert_progress(calls: &[(u64, u64)], size: u64) {
+    assert!(!calls.is_empty());
+    let mut last = 0;
+    for &(done, total) in calls {
+        assert_eq!(total, size);
+        assert!(done > last, "progress went from {last} to {done}");
+        last = done;
+    }
+    assert_eq!(last, size);
+}
+
+const SIZES: [usize; 3] = [100_000, 1, 50_000];
+
+fn build_archive(recorder: &Recorder) -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.set_progress_callback(Box::new(recorder.clone()));
+        for (i, size) in SIZES.iter().enumerate() {
+            let data = vec![i as u8; *size];
+            archive.add_file(format!("file{i}.bin"), &data).unwrap();
+            assert_progress(&recorder.take(), *size as u64);
+        }
+        archive.add_directory("dir").unwrap();
+        assert!(recorder.take().is_empty());
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+#[test]
+fn test_write_progress() {
+    build_archive(&Recorder::default());
+}
+
+#[test]
+fn test_read_progress() {
+    let data = build_archive(&Recorder::default());
+    let recorder = Recorder::default();
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_progress_callback(Box::new(recorder.clone()));
+
+    archive.next_entry().unwrap().unwrap();
+    let mut buf = [0u8; 4096];
+    while archive.read_data(&mut buf).unwrap() > 0 {}
+    assert_progress(&recorder.take(), SIZES[0] as u64);
+
+    archive.next_entry().unwrap().unwrap();
+    archive.read_data_to_vec().unwrap();
+    assert_progress(&recorder.take(), SIZES[1] as u64);
+
+    // A partly read entry is reported as complete when skipped
+    archive.next_entry().unwrap().unwrap();
+    archive.read_data(&mut buf).unwrap();
+    archive.skip_data().unwrap();
+    assert_progress(&recorder.take(), SIZES[2] as u64);
+
+    archive.next_entry().unwrap().unwrap();
+    archive.read_data_to_vec().unwrap();
+    assert!(recorder.take().is_empty());
+}
+
+#[test]
+fn test_read_progress_blocks() {
+    let data = build_archive(&Recorder::default());
+    let recorder = Recorder::default();
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_progress_callback(Box::new(recorder.clone()));
+    archive.next_entry().unwrap().unwrap();
+    while archive.read_data_block().unwrap().is_some() {}
+    assert_progress(&recorder.take(), SIZES[0] as u64);
+}
+
+#[test]
+fn test_write_tracker_counts_all_entries() {
+    let recorder = Recorder::default();
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    archive.set_progress(recorder.clone());
+    for (i, size) in SIZES.iter().enumerate() {
+        archive
+            .add_file(format!("file{i}.bin"), &vec![i as u8; *size])
+            .unwrap();
+    }
+    archive.finish().unwrap();
+
+    let calls = recorder.take();
+    let total: u64 = SIZES.iter().map(|&size| size as u64).sum();
+    assert!(calls.windows(2).all(|w| w[0].0 < w[1].0), "{calls:?}");
+    assert_eq!(calls.last(), Some(&(total, 0)));
+}
+
+#[test]
+fn test_read_tracker_reaches_source_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("progress.tar");
+    std::fs::write(&path, build_archive(&Recorder::default())).unwrap();
+    let file_size = std::fs::metadata(&path).unwrap().len();
+
+    let recorder = Recorder::default();
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.set_progress(recorder.clone());
+    while archive.next_entry().unwrap().is_some() {
+        archive.read_data_to_vec().unwrap();
+    }
+
+    let calls = recorder.take();
+    assert!(!calls.is_empty());
+    assert!(calls.windows(2).all(|w| w[0].0 <= w[1].0), "{calls:?}");
+    assert!(calls.iter().all(|&(_, total)| total == file_size));
+    assert_eq!(calls.last(), Some(&(file_size, file_size)));
+}
@@ -0,0 +1,153 @@
+use libarchive2::{ArchiveFormat, EntryMut, FileType, ReadArchive, WriteArchive};
+
+const FIXTURE: &str = "tests/fixtures/latin1.tar";
+
+#[derive(Debug, PartialEq)]
+struct Names {
+    pathname: Option<Vec<u8>>,
+    symlink: Option<Vec<u8>>,
+    uname: Option<Vec<u8>>,
+    gname: Option<Vec<u8>>,
+    data: Vec<u8>,
+}
+
+fn read_names(archive: &mut ReadArchive) -> Vec<Names> {
+    let mut result = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let mut names = Names {
+            pathname: entry.pathname_bytes(),
+            symlink: entry.symlink_bytes(),
+            uname: entry.uname_bytes(),
+            gname: entry.gname_bytes(),
+            data: Vec::new(),
+        };
+        assert_eq!(entry.hardlink_bytes(), None);
+        names.data = archive.read_data_to_vec().unwrap();
+        result.push(names);
+    }
+    result
+}
+
+#[test]
+fn test_raw_names_from_fixture() {
+    let mut archive = ReadArchive::open(FIXTURE).unwrap();
+    let names = read_names(&mut archive);
+
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[0].pathname.as_deref(), Some(&b"caf\xe9.txt"[..]));
+    assert_eq!(names[0].uname.as_deref(), Some(&b"jos\xe9"[..]));
+    assert_eq!(names[0].gname.as_deref(), Some(&b"staff"[..]));
+    assert_eq!(names[0].symlink, None);
+    assert_eq!(names[1].pathname.as_deref(), Some(&b"link-\xe9"[..]));
+    assert_eq!(names[1].symlink.as_deref(), Some(&b"caf\xe9.txt"[..]));
+}
+
+#[test]
+fn test_raw_pathname_round_trip() {
+    let mut archive = ReadArchive::open(FIXTURE).unwrap();
+    let original = read_names(&mut archive);
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut writer = WriteArchive::new()
+            .format(ArchiveFormat::TarGnu)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut reader = ReadArchive::open(FIXTURE).unwrap();
+        while let Some(entry) = reader.next_entry().unwrap() {
+            if entry.file_type() != FileType::RegularFile {
+                continue;
+            }
+            let mut copy = EntryMut::new();
+            copy.set_pathname_bytes(&entry.pathname_bytes().unwrap())
+                .unwrap();
+            copy.set_file_type(entry.file_type());
+            if let Some(size) = entry.size_opt() {
+                copy.set_size(size);
+            }
+            copy.set_perm(entry.mode()).unwrap();
+            writer.write_header(&copy).unwrap();
+            let data = reader.read_data_to_vec().unwrap();
+            writer.write_data(&data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let copied = read_names(&mut archive);
+    assert_eq!(copied.len(), 1);
+    assert_eq!(copied[0].pathname, original[0].pathname);
+    assert_eq!(copied[0].data, original[0].data);
+}
+
+#[test]
+fn test_raw_and_lossy_pathname() {
+    let mut archive = ReadArchive::open(FIXTURE).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    let lossy = entry.pathname().unwrap();
+    assert_ne!(lossy.as_bytes(), entry.pathname_bytes().unwrap());
+    assert!(lossy.starts_with("caf"));
+}
+
+#[test]
+fn test_set_pathname_bytes_rejects_nul() {
+    let mut entry = EntryMut::new();
+    assert!(entry.set_pathname_bytes(b"bad\0name").is_err());
+    assert_eq!(entry.as_entry().pathname_bytes(), None);
+}
+
+#[test]
+fn test_unset_names_are_none() {
+    let mut archive = ReadArchive::open(FIXTURE).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.symlink_bytes(), None);
+    assert_eq!(entry.hardlink_bytes(), None);
+
+    // Unset names are None rather than empty
+    let new_entry = EntryMut::new();
+    assert_eq!(new_entry.as_entry().pathname_bytes(), None);
+    assert_eq!(new_entry.as_entry().uname_bytes(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_raw_names_fall_back_to_utf8_in_c_locale() {
+    // SAFETY: the C locale is always available, and uselocale only changes
+    // this thread's locale
+    let (c_locale, saved) = unsafe {
+        let c_locale = libc::newlocale(libc::LC_CTYPE_MASK, c"C".as_ptr(), std::ptr::null_mut());
+        assert!(!c_locale.is_null());
+        (c_locale, libc::uselocale(c_locale))
+    };
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname("café.txt").unwrap();
+    entry.set_uname("josé").unwrap();
+    entry.set_gname("équipe").unwrap();
+    entry.set_symlink("ünïcode").unwrap();
+    // An entry holds a symlink or a hardlink target, not both
+    let mut link = EntryMut::new();
+    link.set_hardlink("naïve").unwrap();
+    let entry = entry.as_entry();
+    let pathname = entry.pathname_bytes();
+    let uname = entry.uname_bytes();
+    let gname = entry.gname_bytes();
+    let symlink = entry.symlink_bytes();
+    let hardlink = link.as_entry().hardlink_bytes();
+    let unset = EntryMut::new().as_entry().pathname_bytes();
+
+    // SAFETY: saved is the locale this thread used before
+    unsafe {
+        libc::uselocale(saved);
+        libc::freelocale(c_locale);
+    }
+
+    assert_eq!(pathname.as_deref(), Some("café.txt".as_bytes()));
+    assert_eq!(uname.as_deref(), Some("josé".as_bytes()));
+    assert_eq!(gname.as_deref(), Some("équipe".as_bytes()));
+    assert_eq!(symlink.as_deref(), Some("ünïcode".as_bytes()));
+    assert_eq!(hardlink.as_deref(), Some("naïve".as_bytes()));
+    assert_eq!(unset, None);
+}
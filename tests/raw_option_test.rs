@@ -0,0 +1,92 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, Error, FormatOption, ReadArchive, WriteArchive,
+    ZipCompressionMethod,
+};
+
+const DATA_LEN: usize = 64 * 1024;
+
+fn write(builder: WriteArchive) -> libarchive2::Result<Vec<u8>> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = builder.open_memory(&mut buf, &mut used)?;
+        archive.add_file("data.txt", &[b'a'; DATA_LEN])?;
+        archive.finish()?;
+    }
+    buf.truncate(used);
+    Ok(buf)
+}
+
+fn read_back(data: &[u8]) -> Vec<u8> {
+    let mut archive = ReadArchive::open_memory(data).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    archive.read_data_to_vec().unwrap()
+}
+
+#[test]
+fn test_raw_format_option() {
+    let stored = write(
+        WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .option("zip:compression", "store"),
+    )
+    .unwrap();
+    let deflated = write(WriteArchive::new().format(ArchiveFormat::Zip)).unwrap();
+
+    assert!(stored.len() > DATA_LEN);
+    assert!(deflated.len() < DATA_LEN / 10);
+    assert_eq!(read_back(&stored), vec![b'a'; DATA_LEN]);
+}
+
+#[test]
+fn test_raw_option_overrides_typed_option() {
+    let data = write(
+        WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .format_option(FormatOption::ZipCompressionMethod(
+                ZipCompressionMethod::Deflate,
+            ))
+            .option("zip:compression", "store"),
+    )
+    .unwrap();
+    assert!(data.len() > DATA_LEN);
+}
+
+#[test]
+fn test_raw_filter_option() {
+    let data = write(
+        WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .option("gzip:compression-level", "1")
+            .option("compression-level", "9"),
+    )
+    .unwrap();
+    assert_eq!(read_back(&data), vec![b'a'; DATA_LEN]);
+}
+
+#[test]
+fn test_unknown_raw_option() {
+    for key in [
+        "zip:no-such-option",
+        "no-such-option",
+        "nosuchmodule:compression",
+    ] {
+        let result = write(
+            WriteArchive::new()
+                .format(ArchiveFormat::Zip)
+                .option(key, "1"),
+        );
+        assert!(
+            matches!(result, Err(Error::Archive { .. })),
+            "{key} should be rejected, got {result:?}"
+        );
+    }
+
+    let result = write(
+        WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .option("zip\0", "1"),
+    );
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+}
@@ -0,0 +1,95 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, ReadArchive, WriteArchive};
+use std::io::{Read, Seek, SeekFrom};
+
+const BIG_SIZE: usize = 10 * 1024 * 1024;
+
+fn build_archive(path: &std::path::Path, big: &[u8]) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(path)
+        .unwrap();
+    archive.add_file("big.bin", big).unwrap();
+    archive.add_file("small.txt", b"small").unwrap();
+    archive.finish().unwrap();
+}
+
+fn big_data() -> Vec<u8> {
+    (0..BIG_SIZE as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+        .collect()
+}
+
+#[test]
+fn test_read_data_into_file_matches_read_data_to_vec() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("big.tar.gz");
+    build_archive(&archive_path, &big_data());
+
+    let mut out = tempfile::tempfile().unwrap();
+    let mut archive = ReadArchive::open(&archive_path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_into_file(&mut out).unwrap(), None);
+    assert_eq!(archive.entry_bytes_read(), BIG_SIZE as u64);
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"small");
+
+    let mut written = Vec::new();
+    out.seek(SeekFrom::Start(0)).unwrap();
+    out.read_to_end(&mut written).unwrap();
+    assert_eq!(written.len(), BIG_SIZE);
+
+    let mut archive = ReadArchive::open(&archive_path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    let expected = archive.read_data_to_vec().unwrap();
+    assert!(
+        written == expected,
+        "file contents differ from read_data_to_vec"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_read_data_into_fd_bad_descriptor() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("a.txt", b"hello").unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buf[..used]).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    let err = archive.read_data_into_fd(-1).unwrap_err();
+    assert!(err.is_fatal(), "{:?}", err);
+}
+
+#[test]
+fn test_read_data_into_file_truncated_counts_written_bytes() {
+    let mut buf = vec![0u8; BIG_SIZE + 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("big.bin", &big_data()).unwrap();
+        archive.finish().unwrap();
+    }
+    // Cut the archive a megabyte into the entry's data
+    let truncated = &buf[..1024 * 1024 + 1536];
+
+    let mut out = tempfile::tempfile().unwrap();
+    let mut archive = ReadArchive::open_memory(truncated).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert!(archive.read_data_into_file(&mut out).is_err());
+
+    let written = out.metadata().unwrap().len();
+    assert!(written < BIG_SIZE as u64, "{}", written);
+    assert_eq!(archive.entry_bytes_read(), written);
+    assert_eq!(archive.stats().data_bytes, written);
+}
@@ -0,0 +1,95 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+use std::io::Write;
+
+fn build_archive(big: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0u8; 2 * 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("big.bin", big).unwrap();
+        archive.add_file("small.txt", b"small").unwrap();
+        archive.add_directory("dir").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn big_data() -> Vec<u8> {
+    (0..300_000u32).map(|i| (i * 7 % 256) as u8).collect()
+}
+
+#[test]
+fn test_read_data_into() {
+    let big = big_data();
+    let data = build_archive(&big);
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+
+    let mut outputs = Vec::new();
+    while archive.next_entry().unwrap().is_some() {
+        let mut out = Vec::new();
+        let written = archive.read_data_into(&mut out).unwrap();
+        assert_eq!(written, out.len() as u64);
+        outputs.push(out);
+    }
+    assert_eq!(outputs, vec![big, b"small".to_vec(), Vec::new()]);
+}
+
+#[test]
+fn test_read_data_into_bufwriter_file() {
+    let big = big_data();
+    let data = build_archive(&big);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.bin");
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.next_entry().unwrap();
+    {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path).unwrap());
+        assert_eq!(archive.read_data_into(&mut out).unwrap(), big.len() as u64);
+        out.flush().unwrap();
+    }
+    assert_eq!(std::fs::read(&path).unwrap(), big);
+}
+
+struct FailingWriter {
+    accepted: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.accepted >= 100_000 {
+            return Err(std::io::Error::other("disk full"));
+        }
+        self.accepted += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_read_data_into_writer_error() {
+    let data = build_archive(&big_data());
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.next_entry().unwrap();
+
+    let mut writer = FailingWriter { accepted: 0 };
+    match archive.read_data_into(&mut writer) {
+        Err(Error::Io(e)) => assert_eq!(e.to_string(), "disk full"),
+        other => panic!("expected an I/O error, got {other:?}"),
+    }
+
+    // The archive is still usable afterwards
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "small.txt");
+    let mut out = Vec::new();
+    archive.read_data_into(&mut out).unwrap();
+    assert_eq!(out, b"small");
+}
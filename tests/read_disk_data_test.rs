@@ -0,0 +1,138 @@
+use libarchive2::{ArchiveFormat, ReadArchive, ReadDisk, WriteArchive};
+use std::fs;
+use tempfile::TempDir;
+
+fn read_all(disk: &mut ReadDisk) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1000];
+    loop {
+        let n = disk.read_data(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    data
+}
+
+#[test]
+fn test_read_data_through_read_disk() {
+    let dir = TempDir::new().unwrap();
+    let contents: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(dir.path().join("file.bin"), &contents).unwrap();
+
+    let mut disk = ReadDisk::new().unwrap();
+    disk.open(dir.path()).unwrap();
+    let mut found = false;
+    while let Some(entry) = disk.next_entry().unwrap() {
+        let path = entry.pathname().unwrap();
+        let data = read_all(&mut disk);
+        if path.ends_with("file.bin") {
+            assert_eq!(entry.size_opt(), Some(contents.len() as i64));
+            assert_eq!(data, contents);
+            found = true;
+        } else {
+            assert!(data.is_empty(), "{path}");
+        }
+        if disk.can_descend() {
+            disk.descend().unwrap();
+        }
+    }
+    assert!(found);
+}
+
+#[test]
+fn test_copy_tree_into_archive() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("hello.txt"), b"hello from disk").unwrap();
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut disk = ReadDisk::new().unwrap();
+        disk.open(dir.path()).unwrap();
+        while let Some(entry) = disk.next_entry().unwrap() {
+            archive.write_header(&entry).unwrap();
+            let data = read_all(&mut disk);
+            archive.write_data(&data).unwrap();
+            if disk.can_descend() {
+                disk.descend().unwrap();
+            }
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let mut found = false;
+    while let Some(entry) = archive.next_entry().unwrap() {
+        if entry.pathname().unwrap().ends_with("hello.txt") {
+            assert_eq!(archive.read_data_to_vec().unwrap(), b"hello from disk");
+            found = true;
+        }
+    }
+    assert!(found);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_custom_name_lookup() {
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::{Arc, Mutex};
+
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("owned.txt");
+    fs::write(&file, b"x").unwrap();
+    let meta = fs::metadata(&file).unwrap();
+
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let asked_log = Arc::clone(&asked);
+    let mut disk = ReadDisk::new().unwrap();
+    disk.set_uname_lookup(move |uid| {
+        asked_log.lock().unwrap().push(uid);
+        Some(format!("user{uid}"))
+    })
+    .unwrap();
+    disk.set_gname_lookup(|_| None).unwrap();
+    disk.open(&file).unwrap();
+
+    let entry = disk.next_entry().unwrap().unwrap();
+    assert_eq!(entry.uname(), Some(format!("user{}", meta.uid())));
+    assert_eq!(entry.gname(), None);
+    assert!(asked.lock().unwrap().contains(&(meta.uid() as i64)));
+
+    // Replacing a lookup frees the old closure
+    disk.set_uname_lookup(|_| Some("again".to_string()))
+        .unwrap();
+}
+
+#[test]
+fn test_sourcepath() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("source.txt");
+    fs::write(&file, b"x").unwrap();
+
+    let mut disk = ReadDisk::new().unwrap();
+    disk.open(&file).unwrap();
+    let entry = disk.next_entry().unwrap().unwrap();
+    let sourcepath = entry.sourcepath().expect("ReadDisk sets the source path");
+    assert!(sourcepath.ends_with("source.txt"), "{sourcepath}");
+
+    // Entries read from an archive have none
+    let mut buf = vec![0u8; 16 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("source.txt", b"x").unwrap();
+        archive.finish().unwrap();
+    }
+    let mut archive = ReadArchive::open_memory(&buf[..used]).unwrap();
+    assert_eq!(archive.next_entry().unwrap().unwrap().sourcepath(), None);
+}
@@ -0,0 +1,113 @@
+use libarchive2::{ArchiveMatch, ReadDisk};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+fn build_tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(root.join("Cargo.toml"), "[package]").unwrap();
+    for sub in 0..5 {
+        let sub_dir = root.join(format!("target/debug/deps{sub}"));
+        fs::create_dir_all(&sub_dir).unwrap();
+        for i in 0..50 {
+            fs::write(sub_dir.join(format!("artifact{i}.o")), "x").unwrap();
+        }
+    }
+    dir
+}
+
+fn walk(disk: &mut ReadDisk, root: &Path) -> Vec<String> {
+    disk.open(root).unwrap();
+    let mut seen = Vec::new();
+    while let Some(entry) = disk.next_entry().unwrap() {
+        let path = entry.pathname().unwrap();
+        let relative = Path::new(&path)
+            .strip_prefix(root)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        seen.push(relative);
+        if disk.can_descend() {
+            disk.descend().unwrap();
+        }
+    }
+    seen
+}
+
+#[test]
+fn test_set_matching_skips_excluded_directory() {
+    let tree = build_tree();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_pattern("target").unwrap();
+
+    let mut disk = ReadDisk::new().unwrap();
+    disk.set_matching(matcher).unwrap();
+    let seen = walk(&mut disk, tree.path());
+
+    assert!(seen.iter().any(|p| p == "src/main.rs"), "{seen:?}");
+    assert!(seen.iter().any(|p| p == "Cargo.toml"), "{seen:?}");
+    assert!(!seen.iter().any(|p| p.starts_with("target")), "{seen:?}");
+}
+
+#[test]
+fn test_set_matching_callback_reports_excluded_directory() {
+    let tree = build_tree();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_pattern("target").unwrap();
+
+    let excluded = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&excluded);
+    let mut disk = ReadDisk::new().unwrap();
+    disk.set_matching_with_callback(matcher, move |path| {
+        log.lock().unwrap().push(path.to_string());
+    })
+    .unwrap();
+    let seen = walk(&mut disk, tree.path());
+    assert!(!seen.iter().any(|p| p.starts_with("target")));
+
+    // Only the directory itself is reported; its 250 files are never visited
+    let excluded = excluded.lock().unwrap();
+    assert_eq!(excluded.len(), 1, "{excluded:?}");
+    assert!(excluded[0].ends_with("target"), "{excluded:?}");
+}
+
+#[test]
+fn test_without_matching_everything_is_visited() {
+    let tree = build_tree();
+    let mut disk = ReadDisk::new().unwrap();
+    let seen = walk(&mut disk, tree.path());
+    assert_eq!(
+        seen.iter()
+            .filter(|p| p.starts_with("target/debug/deps"))
+            .filter(|p| p.ends_with(".o"))
+            .count(),
+        250
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_set_matching_owner_exclusion() {
+    use std::os::unix::fs::MetadataExt;
+
+    let tree = build_tree();
+    let uid = fs::metadata(tree.path()).unwrap().uid();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.exclude_uid(uid as i64).unwrap();
+
+    let excluded = Arc::new(Mutex::new(0));
+    let count = Arc::clone(&excluded);
+    let mut disk = ReadDisk::new().unwrap();
+    disk.set_matching_with_callback(matcher, move |_| *count.lock().unwrap() += 1)
+        .unwrap();
+    let seen = walk(&mut disk, tree.path());
+
+    // Every file belongs to us, so even the root is excluded and nothing is
+    // descended into
+    assert!(seen.is_empty(), "{seen:?}");
+    assert_eq!(*excluded.lock().unwrap(), 1);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use libarchive2::{ArchiveFormat, Error, ReadArchive, ReadOption, WriteArchive};
+use std::io::Write;
+
+const LATIN1_TAR: &str = "tests/fixtures/latin1.tar";
+
+fn configured() -> ReadArchive<'static> {
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_filter_all().unwrap();
+    archive.support_format_all().unwrap();
+    archive
+}
+
+/// A stored zip whose entry data no longer matches its CRC
+fn corrupt_zip(dir: &std::path::Path) -> std::path::PathBuf {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .option("zip:compression", "store")
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("data.txt", b"original contents").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let pos = buf.windows(8).position(|w| w == b"original").unwrap();
+    buf[pos..pos + 8].copy_from_slice(b"modified");
+
+    let path = dir.join("corrupt.zip");
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(&buf)
+        .unwrap();
+    path
+}
+
+#[test]
+fn test_tar_header_charset() {
+    let mut archive = configured();
+    archive
+        .set_read_option(ReadOption::TarHeaderCharset("ISO-8859-1".to_string()))
+        .unwrap();
+    let mut archive = archive.open_file(LATIN1_TAR).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    if common::utf8_locale() {
+        assert_eq!(entry.pathname().unwrap(), "caf\u{e9}.txt");
+        assert_eq!(entry.uname().unwrap(), "jos\u{e9}");
+    }
+}
+
+#[test]
+fn test_set_options_string() {
+    let mut archive = configured();
+    archive.set_options("tar:hdrcharset=ISO-8859-1").unwrap();
+    let mut archive = archive.open_file(LATIN1_TAR).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    if common::utf8_locale() {
+        assert_eq!(entry.pathname().unwrap(), "caf\u{e9}.txt");
+    }
+}
+
+#[test]
+fn test_zip_ignore_crc32() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = corrupt_zip(dir.path());
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert!(matches!(
+        archive.read_data_to_vec(),
+        Err(Error::Archive { .. })
+    ));
+
+    for configure in [
+        |a: &mut ReadArchive| a.set_read_option(ReadOption::ZipIgnoreCrc32(true)),
+        |a: &mut ReadArchive| a.set_options("zip:ignorecrc32"),
+    ] {
+        let mut archive = configured();
+        configure(&mut archive).unwrap();
+        let mut archive = archive.open_file(&path).unwrap();
+        archive.next_entry().unwrap().unwrap();
+        assert_eq!(archive.read_data_to_vec().unwrap(), b"modified contents");
+    }
+}
+
+#[test]
+fn test_invalid_read_options() {
+    let mut archive = configured();
+    assert!(archive.set_options("zip:no-such-option").is_err());
+    assert!(archive.set_options("no-such-module:ignorecrc32").is_err());
+    assert!(matches!(
+        archive.set_options("zip:ignorecrc32\0"),
+        Err(Error::InvalidArgument(_))
+    ));
+
+    // Options for a format are only known once it is registered
+    let mut archive = ReadArchive::new().unwrap();
+    assert!(
+        archive
+            .set_read_option(ReadOption::ZipIgnoreCrc32(true))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_open_file_needs_formats() {
+    let archive = ReadArchive::new().unwrap();
+    assert!(archive.open_file(LATIN1_TAR).is_err());
+}
@@ -0,0 +1,154 @@
+use libarchive2::{ArchiveFormat, Error, FormatOption, ReadArchive, WriteArchive, ZipEncryption};
+use std::sync::{Arc, Mutex};
+
+const SECRET: &[u8] = b"This is secret information!";
+
+/// A zip with one AES-256 entry
+///
+/// ZipCrypto checks a wrong passphrase against a single byte, so it would
+/// accept one now and then; AES entries fail reliably.
+fn encrypted_zip() -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .passphrase("correct horse")
+            .format_option(FormatOption::ZipEncryption(ZipEncryption::Aes256))
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("secret.txt", SECRET).unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn configured_reader(passphrase: &str) -> ReadArchive<'static> {
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_filter_all().unwrap();
+    archive.support_format_all().unwrap();
+    archive.set_passphrase(passphrase).unwrap();
+    archive
+}
+
+#[test]
+fn test_set_passphrase_open_buffer() {
+    let data = encrypted_zip();
+    let mut archive = configured_reader("correct horse")
+        .open_buffer(&data)
+        .unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(entry.is_encrypted());
+    assert_eq!(archive.read_data_to_vec().unwrap(), SECRET);
+    assert!(archive.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_set_passphrase_open_stream() {
+    let data = encrypted_zip();
+    let mut archive = configured_reader("correct horse")
+        .open_stream(std::io::Cursor::new(data))
+        .unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), SECRET);
+}
+
+#[test]
+fn test_open_buffer_without_passphrase() {
+    let data = encrypted_zip();
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_format_all().unwrap();
+    let mut archive = archive.open_buffer(&data).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert!(archive.read_data_to_vec().is_err());
+}
+
+#[test]
+fn test_set_passphrase_after_open() {
+    let data = encrypted_zip();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    assert!(matches!(
+        archive.set_passphrase("correct horse"),
+        Err(Error::InvalidArgument(_))
+    ));
+    // The archive is still usable
+    assert_eq!(
+        archive.next_entry().unwrap().unwrap().pathname().unwrap(),
+        "secret.txt"
+    );
+}
+
+/// Open the encrypted zip with a callback offering `candidates` in turn,
+/// returning the reader and the number of times the callback ran
+fn open_with_callback<'a>(
+    data: &'a [u8],
+    candidates: &[&str],
+) -> (ReadArchive<'a>, Arc<Mutex<usize>>) {
+    let calls = Arc::new(Mutex::new(0));
+    let mut remaining: Vec<String> = candidates.iter().rev().map(|s| s.to_string()).collect();
+    let counter = Arc::clone(&calls);
+
+    let mut archive = ReadArchive::new().unwrap();
+    archive.support_filter_all().unwrap();
+    archive.support_format_all().unwrap();
+    archive
+        .set_passphrase_callback(move || {
+            *counter.lock().unwrap() += 1;
+            remaining.pop()
+        })
+        .unwrap();
+    (archive.open_buffer(data).unwrap(), calls)
+}
+
+#[test]
+fn test_passphrase_callback_called_for_encrypted_entry() {
+    let data = encrypted_zip();
+    let (mut archive, calls) = open_with_callback(&data, &["correct horse"]);
+
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(*calls.lock().unwrap(), 0, "asked before the data was read");
+    assert_eq!(archive.read_data_to_vec().unwrap(), SECRET);
+    assert_eq!(*calls.lock().unwrap(), 1);
+}
+
+#[test]
+fn test_passphrase_callback_tries_several() {
+    let data = encrypted_zip();
+    let (mut archive, calls) = open_with_callback(&data, &["wrong", "also wrong", "correct horse"]);
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.zip_info().unwrap().method, Some(99));
+    assert_eq!(archive.read_data_to_vec().unwrap(), SECRET);
+    assert_eq!(*calls.lock().unwrap(), 3);
+}
+
+#[test]
+fn test_passphrase_callback_none_aborts() {
+    let data = encrypted_zip();
+    let (mut archive, calls) = open_with_callback(&data, &[]);
+
+    archive.next_entry().unwrap().unwrap();
+    match archive.read_data_to_vec() {
+        Err(Error::Archive { message, .. }) => {
+            assert!(
+                message.to_lowercase().contains("passphrase"),
+                "unexpected message: {}",
+                message
+            );
+        }
+        other => panic!("expected a passphrase error, got {:?}", other),
+    }
+    assert_eq!(*calls.lock().unwrap(), 1);
+}
+
+#[test]
+fn test_passphrase_callback_after_open() {
+    let data = encrypted_zip();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    assert!(matches!(
+        archive.set_passphrase_callback(|| None),
+        Err(Error::InvalidArgument(_))
+    ));
+    archive.next_entry().unwrap().unwrap();
+}
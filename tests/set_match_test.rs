@@ -0,0 +1,117 @@
+use libarchive2::{
+    ArchiveFormat, ArchiveMatch, EntryMut, ExtractFlags, FileType, ReadArchive, WriteArchive,
+};
+use std::time::{Duration, SystemTime};
+
+const FILES: [(&str, &[u8]); 5] = [
+    ("a.txt", b"alpha"),
+    ("b.bin", b"\x00\x01\x02\x03"),
+    ("c.txt", b"charlie"),
+    ("d.log", b"delta log"),
+    ("e.txt", b"echo"),
+];
+
+fn build_tar() -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for (i, (name, data)) in FILES.iter().enumerate() {
+            let mut entry = EntryMut::new();
+            entry.set_pathname(name).unwrap();
+            entry.set_file_type(FileType::RegularFile);
+            entry.set_perm(0o644).unwrap();
+            entry.set_size(data.len() as i64);
+            entry.set_mtime(SystemTime::UNIX_EPOCH + Duration::from_secs(1000 * (i as u64 + 1)));
+            archive.write_header(&entry).unwrap();
+            archive.write_data(data).unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn txt_matcher() -> ArchiveMatch {
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_pattern("*.txt").unwrap();
+    matcher
+}
+
+#[test]
+fn test_next_entry_skips_excluded() {
+    let data = build_tar();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_match(txt_matcher());
+
+    let mut seen = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        let name = entry.pathname().unwrap();
+        seen.push((name, archive.read_data_to_vec().unwrap()));
+    }
+    assert_eq!(
+        seen,
+        vec![
+            ("a.txt".to_string(), b"alpha".to_vec()),
+            ("c.txt".to_string(), b"charlie".to_vec()),
+            ("e.txt".to_string(), b"echo".to_vec()),
+        ]
+    );
+    // Skipped headers were still read
+    assert_eq!(archive.stats().entries, 5);
+}
+
+#[test]
+fn test_time_filter() {
+    let data = build_tar();
+    let mut matcher = ArchiveMatch::new().unwrap();
+    matcher.include_time_newer_than(2500, 0).unwrap();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_match(matcher);
+
+    let names: Vec<_> = archive
+        .entries()
+        .map(|e| e.unwrap().pathname.unwrap())
+        .collect();
+    assert_eq!(names, vec!["c.txt", "d.log", "e.txt"]);
+}
+
+#[test]
+fn test_extract_to_dir_respects_matcher() {
+    let data = build_tar();
+    let dir = tempfile::tempdir().unwrap();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.set_match(txt_matcher());
+
+    let stats = archive
+        .extract_to_dir(dir.path(), ExtractFlags::PERM)
+        .unwrap();
+    assert_eq!(stats.files, 3);
+    assert_eq!(std::fs::read(dir.path().join("c.txt")).unwrap(), b"charlie");
+    assert!(!dir.path().join("b.bin").exists());
+    assert!(!dir.path().join("d.log").exists());
+}
+
+#[test]
+fn test_take_match() {
+    let data = build_tar();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let mut matcher = txt_matcher();
+    matcher.include_pattern("*.md").unwrap();
+    archive.set_match(matcher);
+
+    archive.next_entry().unwrap().unwrap();
+    let mut matcher = archive.take_match().unwrap();
+    assert!(archive.take_match().is_none());
+    assert_eq!(matcher.unmatched_inclusions().unwrap(), vec!["*.md"]);
+
+    // Without the matcher every remaining entry is returned
+    let mut rest = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        rest.push(entry.pathname().unwrap());
+    }
+    assert_eq!(rest, vec!["b.bin", "c.txt", "d.log", "e.txt"]);
+}
@@ -45,7 +45,7 @@ fn test_sparse_file_write_and_read() {
         let entry = archive.next_entry().unwrap().unwrap();
 
         assert_eq!(entry.pathname().unwrap(), "sparse.bin");
-        assert_eq!(entry.size(), 1024 * 1024);
+        assert_eq!(entry.size_opt(), Some(1024 * 1024));
 
         // Read blocks
         let mut blocks = Vec::new();
@@ -369,7 +369,7 @@ fn test_sparse_large_offset() {
     // Read back and verify
     let mut read_archive = ReadArchive::open(&archive_path).unwrap();
     let entry = read_archive.next_entry().unwrap().unwrap();
-    assert_eq!(entry.size(), 100_000_000);
+    assert_eq!(entry.size_opt(), Some(100_000_000));
 
     // Find the block with our data
     let mut found = false;
@@ -383,3 +383,64 @@ fn test_sparse_large_offset() {
 
     assert!(found, "Should find data at large offset");
 }
+
+/// Rebuild an entry from its data blocks, checking that offsets never decrease
+fn reconstruct(archive: &mut ReadArchive, size: usize) -> (Vec<u8>, Vec<i64>) {
+    let mut contents = vec![0u8; size];
+    let mut offsets = Vec::new();
+    let mut end = 0;
+    while let Some((offset, data)) = archive.read_data_block().unwrap() {
+        assert!(
+            offset >= end,
+            "block at {} overlaps previous end {}",
+            offset,
+            end
+        );
+        let start = offset as usize;
+        contents[start..start + data.len()].copy_from_slice(&data);
+        end = offset + data.len() as i64;
+        offsets.push(offset);
+    }
+    (contents, offsets)
+}
+
+#[test]
+fn test_sparse_fixture_round_trip() {
+    // GNU tar --sparse pax archive: 1 MiB file with data at 0, 512 KiB and the end
+    let mut archive = ReadArchive::open("tests/fixtures/sparse.tar.gz").unwrap();
+    let mut found = false;
+    while let Some(entry) = archive.next_entry().unwrap() {
+        if entry.file_type() != FileType::RegularFile {
+            continue;
+        }
+        let size = entry.size_opt().unwrap() as usize;
+        assert_eq!(size, 1024 * 1024);
+
+        let (contents, offsets) = reconstruct(&mut archive, size);
+        assert!(offsets.len() >= 3);
+        assert!(offsets.contains(&0));
+        assert!(offsets.contains(&(512 * 1024)));
+        assert_eq!(&contents[..5], b"START");
+        assert_eq!(&contents[512 * 1024..512 * 1024 + 6], b"MIDDLE");
+        assert_eq!(&contents[size - 3..], b"END");
+        assert_eq!(contents.iter().filter(|&&b| b != 0).count(), 14);
+        found = true;
+    }
+    assert!(found);
+}
+
+#[test]
+fn test_sparse_trailing_hole() {
+    // 1 MiB file with "HEAD" at 0, "BODY" at 300 KiB and a hole up to the end
+    let mut archive = ReadArchive::open("tests/fixtures/trailing_hole.tar.gz").unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    let size = entry.size_opt().unwrap() as usize;
+    assert_eq!(size, 1024 * 1024);
+
+    let (contents, offsets) = reconstruct(&mut archive, size);
+    assert_eq!(offsets, vec![0, 300 * 1024]);
+    assert_eq!(&contents[..4], b"HEAD");
+    assert_eq!(&contents[300 * 1024..300 * 1024 + 4], b"BODY");
+    assert_eq!(contents.iter().filter(|&&b| b != 0).count(), 8);
+    assert!(archive.next_entry().unwrap().is_none());
+}
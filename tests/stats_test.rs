@@ -0,0 +1,225 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, ProgressCallback, ProgressTracker, ReadArchive, WriteArchive,
+};
+use std::sync::{Arc, Mutex};
+
+fn write_test_archive(path: &std::path::Path) -> libarchive2::WriteStats {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(path)
+        .unwrap();
+
+    archive.add_file("zeros.bin", &vec![0u8; 100_000]).unwrap();
+    archive.add_file("hello.txt", b"Hello, stats!").unwrap();
+    archive.add_directory("dir").unwrap();
+
+    let stats = archive.stats();
+    archive.finish().unwrap();
+    stats
+}
+
+#[test]
+fn test_write_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.tar.gz");
+
+    let stats = write_test_archive(&path);
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.data_bytes, 100_000 + 13);
+    assert!(stats.uncompressed_bytes >= stats.data_bytes);
+    assert!(stats.compressed_bytes < stats.uncompressed_bytes);
+}
+
+fn gzip_tar<'a>() -> WriteArchive<'a> {
+    WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+}
+
+/// Write 1 MB of compressible data in 16 entries
+fn write_compressible(mut archive: WriteArchive<'_>) -> libarchive2::WriteStats {
+    let line = b"the quick brown fox jumps over the lazy dog\n";
+    let data: Vec<u8> = line.iter().copied().cycle().take(64 * 1024).collect();
+    for i in 0..16 {
+        archive.add_file(format!("{}.txt", i), &data).unwrap();
+    }
+    archive.finish().unwrap()
+}
+
+#[test]
+fn test_finish_returns_final_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("final.tar.gz");
+
+    let archive = gzip_tar().open_file(&path).unwrap();
+    let stats = write_compressible(archive);
+    assert_eq!(stats.entries, 16);
+    assert_eq!(stats.data_bytes, 1024 * 1024);
+    assert!(stats.compressed_bytes < stats.uncompressed_bytes);
+    assert!(stats.format_overhead() > 0);
+    // Unlike stats() mid-write, the counters include the flushed trailer
+    let file_len = std::fs::metadata(&path).unwrap().len();
+    assert!(stats.compressed_bytes > 0 && stats.compressed_bytes <= file_len);
+
+    // The same entries written to memory give the same counts. The gzip
+    // output itself may differ by a few bytes, so check it against the buffer
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    let archive = gzip_tar().open_memory(&mut buffer, &mut used).unwrap();
+    let memory_stats = write_compressible(archive);
+    assert_eq!(memory_stats.entries, stats.entries);
+    assert_eq!(memory_stats.data_bytes, stats.data_bytes);
+    assert_eq!(memory_stats.uncompressed_bytes, stats.uncompressed_bytes);
+    assert!(memory_stats.compressed_bytes > 0 && memory_stats.compressed_bytes <= used as u64);
+}
+
+#[test]
+fn test_write_stats_before_open() {
+    let stats = WriteArchive::new().stats();
+    assert_eq!(stats, libarchive2::WriteStats::default());
+}
+
+#[test]
+fn test_read_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.tar.gz");
+    write_test_archive(&path);
+    let file_size = std::fs::metadata(&path).unwrap().len();
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let mut sizes = Vec::new();
+    while archive.next_entry().unwrap().is_some() {
+        assert_eq!(archive.entry_bytes_read(), 0);
+        archive.read_data_to_vec().unwrap();
+        sizes.push(archive.entry_bytes_read());
+    }
+    assert_eq!(sizes, vec![100_000, 13, 0]);
+
+    let stats = archive.stats();
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.data_bytes, 100_000 + 13);
+    assert!(stats.compressed_bytes > 0 && stats.compressed_bytes <= file_size);
+    assert!(stats.uncompressed_bytes >= stats.data_bytes);
+    assert!(stats.compression_ratio() > 1.0);
+}
+
+#[test]
+fn test_read_stats_skipped_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stats.tar.gz");
+    write_test_archive(&path);
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    while archive.next_entry().unwrap().is_some() {
+        archive.skip_data().unwrap();
+    }
+
+    let stats = archive.stats();
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.data_bytes, 0);
+    assert!(stats.uncompressed_bytes >= 100_000);
+}
+
+/// Write 16 entries of 64 KiB that don't compress, about 1 MiB in all
+fn write_progress_archive(path: &std::path::Path) -> u64 {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_file(path)
+        .unwrap();
+    assert_eq!(archive.bytes_written(), 0);
+
+    let mut state = 0x2545_f491_u32;
+    for i in 0..16 {
+        let data: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        archive
+            .add_file(format!("file{:02}.bin", i), &data)
+            .unwrap();
+    }
+    assert!(archive.bytes_written() > 0);
+    archive.finish().unwrap();
+    std::fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn test_read_positions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("progress.tar.gz");
+    let file_size = write_progress_archive(&path);
+    assert!(file_size > 1024 * 1024);
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    assert_eq!(archive.header_position(), 0);
+
+    let mut positions = Vec::new();
+    let mut compressed = Vec::new();
+    for _ in 0..8 {
+        archive.next_entry().unwrap().unwrap();
+        positions.push(archive.header_position());
+        archive.read_data_to_vec().unwrap();
+        compressed.push(archive.bytes_read_compressed());
+    }
+
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{:?}", positions);
+    assert!(
+        compressed.windows(2).all(|w| w[0] <= w[1]),
+        "{:?}",
+        compressed
+    );
+    let halfway = archive.bytes_read_compressed();
+    assert!(
+        halfway > 0 && halfway < file_size,
+        "{} of {}",
+        halfway,
+        file_size
+    );
+    assert!(archive.bytes_read_uncompressed() > *positions.last().unwrap());
+    assert_eq!(archive.stats().compressed_bytes, halfway);
+
+    while archive.next_entry().unwrap().is_some() {}
+    assert_eq!(archive.bytes_read_compressed(), file_size);
+}
+
+/// Records every (bytes_processed, total_bytes) pair it is given
+struct Recorder(Arc<Mutex<Vec<(u64, u64)>>>);
+
+impl ProgressCallback for Recorder {
+    fn on_progress(&mut self, bytes_processed: u64, total_bytes: u64) {
+        self.0.lock().unwrap().push((bytes_processed, total_bytes));
+    }
+}
+
+#[test]
+fn test_progress_tracker_follows_header_position() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("progress.tar.gz");
+    write_progress_archive(&path);
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let mut tracker = ProgressTracker::new(Recorder(Arc::clone(&reported)));
+    tracker.set_total(u64::MAX);
+
+    let mut archive = ReadArchive::open(&path).unwrap();
+    let mut entries = 0;
+    while archive.next_entry().unwrap().is_some() {
+        tracker.set_position(archive.header_position());
+        entries += 1;
+    }
+
+    let reported = reported.lock().unwrap();
+    assert_eq!(reported.len(), entries);
+    assert!(
+        reported.windows(2).all(|w| w[0].0 < w[1].0),
+        "{:?}",
+        reported
+    );
+    assert!(reported.iter().all(|&(_, total)| total == u64::MAX));
+}
@@ -0,0 +1,161 @@
+use libarchive2::{ArchiveFormat, EntryMut, FileType, ReadArchive, WriteArchive, WriteDisk};
+use std::path::Path;
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+/// extract_current writes relative to the working directory, which is
+/// process-wide, so the tests in this file take turns changing it
+static CWD: Mutex<()> = Mutex::new(());
+
+fn github_tarball() -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_directory("project-1.0").unwrap();
+        archive.add_directory("project-1.0/src").unwrap();
+        archive
+            .add_file("project-1.0/src/main.rs", b"fn main() {}")
+            .unwrap();
+        archive.add_file("project-1.0/README", b"readme").unwrap();
+        archive
+            .add_hardlink("project-1.0/README.link", "project-1.0/README")
+            .unwrap();
+        archive.add_file("top-level", b"dropped").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn files_under(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for entry in std::fs::read_dir(current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                stack.push(path.clone());
+            }
+            files.push(
+                path.strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn test_extract_current_strip_components() {
+    let _lock = CWD.lock().unwrap();
+    let data = github_tarball();
+    let temp_dir = TempDir::new().unwrap();
+    let old_cwd = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let result = (|| -> libarchive2::Result<()> {
+        let mut archive = ReadArchive::open_memory(&data)?;
+        let mut disk = WriteDisk::new()?;
+        disk.set_strip_components(1);
+        while archive.next_entry()?.is_some() {
+            archive.extract_current(&mut disk)?;
+        }
+        Ok(())
+    })();
+    std::env::set_current_dir(old_cwd).unwrap();
+    result.unwrap();
+
+    assert_eq!(
+        files_under(temp_dir.path()),
+        vec!["README", "README.link", "src", "src/main.rs"]
+    );
+    let root = temp_dir.path();
+    assert_eq!(
+        std::fs::read(root.join("src/main.rs")).unwrap(),
+        b"fn main() {}"
+    );
+    assert_eq!(std::fs::read(root.join("README.link")).unwrap(), b"readme");
+}
+
+#[test]
+fn test_write_header_strip_components() {
+    let _lock = CWD.lock().unwrap();
+    let data = github_tarball();
+    let temp_dir = TempDir::new().unwrap();
+    let old_cwd = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let result = (|| -> libarchive2::Result<()> {
+        let mut archive = ReadArchive::open_memory(&data)?;
+        let mut disk = WriteDisk::new()?;
+        disk.set_strip_components(2);
+        while let Some(entry) = archive.next_entry()? {
+            let mut out = EntryMut::new();
+            out.set_pathname(entry.pathname().unwrap())?;
+            out.set_file_type(entry.file_type());
+            if let Some(size) = entry.size_opt() {
+                out.set_size(size);
+            }
+            out.set_perm(0o755)?;
+            if entry.file_type() == FileType::Directory {
+                disk.write_header(&out)?;
+                disk.finish_entry()?;
+                continue;
+            }
+            disk.write_header(&out)?;
+            // Kept unstripped so the caller's entry is untouched
+            assert_eq!(out.as_entry().pathname(), entry.pathname());
+            archive.copy_data_to_disk(&mut disk)?;
+            disk.finish_entry()?;
+        }
+        Ok(())
+    })();
+    std::env::set_current_dir(old_cwd).unwrap();
+    result.unwrap();
+
+    // Only project-1.0/src/main.rs has a third component
+    assert_eq!(files_under(temp_dir.path()), vec!["main.rs"]);
+}
+
+#[test]
+fn test_extract_current_twice_strips_once() {
+    let _lock = CWD.lock().unwrap();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_directory("top/mid/leaf").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    let temp_dir = TempDir::new().unwrap();
+    let old_cwd = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let result = (|| -> libarchive2::Result<()> {
+        let mut archive = ReadArchive::open_memory(&buf)?;
+        let mut disk = WriteDisk::new()?;
+        disk.set_strip_components(1);
+        while archive.next_entry()?.is_some() {
+            // The reader's header is not stripped in place, so a second
+            // extraction goes to the same path
+            archive.extract_current(&mut disk)?;
+            archive.extract_current(&mut disk)?;
+        }
+        Ok(())
+    })();
+    std::env::set_current_dir(old_cwd).unwrap();
+    result.unwrap();
+
+    assert_eq!(files_under(temp_dir.path()), vec!["mid", "mid/leaf"]);
+}
@@ -0,0 +1,74 @@
+use libarchive2::{ArchiveFormat, Error, ReadArchive, ReadFormat};
+
+const RAR_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/stored.rar");
+
+fn reader_for(format: ArchiveFormat) -> Result<ReadArchive<'static>, Error> {
+    let mut archive = ReadArchive::new()?;
+    archive.support_filter_all()?;
+    archive.support_format(ReadFormat::Format(format))?;
+    Ok(archive)
+}
+
+#[test]
+fn test_rar_only_reader_reads_rar() {
+    let mut archive = reader_for(ArchiveFormat::Rar)
+        .unwrap()
+        .open_file(RAR_FIXTURE)
+        .unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "hello.txt");
+    assert_eq!(
+        archive.read_data_to_vec().unwrap(),
+        b"Hello from a stored RAR entry\n"
+    );
+    assert_eq!(
+        archive.format_code(),
+        libarchive2_sys::ARCHIVE_FORMAT_RAR as i32
+    );
+    assert!(archive.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_zip_only_reader_rejects_rar() {
+    let result = reader_for(ArchiveFormat::Zip)
+        .unwrap()
+        .open_file(RAR_FIXTURE)
+        .and_then(|mut archive| archive.next_entry().map(|_| ()));
+    match result {
+        Err(Error::Archive { message, .. }) => {
+            assert!(
+                message.to_lowercase().contains("format"),
+                "unexpected message: {}",
+                message
+            );
+        }
+        other => panic!("expected a format error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_only_formats_accepted() {
+    for format in [
+        ArchiveFormat::Rar,
+        ArchiveFormat::Rar5,
+        ArchiveFormat::Lha,
+        ArchiveFormat::Cab,
+        ArchiveFormat::SevenZip,
+        ArchiveFormat::TarGnu,
+        ArchiveFormat::CpioNewc,
+        ArchiveFormat::Warc,
+    ] {
+        assert!(reader_for(format).is_ok(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_shar_is_write_only() {
+    match reader_for(ArchiveFormat::Shar) {
+        Err(Error::InvalidArgument(message)) => {
+            assert!(message.contains("Shar"), "{}", message)
+        }
+        Err(other) => panic!("expected InvalidArgument, got {:?}", other),
+        Ok(_) => panic!("expected InvalidArgument, got a reader"),
+    }
+}
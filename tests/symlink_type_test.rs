@@ -0,0 +1,100 @@
+use libarchive2::{ArchiveFormat, EntryMut, FileType, ReadArchive, SymlinkType, WriteArchive};
+
+fn round_trip(format: ArchiveFormat) -> Vec<(String, SymlinkType)> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for (name, symlink_type) in [
+            ("to_file", SymlinkType::File),
+            ("to_dir", SymlinkType::Directory),
+            ("plain", SymlinkType::Undefined),
+        ] {
+            let mut entry = EntryMut::new();
+            entry.set_pathname(name).unwrap();
+            entry.set_file_type(FileType::SymbolicLink);
+            entry.set_symlink("target").unwrap();
+            entry.set_perm(0o777).unwrap();
+            entry.set_symlink_type(symlink_type);
+            assert_eq!(entry.as_entry().symlink_type(), symlink_type);
+            archive.write_header(&entry).unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let mut result = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        result.push((entry.pathname().unwrap(), entry.symlink_type()));
+    }
+    result
+}
+
+#[test]
+fn test_symlink_type_default() {
+    let entry = EntryMut::new();
+    assert_eq!(entry.as_entry().symlink_type(), SymlinkType::Undefined);
+}
+
+#[test]
+fn test_symlink_type_pax_round_trip() {
+    assert_eq!(
+        round_trip(ArchiveFormat::TarPax),
+        vec![
+            ("to_file".to_string(), SymlinkType::File),
+            ("to_dir".to_string(), SymlinkType::Directory),
+            ("plain".to_string(), SymlinkType::Undefined),
+        ]
+    );
+}
+
+#[test]
+fn test_symlink_type_not_stored_by_zip() {
+    for (_, symlink_type) in round_trip(ArchiveFormat::Zip) {
+        assert_eq!(symlink_type, SymlinkType::Undefined);
+    }
+}
+
+/// Extract a symlink to an existing directory, recorded as a directory symlink
+fn extract_dir_symlink(root: &std::path::Path) -> std::path::PathBuf {
+    std::fs::create_dir(root.join("target")).unwrap();
+    std::fs::write(root.join("target").join("inside.txt"), b"inside").unwrap();
+    let link = root.join("link");
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname(&link).unwrap();
+    entry.set_file_type(FileType::SymbolicLink);
+    entry.set_symlink("target").unwrap();
+    entry.set_perm(0o777).unwrap();
+    entry.set_symlink_type(SymlinkType::Directory);
+
+    let mut disk = libarchive2::WriteDisk::new().unwrap();
+    disk.write_header(&entry).unwrap();
+    disk.finish_entry().unwrap();
+    link
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dir_symlink_extracts_on_unix() {
+    let dir = tempfile::tempdir().unwrap();
+    let link = extract_dir_symlink(dir.path());
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(std::fs::read(link.join("inside.txt")).unwrap(), b"inside");
+}
+
+#[cfg(windows)]
+#[test]
+fn test_dir_symlink_extracts_as_directory_link_on_windows() {
+    use std::os::windows::fs::FileTypeExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let link = extract_dir_symlink(dir.path());
+    let file_type = link.symlink_metadata().unwrap().file_type();
+    assert!(file_type.is_symlink_dir(), "{file_type:?}");
+    assert_eq!(std::fs::read(link.join("inside.txt")).unwrap(), b"inside");
+}
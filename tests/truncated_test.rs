@@ -0,0 +1,82 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, Error, ReadArchive, WriteArchive};
+
+/// Pseudo-random (incompressible) data so the compressed stream is large
+fn noise(len: usize) -> Vec<u8> {
+    let mut state: u32 = 1;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+fn build_tar_gz() -> Vec<u8> {
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        archive.add_file("a.bin", &noise(100_000)).unwrap();
+        archive.add_file("b.bin", &noise(50_000)).unwrap();
+        archive.finish().unwrap();
+    }
+    buffer.truncate(used);
+    buffer
+}
+
+fn read_all(data: &[u8]) -> libarchive2::Result<()> {
+    let mut archive = ReadArchive::open_memory(data)?;
+    while archive.next_entry()?.is_some() {
+        archive.read_data_to_vec()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_complete_archive_reads() {
+    read_all(&build_tar_gz()).unwrap();
+}
+
+#[test]
+fn test_truncated_archive() {
+    let data = build_tar_gz();
+    let len = data.len();
+
+    for cut in [1024, len / 3, len / 2, len - 1024, len - 10] {
+        match read_all(&data[..cut]) {
+            Err(Error::Truncated { bytes_read }) => {
+                assert!(bytes_read <= cut as u64, "cut at {cut}");
+            }
+            other => panic!("cut at {cut}: expected Truncated, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_corrupt_archive_is_not_truncated() {
+    let mut data = build_tar_gz();
+    // Damage the first tar header (stored uncompressed inside the deflate stream,
+    // since the payload doesn't compress) without changing the length
+    data[100] ^= 0x10;
+
+    match read_all(&data) {
+        Err(Error::Archive { .. }) => {}
+        other => panic!("expected a generic archive error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_truncated_io_read_is_unexpected_eof() {
+    use std::io::Read;
+
+    let data = build_tar_gz();
+    let mut archive = ReadArchive::open_memory(&data[..data.len() / 2]).unwrap();
+    archive.next_entry().unwrap().unwrap();
+
+    let err = archive.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
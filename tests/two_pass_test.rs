@@ -0,0 +1,149 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, ReadArchive, WriteArchive};
+use std::io::Read;
+use std::path::Path;
+
+fn write_tar(path: &Path, compression: CompressionFormat, files: &[(&str, Vec<u8>)]) {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(compression)
+        .open_file(path)
+        .unwrap();
+    for (name, data) in files {
+        archive.add_file(name, data).unwrap();
+    }
+    archive.add_directory("dir").unwrap();
+    archive.finish().unwrap();
+}
+
+fn sample_files() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("a.txt", b"first file".to_vec()),
+        ("dir/b.bin", vec![42u8; 100_000]),
+        ("dir/c.txt", "third ".repeat(500).into_bytes()),
+    ]
+}
+
+#[test]
+fn test_two_pass_totals_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar.zst");
+    let files = sample_files();
+    write_tar(&path, CompressionFormat::Zstd, &files);
+
+    let mut two_pass = ReadArchive::open_two_pass(&path).unwrap();
+    let mut scanned_names = Vec::new();
+    let scan = two_pass
+        .scan(|entry| scanned_names.push(entry.pathname().unwrap()))
+        .unwrap();
+    assert_eq!(scan.entries, 4);
+    let expected_bytes: usize = files.iter().map(|(_, data)| data.len()).sum();
+    assert_eq!(scan.bytes, expected_bytes as u64);
+
+    let mut contents = Vec::new();
+    let processed = two_pass
+        .process(|entry, data| {
+            let mut buf = Vec::new();
+            // Mix the io::Read impl with the direct methods
+            if entry.pathname().unwrap() == "dir/b.bin" {
+                data.read_to_end(&mut buf)?;
+            } else {
+                buf = data.read_to_vec()?;
+            }
+            assert_eq!(data.bytes_read(), buf.len() as u64);
+            contents.push((entry.pathname().unwrap(), buf));
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(processed, scan);
+    let names: Vec<_> = contents.iter().map(|(name, _)| name.clone()).collect();
+    assert_eq!(names, scanned_names);
+    for ((name, data), (expected_name, expected)) in contents.iter().zip(&files) {
+        assert_eq!(name, expected_name);
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn test_two_pass_requires_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar.zst");
+    write_tar(&path, CompressionFormat::Zstd, &sample_files());
+
+    let mut two_pass = ReadArchive::open_two_pass(&path).unwrap();
+    assert!(two_pass.process(|_, _| Ok(())).is_err());
+}
+
+#[test]
+fn test_two_pass_detects_changed_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar.zst");
+    write_tar(&path, CompressionFormat::Zstd, &sample_files());
+
+    let mut two_pass = ReadArchive::open_two_pass(&path).unwrap();
+    two_pass.scan(|_| {}).unwrap();
+
+    write_tar(
+        &path,
+        CompressionFormat::Zstd,
+        &[("other.txt", b"replaced".to_vec())],
+    );
+    let mut called = false;
+    let result = two_pass.process(|_, _| {
+        called = true;
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert!(!called);
+}
+
+#[test]
+fn test_two_pass_multi_volume() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar");
+    write_tar(&path, CompressionFormat::None, &sample_files());
+
+    // Split the archive into volumes and read them back as one stream
+    let data = std::fs::read(&path).unwrap();
+    let parts: Vec<_> = data
+        .chunks(data.len() / 3 + 1)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let part = dir.path().join(format!("archive.part{}", i + 1));
+            std::fs::write(&part, chunk).unwrap();
+            part
+        })
+        .collect();
+
+    let mut two_pass = ReadArchive::open_two_pass_filenames(&parts).unwrap();
+    let scan = two_pass.scan(|_| {}).unwrap();
+    let processed = two_pass
+        .process(|_, data| data.read_to_vec().map(|_| ()))
+        .unwrap();
+    assert_eq!(processed, scan);
+}
+
+#[test]
+fn test_two_pass_options_apply_to_both_passes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.tar");
+    write_tar(&path, CompressionFormat::None, &sample_files());
+
+    let mut two_pass =
+        ReadArchive::open_two_pass(&path)
+            .unwrap()
+            .option("tar", "hdrcharset", "UTF-8");
+    let scan = two_pass.scan(|_| {}).unwrap();
+    assert_eq!(scan.entries, 4);
+
+    let mut names = Vec::new();
+    let processed = two_pass
+        .process(|entry, data| {
+            names.push(entry.pathname().unwrap());
+            data.read_to_vec()?;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(processed, scan);
+    assert_eq!(names, ["a.txt", "dir/b.bin", "dir/c.txt", "dir/"]);
+}
@@ -0,0 +1,123 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, EntryMutAclExt, Error, FileType, ReadArchive,
+    UnsupportedMetadataPolicy, WriteArchive,
+};
+
+fn xattr_entry() -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname("tagged.txt").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_perm(0o644).unwrap();
+    entry.set_size(5);
+    entry.add_xattr("user.origin", b"test").unwrap();
+    entry
+}
+
+/// Write the xattr entry under `policy` and return the warnings, the
+/// write_header result and the pathnames read back
+fn write_xattr_entry(
+    format: ArchiveFormat,
+    policy: UnsupportedMetadataPolicy,
+) -> (Vec<String>, libarchive2::Result<()>, Vec<String>) {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let (warnings, result) = {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .on_unsupported_metadata(policy)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let result = archive.write_header(&xattr_entry());
+        if result.is_ok() {
+            archive.write_data(b"hello").unwrap();
+        }
+        let warnings = archive.take_warnings();
+        assert!(archive.warnings().is_empty());
+        archive.finish().unwrap();
+        (warnings, result)
+    };
+
+    let mut archive = ReadArchive::open_memory(&buf[..used]).unwrap();
+    let mut names = Vec::new();
+    while let Some(entry) = archive.next_entry().unwrap() {
+        names.push(entry.pathname().unwrap());
+    }
+    (warnings, result, names)
+}
+
+#[test]
+fn test_zip_xattr_ignore() {
+    let (warnings, result, names) =
+        write_xattr_entry(ArchiveFormat::Zip, UnsupportedMetadataPolicy::Ignore);
+    assert!(result.is_ok());
+    assert!(warnings.is_empty());
+    assert_eq!(names, vec!["tagged.txt"]);
+}
+
+#[test]
+fn test_zip_xattr_warn() {
+    let (warnings, result, names) =
+        write_xattr_entry(ArchiveFormat::Zip, UnsupportedMetadataPolicy::Warn);
+    assert!(result.is_ok());
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("tagged.txt"), "{}", warnings[0]);
+    assert!(
+        warnings[0].contains("extended attributes"),
+        "{}",
+        warnings[0]
+    );
+    assert_eq!(names, vec!["tagged.txt"]);
+}
+
+#[test]
+fn test_zip_xattr_error() {
+    let (warnings, result, names) =
+        write_xattr_entry(ArchiveFormat::Zip, UnsupportedMetadataPolicy::Error);
+    match result {
+        Err(Error::InvalidArgument(message)) => {
+            assert!(message.contains("tagged.txt"), "{}", message);
+            assert!(message.contains("extended attributes"), "{}", message);
+        }
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+    assert!(warnings.is_empty());
+    assert!(names.is_empty());
+}
+
+#[test]
+fn test_pax_xattr_no_warning() {
+    for policy in [
+        UnsupportedMetadataPolicy::Warn,
+        UnsupportedMetadataPolicy::Error,
+    ] {
+        let (warnings, result, names) = write_xattr_entry(ArchiveFormat::TarPax, policy);
+        assert!(result.is_ok());
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(names, vec!["tagged.txt"]);
+    }
+}
+
+#[test]
+fn test_device_node_in_7z() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::SevenZip)
+        .on_unsupported_metadata(UnsupportedMetadataPolicy::Warn)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname("dev/null").unwrap();
+    entry.set_file_type(FileType::CharacterDevice);
+    entry.set_perm(0o666).unwrap();
+    entry.set_rdevmajor(1);
+    entry.set_rdevminor(3);
+    archive.write_header(&entry).unwrap();
+    archive.add_file("plain.txt", b"plain").unwrap();
+
+    assert_eq!(archive.warnings().len(), 1);
+    assert!(archive.warnings()[0].starts_with("dev/null:"));
+    assert!(archive.warnings()[0].contains("device node"));
+    archive.finish().unwrap();
+}
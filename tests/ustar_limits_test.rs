@@ -0,0 +1,97 @@
+use libarchive2::{ArchiveFormat, EntryMut, Error, FileType, ReadArchive, WriteArchive};
+
+const BIG_ID: u64 = 4_000_000;
+
+fn write_with_ids(format: ArchiveFormat, uid: u64, gid: u64) -> libarchive2::Result<Vec<u8>> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(format)
+            .open_memory(&mut buffer, &mut used)?;
+
+        let mut entry = EntryMut::new();
+        entry.set_pathname("file.txt")?;
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o644)?;
+        entry.set_size(4);
+        entry.set_uid(uid);
+        entry.set_gid(gid);
+        archive.write_header(&entry)?;
+        archive.write_data(b"data")?;
+        archive.finish()?;
+    }
+    buffer.truncate(used);
+    Ok(buffer)
+}
+
+#[test]
+fn test_ustar_uid_overflow_is_rejected() {
+    match write_with_ids(ArchiveFormat::TarUstar, BIG_ID, 100) {
+        Err(Error::InvalidArgument(msg)) => {
+            assert!(msg.contains("uid"), "message should name the field: {msg}");
+            assert!(
+                msg.contains("4000000"),
+                "message should name the value: {msg}"
+            );
+            assert!(msg.contains("TarPax"), "message should suggest pax: {msg}");
+        }
+        other => panic!("expected InvalidArgument, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_ustar_gid_overflow_via_default_override() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarUstar)
+        .default_gid(BIG_ID)
+        .open_memory(&mut buffer, &mut used)
+        .unwrap();
+
+    match archive.add_file("file.txt", b"data") {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains("gid")),
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_ustar_size_overflow_is_rejected() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarUstar)
+        .open_memory(&mut buffer, &mut used)
+        .unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname("huge.bin").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(8 * 1024 * 1024 * 1024);
+    match archive.write_header(&entry) {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains("size")),
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_ustar_ids_within_limit() {
+    let data = write_with_ids(ArchiveFormat::TarUstar, 262_143, 262_143).unwrap();
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.uid(), Some(262_143));
+    assert_eq!(entry.gid(), Some(262_143));
+}
+
+#[test]
+fn test_large_ids_roundtrip_in_pax_and_gnu() {
+    for format in [ArchiveFormat::TarPax, ArchiveFormat::TarGnu] {
+        let data = write_with_ids(format, BIG_ID, BIG_ID + 1).unwrap();
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        let entry = archive.next_entry().unwrap().unwrap();
+        assert_eq!(entry.uid(), Some(BIG_ID), "format {:?}", format);
+        assert_eq!(entry.gid(), Some(BIG_ID + 1), "format {:?}", format);
+        assert_eq!(archive.read_data_to_vec().unwrap(), b"data");
+    }
+}
@@ -0,0 +1,131 @@
+use libarchive2::{ArchiveFormat, CompressionFormat, HashAlgo, ReadArchive, WriteArchive};
+
+fn sample(seed: u8) -> Vec<u8> {
+    (0..20_000u32)
+        .map(|i| b"abcdefghij"[(i as usize * (seed as usize + 3)) % 10])
+        .collect()
+}
+
+fn build_zip() -> Vec<u8> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for (name, seed) in [("one.txt", 1), ("two.txt", 2), ("three.txt", 3)] {
+            archive.add_file(name, &sample(seed)).unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+/// Offset of the compressed data of the `index`th local file header
+fn data_offset(zip: &[u8], index: usize) -> usize {
+    let header = zip
+        .windows(4)
+        .enumerate()
+        .filter(|(_, w)| *w == b"PK\x03\x04")
+        .nth(index)
+        .unwrap()
+        .0;
+    let name_len = u16::from_le_bytes([zip[header + 26], zip[header + 27]]) as usize;
+    let extra_len = u16::from_le_bytes([zip[header + 28], zip[header + 29]]) as usize;
+    header + 30 + name_len + extra_len
+}
+
+#[test]
+fn test_verify_intact_archive() {
+    let zip = build_zip();
+    let report = ReadArchive::open_memory(&zip).unwrap().verify().unwrap();
+    assert!(report.is_ok(), "{report:?}");
+    assert_eq!(report.entries, 3);
+    assert_eq!(report.bytes, 60_000);
+}
+
+#[test]
+fn test_verify_reports_corrupt_entry() {
+    let mut zip = build_zip();
+    let offset = data_offset(&zip, 1) + 20;
+    zip[offset] ^= 0xff;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("corrupt.zip");
+    std::fs::write(&path, &zip).unwrap();
+
+    let report = ReadArchive::open(&path).unwrap().verify().unwrap();
+    assert_eq!(report.entries, 3);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].pathname.as_deref(), Some("two.txt"));
+    assert!(!report.is_ok());
+}
+
+#[test]
+fn test_read_data_hash() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .compression(CompressionFormat::Gzip)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("abc", b"abc").unwrap();
+        archive.add_file("empty", b"").unwrap();
+        archive.add_file("million", &vec![b'a'; 1_000_000]).unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let expected = [
+        (
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+            "900150983cd24fb0d6963f7d28e17f72",
+        ),
+        (
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "d41d8cd98f00b204e9800998ecf8427e",
+        ),
+        (
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+            "7707d6ae4e027c70eea2a935c2296f21",
+        ),
+    ];
+    for algo_index in 0..3 {
+        let mut archive = ReadArchive::open_memory(&buf).unwrap();
+        for digests in &expected {
+            archive.next_entry().unwrap().unwrap();
+            let (algo, digest) = match algo_index {
+                0 => (HashAlgo::Sha256, digests.0),
+                1 => (HashAlgo::Sha1, digests.1),
+                _ => (HashAlgo::Md5, digests.2),
+            };
+            assert_eq!(archive.read_data_hash(algo).unwrap(), digest);
+        }
+    }
+}
+
+#[test]
+fn test_verify_reports_read_stats() {
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Gzip)
+        .open_growable_memory()
+        .unwrap();
+    archive.add_file("one.txt", &sample(1)).unwrap();
+    archive.add_file("two.txt", &sample(2)).unwrap();
+    let data = archive.finish_to_vec().unwrap();
+
+    let report = ReadArchive::open_memory(&data).unwrap().verify().unwrap();
+    assert!(report.is_ok(), "{report:?}");
+    assert_eq!(report.stats.entries, 2);
+    assert_eq!(report.stats.data_bytes, 40_000);
+    assert!(report.stats.compressed_bytes > 0);
+    assert!(report.stats.uncompressed_bytes > report.stats.compressed_bytes);
+}
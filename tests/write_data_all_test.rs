@@ -0,0 +1,94 @@
+use libarchive2::{
+    ArchiveFormat, CallbackWriter, CompressionFormat, EntryMut, Error, FileType, ReadArchive,
+    WriteArchive,
+};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Writer that accepts at most 7 bytes per call
+struct Trickle(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(7);
+        self.0.lock().unwrap().extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn write_trickled(format: ArchiveFormat, compression: CompressionFormat) -> Vec<u8> {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let mut archive = WriteArchive::new()
+        .format(format)
+        .compression(compression)
+        .open_callback(CallbackWriter::new(Trickle(Arc::clone(&output))))
+        .unwrap();
+    archive.add_file("big.bin", &payload(300_000)).unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname("chunks.bin").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(10_000);
+    entry.set_perm(0o644).unwrap();
+    archive.write_header(&entry).unwrap();
+    for chunk in payload(10_000).chunks(333) {
+        archive.write_data_all(chunk).unwrap();
+    }
+    archive.finish().unwrap();
+
+    Arc::try_unwrap(output).unwrap().into_inner().unwrap()
+}
+
+fn check_intact(bytes: &[u8]) {
+    let mut archive = ReadArchive::open_memory(bytes).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "big.bin");
+    assert!(archive.read_data_to_vec().unwrap() == payload(300_000));
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "chunks.bin");
+    assert!(archive.read_data_to_vec().unwrap() == payload(10_000));
+    assert!(archive.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_short_writes_zip() {
+    check_intact(&write_trickled(ArchiveFormat::Zip, CompressionFormat::None));
+}
+
+#[test]
+fn test_short_writes_tar_gz() {
+    check_intact(&write_trickled(
+        ArchiveFormat::TarPax,
+        CompressionFormat::Gzip,
+    ));
+}
+
+#[test]
+fn test_write_data_all_past_header_size() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_memory(&mut buffer, &mut used)
+        .unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname("small.txt").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(4);
+    entry.set_perm(0o644).unwrap();
+    archive.write_header(&entry).unwrap();
+
+    assert!(matches!(
+        archive.write_data_all(b"too long"),
+        Err(Error::InvalidArgument(_))
+    ));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use libarchive2::{EntryMut, ExtractFlags, FileType, WriteDisk};
+use std::sync::{Arc, Mutex};
+
+fn file_entry(path: &std::path::Path, uname: &str) -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname(path).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(2);
+    entry.set_perm(0o644).unwrap();
+    entry.set_uname(uname).unwrap();
+    entry.set_uid(4242);
+    entry.set_gid(4242);
+    entry
+}
+
+fn write_file(disk: &mut WriteDisk, entry: &EntryMut) {
+    disk.write_header(entry).unwrap();
+    disk.write_data(b"hi").unwrap();
+    disk.finish_entry().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_user_lookup_to_current_uid() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+    let dir = tempfile::tempdir().unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::OWNER).unwrap();
+    let names = Arc::clone(&seen);
+    disk.set_user_lookup(move |name, stored| {
+        names
+            .lock()
+            .unwrap()
+            .push((name.map(str::to_string), stored));
+        uid as i64
+    })
+    .unwrap();
+    disk.set_group_lookup(move |_, _| gid as i64).unwrap();
+
+    let path = dir.path().join("mine");
+    write_file(&mut disk, &file_entry(&path, "builder"));
+    disk.close().unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), [(Some("builder".to_string()), 4242)]);
+    let metadata = std::fs::metadata(&path).unwrap();
+    assert_eq!(metadata.uid(), uid);
+    assert_eq!(metadata.gid(), gid);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_user_lookup_to_unassignable_uid_warns() {
+    // Without root this maps to root itself
+    let Some(uid) = common::unassignable_uid() else {
+        eprintln!("skipped: running as root with every uid mapped, so chown cannot fail");
+        return;
+    };
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::OWNER).unwrap();
+    disk.set_user_lookup(move |_, _| uid as i64).unwrap();
+    disk.set_group_lookup(|_, _| 0).unwrap();
+
+    let path = dir.path().join("root-owned");
+    write_file(&mut disk, &file_entry(&path, "root"));
+
+    assert!(disk.last_warning().is_some());
+    assert_eq!(std::fs::read(&path).unwrap(), b"hi");
+    disk.close().unwrap();
+}
+
+#[test]
+fn test_lookup_not_called_without_owner_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let calls = Arc::new(Mutex::new(0));
+
+    let mut disk = WriteDisk::new().unwrap();
+    let counter = Arc::clone(&calls);
+    disk.set_user_lookup(move |_, uid| {
+        *counter.lock().unwrap() += 1;
+        uid
+    })
+    .unwrap();
+
+    write_file(&mut disk, &file_entry(&dir.path().join("plain"), "nobody"));
+    disk.close().unwrap();
+    assert_eq!(*calls.lock().unwrap(), 0);
+}
+
+#[test]
+fn test_current_file_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    assert!(disk.current_file_path().is_none());
+
+    let path = dir.path().join("first");
+    write_file(&mut disk, &file_entry(&path, "user"));
+    assert_eq!(disk.current_file_path(), Some(path.as_path()));
+
+    // Stripped paths are reported as written; fully stripped entries clear it
+    disk.set_strip_components(1);
+    let mut entry = EntryMut::new();
+    entry.set_pathname("top").unwrap();
+    entry.set_file_type(FileType::Directory);
+    entry.set_perm(0o755).unwrap();
+    disk.write_header(&entry).unwrap();
+    assert!(disk.current_file_path().is_none());
+    disk.finish_entry().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_skip_file_refuses_overwrite() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let protected = dir.path().join("archive.tar");
+    std::fs::write(&protected, b"archive").unwrap();
+    let metadata = std::fs::metadata(&protected).unwrap();
+
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_skip_file(metadata.dev(), metadata.ino()).unwrap();
+    assert!(disk.write_header(&file_entry(&protected, "user")).is_err());
+    assert_eq!(disk.current_file_path(), Some(protected.as_path()));
+    assert_eq!(std::fs::read(&protected).unwrap(), b"archive");
+
+    // Other files are written as usual
+    write_file(&mut disk, &file_entry(&dir.path().join("other"), "user"));
+    assert_eq!(std::fs::read(dir.path().join("other")).unwrap(), b"hi");
+}
@@ -0,0 +1,109 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, ExtractFlags, FileType, ReadArchive, WriteArchive, WriteDisk,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+const SIZE: i64 = 10 * 1024 * 1024;
+
+fn write_sparse_tar(temp_dir: &TempDir) -> std::path::PathBuf {
+    use std::io::{Seek, SeekFrom, Write};
+
+    // Only the first and last 4 KiB hold data; the rest is a hole, which
+    // libarchive's disk reader records as the entry's sparse map
+    let source = temp_dir.path().join("source.img");
+    let mut file = std::fs::File::create(&source).unwrap();
+    file.write_all(&[0xaa; 4096]).unwrap();
+    file.seek(SeekFrom::Start(SIZE as u64 - 4096)).unwrap();
+    file.write_all(&[0xbb; 4096]).unwrap();
+    drop(file);
+
+    let path = temp_dir.path().join("sparse.tar");
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .open_file(&path)
+        .unwrap();
+    archive.add_path(&source, Some("disk.img")).unwrap();
+    archive.finish().unwrap();
+    path
+}
+
+fn extract(archive_path: &Path, dest: &Path, flags: ExtractFlags) -> u64 {
+    let mut archive = ReadArchive::open(archive_path).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(flags).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    let mut out = EntryMut::new();
+    out.set_pathname(dest).unwrap();
+    out.set_file_type(FileType::RegularFile);
+    if let Some(size) = entry.size_opt() {
+        out.set_size(size);
+    }
+    out.set_perm(0o644).unwrap();
+    disk.write_header(&out).unwrap();
+    let copied = archive.copy_data_to_disk(&mut disk).unwrap();
+    disk.finish_entry().unwrap();
+    copied
+}
+
+fn check_contents(path: &Path) {
+    let data = std::fs::read(path).unwrap();
+    assert_eq!(data.len() as i64, SIZE);
+    assert!(data[..4096].iter().all(|&b| b == 0xaa));
+    assert!(data[4096..SIZE as usize - 4096].iter().all(|&b| b == 0));
+    assert!(data[SIZE as usize - 4096..].iter().all(|&b| b == 0xbb));
+}
+
+#[test]
+fn test_copy_data_to_disk_keeps_holes() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = write_sparse_tar(&temp_dir);
+
+    let dest = temp_dir.path().join("disk.img");
+    let copied = extract(&archive_path, &dest, ExtractFlags::SPARSE);
+    check_contents(&dest);
+    if copied == SIZE as u64 {
+        println!("Skipping test: the temp filesystem does not report holes");
+        return;
+    }
+    assert_eq!(copied, 8192);
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // st_blocks counts 512-byte units; the data is only 8 KiB, so allow
+        // generous filesystem overhead while still catching written zeros
+        let allocated = std::fs::metadata(&dest).unwrap().blocks() * 512;
+        assert!(
+            allocated < SIZE as u64 / 4,
+            "{} bytes allocated for a {} byte sparse file",
+            allocated,
+            SIZE
+        );
+    }
+}
+
+#[test]
+fn test_write_data_block() {
+    let temp_dir = TempDir::new().unwrap();
+    let dest = temp_dir.path().join("blocks.bin");
+
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::SPARSE).unwrap();
+    let mut entry = EntryMut::new();
+    entry.set_pathname(&dest).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(SIZE);
+    entry.set_perm(0o644).unwrap();
+    disk.write_header(&entry).unwrap();
+    assert_eq!(disk.write_data_block(0, &[0xaa; 4096]).unwrap(), 4096);
+    assert_eq!(
+        disk.write_data_block(SIZE - 4096, &[0xbb; 4096]).unwrap(),
+        4096
+    );
+    disk.finish_entry().unwrap();
+    disk.close().unwrap();
+
+    check_contents(&dest);
+}
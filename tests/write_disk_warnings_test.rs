@@ -0,0 +1,132 @@
+mod common;
+
+use libarchive2::{
+    ArchiveFormat, EntryMut, ExtractFlags, FileType, ReadArchive, WriteArchive, WriteDisk,
+};
+
+fn empty_file(path: &std::path::Path) -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname(path).unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(0);
+    entry.set_perm(0o644).unwrap();
+    entry
+}
+
+#[test]
+fn test_data_for_empty_file_is_a_warning() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    assert!(disk.last_warning().is_none());
+
+    disk.write_header(&empty_file(&dir.path().join("empty")))
+        .unwrap();
+    assert_eq!(disk.write_data(b"ignored").unwrap(), 7);
+    disk.finish_entry().unwrap();
+
+    let warning = disk.last_warning().expect("warning recorded");
+    assert!(warning.contains("empty file"), "{warning}");
+    assert_eq!(disk.warnings().len(), 1);
+    assert_eq!(
+        std::fs::metadata(dir.path().join("empty")).unwrap().len(),
+        0
+    );
+
+    // Later entries still extract and don't clear the record
+    let mut entry = empty_file(&dir.path().join("full"));
+    entry.set_size(3);
+    disk.write_header(&entry).unwrap();
+    disk.write_data(b"abc").unwrap();
+    disk.finish_entry().unwrap();
+    assert_eq!(std::fs::read(dir.path().join("full")).unwrap(), b"abc");
+    assert_eq!(disk.warnings().len(), 1);
+}
+
+#[test]
+fn test_data_block_for_directory_is_a_warning() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+
+    let mut entry = EntryMut::new();
+    entry.set_pathname(dir.path().join("sub")).unwrap();
+    entry.set_file_type(FileType::Directory);
+    entry.set_perm(0o755).unwrap();
+    disk.write_header(&entry).unwrap();
+    assert_eq!(disk.write_data_block(0, b"data").unwrap(), 4);
+    disk.finish_entry().unwrap();
+
+    assert!(dir.path().join("sub").is_dir());
+    assert_eq!(disk.warnings().len(), 1);
+}
+
+#[test]
+fn test_clean_extraction_has_no_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("out");
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        let mut entry = EntryMut::new();
+        entry.set_pathname(root.join("a.txt")).unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_size(5);
+        entry.set_perm(0o644).unwrap();
+        archive.write_header(&entry).unwrap();
+        archive.write_data(b"hello").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let mut disk = WriteDisk::new().unwrap();
+    disk.set_options(ExtractFlags::TIME | ExtractFlags::PERM)
+        .unwrap();
+    while archive.next_entry().unwrap().is_some() {
+        archive.extract_current(&mut disk).unwrap();
+    }
+    assert!(disk.warnings().is_empty());
+    assert_eq!(std::fs::read(root.join("a.txt")).unwrap(), b"hello");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_owner_as_non_root_extracts_with_warnings() {
+    let Some(uid) = common::unassignable_uid() else {
+        eprintln!("skipped: running as root with every uid mapped, so chown cannot fail");
+        return;
+    };
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        for name in ["a.txt", "b.txt"] {
+            let mut entry = empty_file(std::path::Path::new(name));
+            entry.set_size(5);
+            entry.set_uid(uid as u64);
+            archive.write_header(&entry).unwrap();
+            archive.write_data(b"hello").unwrap();
+        }
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut archive = ReadArchive::open_memory(&buf).unwrap();
+    let stats = archive
+        .extract_to_dir(dir.path(), ExtractFlags::OWNER)
+        .unwrap();
+    assert_eq!(stats.files, 2);
+    assert_eq!(stats.warnings, 2);
+    for name in ["a.txt", "b.txt"] {
+        assert_eq!(std::fs::read(dir.path().join(name)).unwrap(), b"hello");
+    }
+}
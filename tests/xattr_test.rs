@@ -0,0 +1,50 @@
+use libarchive2::{ArchiveFormat, EntryAclExt, ReadArchive, WriteArchive};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_add_file_with_xattrs() {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .default_mtime(mtime)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        archive
+            .add_file_with_xattrs(
+                "tool",
+                b"contents",
+                &[
+                    ("user.checksum", b"abc123"),
+                    ("security.capability", &[1, 0, 0, 2, 0, 0x20, 0, 0]),
+                ],
+            )
+            .unwrap();
+        archive.add_file("plain.txt", b"no xattrs").unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buffer[..used]).unwrap();
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "tool");
+    assert_eq!(entry.mtime(), Some(mtime));
+    // The pax writer stores xattrs under both the LIBARCHIVE.xattr and SCHILY.xattr
+    // keywords, and the reader reports both copies
+    let xattrs: BTreeMap<String, Vec<u8>> = entry
+        .xattrs()
+        .into_iter()
+        .map(|x| (x.name, x.value))
+        .collect();
+    assert_eq!(xattrs.len(), 2);
+    assert_eq!(xattrs["security.capability"], [1, 0, 0, 2, 0, 0x20, 0, 0]);
+    assert_eq!(xattrs["user.checksum"], b"abc123");
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"contents");
+
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.xattr_count(), 0);
+    assert!(!entry.has_acl());
+}
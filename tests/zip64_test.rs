@@ -0,0 +1,85 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, Error, FileType, FormatOption, ReadArchive, WriteArchive, Zip64Mode,
+};
+
+const ZIP64_EOCD: &[u8] = b"PK\x06\x06";
+const ZIP64_LOCATOR: &[u8] = b"PK\x06\x07";
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn small_zip(mode: Zip64Mode) -> Vec<u8> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .format_option(FormatOption::ZipZip64(mode))
+            .open_memory(&mut buf, &mut used)
+            .unwrap();
+        archive.add_file("hello.txt", b"hello zip64").unwrap();
+        archive.finish().unwrap();
+    }
+    buf.truncate(used);
+    buf
+}
+
+fn oversized_entry() -> EntryMut {
+    let mut entry = EntryMut::new();
+    entry.set_pathname("huge.bin").unwrap();
+    entry.set_file_type(FileType::RegularFile);
+    entry.set_size(5 * 1024 * 1024 * 1024);
+    entry.set_perm(0o644).unwrap();
+    entry
+}
+
+#[test]
+fn test_zip64_always_writes_zip64_records() {
+    let data = small_zip(Zip64Mode::Always);
+    assert!(contains(&data, ZIP64_EOCD));
+    assert!(contains(&data, ZIP64_LOCATOR));
+
+    let mut archive = ReadArchive::open_memory(&data).unwrap();
+    archive.next_entry().unwrap().unwrap();
+    assert_eq!(archive.read_data_to_vec().unwrap(), b"hello zip64");
+}
+
+#[test]
+fn test_zip64_auto_and_never_small_archive() {
+    for mode in [Zip64Mode::Auto, Zip64Mode::Never] {
+        let data = small_zip(mode);
+        assert!(!contains(&data, ZIP64_EOCD), "{mode:?}");
+
+        let mut archive = ReadArchive::open_memory(&data).unwrap();
+        archive.next_entry().unwrap().unwrap();
+        assert_eq!(archive.read_data_to_vec().unwrap(), b"hello zip64");
+    }
+}
+
+#[test]
+fn test_zip64_never_rejects_oversized_entry() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .format_option(FormatOption::ZipZip64(Zip64Mode::Never))
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+
+    match archive.write_header(&oversized_entry()) {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains("Zip64"), "{msg}"),
+        other => panic!("expected InvalidArgument, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_zip64_auto_accepts_oversized_header() {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut used = 0;
+    let mut archive = WriteArchive::new()
+        .format(ArchiveFormat::Zip)
+        .open_memory(&mut buf, &mut used)
+        .unwrap();
+    archive.write_header(&oversized_entry()).unwrap();
+}
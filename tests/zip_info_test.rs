@@ -0,0 +1,67 @@
+use libarchive2::{
+    ArchiveFormat, EntryMut, FileType, FormatOption, ReadArchive, WriteArchive,
+    ZipCompressionMethod,
+};
+
+fn write_zip(buffer: &mut [u8], method: ZipCompressionMethod) -> usize {
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::Zip)
+            .format_option(FormatOption::ZipCompressionMethod(method))
+            .open_memory(buffer, &mut used)
+            .unwrap();
+        let data = "zip info ".repeat(1000);
+        let mut entry = EntryMut::new();
+        entry.set_pathname("file.txt").unwrap();
+        entry.set_file_type(FileType::RegularFile);
+        entry.set_perm(0o640).unwrap();
+        entry.set_size(data.len() as i64);
+        archive.write_header(&entry).unwrap();
+        archive.write_data(data.as_bytes()).unwrap();
+        archive.finish().unwrap();
+    }
+    used
+}
+
+#[test]
+fn test_zip_info_stored_vs_deflated() {
+    let mut stored = vec![0u8; 64 * 1024];
+    let stored_len = write_zip(&mut stored, ZipCompressionMethod::Store);
+    let mut deflated = vec![0u8; 64 * 1024];
+    let deflated_len = write_zip(&mut deflated, ZipCompressionMethod::Deflate);
+
+    let mut archive = ReadArchive::open_memory(&stored[..stored_len]).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    let stored_info = entry.zip_info().unwrap();
+    assert_eq!(stored_info.method, Some(0));
+    assert_eq!(stored_info.method_name, "uncompressed");
+    assert!(stored_info.is_stored());
+    assert!(stored_info.version_needed >= 10);
+
+    let mut archive = ReadArchive::open_memory(&deflated[..deflated_len]).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    let deflated_info = entry.zip_info().unwrap();
+    assert_eq!(deflated_info.method, Some(8));
+    assert!(!deflated_info.is_stored());
+    assert!(deflated_info.version_needed >= 20);
+    assert_eq!(entry.mode() & 0o777, 0o640);
+}
+
+#[test]
+fn test_zip_info_not_zip() {
+    let mut buffer = vec![0u8; 16 * 1024];
+    let mut used = 0;
+    {
+        let mut archive = WriteArchive::new()
+            .format(ArchiveFormat::TarPax)
+            .open_memory(&mut buffer, &mut used)
+            .unwrap();
+        archive.add_file("file.txt", b"data").unwrap();
+        archive.finish().unwrap();
+    }
+
+    let mut archive = ReadArchive::open_memory(&buffer[..used]).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert!(entry.zip_info().is_none());
+}
@@ -0,0 +1,149 @@
+use libarchive2::{
+    ArchiveFormat, CompressionFormat, Error, FilterOption, ReadArchive, WriteArchive,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Compressible but not trivially repetitive text
+fn synthetic_data(len: usize) -> Vec<u8> {
+    const WORDS: &[&[u8]] = &[
+        b"archive ",
+        b"entry ",
+        b"header ",
+        b"block ",
+        b"stream ",
+        b"filter ",
+        b"zstd ",
+        b"window ",
+        b"thread ",
+        b"level ",
+        b"\n",
+    ];
+    let mut state = 0x9e37_79b9u32;
+    let mut data = Vec::with_capacity(len + 16);
+    while data.len() < len {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        data.extend_from_slice(WORDS[state as usize % WORDS.len()]);
+        if state.is_multiple_of(7) {
+            data.extend_from_slice(&state.to_le_bytes());
+        }
+    }
+    data.truncate(len);
+    data
+}
+
+fn write_zstd(path: &Path, options: &[FilterOption], data: &[u8]) -> libarchive2::Result<()> {
+    let mut builder = WriteArchive::new()
+        .format(ArchiveFormat::TarPax)
+        .compression(CompressionFormat::Zstd);
+    for option in options {
+        builder = builder.filter_option(option.clone());
+    }
+    let mut archive = builder.open_file(path)?;
+    archive.add_file("data.bin", data)?;
+    archive.finish()?;
+    Ok(())
+}
+
+fn read_back(path: &Path) -> Vec<u8> {
+    let mut archive = ReadArchive::open(path).unwrap();
+    let entry = archive.next_entry().unwrap().unwrap();
+    assert_eq!(entry.pathname().unwrap(), "data.bin");
+    archive.read_data_to_vec().unwrap()
+}
+
+#[test]
+fn test_zstd_threads() {
+    let temp_dir = TempDir::new().unwrap();
+    let single = temp_dir.path().join("single.tar.zst");
+    let threaded = temp_dir.path().join("threaded.tar.zst");
+    let data = synthetic_data(50 * 1024 * 1024);
+
+    write_zstd(&single, &[FilterOption::ZstdThreads(1)], &data).unwrap();
+    write_zstd(&threaded, &[FilterOption::ZstdThreads(4)], &data).unwrap();
+
+    // Multithreaded zstd splits the input into jobs, so the output differs
+    // slightly but should compress about as well
+    let single_size = std::fs::metadata(&single).unwrap().len() as f64;
+    let threaded_size = std::fs::metadata(&threaded).unwrap().len() as f64;
+    assert!(threaded_size < data.len() as f64 / 2.0);
+    assert!((threaded_size / single_size - 1.0).abs() < 0.1);
+
+    assert!(read_back(&threaded) == data);
+}
+
+#[test]
+fn test_zstd_threads_auto() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("auto.tar.zst");
+    let data = synthetic_data(4 * 1024 * 1024);
+
+    write_zstd(&path, &[FilterOption::ZstdThreads(0)], &data).unwrap();
+    assert!(read_back(&path) == data);
+}
+
+#[test]
+fn test_zstd_high_level() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("ultra.tar.zst");
+    let data = synthetic_data(256 * 1024);
+
+    write_zstd(&path, &[FilterOption::ZstdCompressionLevel(22)], &data).unwrap();
+    assert_eq!(read_back(&path), data);
+}
+
+#[test]
+fn test_zstd_level_19_beats_level_3() {
+    let temp_dir = TempDir::new().unwrap();
+    let fast = temp_dir.path().join("fast.tar.zst");
+    let strong = temp_dir.path().join("strong.tar.zst");
+    let data = synthetic_data(4 * 1024 * 1024);
+
+    write_zstd(&fast, &[FilterOption::ZstdCompressionLevel(3)], &data).unwrap();
+    write_zstd(&strong, &[FilterOption::ZstdCompressionLevel(19)], &data).unwrap();
+
+    let fast_size = std::fs::metadata(&fast).unwrap().len();
+    let strong_size = std::fs::metadata(&strong).unwrap().len();
+    assert!(
+        strong_size < fast_size,
+        "level 19 gave {strong_size} bytes, level 3 gave {fast_size}"
+    );
+    assert!(read_back(&strong) == data);
+}
+
+#[test]
+fn test_zstd_long_distance_matching() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("long.tar.zst");
+    let data = synthetic_data(1024 * 1024);
+
+    write_zstd(
+        &path,
+        &[
+            FilterOption::ZstdLongDistanceMatching(27),
+            FilterOption::ZstdThreads(2),
+        ],
+        &data,
+    )
+    .unwrap();
+    assert_eq!(read_back(&path), data);
+}
+
+#[test]
+fn test_zstd_invalid_options() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("invalid.tar.zst");
+
+    for option in [
+        FilterOption::ZstdCompressionLevel(23),
+        FilterOption::ZstdLongDistanceMatching(32),
+        FilterOption::ZstdLongDistanceMatching(5),
+    ] {
+        match write_zstd(&path, std::slice::from_ref(&option), b"data") {
+            Err(Error::Archive { message, .. }) => assert!(message.contains("zstd"), "{message}"),
+            other => panic!("{option:?} should be rejected, got {other:?}"),
+        }
+    }
+}